@@ -2,37 +2,63 @@ use bevy::prelude::*;
 use bevy::log::LogPlugin;
 use crate::core_asset_plugin::CoreAssetPlugin;
 use crate::asset_template_plugin::AssetTemplatePlugin;
-use crate::ocpp_protocol_plugin::{OcppProtocolPlugin, OcppFromAssetChannel, OcppToAssetChannel};
-use crate::modbus_protocol_plugin::{ModbusProtocolPlugin, ModbusRequestChannel, ModbusResponseChannel};
-use crate::balancer_comms_plugin::{BalancerCommsPlugin, resources::{BalancerSetpointReceiver, BalancerMeteringSender}};
+use crate::ocpp_protocol_plugin::{OcppProtocolPlugin, OcppFromAssetChannel, OcppResponseFromAssetChannel, OcppToAssetChannel};
+use crate::modbus_protocol_plugin::{ModbusProtocolPlugin, ModbusRegisterMaps, ModbusRequestChannel, ModbusResponseChannel, ModbusWriteRequestChannel, ModbusWriteResponseChannel};
+use crate::balancer_comms_plugin::{BalancerCommsPlugin, resources::{BalancerSetpointReceiver, BalancerSetpointBatchReceiver, BalancerScheduleReceiver, BalancerMeteringSender, BalancerSiteTotalsSender, BalancerStatusSender}};
+use crate::site_constraint_plugin::SiteConstraintPlugin;
 use crate::visualization_plugin::VisualizationPlugin;
 use crossbeam_channel::{unbounded, Sender, Receiver};
 use bevy_egui::EguiPlugin;
 use crate::visualization_plugin::log_capture::LogReceiver;
-use crate::balancer_comms_plugin::balancer_messages::{BalancerSetpointMessage, BalancerMeteringMessage};
-use crate::modbus_protocol_plugin::{ModbusRequest, ModbusResponse};
-use crate::ocpp_protocol_plugin::events::{OcppRequestFromAsset, OcppCommandToAsset};
-use crate::asset_template_plugin::SiteConfig;
+use crate::balancer_comms_plugin::balancer_messages::{BalancerSetpointMessage, BalancerSetpointBatchMessage, BalancerScheduleMessage, BalancerMeteringMessage, BalancerSiteTotalsMessage, BalancerAssetStatusMessage};
+use crate::modbus_protocol_plugin::{ModbusRequest, ModbusResponse, ModbusWriteRequest, ModbusWriteResponse};
+use crate::ocpp_protocol_plugin::events::{OcppRequestFromAsset, OcppCommandToAsset, OcppResponseFromAsset};
+use crate::asset_template_plugin::{SiteConfig, SiteConfigReloadReceiver, AssetRegistryCommandReceiver, AssetRegistryResultSender, AssetRegistryResult, EAssetRegistryCommand};
+use crate::common::error::AppError;
 
 /// External channel ends for production integration or tests.
 pub struct AppExternalChannelEnds {
     // balancer ↔ Bevy
     pub balancer_setpoint_sender: Sender<BalancerSetpointMessage>,
     pub balancer_setpoint_receiver: Receiver<BalancerSetpointMessage>,
+    pub balancer_setpoint_batch_sender: Sender<BalancerSetpointBatchMessage>,
+    pub balancer_setpoint_batch_receiver: Receiver<BalancerSetpointBatchMessage>,
+    pub balancer_schedule_sender: Sender<BalancerScheduleMessage>,
+    pub balancer_schedule_receiver: Receiver<BalancerScheduleMessage>,
     pub balancer_metering_sender: Sender<BalancerMeteringMessage>,
     pub balancer_metering_receiver: Receiver<BalancerMeteringMessage>,
+    pub balancer_site_totals_sender: Sender<BalancerSiteTotalsMessage>,
+    pub balancer_site_totals_receiver: Receiver<BalancerSiteTotalsMessage>,
+    pub balancer_status_sender: Sender<BalancerAssetStatusMessage>,
+    pub balancer_status_receiver: Receiver<BalancerAssetStatusMessage>,
 
     // Modbus ↔ Bevy
     pub modbus_request_sender: Sender<crate::modbus_protocol_plugin::ModbusRequest>,
     pub modbus_request_receiver: Receiver<crate::modbus_protocol_plugin::ModbusRequest>,
     pub modbus_response_sender: Sender<crate::modbus_protocol_plugin::ModbusResponse>,
     pub modbus_response_receiver: Receiver<crate::modbus_protocol_plugin::ModbusResponse>,
+    pub modbus_write_request_sender: Sender<ModbusWriteRequest>,
+    pub modbus_write_request_receiver: Receiver<ModbusWriteRequest>,
+    pub modbus_write_response_sender: Sender<ModbusWriteResponse>,
+    pub modbus_write_response_receiver: Receiver<ModbusWriteResponse>,
 
     // OCPP ↔ Bevy
     pub ocpp_from_asset_sender: Sender<crate::ocpp_protocol_plugin::events::OcppRequestFromAsset>,
     pub ocpp_from_asset_receiver: Receiver<crate::ocpp_protocol_plugin::events::OcppRequestFromAsset>,
     pub ocpp_to_asset_sender: Sender<crate::ocpp_protocol_plugin::events::OcppCommandToAsset>,
     pub ocpp_to_asset_receiver: Receiver<crate::ocpp_protocol_plugin::events::OcppCommandToAsset>,
+    pub ocpp_response_from_asset_sender: Sender<OcppResponseFromAsset>,
+    pub ocpp_response_from_asset_receiver: Receiver<OcppResponseFromAsset>,
+
+    // Site config reloads -> Bevy
+    pub site_config_reload_sender: Sender<String>,
+    pub site_config_reload_receiver: Receiver<String>,
+
+    // Asset registration ↔ Bevy
+    pub asset_registry_command_sender: Sender<EAssetRegistryCommand>,
+    pub asset_registry_command_receiver: Receiver<EAssetRegistryCommand>,
+    pub asset_registry_result_sender: Sender<AssetRegistryResult>,
+    pub asset_registry_result_receiver: Receiver<AssetRegistryResult>,
 }
 
 #[derive(PartialEq, Eq)]
@@ -41,29 +67,48 @@ pub enum AppMode {
     Headless,
 }
 
+/// Build the app from a site config, or every problem found in the config.
 pub fn setup_bevy_app(
     config_json: String,
     mode: AppMode,
     log_receiver: Option<Receiver<String>>,
-) -> (App, AppExternalChannelEnds) {
+) -> Result<(App, AppExternalChannelEnds), Vec<AppError>> {
+    // Parse and validate the SiteConfig before building anything from it
+    let site_config = SiteConfig::from_json(&config_json)?;
     let mut app = App::new();
 
-    // Parse and insert SiteConfig here
-    let site_config: SiteConfig = serde_json::from_str(&config_json)
-        .expect("Invalid site_config.json");
+    if let Some(fallback) = &site_config.balancer_fallback {
+        app.insert_resource(fallback.clone());
+    }
+    app.insert_resource(site_config.metering_export.clone());
+    app.insert_resource(ModbusRegisterMaps::new(site_config.register_maps.clone(), site_config.write_register_maps.clone()));
     app.insert_resource(site_config);
 
     // Balancer channels
     let (balancer_setpoint_sender, balancer_setpoint_receiver) = unbounded::<BalancerSetpointMessage>();
+    let (balancer_setpoint_batch_sender, balancer_setpoint_batch_receiver) = unbounded::<BalancerSetpointBatchMessage>();
+    let (balancer_schedule_sender, balancer_schedule_receiver) = unbounded::<BalancerScheduleMessage>();
     let (balancer_metering_sender, balancer_metering_receiver) = unbounded::<BalancerMeteringMessage>();
+    let (balancer_site_totals_sender, balancer_site_totals_receiver) = unbounded::<BalancerSiteTotalsMessage>();
+    let (balancer_status_sender, balancer_status_receiver) = unbounded::<BalancerAssetStatusMessage>();
 
     // Modbus channels
     let (modbus_request_sender, modbus_request_receiver) = unbounded::<ModbusRequest>();
     let (modbus_response_sender, modbus_response_receiver) = unbounded::<ModbusResponse>();
+    let (modbus_write_request_sender, modbus_write_request_receiver) = unbounded::<ModbusWriteRequest>();
+    let (modbus_write_response_sender, modbus_write_response_receiver) = unbounded::<ModbusWriteResponse>();
 
     // OCPP channels
     let (ocpp_from_asset_sender, ocpp_from_asset_receiver) = unbounded::<OcppRequestFromAsset>();
     let (ocpp_to_asset_sender, ocpp_to_asset_receiver) = unbounded::<OcppCommandToAsset>();
+    let (ocpp_response_from_asset_sender, ocpp_response_from_asset_receiver) = unbounded::<OcppResponseFromAsset>();
+
+    // Site config reload channel
+    let (site_config_reload_sender, site_config_reload_receiver) = unbounded::<String>();
+
+    // Asset registration channels
+    let (asset_registry_command_sender, asset_registry_command_receiver) = unbounded::<EAssetRegistryCommand>();
+    let (asset_registry_result_sender, asset_registry_result_receiver) = unbounded::<AssetRegistryResult>();
 
 
     match mode {
@@ -77,11 +122,18 @@ pub fn setup_bevy_app(
             
             let viz_channels = crate::visualization_plugin::setup_visualization_channels(
                 balancer_setpoint_sender.clone(),
+                balancer_setpoint_batch_sender.clone(),
+                balancer_schedule_sender.clone(),
                 ocpp_from_asset_sender.clone(),
+                ocpp_response_from_asset_sender.clone(),
                 modbus_response_sender.clone(),
+                modbus_write_response_sender.clone(),
                 balancer_metering_receiver.clone(),
+                balancer_site_totals_receiver.clone(),
+                balancer_status_receiver.clone(),
                 ocpp_to_asset_receiver.clone(),
                 modbus_request_receiver.clone(),
+                modbus_write_request_receiver.clone(),
             );
             app.insert_resource(viz_channels);
             if let Some(receiver) = log_receiver {
@@ -98,30 +150,61 @@ pub fn setup_bevy_app(
        .add_plugins(CoreAssetPlugin)
        .add_plugins(AssetTemplatePlugin)
        .add_plugins(BalancerCommsPlugin)
+       .add_plugins(SiteConstraintPlugin)
        .add_plugins(ModbusProtocolPlugin)
        .add_plugins(OcppProtocolPlugin)
 
        // insert only the halves needed by ECS/plugin logic:
        .insert_resource(BalancerSetpointReceiver(balancer_setpoint_receiver.clone()))
+       .insert_resource(BalancerSetpointBatchReceiver(balancer_setpoint_batch_receiver.clone()))
+       .insert_resource(BalancerScheduleReceiver(balancer_schedule_receiver.clone()))
        .insert_resource(BalancerMeteringSender(balancer_metering_sender.clone()))
+       .insert_resource(BalancerSiteTotalsSender(balancer_site_totals_sender.clone()))
+       .insert_resource(BalancerStatusSender(balancer_status_sender.clone()))
        .insert_resource(ModbusRequestChannel(modbus_request_sender.clone()))
        .insert_resource(ModbusResponseChannel(modbus_response_receiver.clone()))
+       .insert_resource(ModbusWriteRequestChannel(modbus_write_request_sender.clone()))
+       .insert_resource(ModbusWriteResponseChannel(modbus_write_response_receiver.clone()))
        .insert_resource(OcppFromAssetChannel(ocpp_from_asset_receiver.clone()))
-       .insert_resource(OcppToAssetChannel(ocpp_to_asset_sender.clone()));
+       .insert_resource(OcppToAssetChannel(ocpp_to_asset_sender.clone()))
+       .insert_resource(OcppResponseFromAssetChannel(ocpp_response_from_asset_receiver.clone()))
+       .insert_resource(SiteConfigReloadReceiver(site_config_reload_receiver.clone()))
+       .insert_resource(AssetRegistryCommandReceiver(asset_registry_command_receiver.clone()))
+       .insert_resource(AssetRegistryResultSender(asset_registry_result_sender.clone()));
 
     let channels = AppExternalChannelEnds {
         balancer_setpoint_sender,
         balancer_setpoint_receiver,
+        balancer_setpoint_batch_sender,
+        balancer_setpoint_batch_receiver,
+        balancer_schedule_sender,
+        balancer_schedule_receiver,
         balancer_metering_sender,
         balancer_metering_receiver,
+        balancer_site_totals_sender,
+        balancer_site_totals_receiver,
+        balancer_status_sender,
+        balancer_status_receiver,
         modbus_request_sender,
         modbus_request_receiver,
         modbus_response_sender,
         modbus_response_receiver,
+        modbus_write_request_sender,
+        modbus_write_request_receiver,
+        modbus_write_response_sender,
+        modbus_write_response_receiver,
         ocpp_from_asset_sender,
         ocpp_from_asset_receiver,
         ocpp_to_asset_sender,
         ocpp_to_asset_receiver,
+        ocpp_response_from_asset_sender,
+        ocpp_response_from_asset_receiver,
+        site_config_reload_sender,
+        site_config_reload_receiver,
+        asset_registry_command_sender,
+        asset_registry_command_receiver,
+        asset_registry_result_sender,
+        asset_registry_result_receiver,
     };
-    (app, channels)
+    Ok((app, channels))
 }
@@ -0,0 +1,288 @@
+// Rewrites the `assets` section of a site config file's text and nothing else, so comments, key order, placeholders
+// and the other sections stay as written.
+
+use std::ops::Range;
+use toml_edit::{Array, ArrayOfTables, DocumentMut, Item, Table, Value};
+use crate::asset_template_plugin::config::AssetInstance;
+use crate::asset_template_plugin::loader::EConfigFormat;
+
+/// An entry of the new `assets` section.
+enum EAssetEntry<'a> {
+    /// The file's entry at this index, kept as written.
+    Written(usize),
+    New(&'a AssetInstance),
+}
+
+/// `text` with its `assets` section replaced by `assets`. `written` holds the file's entries as loaded, placeholders
+/// filled; an asset equal to one of them keeps that entry as written.
+pub(super) fn replace_assets(format: EConfigFormat, text: &str, written: &[Option<AssetInstance>], assets: &[&AssetInstance]) -> Result<String, String> {
+    match format {
+        EConfigFormat::Json => replace_json_assets(text, written, assets),
+        EConfigFormat::Toml => replace_toml_assets(text, written, assets),
+        EConfigFormat::Yaml => Ok(replace_yaml_assets(text, written, assets)),
+    }
+}
+
+/// Each asset as a written entry it is unchanged from, or as a new one. Written entries are only kept when the file
+/// has as many as it loads, e.g. not when one placeholder stands for several entries.
+fn entries<'a>(written_count: usize, written: &[Option<AssetInstance>], assets: &[&'a AssetInstance]) -> Vec<EAssetEntry<'a>> {
+    assets.iter().map(|asset| {
+        let index = (written_count == written.len())
+            .then(|| written.iter().position(|entry| entry.as_ref() == Some(*asset)))
+            .flatten();
+        index.map_or(EAssetEntry::New(asset), EAssetEntry::Written)
+    }).collect()
+}
+
+/// The whitespace that the line holding `at` starts with.
+fn line_indent(text: &str, at: usize) -> &str {
+    let line_start = text[..at].rfind('\n').map_or(0, |newline| newline + 1);
+    let line = &text[line_start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+fn skip_whitespace(text: &str, at: usize) -> usize {
+    text.len() - text[at..].trim_start().len()
+}
+
+fn expect(text: &str, at: usize, expected: &[u8]) -> Result<u8, String> {
+    match text.as_bytes().get(at) {
+        Some(byte) if expected.contains(byte) => Ok(*byte),
+        _ => Err(format!("expected one of {:?} at byte {}", String::from_utf8_lossy(expected), at)),
+    }
+}
+
+fn json_string_end(text: &str, start: usize) -> Result<usize, String> {
+    let bytes = text.as_bytes();
+    let mut at = start + 1;
+    while at < bytes.len() {
+        match bytes[at] {
+            b'\\' => at += 2,
+            b'"' => return Ok(at + 1),
+            _ => at += 1,
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+/// The end of the JSON value starting at `start`. An unquoted `${...}` placeholder counts as a value.
+fn json_value_end(text: &str, start: usize) -> Result<usize, String> {
+    let bytes = text.as_bytes();
+    match bytes.get(start) {
+        Some(b'"') => json_string_end(text, start),
+        Some(b'{' | b'[') => {
+            let (mut at, mut depth) = (start, 0);
+            while at < bytes.len() {
+                match bytes[at] {
+                    b'"' => {
+                        at = json_string_end(text, at)?;
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return Ok(at + 1);
+                        }
+                    }
+                    _ => {}
+                }
+                at += 1;
+            }
+            Err("unterminated object or array".to_string())
+        }
+        Some(b'$') if text[start..].starts_with("${") => {
+            text[start..].find('}').map(|len| start + len + 1).ok_or_else(|| "unterminated placeholder".to_string())
+        }
+        Some(_) => Ok(text[start..].find(|c: char| c == ',' || c == '}' || c == ']' || c.is_whitespace()).map_or(text.len(), |len| start + len)),
+        None => Err("unexpected end of file".to_string()),
+    }
+}
+
+/// Where the top-level `assets` array of a JSON document is, with its entries, and where the document's object closes.
+struct JsonAssets {
+    array: Option<Range<usize>>,
+    entries: Vec<Range<usize>>,
+    object_end: usize,
+}
+
+fn find_json_assets(text: &str) -> Result<JsonAssets, String> {
+    let mut found = JsonAssets { array: None, entries: Vec::new(), object_end: 0 };
+    let mut at = skip_whitespace(text, 0);
+    expect(text, at, b"{")?;
+    at = skip_whitespace(text, at + 1);
+    if text[at..].starts_with('}') {
+        found.object_end = at;
+        return Ok(found);
+    }
+    loop {
+        expect(text, at, b"\"")?;
+        let key_end = json_string_end(text, at)?;
+        let key: String = serde_json::from_str(&text[at..key_end]).map_err(|e| e.to_string())?;
+        at = skip_whitespace(text, key_end);
+        expect(text, at, b":")?;
+        let value_start = skip_whitespace(text, at + 1);
+        let value_end = json_value_end(text, value_start)?;
+        if key == "assets" {
+            expect(text, value_start, b"[")?;
+            found.array = Some(value_start..value_end);
+            let mut entry_start = skip_whitespace(text, value_start + 1);
+            while entry_start < value_end - 1 {
+                let entry_end = json_value_end(text, entry_start)?;
+                found.entries.push(entry_start..entry_end);
+                let after = skip_whitespace(text, entry_end);
+                entry_start = if expect(text, after, b",]")? == b',' { skip_whitespace(text, after + 1) } else { after };
+            }
+        }
+        at = skip_whitespace(text, value_end);
+        if expect(text, at, b",}")? == b'}' {
+            found.object_end = at;
+            return Ok(found);
+        }
+        at = skip_whitespace(text, at + 1);
+    }
+}
+
+fn replace_json_assets(text: &str, written: &[Option<AssetInstance>], assets: &[&AssetInstance]) -> Result<String, String> {
+    let found = find_json_assets(text)?;
+    let base_indent = match &found.array {
+        Some(array) => line_indent(text, array.start).to_string(),
+        None => "    ".to_string(),
+    };
+    let indent = format!("{base_indent}    ");
+    let mut entry_texts = Vec::new();
+    for entry in entries(found.entries.len(), written, assets) {
+        entry_texts.push(match entry {
+            EAssetEntry::Written(index) => text[found.entries[index].clone()].to_string(),
+            EAssetEntry::New(asset) => {
+                let mut json = Vec::new();
+                let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+                serde::Serialize::serialize(asset, &mut serde_json::Serializer::with_formatter(&mut json, formatter)).map_err(|e| e.to_string())?;
+                String::from_utf8(json).expect("JSON is UTF-8").replace('\n', &format!("\n{indent}"))
+            }
+        });
+    }
+    let array = if entry_texts.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{indent}{}\n{base_indent}]", entry_texts.join(&format!(",\n{indent}")))
+    };
+
+    Ok(match found.array {
+        Some(range) => format!("{}{}{}", &text[..range.start], array, &text[range.end..]),
+        None => {
+            let before = text[..found.object_end].trim_end();
+            let comma = if before.ends_with('{') { "" } else { "," };
+            format!("{before}{comma}\n{base_indent}\"assets\": {array}\n{}", &text[found.object_end..])
+        }
+    })
+}
+
+fn replace_toml_assets(text: &str, written: &[Option<AssetInstance>], assets: &[&AssetInstance]) -> Result<String, String> {
+    let mut document: DocumentMut = text.parse()
+        .map_err(|e| format!("{e}; to save to a TOML file, its placeholders must be inside strings"))?;
+    let new_table = |asset: &AssetInstance| toml_edit::ser::to_document(asset).map(DocumentMut::into_table).map_err(|e| e.to_string());
+
+    let section = match document.remove("assets") {
+        // An inline `assets = [ ... ]` array stays inline
+        Some(Item::Value(Value::Array(array))) => {
+            let mut new_array = Array::new();
+            for entry in entries(array.len(), written, assets) {
+                match entry {
+                    EAssetEntry::Written(index) => new_array.push_formatted(array.get(index).expect("index within array").clone()),
+                    EAssetEntry::New(asset) => new_array.push(new_table(asset)?.into_inline_table()),
+                }
+            }
+            Item::Value(Value::Array(new_array))
+        }
+        Some(Item::ArrayOfTables(tables)) => {
+            let mut new_tables = ArrayOfTables::new();
+            for entry in entries(tables.len(), written, assets) {
+                new_tables.push(match entry {
+                    EAssetEntry::Written(index) => tables.get(index).expect("index within tables").clone(),
+                    EAssetEntry::New(asset) => new_table(asset)?,
+                });
+            }
+            Item::ArrayOfTables(new_tables)
+        }
+        None => Item::ArrayOfTables(assets.iter().map(|asset| new_table(asset)).collect::<Result<Vec<Table>, String>>()?.into_iter().collect()),
+        Some(_) => return Err("assets must be an array of tables".to_string()),
+    };
+    document.insert("assets", section);
+    Ok(document.to_string())
+}
+
+/// Where the top-level `assets:` of a YAML document is: the lines from its key to its last entry, and each entry of a
+/// block sequence. A flow sequence such as `[ ... ]` has no entries to keep.
+struct YamlAssets {
+    section: Range<usize>,
+    entries: Option<Vec<Range<usize>>>,
+}
+
+fn find_yaml_assets(text: &str) -> Option<YamlAssets> {
+    let mut lines = text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line))
+    });
+    let (section_start, key_line) = lines.by_ref().find(|(_, line)| {
+        line.strip_prefix("assets:").is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t', '\r', '\n', '#']))
+    })?;
+    let value = key_line["assets:".len()..].split(" #").next().unwrap_or_default().trim();
+    let mut section_end = section_start + key_line.len();
+    let mut items = Vec::new();
+    let mut item_indent = None;
+    for (start, line) in lines {
+        let content = line.trim_start_matches([' ', '\t']);
+        let is_blank_or_comment = content.trim().is_empty() || content.starts_with('#');
+        if !is_blank_or_comment && !line.starts_with([' ', '\t', '-']) {
+            break;
+        }
+        if is_blank_or_comment {
+            continue;
+        }
+        section_end = start + line.len();
+        let indent = line.len() - content.len();
+        if (content.starts_with("- ") || content.trim_end() == "-") && *item_indent.get_or_insert(indent) == indent {
+            items.push(start);
+        }
+    }
+    let entries = match value {
+        "" => Some(items.iter().enumerate().map(|(i, &start)| start..items.get(i + 1).copied().unwrap_or(section_end)).collect()),
+        "[]" => Some(Vec::new()),
+        _ => None,
+    };
+    Some(YamlAssets { section: section_start..section_end, entries })
+}
+
+fn replace_yaml_assets(text: &str, written: &[Option<AssetInstance>], assets: &[&AssetInstance]) -> String {
+    let found = find_yaml_assets(text);
+    let written_entries = found.as_ref().and_then(|found| found.entries.clone()).unwrap_or_default();
+    let indent = written_entries.first().map_or("  ", |entry| line_indent(text, entry.start));
+    let mut section = String::from("assets:\n");
+    for entry in entries(written_entries.len(), written, assets) {
+        match entry {
+            EAssetEntry::Written(index) => {
+                section.push_str(&text[written_entries[index].clone()]);
+                if !section.ends_with('\n') {
+                    section.push('\n');
+                }
+            }
+            EAssetEntry::New(asset) => {
+                let yaml = serde_yaml::to_string(asset).expect("assets serialize to YAML");
+                for (i, line) in yaml.lines().enumerate() {
+                    section.push_str(&format!("{indent}{}{line}\n", if i == 0 { "- " } else { "  " }));
+                }
+            }
+        }
+    }
+    if assets.is_empty() {
+        section = "assets: []\n".to_string();
+    }
+
+    match found {
+        Some(found) => format!("{}{}{}", &text[..found.section.start], section, &text[found.section.end..]),
+        None if text.is_empty() || text.ends_with('\n') => format!("{text}\n{section}"),
+        None => format!("{text}\n\n{section}"),
+    }
+}
@@ -1,32 +1,98 @@
 // a strongly typed configuration schema for asset templates and instances
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use crate::common::types::EAssetType;
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct AssetTemplate {
-    pub asset_type: EAssetType,
-    #[serde(rename = "components")]
+    /// Parent template whose components this one inherits; its own entries replace the parent's of the same type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// May be left to the parent template.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset_type: Option<EAssetType>,
+    #[serde(rename = "components", default)]
     pub component_configs: Vec<ComponentConfig>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct AssetInstance {
     pub external_id: String,
     pub template_id: String,
     pub instance_components: Vec<ComponentConfig>,
+    /// Individual fields to change in the asset's components, keyed by component type,
+    /// e.g. `"charger_electrical_config": { "active_phase_count": 1 }`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ComponentConfig {
     AssetInfo { make: String, model: String },
     ChargerElectricalConfig { nominal_voltage_ln: f32, active_phase_count: u8 },
+    Connectors { count: u32, max_current_a: f32, phases: u8, connector_type: String },
     OcppConfig { version: String, charge_point_id: String },
-    OcppProfileBehavior { rate_unit: String, profile_phases_in_ocpp_message: u8 },
+    OcppProfileBehavior {
+        rate_unit: String,
+        profile_phases_in_ocpp_message: u8,
+        #[serde(default)]
+        send_schedules: bool,
+    },
     AlfenSpecificConfig { default_tx_profile_power_watts: f32 },
     MeteringSource { source_type: String, details: serde_json::Value },
-    ModbusControlConfig { ip: String, port: u16, unit_id: u8 },
+    FallbackWeight { weight: f32 },
+    SafeSetpoint { power_kw: f32 },
+    GridConnectionLimits { import_capacity_kw: f32, export_capacity_kw: f32 },
+    ModbusControlConfig { ip: String, port: u16, unit_id: u8, write_register_map_key: String },
+}
+
+impl ComponentConfig {
+    /// The `type` tag the entry is written with in the site config.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ComponentConfig::AssetInfo { .. } => "asset_info",
+            ComponentConfig::ChargerElectricalConfig { .. } => "charger_electrical_config",
+            ComponentConfig::Connectors { .. } => "connectors",
+            ComponentConfig::OcppConfig { .. } => "ocpp_config",
+            ComponentConfig::OcppProfileBehavior { .. } => "ocpp_profile_behavior",
+            ComponentConfig::AlfenSpecificConfig { .. } => "alfen_specific_config",
+            ComponentConfig::MeteringSource { .. } => "metering_source",
+            ComponentConfig::FallbackWeight { .. } => "fallback_weight",
+            ComponentConfig::SafeSetpoint { .. } => "safe_setpoint",
+            ComponentConfig::GridConnectionLimits { .. } => "grid_connection_limits",
+            ComponentConfig::ModbusControlConfig { .. } => "modbus_control_config",
+        }
+    }
+
+    /// Whether the entry may appear on an asset of `asset_type`.
+    pub fn allowed_on(&self, asset_type: EAssetType) -> bool {
+        match self {
+            ComponentConfig::ChargerElectricalConfig { .. }
+            | ComponentConfig::Connectors { .. }
+            | ComponentConfig::OcppConfig { .. }
+            | ComponentConfig::OcppProfileBehavior { .. }
+            | ComponentConfig::AlfenSpecificConfig { .. } => asset_type == EAssetType::Charger,
+            ComponentConfig::ModbusControlConfig { .. } => asset_type == EAssetType::Battery,
+            ComponentConfig::GridConnectionLimits { .. } => asset_type == EAssetType::GridConnection,
+            ComponentConfig::AssetInfo { .. }
+            | ComponentConfig::MeteringSource { .. }
+            | ComponentConfig::FallbackWeight { .. }
+            | ComponentConfig::SafeSetpoint { .. } => true,
+        }
+    }
+
+    /// Other entries the asset must also have for this one to take effect.
+    pub fn requires(&self) -> &'static [&'static str] {
+        match self {
+            // Charging profile limits are converted using the charger's voltage and phase count
+            ComponentConfig::OcppProfileBehavior { .. } => &["charger_electrical_config"],
+            // The Alfen init sequence is sent over the charger's OCPP connection
+            ComponentConfig::AlfenSpecificConfig { .. } => &["ocpp_config"],
+            _ => &[],
+        }
+    }
 }
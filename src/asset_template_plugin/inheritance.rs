@@ -0,0 +1,168 @@
+// Resolves template inheritance and instance field overrides into the flat list of components an asset is spawned with.
+
+use serde_json::Value;
+use crate::asset_template_plugin::SiteConfig;
+use crate::asset_template_plugin::config::{AssetTemplate, ComponentConfig};
+use crate::common::error::AppError;
+use crate::common::types::EAssetType;
+
+/// An asset as it is spawned: its template chain flattened, with its own entries and field overrides applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedAsset {
+    pub asset_type: EAssetType,
+    /// One entry per component type, with the JSON path of the entry that set it last.
+    pub components: Vec<(String, ComponentConfig)>,
+}
+
+impl ResolvedAsset {
+    pub fn component_configs(&self) -> impl Iterator<Item = &ComponentConfig> {
+        self.components.iter().map(|(_, cfg)| cfg)
+    }
+
+    /// Add `cfg`, replacing any entry of the same type in place.
+    fn set(&mut self, path: String, cfg: ComponentConfig) {
+        match self.components.iter_mut().find(|(_, existing)| existing.name() == cfg.name()) {
+            Some(existing) => *existing = (path, cfg),
+            None => self.components.push((path, cfg)),
+        }
+    }
+}
+
+impl SiteConfig {
+    /// `template_id`, which must exist, and the templates it extends, root first.
+    pub(super) fn template_chain(&self, template_id: &str) -> Result<Vec<(&str, &AssetTemplate)>, AppError> {
+        let mut chain: Vec<(&str, &AssetTemplate)> = Vec::new();
+        let mut current = template_id;
+        loop {
+            let template = match self.asset_templates.get_key_value(current) {
+                Some((id, template)) => (id.as_str(), template),
+                None => {
+                    let (child_id, _) = chain.last().expect("the first template is known to exist");
+                    return Err(AppError::UnknownParentTemplate {
+                        template_id: child_id.to_string(),
+                        path: format!("asset_templates.{child_id}.extends"),
+                        parent_id: current.to_string(),
+                    });
+                }
+            };
+            if chain.iter().any(|(id, _)| *id == current) {
+                let (child_id, _) = chain.last().expect("a cycle has at least one link");
+                let cycle: Vec<&str> = chain.iter().map(|(id, _)| *id).chain([current]).collect();
+                return Err(AppError::TemplateCycle {
+                    template_id: template_id.to_string(),
+                    path: format!("asset_templates.{child_id}.extends"),
+                    cycle: cycle.join(" -> "),
+                });
+            }
+            chain.push(template);
+            match &template.1.extends {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+
+    /// Inheritance problems with every template, in template id order.
+    pub fn validate_templates(&self) -> Vec<AppError> {
+        let mut template_ids: Vec<&String> = self.asset_templates.keys().collect();
+        template_ids.sort();
+        template_ids.into_iter()
+            .filter_map(|template_id| match self.template_chain(template_id) {
+                Err(e) => Some(e),
+                Ok(chain) if chain.iter().all(|(_, template)| template.asset_type.is_none()) => Some(AppError::MissingAssetType {
+                    template_id: template_id.clone(),
+                    path: format!("asset_templates.{template_id}.asset_type"),
+                }),
+                Ok(_) => None,
+            })
+            .collect()
+    }
+
+    /// `assets[index]` with its template chain, own components and overrides applied, in that order.
+    pub fn resolve_asset(&self, index: usize) -> Result<ResolvedAsset, Vec<AppError>> {
+        let instance = &self.assets[index];
+        let asset_id = &instance.external_id;
+        let template_id = &instance.template_id;
+        if !self.asset_templates.contains_key(template_id) {
+            return Err(vec![AppError::UnknownTemplate {
+                asset_id: asset_id.clone(),
+                template_id: template_id.clone(),
+                path: format!("assets[{index}].template_id"),
+            }]);
+        }
+        let chain = self.template_chain(template_id).map_err(|e| vec![e])?;
+        let Some(asset_type) = chain.iter().rev().find_map(|(_, template)| template.asset_type) else {
+            return Err(vec![AppError::MissingAssetType {
+                template_id: template_id.clone(),
+                path: format!("asset_templates.{template_id}.asset_type"),
+            }]);
+        };
+
+        let mut resolved = ResolvedAsset { asset_type, components: Vec::new() };
+        for (id, template) in &chain {
+            for (i, cfg) in template.component_configs.iter().enumerate() {
+                resolved.set(format!("asset_templates.{id}.components[{i}]"), cfg.clone());
+            }
+        }
+        for (i, cfg) in instance.instance_components.iter().enumerate() {
+            resolved.set(format!("assets[{index}].instance_components[{i}]"), cfg.clone());
+        }
+
+        let mut errors = Vec::new();
+        for (component, fields) in &instance.overrides {
+            let path = format!("assets[{index}].overrides.{component}");
+            let Some(cfg) = resolved.component_configs().find(|cfg| cfg.name() == component) else {
+                errors.push(AppError::UnknownOverride { asset_id: asset_id.clone(), template_id: template_id.clone(), path, component: component.clone() });
+                continue;
+            };
+            match with_overridden_fields(cfg, fields) {
+                Ok(overridden) => resolved.set(path, overridden),
+                Err(reason) => errors.push(AppError::InvalidComponent {
+                    asset_id: asset_id.clone(),
+                    template_id: template_id.clone(),
+                    path,
+                    component: cfg.name(),
+                    reason,
+                }),
+            }
+        }
+        if errors.is_empty() { Ok(resolved) } else { Err(errors) }
+    }
+}
+
+/// `cfg` with `fields` merged in; nested objects such as metering `details` are merged field by field too.
+fn with_overridden_fields(cfg: &ComponentConfig, fields: &Value) -> Result<ComponentConfig, String> {
+    let Value::Object(fields) = fields else {
+        return Err("overrides must be an object of fields".to_string());
+    };
+    let mut merged = serde_json::to_value(cfg).map_err(|e| e.to_string())?;
+    let Value::Object(existing) = &mut merged else {
+        unreachable!("components serialize as objects");
+    };
+    for (field, value) in fields {
+        match existing.get_mut(field) {
+            _ if field == "type" => return Err("the component type cannot be overridden".to_string()),
+            Some(current) => merge_value(current, value),
+            None => return Err(format!("unknown field '{field}'")),
+        }
+    }
+    serde_json::from_value(merged).map_err(|e| e.to_string())
+}
+
+fn merge_value(target: &mut Value, value: &Value) {
+    match (target, value) {
+        (Value::Object(target), Value::Object(fields)) => {
+            for (field, value) in fields {
+                match target.get_mut(field) {
+                    Some(current) => merge_value(current, value),
+                    None => {
+                        target.insert(field.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value.clone(),
+    }
+}
@@ -0,0 +1,202 @@
+// Reads site config files: JSON, TOML or YAML by extension, with `include`d files merged in
+// and `${VAR}` / `${VAR:-default}` placeholders filled from the environment before parsing.
+
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use crate::asset_template_plugin::asset_section::replace_assets;
+use crate::asset_template_plugin::config::AssetInstance;
+use crate::common::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl EConfigFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(EConfigFormat::Json),
+            "toml" => Some(EConfigFormat::Toml),
+            "yaml" | "yml" => Some(EConfigFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    fn parse(self, text: &str) -> Result<Value, String> {
+        let value: Value = match self {
+            EConfigFormat::Json => serde_json::from_str(text).map_err(|e| e.to_string())?,
+            EConfigFormat::Toml => toml::from_str(text).map_err(|e| e.to_string())?,
+            EConfigFormat::Yaml => serde_yaml::from_str(text).map_err(|e| e.to_string())?,
+        };
+        if value.is_object() { Ok(value) } else { Err("the top level must be a table of sections".to_string()) }
+    }
+}
+
+/// A site config read from disk, as the JSON that `setup_bevy_app` and reloads take.
+#[derive(Debug, Clone)]
+pub struct LoadedSiteConfig {
+    pub json: String,
+    /// The file and every file it includes, to watch for changes.
+    pub files: Vec<PathBuf>,
+}
+
+/// Read the site config at `path` with placeholders taken from the process environment.
+pub fn load_site_config(path: &Path) -> Result<LoadedSiteConfig, Vec<AppError>> {
+    load_site_config_with_env(path, &|name| std::env::var(name).ok())
+}
+
+/// Read the site config at `path`, looking placeholders up with `env`.
+pub fn load_site_config_with_env(path: &Path, env: &dyn Fn(&str) -> Option<String>) -> Result<LoadedSiteConfig, Vec<AppError>> {
+    let mut files = Vec::new();
+    let value = load_value(path, env, &mut Vec::new(), &mut files)?;
+    Ok(LoadedSiteConfig { json: value.to_string(), files })
+}
+
+/// Replace the `assets` section of the site config file at `path` with `assets`, in the file's own format. Only that
+/// section is rewritten: comments, key order and the other sections stay as written. Assets that come from included
+/// files are left to those files, and assets that did not change keep their entry as written, placeholders included.
+pub fn save_site_assets(path: &Path, assets: &[AssetInstance]) -> Result<(), Vec<AppError>> {
+    let display = path.display().to_string();
+    let unreadable = |e: std::io::Error| vec![AppError::ConfigFileUnreadable { path: display.clone(), reason: e.to_string() }];
+    let invalid = |reason: String| vec![AppError::ConfigFileInvalid { path: display.clone(), reason }];
+    let format = EConfigFormat::from_path(path).ok_or_else(|| vec![AppError::UnsupportedConfigFormat { path: display.clone() }])?;
+    let text = std::fs::read_to_string(path).map_err(unreadable)?;
+    let env = |name: &str| std::env::var(name).ok();
+    let interpolated = interpolate_env(&text, &env)
+        .map_err(|names| names.into_iter().map(|name| AppError::UndefinedEnvVar { path: display.clone(), name }).collect::<Vec<_>>())?;
+    let interpolated = format.parse(&interpolated).map_err(invalid)?;
+
+    let included_ids = included_asset_ids(path)?;
+    let written: Vec<Option<AssetInstance>> = asset_entries(&interpolated).map(|entry| serde_json::from_value(entry.clone()).ok()).collect();
+    let own_assets: Vec<&AssetInstance> = assets.iter().filter(|asset| !included_ids.contains(&asset.external_id)).collect();
+    let text = replace_assets(format, &text, &written, &own_assets).map_err(invalid)?;
+
+    // Write then rename so the file watcher never reads a half-written config
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    std::fs::write(&tmp_path, text).map_err(unreadable)?;
+    std::fs::rename(&tmp_path, path).map_err(unreadable)
+}
+
+/// The `external_id`s of the assets that the site config file at `path` takes from the files it includes.
+pub fn included_asset_ids(path: &Path) -> Result<HashSet<String>, Vec<AppError>> {
+    let display = path.display().to_string();
+    let unreadable = |e: std::io::Error| vec![AppError::ConfigFileUnreadable { path: display.clone(), reason: e.to_string() }];
+    let invalid = |reason: String| vec![AppError::ConfigFileInvalid { path: display.clone(), reason }];
+    let format = EConfigFormat::from_path(path).ok_or_else(|| vec![AppError::UnsupportedConfigFormat { path: display.clone() }])?;
+    let text = std::fs::read_to_string(path).map_err(unreadable)?;
+    let env = |name: &str| std::env::var(name).ok();
+    let text = interpolate_env(&text, &env)
+        .map_err(|names| names.into_iter().map(|name| AppError::UndefinedEnvVar { path: display.clone(), name }).collect::<Vec<_>>())?;
+    let document = format.parse(&text).map_err(invalid)?;
+
+    let canonical = path.canonicalize().map_err(unreadable)?;
+    let mut included_ids = HashSet::new();
+    for include in include_paths(&document).map_err(invalid)? {
+        let included = load_value(&sibling(path, &include), &env, &mut vec![canonical.clone()], &mut Vec::new())?;
+        included_ids.extend(asset_entries(&included).filter_map(|entry| Some(entry.get("external_id")?.as_str()?.to_string())));
+    }
+    Ok(included_ids)
+}
+
+fn asset_entries(document: &Value) -> impl Iterator<Item = &Value> {
+    document.get("assets").and_then(Value::as_array).into_iter().flatten()
+}
+
+/// `path` with its placeholders filled and its includes merged in; `stack` holds the files including it.
+fn load_value(path: &Path, env: &dyn Fn(&str) -> Option<String>, stack: &mut Vec<PathBuf>, files: &mut Vec<PathBuf>) -> Result<Value, Vec<AppError>> {
+    let display = path.display().to_string();
+    let unreadable = |e: std::io::Error| vec![AppError::ConfigFileUnreadable { path: display.clone(), reason: e.to_string() }];
+    let canonical = path.canonicalize().map_err(unreadable)?;
+    if let Some(start) = stack.iter().position(|included| *included == canonical) {
+        let cycle: Vec<String> = stack[start..].iter().chain([&canonical]).map(|file| file.display().to_string()).collect();
+        return Err(vec![AppError::IncludeCycle { path: display, cycle: cycle.join(" -> ") }]);
+    }
+    let format = EConfigFormat::from_path(path).ok_or_else(|| vec![AppError::UnsupportedConfigFormat { path: display.clone() }])?;
+    let text = std::fs::read_to_string(path).map_err(unreadable)?;
+    let text = interpolate_env(&text, env)
+        .map_err(|names| names.into_iter().map(|name| AppError::UndefinedEnvVar { path: display.clone(), name }).collect::<Vec<_>>())?;
+    let invalid = |reason: String| vec![AppError::ConfigFileInvalid { path: display.clone(), reason }];
+    let mut value = format.parse(&text).map_err(invalid)?;
+    files.push(path.to_path_buf());
+
+    let includes = include_paths(&value).map_err(invalid)?;
+    value.as_object_mut().expect("parsed configs are objects").remove("include");
+    stack.push(canonical);
+    let mut merged = Value::Object(Map::new());
+    let mut errors = Vec::new();
+    for include in includes {
+        match load_value(&sibling(path, &include), env, stack, files) {
+            Ok(included) => merge_sections(&mut merged, included),
+            Err(include_errors) => errors.extend(include_errors),
+        }
+    }
+    stack.pop();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    // The including file's own sections win over anything it includes
+    merge_sections(&mut merged, value);
+    Ok(merged)
+}
+
+/// The `include` directive: one path or a list of them, relative to the including file.
+fn include_paths(document: &Value) -> Result<Vec<String>, String> {
+    match document.get("include") {
+        None => Ok(Vec::new()),
+        Some(Value::String(include)) => Ok(vec![include.clone()]),
+        Some(Value::Array(includes)) => includes.iter()
+            .map(|include| include.as_str().map(str::to_string).ok_or_else(|| "include entries must be paths".to_string()))
+            .collect(),
+        Some(_) => Err("include must be a path or a list of paths".to_string()),
+    }
+}
+
+fn sibling(path: &Path, relative: &str) -> PathBuf {
+    path.parent().unwrap_or(Path::new("")).join(relative)
+}
+
+/// Merge `overlay`'s sections into `base`: keyed sections such as `asset_templates` merge by key with `overlay` winning,
+/// lists such as `assets` are appended, and anything else is replaced.
+fn merge_sections(base: &mut Value, overlay: Value) {
+    let (Value::Object(base), Value::Object(overlay)) = (base, overlay) else {
+        unreachable!("configs are objects");
+    };
+    for (section, value) in overlay {
+        match (base.get_mut(&section), value) {
+            (Some(Value::Object(existing)), Value::Object(entries)) => existing.extend(entries),
+            (Some(Value::Array(existing)), Value::Array(entries)) => existing.extend(entries),
+            (_, value) => {
+                base.insert(section, value);
+            }
+        }
+    }
+}
+
+/// `text` with `${NAME}` and `${NAME:-default}` replaced, or the names that are neither set nor defaulted.
+fn interpolate_env(text: &str, env: &dyn Fn(&str) -> Option<String>) -> Result<String, Vec<String>> {
+    let mut result = String::with_capacity(text.len());
+    let mut undefined = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let placeholder = &rest[start + 2..start + 2 + len];
+        let (name, default) = match placeholder.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (placeholder, None),
+        };
+        match env(name).or_else(|| default.map(str::to_string)) {
+            Some(value) => result.push_str(&value),
+            None => undefined.push(name.to_string()),
+        }
+        rest = &rest[start + 2 + len + 1..];
+    }
+    result.push_str(rest);
+    if undefined.is_empty() { Ok(result) } else { Err(undefined) }
+}
@@ -3,8 +3,18 @@ use crate::common::external_id_map::ExternalIdMap;
 pub mod config;
 pub mod systems;
 pub mod resources;
+pub mod validation;
+pub mod inheritance;
+pub mod reload;
+pub mod registry;
+pub mod loader;
+mod asset_section;
 
-pub use resources::SiteConfig;
+pub use inheritance::ResolvedAsset;
+pub use resources::{SiteConfig, SiteConfigReloadReceiver, AssetRegistryCommandReceiver, AssetRegistryResultSender, AssetSaveWorker, SiteConfigPersistPath};
+pub use reload::{reload_site_config_system, watch_site_config_file};
+pub use loader::{load_site_config, load_site_config_with_env, save_site_assets, EConfigFormat, LoadedSiteConfig};
+pub use registry::{asset_registry_system, AssetRegistryResult, EAssetRegistryCommand};
 pub use systems::spawn_assets_from_config_system;
 
 #[derive(Resource)]
@@ -16,6 +26,8 @@ impl Plugin for AssetTemplatePlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ExternalIdMap::default())
            .insert_resource(TotalAssets(0))
-           .add_systems(Startup, spawn_assets_from_config_system);
+           .insert_resource(registry::spawn_asset_save_worker())
+           .add_systems(Startup, spawn_assets_from_config_system)
+           .add_systems(Update, (reload_site_config_system, asset_registry_system).chain());
     }
 }
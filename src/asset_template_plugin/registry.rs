@@ -0,0 +1,181 @@
+// Runtime asset registration: commissioning tools add, remove and update assets over a channel instead of editing the site config.
+
+use bevy::prelude::*;
+use crossbeam_channel::{unbounded, TryRecvError};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use crate::asset_template_plugin::{SiteConfig, TotalAssets};
+use crate::asset_template_plugin::config::{AssetInstance, ComponentConfig};
+use crate::asset_template_plugin::loader::{included_asset_ids, save_site_assets};
+use crate::asset_template_plugin::reload::switch_site_config;
+use crate::asset_template_plugin::resources::{AssetRegistryCommandReceiver, AssetRegistryResultSender, AssetSaveRequest, AssetSaveWorker, SiteConfigPersistPath};
+use crate::common::error::AppError;
+use crate::common::external_id_map::ExternalIdMap;
+use crate::modbus_protocol_plugin::ModbusRegisterMaps;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "command")]
+pub enum EAssetRegistryCommand {
+    /// Spawn a new asset from a template, as if it had been listed in the site config's `assets`.
+    AddAsset { external_id: String, template_id: String, instance_components: Vec<ComponentConfig> },
+    /// Despawn an asset and drop it from the site config.
+    RemoveAsset { external_id: String },
+    /// Replace an asset's own components; entries that did not change are left alone.
+    UpdateAssetComponents { external_id: String, instance_components: Vec<ComponentConfig> },
+}
+
+impl EAssetRegistryCommand {
+    pub fn external_id(&self) -> &str {
+        match self {
+            EAssetRegistryCommand::AddAsset { external_id, .. }
+            | EAssetRegistryCommand::RemoveAsset { external_id }
+            | EAssetRegistryCommand::UpdateAssetComponents { external_id, .. } => external_id,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AssetRegistryResult {
+    pub external_id: String,
+    /// Why the command was rejected; a rejected command changes nothing.
+    pub result: Result<(), Vec<AppError>>,
+}
+
+/// The site config with `command` applied, checked so that its asset can be spawned.
+fn config_with_command(config: &SiteConfig, command: &EAssetRegistryCommand) -> Result<SiteConfig, Vec<AppError>> {
+    let mut new_config = config.clone();
+    let position = new_config.assets.iter().position(|instance| instance.external_id == command.external_id());
+    let changed_index = match (command, position) {
+        (EAssetRegistryCommand::AddAsset { external_id, template_id, instance_components }, _) => {
+            new_config.assets.push(AssetInstance {
+                external_id: external_id.clone(),
+                template_id: template_id.clone(),
+                instance_components: instance_components.clone(),
+                overrides: Default::default(),
+            });
+            new_config.assets.len() - 1
+        }
+        (EAssetRegistryCommand::RemoveAsset { external_id }, Some(index)) => {
+            new_config.assets.remove(index);
+            for members in new_config.asset_groups.values_mut() {
+                members.retain(|member| member != external_id);
+            }
+            return Ok(new_config);
+        }
+        (EAssetRegistryCommand::UpdateAssetComponents { instance_components, .. }, Some(index)) => {
+            new_config.assets[index].instance_components = instance_components.clone();
+            index
+        }
+        (_, None) => return Err(vec![AppError::UnknownAsset { asset_id: command.external_id().to_string() }]),
+    };
+    let errors = new_config.spawn_errors(changed_index);
+    if errors.is_empty() { Ok(new_config) } else { Err(errors) }
+}
+
+/// Write `request` to its file; a change to an asset from an included file is refused, as the included file is not
+/// written and the change would be lost on the next reload.
+fn save_assets(request: &AssetSaveRequest) -> Result<(), Vec<AppError>> {
+    let edits_existing = !matches!(request.command, EAssetRegistryCommand::AddAsset { .. });
+    if edits_existing && included_asset_ids(&request.path)?.contains(request.command.external_id()) {
+        return Err(vec![AppError::AssetInIncludedFile {
+            asset_id: request.command.external_id().to_string(),
+            path: request.path.display().to_string(),
+        }]);
+    }
+    save_site_assets(&request.path, &request.assets)
+}
+
+/// Start the thread that saves assets for `asset_registry_system`; it stops once the worker resource is dropped.
+pub(super) fn spawn_asset_save_worker() -> AssetSaveWorker {
+    let (requests, request_receiver) = unbounded::<AssetSaveRequest>();
+    let (result_sender, results) = unbounded();
+    std::thread::Builder::new()
+        .name("asset-save-worker".to_string())
+        .spawn(move || {
+            for request in request_receiver {
+                if result_sender.send(save_assets(&request)).is_err() {
+                    return;
+                }
+            }
+        })
+        .expect("Failed to spawn asset save worker thread");
+    AssetSaveWorker { requests, results }
+}
+
+/// A command whose change is being saved, applied once the save succeeds.
+pub struct PendingSave {
+    external_id: String,
+    path: PathBuf,
+    new_config: SiteConfig,
+}
+
+fn save_worker_stopped(path: &Path) -> Vec<AppError> {
+    vec![AppError::ConfigFileUnreadable { path: path.display().to_string(), reason: "the asset save worker has stopped".to_string() }]
+}
+
+fn send_result(result_sender: &AssetRegistryResultSender, external_id: &str, result: Result<(), Vec<AppError>>) {
+    if let Err(errors) = &result {
+        for e in errors {
+            warn!("Asset registry command for '{}' rejected: {}", external_id, e);
+        }
+    }
+    if result_sender.0.send(AssetRegistryResult { external_id: external_id.to_string(), result }).is_err() {
+        warn!("Asset registry result channel closed");
+    }
+}
+
+/// Apply asset registry commands in order. With a `SiteConfigPersistPath`, a command's change is saved first and only
+/// applied once the save succeeds; until then the commands after it wait, so each is checked against the config it
+/// will change.
+#[allow(clippy::too_many_arguments)]
+pub fn asset_registry_system(
+    mut commands: Commands,
+    command_receiver: Res<AssetRegistryCommandReceiver>,
+    result_sender: Res<AssetRegistryResultSender>,
+    persist_path: Option<Res<SiteConfigPersistPath>>,
+    save_worker: Res<AssetSaveWorker>,
+    mut pending_save: Local<Option<PendingSave>>,
+    mut config: ResMut<SiteConfig>,
+    mut id_map: ResMut<ExternalIdMap>,
+    mut total_assets: ResMut<TotalAssets>,
+    register_maps: Res<ModbusRegisterMaps>,
+) {
+    if let Some(pending) = pending_save.take() {
+        let result = match save_worker.results.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => {
+                *pending_save = Some(pending);
+                return;
+            }
+            Err(TryRecvError::Disconnected) => Err(save_worker_stopped(&pending.path)),
+        };
+        if result.is_ok() {
+            info!("Saved assets to {}", pending.path.display());
+            switch_site_config(&mut commands, &mut id_map, &mut total_assets, &register_maps, &mut config, pending.new_config);
+        }
+        send_result(&result_sender, &pending.external_id, result);
+    }
+
+    while let Ok(command) = command_receiver.0.try_recv() {
+        let new_config = match config_with_command(&config, &command) {
+            Ok(new_config) => new_config,
+            Err(errors) => {
+                send_result(&result_sender, command.external_id(), Err(errors));
+                continue;
+            }
+        };
+        let external_id = command.external_id().to_string();
+        let Some(SiteConfigPersistPath(path)) = persist_path.as_deref() else {
+            switch_site_config(&mut commands, &mut id_map, &mut total_assets, &register_maps, &mut config, new_config);
+            send_result(&result_sender, &external_id, Ok(()));
+            continue;
+        };
+        let request = AssetSaveRequest { path: path.clone(), command, assets: new_config.assets.clone() };
+        if save_worker.requests.send(request).is_err() {
+            send_result(&result_sender, &external_id, Err(save_worker_stopped(path)));
+            continue;
+        }
+        *pending_save = Some(PendingSave { external_id, path: path.clone(), new_config });
+        return;
+    }
+}
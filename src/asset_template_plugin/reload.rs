@@ -0,0 +1,166 @@
+// Applies a changed site config to the running world: new assets are spawned, removed ones despawned,
+// and changed ones updated in place, leaving unchanged assets and their OCPP sessions alone.
+
+use bevy::prelude::*;
+use crossbeam_channel::Sender;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use crate::asset_template_plugin::{SiteConfig, TotalAssets};
+use crate::asset_template_plugin::loader::load_site_config;
+use crate::asset_template_plugin::resources::SiteConfigReloadReceiver;
+use crate::asset_template_plugin::config::ComponentConfig;
+use crate::asset_template_plugin::systems::{apply_component, asset_is_spawnable, remove_component, spawn_asset};
+use crate::balancer_comms_plugin::BalancerFallbackConfig;
+use crate::common::external_id_map::ExternalIdMap;
+use crate::modbus_protocol_plugin::ModbusRegisterMaps;
+
+pub fn reload_site_config_system(
+    mut commands: Commands,
+    reload_receiver: Res<SiteConfigReloadReceiver>,
+    mut config: ResMut<SiteConfig>,
+    mut id_map: ResMut<ExternalIdMap>,
+    mut total_assets: ResMut<TotalAssets>,
+    register_maps: Res<ModbusRegisterMaps>,
+) {
+    // Only the newest queued config matters
+    let Some(config_json) = reload_receiver.0.try_iter().last() else {
+        return;
+    };
+    let new_config = match SiteConfig::from_json(&config_json) {
+        Ok(new_config) => new_config,
+        Err(errors) => {
+            for e in errors {
+                error!("{}", e);
+            }
+            warn!("Site config reload rejected; keeping the current config");
+            return;
+        }
+    };
+
+    switch_site_config(&mut commands, &mut id_map, &mut total_assets, &register_maps, &mut config, new_config);
+    info!("Site config reloaded");
+}
+
+/// Bring the world and the site-wide resources from `config` to `new_config`, which must be valid, then store it.
+pub(super) fn switch_site_config(
+    commands: &mut Commands,
+    id_map: &mut ExternalIdMap,
+    total_assets: &mut TotalAssets,
+    register_maps: &ModbusRegisterMaps,
+    config: &mut SiteConfig,
+    mut new_config: SiteConfig,
+) {
+    let new_ids: HashSet<&str> = new_config.assets.iter().map(|instance| instance.external_id.as_str()).collect();
+    for instance in &config.assets {
+        if new_ids.contains(instance.external_id.as_str()) {
+            continue;
+        }
+        if let Some(entity) = id_map.0.remove(&instance.external_id) {
+            commands.entity(entity).despawn();
+            info!("Despawned '{}'", instance.external_id);
+        }
+    }
+
+    // Changed assets left as they are, whose current entry the stored config must keep
+    let mut kept = Vec::new();
+    for (index, instance) in new_config.assets.iter().enumerate() {
+        let Some(&entity) = id_map.0.get(&instance.external_id) else {
+            // New, or skipped as invalid before
+            if asset_is_spawnable(&new_config, index) {
+                spawn_asset(commands, id_map, &new_config, index);
+            } else {
+                error!("Asset '{}' has an invalid config; not spawning", instance.external_id);
+            }
+            continue;
+        };
+
+        // Anything in the id map was spawned from the current config
+        let Some(old_index) = config.assets.iter().position(|old| old.external_id == instance.external_id) else {
+            error!("Asset '{}' is spawned but missing from the current config; leaving it alone", instance.external_id);
+            continue;
+        };
+        let old = match config.resolve_asset(old_index) {
+            Ok(old) => old,
+            Err(errors) => {
+                for e in errors {
+                    error!("{}", e);
+                }
+                error!("Asset '{}' no longer resolves in the current config; leaving it alone", instance.external_id);
+                continue;
+            }
+        };
+        let new = match new_config.resolve_asset(index) {
+            Ok(new) if new.asset_type == old.asset_type && new.component_configs().eq(old.component_configs()) => continue,
+            Ok(new) if asset_is_spawnable(&new_config, index) => new,
+            _ => {
+                error!("Asset '{}' has an invalid config; keeping its current one", instance.external_id);
+                kept.push((index, old_index));
+                continue;
+            }
+        };
+        let (old_components, new_components): (Vec<&ComponentConfig>, Vec<&ComponentConfig>) = (old.component_configs().collect(), new.component_configs().collect());
+
+        if old.asset_type != new.asset_type {
+            commands.entity(entity).despawn();
+            id_map.0.remove(&instance.external_id);
+            spawn_asset(commands, id_map, &new_config, index);
+            continue;
+        }
+        for old_cfg in &old_components {
+            if !new_components.iter().any(|new_cfg| new_cfg.name() == old_cfg.name()) {
+                remove_component(commands, entity, old_cfg);
+            }
+        }
+        for new_cfg in &new_components {
+            if !old_components.contains(new_cfg) {
+                apply_component(commands, entity, new_cfg);
+            }
+        }
+        info!("Updated '{}'", instance.external_id);
+    }
+
+    match &new_config.balancer_fallback {
+        Some(fallback) => commands.insert_resource(fallback.clone()),
+        None => commands.remove_resource::<BalancerFallbackConfig>(),
+    }
+    commands.insert_resource(new_config.metering_export.clone());
+    register_maps.replace(new_config.register_maps.clone(), new_config.write_register_maps.clone());
+    total_assets.0 = new_config.assets.len();
+    for (index, old_index) in kept {
+        new_config.assets[index] = config.assets[old_index].clone();
+    }
+    *config = new_config;
+}
+
+/// Blocking loop that loads the site config at `path` and sends it, as JSON, to `reload_sender` whenever the file or one
+/// of its includes changes; runs until the receiving app is gone. A config that fails to load is logged and not sent.
+pub fn watch_site_config_file(path: PathBuf, reload_sender: Sender<String>, poll_interval: Duration) {
+    let modified = |files: &[PathBuf]| -> Vec<Option<SystemTime>> {
+        files.iter().map(|file| std::fs::metadata(file).and_then(|m| m.modified()).ok()).collect()
+    };
+    let mut files = load_site_config(&path).map(|loaded| loaded.files).unwrap_or_else(|_| vec![path.clone()]);
+    let mut last_modified = modified(&files);
+    loop {
+        std::thread::sleep(poll_interval);
+        if modified(&files) == last_modified {
+            continue;
+        }
+        match load_site_config(&path) {
+            Ok(loaded) => {
+                info!("{} changed; reloading", path.display());
+                files = loaded.files;
+                last_modified = modified(&files);
+                if reload_sender.send(loaded.json).is_err() {
+                    return;
+                }
+            }
+            Err(errors) => {
+                for e in &errors {
+                    warn!("Failed to load {}: {}", path.display(), e);
+                }
+                last_modified = modified(&files);
+            }
+        }
+    }
+}
@@ -1,5 +1,9 @@
 use bevy::prelude::Resource;
 use crate::asset_template_plugin::config::{AssetInstance, AssetTemplate};
+use crate::asset_template_plugin::registry::{AssetRegistryResult, EAssetRegistryCommand};
+use crate::modbus_protocol_plugin::{ModbusRegisterMap, ModbusWriteRegisterMap};
+use crate::balancer_comms_plugin::{BalancerFallbackConfig, MeteringExportPolicies};
+use crate::common::error::AppError;
 use serde::Deserialize;
 use std::collections::HashMap;
 
@@ -8,4 +12,49 @@ use std::collections::HashMap;
 pub struct SiteConfig {
     pub asset_templates: HashMap<String, AssetTemplate>,
     pub assets: Vec<AssetInstance>,
-}
\ No newline at end of file
+    /// Modbus register layouts, referenced by `register_map_key` in Modbus metering sources.
+    #[serde(default)]
+    pub register_maps: HashMap<String, ModbusRegisterMap>,
+    /// Modbus setpoint write layouts, referenced by `write_register_map_key` in `modbus_control_config`.
+    #[serde(default)]
+    pub write_register_maps: HashMap<String, ModbusWriteRegisterMap>,
+    /// Local load balancing while the external balancer is silent; disabled when absent.
+    #[serde(default)]
+    pub balancer_fallback: Option<BalancerFallbackConfig>,
+    /// Named sets of asset external IDs that balancer group setpoints can target.
+    #[serde(default)]
+    pub asset_groups: HashMap<String, Vec<String>>,
+    /// Metering export policies by asset type; types without one send every change.
+    #[serde(default)]
+    pub metering_export: MeteringExportPolicies,
+}
+/// New site config JSON to apply to the running app, e.g. from `watch_site_config_file`.
+#[derive(Resource)]
+pub struct SiteConfigReloadReceiver(pub crossbeam_channel::Receiver<String>);
+
+/// Asset registration commands from commissioning tools.
+#[derive(Resource)]
+pub struct AssetRegistryCommandReceiver(pub crossbeam_channel::Receiver<EAssetRegistryCommand>);
+
+/// The outcome of each asset registration command, in the order they were received.
+#[derive(Resource)]
+pub struct AssetRegistryResultSender(pub crossbeam_channel::Sender<AssetRegistryResult>);
+
+/// Channel ends of the thread that writes asset registration changes to the `SiteConfigPersistPath` file, off the frame.
+#[derive(Resource)]
+pub struct AssetSaveWorker {
+    pub(crate) requests: crossbeam_channel::Sender<AssetSaveRequest>,
+    pub(crate) results: crossbeam_channel::Receiver<Result<(), Vec<AppError>>>,
+}
+
+/// The site's assets after a registry command, to write to `path`.
+#[derive(Debug)]
+pub struct AssetSaveRequest {
+    pub path: std::path::PathBuf,
+    pub command: EAssetRegistryCommand,
+    pub assets: Vec<AssetInstance>,
+}
+
+/// Site config file that asset registration changes are written back to; without it they last until restart.
+#[derive(Resource, Debug, Clone)]
+pub struct SiteConfigPersistPath(pub std::path::PathBuf);
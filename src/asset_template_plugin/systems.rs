@@ -1,17 +1,21 @@
 use bevy::prelude::*;
 use crate::asset_template_plugin::{SiteConfig, TotalAssets};
-use crate::core_asset_plugin::{ExternalId, AssetInfo, CurrentMeterReading, TargetPowerSetpointKw, LastAppliedSetpointKw, MeteringSource};
-use crate::ocpp_protocol_plugin::{OcppConfig, OcppProfileBehavior, ChargerElectricalConfig, Guns, Gun, EGunStatusOcpp, OcppConnectionState, AlfenSpecificConfig, GenericChargerInitializationStatus, AlfenSpecialInitStatus};
+use crate::core_asset_plugin::{ExternalId, AssetInfo, CurrentMeterReading, RequestedSetpointKw, SetpointSchedule, TargetPowerSetpointKw, LastAppliedSetpointKw, MeteringSource};
+use crate::ocpp_protocol_plugin::{OcppConfig, OcppProfileBehavior, ChargerElectricalConfig, Guns, Gun, EGunStatusOcpp, OcppConnectionState, AlfenSpecificConfig, GenericChargerInitializationStatus, AlfenSpecialInitStatus, OcppCallOutcomes, OcppPendingSetpoint, Ocpp201DeviceModel};
+use crate::ocpp_protocol_plugin::types::EOcppVersion;
 use crate::modbus_protocol_plugin::ModbusControlConfig;
+use crate::site_constraint_plugin::GridConnectionLimits;
+use crate::balancer_comms_plugin::{FallbackWeight, SafeSetpointKw, SetpointValidity, MeteringExportState};
 use crate::common::types::{EAssetType, EOperationalStatus};
 use super::config::ComponentConfig;
 use crate::common::external_id_map::ExternalIdMap;
 
-fn apply_component(
+/// Insert the component(s) a config entry describes. Entries are checked by `SiteConfig::validate_asset` before spawning,
+/// so the enum strings and metering details here always parse.
+pub(super) fn apply_component(
     commands: &mut Commands,
     entity: Entity,
     cfg: &ComponentConfig,
-    asset_type: EAssetType,
 ) {
     match cfg {
         ComponentConfig::AssetInfo { make, model } => {
@@ -20,13 +24,32 @@ fn apply_component(
         ComponentConfig::ChargerElectricalConfig { nominal_voltage_ln, active_phase_count } => {
             commands.entity(entity).insert(ChargerElectricalConfig { nominal_voltage_ln: *nominal_voltage_ln, active_phase_count: *active_phase_count });
         }
+        ComponentConfig::Connectors { count, max_current_a, phases, connector_type } => {
+            let connector_type = connector_type.parse().expect("validated connector_type");
+            set_connector_layout(commands, entity, (1..=*count).map(|connector_id| Gun {
+                gun_id: connector_id,
+                connector_id,
+                status: EGunStatusOcpp::Available,
+                connector_type,
+                max_current_a: Some(*max_current_a),
+                phases: Some(*phases),
+                ..Default::default()
+            }).collect());
+        }
         ComponentConfig::OcppConfig { version, charge_point_id } => {
-            commands.entity(entity).insert(OcppConfig { charge_point_id: charge_point_id.clone(), version: version.parse().unwrap() });
+            let version = version.parse().expect("validated version");
+            commands.entity(entity).insert(OcppConfig { charge_point_id: charge_point_id.clone(), version });
+            if version == EOcppVersion::V2_0_1 {
+                commands.entity(entity).insert(Ocpp201DeviceModel::default());
+            } else {
+                commands.entity(entity).remove::<Ocpp201DeviceModel>();
+            }
         }
-        ComponentConfig::OcppProfileBehavior { rate_unit, profile_phases_in_ocpp_message } => {
+        ComponentConfig::OcppProfileBehavior { rate_unit, profile_phases_in_ocpp_message, send_schedules } => {
             commands.entity(entity).insert(OcppProfileBehavior {
-                rate_unit: rate_unit.parse().unwrap(),
+                rate_unit: rate_unit.parse().expect("validated rate_unit"),
                 profile_phases_in_ocpp_message: *profile_phases_in_ocpp_message,
+                send_schedules: *send_schedules,
             });
         }
         ComponentConfig::AlfenSpecificConfig { default_tx_profile_power_watts } => {
@@ -36,16 +59,131 @@ fn apply_component(
         }
         ComponentConfig::MeteringSource { source_type, details } => {
             commands.entity(entity).insert(MeteringSource {
-                source_type: source_type.parse().unwrap(),
-                details: Some(serde_json::from_value(details.clone()).unwrap()),
+                source_type: source_type.parse().expect("validated source_type"),
+                details: Some(serde_json::from_value(details.clone()).expect("validated metering details")),
             });
         }
-        ComponentConfig::ModbusControlConfig { ip, port, unit_id } if asset_type == EAssetType::Battery => {
-            commands.entity(entity).insert(ModbusControlConfig { ip: ip.clone(), port: *port, unit_id: *unit_id });
+        ComponentConfig::ModbusControlConfig { ip, port, unit_id, write_register_map_key } => {
+            commands.entity(entity).insert(ModbusControlConfig {
+                ip: ip.clone(),
+                port: *port,
+                unit_id: *unit_id,
+                write_register_map_key: write_register_map_key.clone(),
+            });
+        }
+        ComponentConfig::FallbackWeight { weight } => {
+            commands.entity(entity).insert(FallbackWeight(*weight));
+        }
+        ComponentConfig::SafeSetpoint { power_kw } => {
+            commands.entity(entity).insert(SafeSetpointKw(*power_kw));
+        }
+        ComponentConfig::GridConnectionLimits { import_capacity_kw, export_capacity_kw } => {
+            commands.entity(entity).insert(GridConnectionLimits {
+                import_capacity_kw: *import_capacity_kw,
+                export_capacity_kw: *export_capacity_kw,
+            });
+        }
+    }
+}
+
+/// Remove what `apply_component` inserted for an entry the asset no longer has.
+pub(super) fn remove_component(commands: &mut Commands, entity: Entity, cfg: &ComponentConfig) {
+    let mut entity_commands = commands.entity(entity);
+    match cfg {
+        ComponentConfig::AssetInfo { .. } => { entity_commands.remove::<AssetInfo>(); }
+        ComponentConfig::ChargerElectricalConfig { .. } => { entity_commands.remove::<ChargerElectricalConfig>(); }
+        ComponentConfig::Connectors { .. } => { set_connector_layout(commands, entity, default_guns().0); }
+        ComponentConfig::OcppConfig { .. } => { entity_commands.remove::<(OcppConfig, Ocpp201DeviceModel)>(); }
+        ComponentConfig::OcppProfileBehavior { .. } => { entity_commands.remove::<OcppProfileBehavior>(); }
+        ComponentConfig::AlfenSpecificConfig { .. } => { entity_commands.remove::<(AlfenSpecificConfig, AlfenSpecialInitStatus)>(); }
+        ComponentConfig::MeteringSource { .. } => { entity_commands.remove::<MeteringSource>(); }
+        ComponentConfig::FallbackWeight { .. } => { entity_commands.remove::<FallbackWeight>(); }
+        ComponentConfig::SafeSetpoint { .. } => { entity_commands.remove::<SafeSetpointKw>(); }
+        ComponentConfig::GridConnectionLimits { .. } => { entity_commands.remove::<GridConnectionLimits>(); }
+        ComponentConfig::ModbusControlConfig { .. } => { entity_commands.remove::<ModbusControlConfig>(); }
+    }
+}
+
+/// Give a charger the connectors in `layout`. Connectors it already has keep their status, session and meter readings and
+/// only take the new ratings, so a config change does not disturb a session; connectors not in `layout` are dropped.
+fn set_connector_layout(commands: &mut Commands, entity: Entity, layout: Vec<Gun>) {
+    commands.entity(entity).queue(move |mut charger: EntityWorldMut| {
+        let connector_ids: Vec<u32> = layout.iter().map(|gun| gun.connector_id).collect();
+        let Some(mut guns) = charger.get_mut::<Guns>() else {
+            charger.insert(Guns(layout));
+            return;
+        };
+        let mut existing = std::mem::take(&mut guns.0);
+        guns.0 = layout.into_iter().map(|gun| match existing.iter().position(|old| old.connector_id == gun.connector_id) {
+            Some(index) => Gun {
+                gun_id: gun.gun_id,
+                connector_type: gun.connector_type,
+                max_current_a: gun.max_current_a,
+                phases: gun.phases,
+                ..existing.swap_remove(index)
+            },
+            None => gun,
+        }).collect();
+        // Connector 0 stands for the whole charger
+        if let Some(mut reading) = charger.get_mut::<CurrentMeterReading>() {
+            reading.connectors.retain(|connector| connector.connector_id == 0 || connector_ids.contains(&connector.connector_id));
         }
-        // Skip irrelevant or mis-typed entries
-        _ => (),
+    });
+}
+
+/// A charger's single gun when its config has no `connectors` entry.
+fn default_guns() -> Guns {
+    Guns(vec![Gun { gun_id: 1, connector_id: 1, status: EGunStatusOcpp::Available, ..Default::default() }])
+}
+
+/// Logs why `assets[index]` cannot be spawned; true if it can.
+pub(super) fn asset_is_spawnable(config: &SiteConfig, index: usize) -> bool {
+    let errors = config.spawn_errors(index);
+    for e in &errors {
+        error!("{}", e);
+    }
+    errors.is_empty()
+}
+
+/// Spawn `assets[index]`, which must be spawnable, and record it in the id map.
+pub(super) fn spawn_asset(commands: &mut Commands, id_map: &mut ExternalIdMap, config: &SiteConfig, index: usize) {
+    let instance = &config.assets[index];
+    let resolved = config.resolve_asset(index).expect("spawnable asset resolves");
+
+    let entity = commands.spawn_empty()
+        .insert((
+            ExternalId(instance.external_id.clone()),
+            resolved.asset_type,
+            CurrentMeterReading::default(),
+            RequestedSetpointKw::default(),
+            SetpointValidity::default(),
+            SetpointSchedule::default(),
+            MeteringExportState::default(),
+            TargetPowerSetpointKw::default(),
+            LastAppliedSetpointKw::default(),
+            EOperationalStatus::default(),
+        ))
+        .id();
+
+    // record the mapping once:
+    id_map.0.insert(instance.external_id.clone(), entity);
+
+    // Charger-specific defaults; a `connectors` entry replaces the single default gun
+    if resolved.asset_type == EAssetType::Charger {
+        commands.entity(entity).insert((
+            default_guns(),
+            OcppConnectionState::default(),
+            GenericChargerInitializationStatus::default(),
+            OcppCallOutcomes::default(),
+            OcppPendingSetpoint::default(),
+        ));
+    }
+
+    // Apply the inherited, own and overridden components
+    for cfg in resolved.component_configs() {
+        apply_component(commands, entity, cfg);
     }
+    info!("Spawned '{}'", instance.external_id);
 }
 
 pub fn spawn_assets_from_config_system(
@@ -56,42 +194,11 @@ pub fn spawn_assets_from_config_system(
 ) {
     total_assets.0 = config.assets.len();
 
-    for instance in &config.assets {
-        let template = match config.asset_templates.get(&instance.template_id) {
-            Some(t) => t,
-            None => {
-                warn!("Missing template '{}'", instance.template_id);
-                continue;
-            }
-        };
-
-        let entity = commands.spawn_empty()
-            .insert((
-                ExternalId(instance.external_id.clone()),
-                template.asset_type,
-                CurrentMeterReading::default(),
-                TargetPowerSetpointKw::default(),
-                LastAppliedSetpointKw::default(),
-                EOperationalStatus::default(),
-            ))
-            .id();
-
-        // record the mapping once:
-        id_map.0.insert(instance.external_id.clone(), entity);
-
-        // Charger-specific defaults
-        if template.asset_type == EAssetType::Charger {
-            commands.entity(entity).insert((
-                Guns(vec![Gun { gun_id: 1, connector_id: 1, status: EGunStatusOcpp::Available, ..Default::default() }]),
-                OcppConnectionState::default(),
-                GenericChargerInitializationStatus::default(),
-            ));
-        }
-
-        // Apply both template and instance components
-        for cfg in template.component_configs.iter().chain(&instance.instance_components) {
-            apply_component(&mut commands, entity, cfg, template.asset_type);
+    for (index, instance) in config.assets.iter().enumerate() {
+        if !asset_is_spawnable(&config, index) {
+            error!("Asset '{}' has an invalid config; not spawning", instance.external_id);
+            continue;
         }
-        info!("Spawned '{}'", instance.external_id);
+        spawn_asset(&mut commands, &mut id_map, &config, index);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,163 @@
+// Checks a site config before anything is spawned from it, so bad entries surface as errors rather than panics or silently missing components.
+
+use std::str::FromStr;
+use crate::asset_template_plugin::SiteConfig;
+use crate::asset_template_plugin::config::ComponentConfig;
+use crate::common::error::AppError;
+use crate::common::types::EMeteringDataSource;
+use crate::core_asset_plugin::MeteringSourceDetails;
+use crate::ocpp_protocol_plugin::EConnectorType;
+use crate::ocpp_protocol_plugin::types::{EChargingRateUnit, EOcppVersion};
+
+impl SiteConfig {
+    /// Parse and validate a site config.
+    pub fn from_json(config_json: &str) -> Result<Self, Vec<AppError>> {
+        let config: SiteConfig = serde_json::from_str(config_json).map_err(|e| vec![AppError::from(e)])?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Every problem with the templates, then with every asset in config order, then with the asset groups.
+    pub fn validate(&self) -> Result<(), Vec<AppError>> {
+        let mut errors = self.validate_templates();
+        for (index, instance) in self.assets.iter().enumerate() {
+            // Assets of a template that cannot be resolved would only repeat its errors
+            let broken_template = self.asset_templates.contains_key(&instance.template_id) && self.template_chain(&instance.template_id).is_err();
+            if !broken_template {
+                errors.extend(self.validate_asset(index));
+            }
+        }
+        errors.extend(self.validate_asset_groups());
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Group members that name no asset, by group id.
+    fn validate_asset_groups(&self) -> Vec<AppError> {
+        let mut group_ids: Vec<&String> = self.asset_groups.keys().collect();
+        group_ids.sort();
+        group_ids.into_iter()
+            .flat_map(|group_id| self.asset_groups[group_id].iter().enumerate().map(move |(index, member)| (group_id, index, member)))
+            .filter(|(_, _, member)| !self.assets.iter().any(|instance| &instance.external_id == *member))
+            .map(|(group_id, index, member)| AppError::UnknownGroupMember {
+                group_id: group_id.clone(),
+                path: format!("asset_groups.{group_id}[{index}]"),
+                asset_id: member.clone(),
+            })
+            .collect()
+    }
+
+    /// Problems with `assets[index]`, checked against its templates and the assets before it.
+    pub fn validate_asset(&self, index: usize) -> Vec<AppError> {
+        let instance = &self.assets[index];
+        let asset_id = instance.external_id.clone();
+        let template_id = instance.template_id.clone();
+        let mut errors = Vec::new();
+
+        if self.assets[..index].iter().any(|earlier| earlier.external_id == instance.external_id) {
+            errors.push(AppError::DuplicateExternalId {
+                asset_id: asset_id.clone(),
+                template_id: template_id.clone(),
+                path: format!("assets[{index}].external_id"),
+            });
+        }
+
+        let resolved = match self.resolve_asset(index) {
+            Ok(resolved) => resolved,
+            Err(resolve_errors) => {
+                errors.extend(resolve_errors);
+                return errors;
+            }
+        };
+
+        for (path, cfg) in &resolved.components {
+            if !cfg.allowed_on(resolved.asset_type) {
+                errors.push(AppError::ComponentNotAllowed {
+                    asset_id: asset_id.clone(),
+                    template_id: template_id.clone(),
+                    path: path.clone(),
+                    component: cfg.name(),
+                    asset_type: resolved.asset_type,
+                });
+                continue;
+            }
+            let bad_enum = |field: &str, value: &str, expected: &'static str| AppError::InvalidEnumValue {
+                asset_id: asset_id.clone(),
+                template_id: template_id.clone(),
+                path: format!("{path}.{field}"),
+                value: value.to_string(),
+                expected,
+            };
+            match cfg {
+                ComponentConfig::Connectors { connector_type, .. } if EConnectorType::from_str(connector_type).is_err() => {
+                    errors.push(bad_enum("connector_type", connector_type, "Type1, Type2, Ccs1, Ccs2, Chademo"));
+                }
+                ComponentConfig::OcppConfig { version, .. } if EOcppVersion::from_str(version).is_err() => {
+                    errors.push(bad_enum("version", version, "V1_6J, V2_0_1"));
+                }
+                ComponentConfig::OcppProfileBehavior { rate_unit, .. } if EChargingRateUnit::from_str(rate_unit).is_err() => {
+                    errors.push(bad_enum("rate_unit", rate_unit, "Watts, Amps"));
+                }
+                ComponentConfig::MeteringSource { source_type, details } => {
+                    if EMeteringDataSource::from_str(source_type).is_err() {
+                        errors.push(bad_enum("source_type", source_type, "Ocpp, Modbus, InternalCalculation"));
+                    }
+                    if let Err(e) = serde_json::from_value::<MeteringSourceDetails>(details.clone()) {
+                        errors.push(AppError::InvalidComponent {
+                            asset_id: asset_id.clone(),
+                            template_id: template_id.clone(),
+                            path: format!("{path}.details"),
+                            component: cfg.name(),
+                            reason: e.to_string(),
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let mut missing: Vec<&'static str> = Vec::new();
+        for component in resolved.component_configs().flat_map(|cfg| cfg.requires().iter().copied()) {
+            if !missing.contains(&component) && !resolved.component_configs().any(|cfg| cfg.name() == component) {
+                missing.push(component);
+            }
+        }
+        for component in missing {
+            errors.push(AppError::MissingComponent {
+                asset_id: asset_id.clone(),
+                template_id: template_id.clone(),
+                path: format!("assets[{index}]"),
+                component,
+                asset_type: resolved.asset_type,
+            });
+        }
+        errors
+    }
+
+    /// `validate_asset`, plus Modbus register maps referenced by `assets[index]` that the config does not define.
+    /// Such assets are not spawned, but do not make the whole config invalid.
+    pub fn spawn_errors(&self, index: usize) -> Vec<AppError> {
+        let errors = self.validate_asset(index);
+        if !errors.is_empty() {
+            return errors;
+        }
+        let instance = &self.assets[index];
+        let resolved = self.resolve_asset(index).expect("validated asset resolves");
+        resolved.components.into_iter()
+            .filter_map(|(path, cfg)| {
+                let (path, key) = match cfg {
+                    ComponentConfig::MeteringSource { details, .. } => match serde_json::from_value::<MeteringSourceDetails>(details) {
+                        Ok(MeteringSourceDetails::Modbus { register_map_key, .. }) if !self.register_maps.contains_key(&register_map_key) => {
+                            (format!("{path}.details.modbus.register_map_key"), register_map_key)
+                        }
+                        _ => return None,
+                    },
+                    ComponentConfig::ModbusControlConfig { write_register_map_key, .. } if !self.write_register_maps.contains_key(&write_register_map_key) => {
+                        (format!("{path}.write_register_map_key"), write_register_map_key)
+                    }
+                    _ => return None,
+                };
+                Some(AppError::UnknownRegisterMap { asset_id: instance.external_id.clone(), template_id: instance.template_id.clone(), path, key })
+            })
+            .collect()
+    }
+}
@@ -1,12 +1,62 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use chrono::{DateTime, Utc};
+use crate::common::types::{EAssetType, EOperationalStatus};
+use crate::core_asset_plugin::{ConnectorMeterReading, ElectricalMeasurements};
+use crate::ocpp_protocol_plugin::EGunStatusOcpp;
 
 // External message formats
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BalancerSetpointMessage {
     pub external_id: String,
     pub target_power_kw: f32,
+    /// After this the asset reverts to its safe setpoint unless a newer message arrives.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
+    /// Increases with every message for the asset; older sequences are rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    /// When the balancer issued the setpoint; used for ordering when no sequence is given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issued_at: Option<DateTime<Utc>>,
+}
+
+/// Target for every asset in a site config `asset_groups` entry, shared equally among its members.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BalancerGroupSetpointMessage {
+    pub group_id: String,
+    pub target_power_kw: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issued_at: Option<DateTime<Utc>>,
+}
+
+/// Setpoints for many assets, applied together in one frame or not at all.
+/// Assets with their own entry in `setpoints` are left out of any group share.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BalancerSetpointBatchMessage {
+    #[serde(default)]
+    pub setpoints: Vec<BalancerSetpointMessage>,
+    #[serde(default)]
+    pub group_setpoints: Vec<BalancerGroupSetpointMessage>,
+}
+
+/// Setpoint plan for one asset; replaces any schedule sent before.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BalancerScheduleMessage {
+    pub external_id: String,
+    pub periods: Vec<BalancerSchedulePeriod>,
+}
+
+/// Each period runs from `start` until the next period starts; the last one runs on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalancerSchedulePeriod {
+    pub start: DateTime<Utc>,
+    pub power_kw: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,4 +65,38 @@ pub struct BalancerMeteringMessage {
     pub power_kw: f32,
     pub energy_kwh: f64,
     pub timestamp: DateTime<Utc>,
+    /// How the reading splits across connectors, for chargers metered per connector.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connectors: Vec<ConnectorMeterReading>,
+    /// Status of each charger connector, so the balancer knows which can take power.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connector_status: Vec<BalancerConnectorStatus>,
+    #[serde(flatten)]
+    pub measurements: ElectricalMeasurements,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BalancerConnectorStatus {
+    pub connector_id: u32,
+    pub status: EGunStatusOcpp,
+}
+
+/// Site-wide sums of the latest readings, sent in every cycle that exports metering.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalancerSiteTotalsMessage {
+    pub timestamp: DateTime<Utc>,
+    /// Sum over all assets other than grid connections.
+    pub assets_power_kw: f32,
+    pub power_kw_by_asset_type: HashMap<EAssetType, f32>,
+    /// Metered power at the grid connection, if the site has a metered one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grid_power_kw: Option<f32>,
+}
+
+/// Sent whenever an asset's `EOperationalStatus` changes, e.g. a charger going silent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalancerAssetStatusMessage {
+    pub external_id: String,
+    pub status: EOperationalStatus,
+    pub timestamp: DateTime<Utc>,
 }
@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Relative share of the fallback power budget; chargers default to 1 and other assets to 0.
+#[derive(Component, Debug, Clone, Copy, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+pub struct FallbackWeight(pub f32);
+
+/// Setpoint an asset reverts to when its balancer setpoint lapses; 0 kW when not configured.
+#[derive(Component, Debug, Clone, Copy, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+pub struct SafeSetpointKw(pub f32);
+
+/// Validity and ordering of the setpoint last accepted from the balancer for an asset.
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct SetpointValidity {
+    /// `Time::elapsed_secs_f64` at which the setpoint lapses; `None` if it never does.
+    pub expires_at_secs: Option<f64>,
+    pub last_sequence: Option<u64>,
+    #[reflect(ignore)]
+    pub last_issued_at: Option<DateTime<Utc>>,
+    /// The requested setpoint was set by the fallback balancer and is reverted when control is handed back.
+    pub fallback_owned: bool,
+}
+
+/// What was last sent to the balancer for an asset, for its metering export policy.
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct MeteringExportState {
+    /// `Time::elapsed_secs_f64` of the last metering message; `None` before the first.
+    pub last_sent_secs: Option<f64>,
+    pub last_sent_power_kw: f32,
+}
@@ -1,7 +1,12 @@
 use bevy::prelude::*;
+use chrono::{DateTime, Utc};
 
 #[derive(Event, Debug, Clone)]
 pub struct SetpointCommand {
     pub entity: Entity,
     pub power_kw: f32,
+    /// `Time::elapsed_secs_f64` at which the setpoint lapses.
+    pub expires_at_secs: Option<f64>,
+    pub sequence: Option<u64>,
+    pub issued_at: Option<DateTime<Utc>>,
 }
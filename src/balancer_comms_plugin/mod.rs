@@ -1,11 +1,13 @@
 use bevy::prelude::*;
-use crate::core_asset_plugin::TargetPowerSetpointKw;
+use crate::core_asset_plugin::{ESetpointFlow, TargetPowerSetpointKw};
 
+pub mod components;
 pub mod events;
 pub mod systems;
 pub mod resources;
 pub mod balancer_messages;
 
+pub use components::*;
 pub use events::*;
 pub use systems::*;
 pub use resources::*;
@@ -16,11 +18,27 @@ pub struct BalancerCommsPlugin;
 impl Plugin for BalancerCommsPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<TargetPowerSetpointKw>()
+           .register_type::<FallbackWeight>()
+           .register_type::<SafeSetpointKw>()
+           .register_type::<SetpointValidity>()
+           .register_type::<MeteringExportState>()
+           .init_resource::<MeteringExportPolicies>()
+           .init_resource::<BalancerWatchdog>()
+           .init_resource::<EBalancerMode>()
            .add_event::<SetpointCommand>()
            .add_systems(Update, (
-               receive_external_setpoints,      // balancer external -> orchestrator internal
-               apply_setpoint_commands,         // Process commands
+               (
+                   receive_external_setpoints,  // balancer external -> orchestrator internal
+                   receive_external_schedules,  // balancer external -> orchestrator internal
+                   apply_setpoint_commands,     // Process commands
+                   expire_setpoints,            // Revert lapsed setpoints to their safe default
+                   apply_setpoint_schedules,    // Request each schedule slot as it starts
+                   supervise_balancer_link,     // Watchdog on balancer messages
+                   apply_fallback_setpoints,    // Local setpoints while the balancer is silent
+                   release_fallback_setpoints,  // Revert fallback setpoints once the balancer is back
+               ).chain().in_set(ESetpointFlow::Request),
                export_metering_data,            // orchestrator internal -> balancer external
+               export_asset_status,             // orchestrator internal -> balancer external
            ));
 
         info!("BalancerCommsPlugin loaded.");
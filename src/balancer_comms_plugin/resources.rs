@@ -1,9 +1,78 @@
 use bevy::prelude::Resource;
-use super::balancer_messages::{BalancerSetpointMessage, BalancerMeteringMessage};
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::common::types::EAssetType;
+use super::balancer_messages::{BalancerSetpointMessage, BalancerSetpointBatchMessage, BalancerScheduleMessage, BalancerMeteringMessage, BalancerAssetStatusMessage, BalancerSiteTotalsMessage};
 
 // External interfaces as resources
 #[derive(Resource)]
 pub struct BalancerSetpointReceiver(pub crossbeam_channel::Receiver<BalancerSetpointMessage>);
 
+#[derive(Resource)]
+pub struct BalancerSetpointBatchReceiver(pub crossbeam_channel::Receiver<BalancerSetpointBatchMessage>);
+
+#[derive(Resource)]
+pub struct BalancerScheduleReceiver(pub crossbeam_channel::Receiver<BalancerScheduleMessage>);
+
 #[derive(Resource)]
 pub struct BalancerMeteringSender(pub crossbeam_channel::Sender<BalancerMeteringMessage>);
+
+#[derive(Resource)]
+pub struct BalancerSiteTotalsSender(pub crossbeam_channel::Sender<BalancerSiteTotalsMessage>);
+
+#[derive(Resource)]
+pub struct BalancerStatusSender(pub crossbeam_channel::Sender<BalancerAssetStatusMessage>);
+
+/// Whether setpoints currently come from the external balancer or the built-in fallback.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EBalancerMode {
+    #[default]
+    External,
+    Fallback,
+}
+
+/// How the fallback shares out its power budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum EFallbackStrategy {
+    /// Every participating asset gets the same share.
+    #[default]
+    EqualShare,
+    /// Shares proportional to each asset's `FallbackWeight`.
+    Weighted,
+}
+
+/// Local load balancing used while the external balancer is silent, from the site config's `balancer_fallback`.
+#[derive(Resource, Debug, Clone, Deserialize)]
+pub struct BalancerFallbackConfig {
+    /// Seconds without a balancer message before the fallback takes over.
+    pub timeout_secs: f64,
+    /// Power shared out among participating assets while the fallback is active.
+    pub site_power_kw: f32,
+    #[serde(default)]
+    pub strategy: EFallbackStrategy,
+}
+
+/// `Time::elapsed_secs_f64` of the last message from the external balancer; startup counts as contact.
+#[derive(Resource, Debug, Default)]
+pub struct BalancerWatchdog {
+    pub last_message_secs: f64,
+}
+
+/// When an asset type's readings go to the balancer; the three rules combine.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct MeteringExportPolicy {
+    /// Send on this fixed cadence, changed or not.
+    #[serde(default)]
+    pub interval_secs: Option<f64>,
+    /// Send changes as they happen once power has moved this far from the value last sent.
+    /// Without it, changes go out as they happen only when there is no `interval_secs`.
+    #[serde(default)]
+    pub deadband_kw: Option<f32>,
+    /// Send even without changes once nothing has gone out for this long.
+    #[serde(default)]
+    pub max_silence_secs: Option<f64>,
+}
+
+/// Export policies by asset type, from the site config's `metering_export`; asset types without one send every change.
+#[derive(Resource, Debug, Clone, Default, Deserialize)]
+pub struct MeteringExportPolicies(pub HashMap<EAssetType, MeteringExportPolicy>);
@@ -1,57 +1,441 @@
 use bevy::prelude::*;
 use super::events::SetpointCommand;
-use super::resources::{BalancerSetpointReceiver, BalancerMeteringSender};
-use super::balancer_messages::BalancerMeteringMessage;
-use crate::core_asset_plugin::{ExternalId, CurrentMeterReading, TargetPowerSetpointKw};
+use super::components::{FallbackWeight, SafeSetpointKw, SetpointValidity, MeteringExportState};
+use super::resources::{BalancerSetpointReceiver, BalancerSetpointBatchReceiver, BalancerScheduleReceiver, BalancerMeteringSender, BalancerStatusSender, BalancerWatchdog, BalancerFallbackConfig, EBalancerMode, EFallbackStrategy, BalancerSiteTotalsSender, MeteringExportPolicies, MeteringExportPolicy};
+use super::balancer_messages::{BalancerSetpointMessage, BalancerSetpointBatchMessage, BalancerMeteringMessage, BalancerConnectorStatus, BalancerAssetStatusMessage, BalancerSiteTotalsMessage};
+use crate::core_asset_plugin::{ExternalId, CurrentMeterReading, MeteringSource, RequestedSetpointKw, SetpointSchedule, SchedulePeriod};
 use crate::common::external_id_map::ExternalIdMap;
+use crate::ocpp_protocol_plugin::Guns;
+use crate::asset_template_plugin::SiteConfig;
+use crate::common::types::{EAssetType, EOperationalStatus};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 
-/// Receives external setpoints and translates them to entity-specific commands
+/// Sequence and issue time of the setpoint last accepted for each asset in this frame, ahead of `SetpointValidity`
+type AcceptedOrdering = HashMap<Entity, (Option<u64>, Option<DateTime<Utc>>)>;
+
+/// Receives external setpoints and batches and translates them to entity-specific commands,
+/// dropping lapsed and out-of-order setpoints and rejecting a batch whole if any of its entries is.
+#[allow(clippy::too_many_arguments)]
 pub fn receive_external_setpoints(
     receiver: Option<Res<BalancerSetpointReceiver>>,
+    batch_receiver: Option<Res<BalancerSetpointBatchReceiver>>,
     id_map: Res<ExternalIdMap>,
+    config: Option<Res<SiteConfig>>,
+    validity: Query<&SetpointValidity>,
     mut command_writer: EventWriter<SetpointCommand>,
+    time: Res<Time>,
+    mut watchdog: ResMut<BalancerWatchdog>,
+) {
+    let now_secs = time.elapsed_secs_f64();
+    let mut accepted = AcceptedOrdering::new();
+
+    if let Some(receiver) = receiver {
+        while let Ok(message) = receiver.0.try_recv() {
+            watchdog.last_message_secs = now_secs;
+            let Some(&entity) = id_map.0.get(&message.external_id) else {
+                warn!("No asset for external_id '{}'", message.external_id);
+                continue;
+            };
+            match accept_setpoint(entity, &message, now_secs, &validity, &accepted) {
+                Ok(command) => {
+                    record_accepted(&mut accepted, &command, &validity);
+                    command_writer.write(command);
+                }
+                Err(e) => warn!("Ignoring setpoint: {}", e),
+            }
+        }
+    }
+
+    if let Some(batch_receiver) = batch_receiver {
+        while let Ok(batch) = batch_receiver.0.try_recv() {
+            watchdog.last_message_secs = now_secs;
+            let groups = config.as_ref().map(|config| &config.asset_groups);
+            let commands = resolve_batch(&batch, &id_map, groups).and_then(|setpoints| {
+                setpoints.iter()
+                    .map(|(entity, message)| accept_setpoint(*entity, message, now_secs, &validity, &accepted))
+                    .collect::<Result<Vec<_>, _>>()
+            });
+            match commands {
+                Ok(commands) => {
+                    info!("Applying batch of {} setpoints", commands.len());
+                    for command in &commands {
+                        record_accepted(&mut accepted, command, &validity);
+                    }
+                    command_writer.write_batch(commands);
+                }
+                Err(e) => warn!("Rejecting setpoint batch: {}", e),
+            }
+        }
+    }
+}
+
+/// Receives setpoint schedules and stores them on their assets, replacing any earlier schedule
+pub fn receive_external_schedules(
+    receiver: Option<Res<BalancerScheduleReceiver>>,
+    id_map: Res<ExternalIdMap>,
+    mut query: Query<&mut SetpointSchedule>,
+    time: Res<Time>,
+    mut watchdog: ResMut<BalancerWatchdog>,
 ) {
     let Some(receiver) = receiver else { return };
-    
+
     while let Ok(message) = receiver.0.try_recv() {
-        if let Some(&entity) = id_map.0.get(&message.external_id) {
-            command_writer.write(SetpointCommand { entity, power_kw: message.target_power_kw });
-        } else {
+        watchdog.last_message_secs = time.elapsed_secs_f64();
+        let Some(mut schedule) = id_map.0.get(&message.external_id).and_then(|&entity| query.get_mut(entity).ok()) else {
             warn!("No asset for external_id '{}'", message.external_id);
+            continue;
+        };
+
+        // Slot starts are moved onto the app clock once, so schedules follow `Time` like setpoint expiry
+        let now = Utc::now();
+        let mut periods: Vec<SchedulePeriod> = message.periods.iter()
+            .map(|period| SchedulePeriod {
+                start: period.start,
+                start_secs: time.elapsed_secs_f64() + (period.start - now).num_milliseconds() as f64 / 1000.0,
+                power_kw: period.power_kw,
+            })
+            .collect();
+        periods.sort_by_key(|period| period.start);
+        info!("Received schedule of {} periods for '{}'", periods.len(), message.external_id);
+        *schedule = SetpointSchedule { periods, active_period: None };
+    }
+}
+
+/// Requests each schedule slot's setpoint once as the slot starts, so immediate setpoints hold until the next slot
+pub fn apply_setpoint_schedules(
+    time: Res<Time>,
+    mut query: Query<(&ExternalId, &mut SetpointSchedule, &mut RequestedSetpointKw)>,
+) {
+    for (id, mut schedule, mut requested) in query.iter_mut() {
+        let Some(current) = schedule.period_at(time.elapsed_secs_f64()) else {
+            continue;
+        };
+        if schedule.active_period == Some(current) {
+            continue;
         }
+        let power_kw = schedule.periods[current].power_kw;
+        info!("Schedule slot starting for '{}': requesting {}kW", id.0, power_kw);
+        requested.0 = power_kw;
+        // `Changed<SetpointSchedule>` signals a new schedule, not the slot moving on
+        schedule.bypass_change_detection().active_period = Some(current);
     }
 }
 
-/// Applies setpoint commands to their target entities
+/// Command for one asset's setpoint, or why it is refused: its validity lapsed before it arrived,
+/// or it is older than the setpoint last accepted for the asset
+fn accept_setpoint(
+    entity: Entity,
+    message: &BalancerSetpointMessage,
+    now_secs: f64,
+    validity: &Query<&SetpointValidity>,
+    accepted: &AcceptedOrdering,
+) -> Result<SetpointCommand, String> {
+    // Wall-clock validity is converted to app time here so expiry follows `Time`
+    let expires_at_secs = match message.valid_until {
+        Some(valid_until) => {
+            let remaining_secs = (valid_until - Utc::now()).num_milliseconds() as f64 / 1000.0;
+            if remaining_secs <= 0.0 {
+                return Err(format!("setpoint for '{}' lapsed at {}", message.external_id, valid_until));
+            }
+            Some(now_secs + remaining_secs)
+        }
+        None => None,
+    };
+
+    // Sequence numbers order messages; issue times only when the balancer sends no sequence
+    let (last_sequence, last_issued_at) = last_ordering(entity, validity, accepted);
+    let stale = match (message.sequence, last_sequence) {
+        (Some(sequence), Some(last)) => sequence <= last,
+        (None, _) => matches!((message.issued_at, last_issued_at), (Some(issued), Some(last)) if issued < last),
+        (Some(_), None) => false,
+    };
+    if stale {
+        return Err(format!("out-of-order setpoint {}kW for '{}' (sequence {:?}, issued {:?})",
+            message.target_power_kw, message.external_id, message.sequence, message.issued_at));
+    }
+
+    Ok(SetpointCommand {
+        entity,
+        power_kw: message.target_power_kw,
+        expires_at_secs,
+        sequence: message.sequence,
+        issued_at: message.issued_at,
+    })
+}
+
+fn last_ordering(entity: Entity, validity: &Query<&SetpointValidity>, accepted: &AcceptedOrdering) -> (Option<u64>, Option<DateTime<Utc>>) {
+    accepted.get(&entity).copied()
+        .or_else(|| validity.get(entity).ok().map(|validity| (validity.last_sequence, validity.last_issued_at)))
+        .unwrap_or_default()
+}
+
+fn record_accepted(accepted: &mut AcceptedOrdering, command: &SetpointCommand, validity: &Query<&SetpointValidity>) {
+    let (last_sequence, last_issued_at) = last_ordering(command.entity, validity, accepted);
+    accepted.insert(command.entity, (command.sequence.or(last_sequence), command.issued_at.or(last_issued_at)));
+}
+
+/// Expands a batch into per-asset setpoints, failing on the first unknown asset or group,
+/// or on an asset the batch targets more than once
+fn resolve_batch(
+    batch: &BalancerSetpointBatchMessage,
+    id_map: &ExternalIdMap,
+    groups: Option<&HashMap<String, Vec<String>>>,
+) -> Result<Vec<(Entity, BalancerSetpointMessage)>, String> {
+    let lookup = |external_id: &str| id_map.0.get(external_id).copied().ok_or_else(|| format!("no asset for external_id '{}'", external_id));
+
+    let mut setpoints = Vec::new();
+    for group_setpoint in &batch.group_setpoints {
+        let members = groups.and_then(|groups| groups.get(&group_setpoint.group_id))
+            .ok_or_else(|| format!("no asset group '{}'", group_setpoint.group_id))?;
+        let sharing: Vec<&String> = members.iter()
+            .filter(|member| !batch.setpoints.iter().any(|setpoint| &setpoint.external_id == *member))
+            .collect();
+        for member in &sharing {
+            setpoints.push((lookup(member)?, BalancerSetpointMessage {
+                external_id: (*member).clone(),
+                target_power_kw: group_setpoint.target_power_kw / sharing.len() as f32,
+                valid_until: group_setpoint.valid_until,
+                sequence: group_setpoint.sequence,
+                issued_at: group_setpoint.issued_at,
+            }));
+        }
+    }
+    for setpoint in &batch.setpoints {
+        setpoints.push((lookup(&setpoint.external_id)?, setpoint.clone()));
+    }
+    for (index, (_, setpoint)) in setpoints.iter().enumerate() {
+        if setpoints[..index].iter().any(|(_, earlier)| earlier.external_id == setpoint.external_id) {
+            return Err(format!("asset '{}' is targeted more than once", setpoint.external_id));
+        }
+    }
+    Ok(setpoints)
+}
+
+/// Records setpoint commands as the requested setpoint of their target entities;
+/// lapsed and out-of-order setpoints were already dropped when they were received
 pub fn apply_setpoint_commands(
     mut commands: EventReader<SetpointCommand>,
-    mut query: Query<(&ExternalId, &mut TargetPowerSetpointKw)>,
+    mut query: Query<(&ExternalId, &mut RequestedSetpointKw, &mut SetpointValidity)>,
 ) {
     for command in commands.read() {
-        if let Ok((id, mut setpoint)) = query.get_mut(command.entity) {
-            info!("Setting {}kW for '{}'", command.power_kw, id.0);
-            setpoint.0 = command.power_kw;
+        let Ok((id, mut setpoint, mut validity)) = query.get_mut(command.entity) else { continue };
+
+        info!("Requesting {}kW for '{}'", command.power_kw, id.0);
+        setpoint.0 = command.power_kw;
+        validity.expires_at_secs = command.expires_at_secs;
+        validity.fallback_owned = false;
+        if command.sequence.is_some() {
+            validity.last_sequence = command.sequence;
+        }
+        if command.issued_at.is_some() {
+            validity.last_issued_at = command.issued_at;
+        }
+    }
+}
+
+/// Reverts assets whose balancer setpoint has lapsed to their safe setpoint
+pub fn expire_setpoints(
+    time: Res<Time>,
+    mut query: Query<(&ExternalId, &mut RequestedSetpointKw, &mut SetpointValidity, Option<&SafeSetpointKw>)>,
+) {
+    let now = time.elapsed_secs_f64();
+    for (id, mut setpoint, mut validity, safe) in query.iter_mut() {
+        if !validity.expires_at_secs.is_some_and(|expires_at| now >= expires_at) {
+            continue;
         }
+        let safe_kw = safe.map_or(0.0, |safe| safe.0);
+        warn!("Setpoint {}kW for '{}' lapsed; reverting to safe setpoint {}kW", setpoint.0, id.0, safe_kw);
+        setpoint.0 = safe_kw;
+        validity.expires_at_secs = None;
     }
 }
 
-/// Exports metering data when it changes
+/// Switches to the fallback when the external balancer has been silent past the configured timeout, and back once it speaks again
+pub fn supervise_balancer_link(
+    time: Res<Time>,
+    config: Option<Res<BalancerFallbackConfig>>,
+    watchdog: Res<BalancerWatchdog>,
+    mut mode: ResMut<EBalancerMode>,
+) {
+    let Some(config) = config else { return };
+
+    let silent_secs = time.elapsed_secs_f64() - watchdog.last_message_secs;
+    match *mode {
+        EBalancerMode::External if silent_secs > config.timeout_secs => {
+            warn!("No balancer message for {:.0} s; fallback balancer sharing {} kW ({:?})", silent_secs, config.site_power_kw, config.strategy);
+            *mode = EBalancerMode::Fallback;
+        }
+        EBalancerMode::Fallback if silent_secs <= config.timeout_secs => {
+            info!("External balancer is back; handing control back");
+            *mode = EBalancerMode::External;
+        }
+        _ => {}
+    }
+}
+
+/// Shares the fallback power budget among participating assets while the fallback is active;
+/// the others are held at their safe setpoint
+#[allow(clippy::type_complexity)]
+pub fn apply_fallback_setpoints(
+    mode: Res<EBalancerMode>,
+    config: Option<Res<BalancerFallbackConfig>>,
+    mut query: Query<(&EAssetType, Option<&FallbackWeight>, Option<&SafeSetpointKw>, &mut RequestedSetpointKw, &mut SetpointValidity)>,
+) {
+    let (EBalancerMode::Fallback, Some(config)) = (*mode, config) else { return };
+
+    let weight = |asset_type: &EAssetType, configured: Option<&FallbackWeight>| match configured {
+        Some(weight) => weight.0.max(0.0),
+        None if *asset_type == EAssetType::Charger => 1.0,
+        None => 0.0,
+    };
+    let (participants, total_weight) = query.iter()
+        .map(|(asset_type, configured, ..)| weight(asset_type, configured))
+        .filter(|&w| w > 0.0)
+        .fold((0usize, 0.0f32), |(count, total), w| (count + 1, total + w));
+
+    for (asset_type, configured, safe, mut requested, mut validity) in query.iter_mut() {
+        let w = weight(asset_type, configured);
+        let share_kw = if w <= 0.0 {
+            safe.map_or(0.0, |safe| safe.0)
+        } else {
+            match config.strategy {
+                EFallbackStrategy::EqualShare => config.site_power_kw / participants as f32,
+                EFallbackStrategy::Weighted => config.site_power_kw * w / total_weight,
+            }
+        };
+        requested.set_if_neq(RequestedSetpointKw(share_kw));
+        if !validity.fallback_owned {
+            validity.fallback_owned = true;
+            validity.expires_at_secs = None;
+        }
+    }
+}
+
+/// Once the external balancer is back, reverts setpoints still owned by the fallback to their safe setpoint,
+/// so assets the balancer does not address do not keep the fallback's share
+pub fn release_fallback_setpoints(
+    mode: Res<EBalancerMode>,
+    mut query: Query<(&ExternalId, Option<&SafeSetpointKw>, &mut RequestedSetpointKw, &mut SetpointValidity)>,
+) {
+    if !mode.is_changed() || *mode != EBalancerMode::External {
+        return;
+    }
+    for (id, safe, mut requested, mut validity) in query.iter_mut() {
+        if !validity.fallback_owned {
+            continue;
+        }
+        let safe_kw = safe.map_or(0.0, |safe| safe.0);
+        info!("Fallback setpoint {}kW for '{}' handed back; reverting to safe setpoint {}kW", requested.0, id.0, safe_kw);
+        requested.set_if_neq(RequestedSetpointKw(safe_kw));
+        validity.fallback_owned = false;
+    }
+}
+
+/// Whether an asset's metering is due for export under its asset type's policy
+fn metering_export_due(
+    policy: Option<&MeteringExportPolicy>,
+    state: &MeteringExportState,
+    power_kw: f32,
+    reading_changed: bool,
+    connectors_changed: bool,
+    now_secs: f64,
+) -> bool {
+    let Some(policy) = policy else { return reading_changed || connectors_changed };
+
+    let since_sent = state.last_sent_secs.map(|last| now_secs - last);
+    let elapsed = |secs: Option<f64>| secs.is_some_and(|secs| since_sent.is_none_or(|since| since >= secs));
+    let moved = reading_changed && match policy.deadband_kw {
+        Some(deadband_kw) => (power_kw - state.last_sent_power_kw).abs() >= deadband_kw,
+        None => policy.interval_secs.is_none(),
+    };
+    elapsed(policy.interval_secs) || moved || connectors_changed || elapsed(policy.max_silence_secs)
+}
+
+/// Exports metering data, with connector status for chargers, as each asset type's export policy asks,
+/// followed by the site totals whenever anything went out.
+///
+/// Runs in `Update` rather than `FixedUpdate`: deadband moves and connector status changes go out the frame they
+/// happen, and each asset type's `interval_secs` may be finer than, or not a multiple of, the site-wide fixed step.
+/// The cadence is still measured on virtual time, so it pauses and scales with the app as `FixedUpdate` would.
+#[allow(clippy::type_complexity)]
 pub fn export_metering_data(
-    query: Query<(&ExternalId, &CurrentMeterReading), Changed<CurrentMeterReading>>,
+    time: Res<Time>,
+    policies: Res<MeteringExportPolicies>,
+    mut query: Query<(&ExternalId, &EAssetType, Ref<CurrentMeterReading>, Option<Ref<Guns>>, Option<&MeteringSource>, &mut MeteringExportState)>,
     sender: Option<Res<BalancerMeteringSender>>,
+    totals_sender: Option<Res<BalancerSiteTotalsSender>>,
 ) {
     let Some(sender) = sender else { return };
-    
-    for (id, reading) in query.iter() {
+    let now_secs = time.elapsed_secs_f64();
+    let mut exported = false;
+
+    for (id, asset_type, reading, guns, _, mut state) in query.iter_mut() {
+        let connectors_changed = guns.as_ref().is_some_and(|guns| guns.is_changed());
+        if !metering_export_due(policies.0.get(asset_type), &state, reading.power_kw, reading.is_changed(), connectors_changed, now_secs) {
+            continue;
+        }
+        state.last_sent_secs = Some(now_secs);
+        state.last_sent_power_kw = reading.power_kw;
+        exported = true;
+
         let message = BalancerMeteringMessage {
             external_id: id.0.clone(),
             power_kw: reading.power_kw,
             energy_kwh: reading.energy_kwh,
             timestamp: reading.timestamp,
+            connectors: reading.connectors.clone(),
+            connector_status: guns.map(|guns| guns.0.iter()
+                .map(|gun| BalancerConnectorStatus { connector_id: gun.connector_id, status: gun.status.clone() })
+                .collect())
+                .unwrap_or_default(),
+            measurements: reading.measurements.clone(),
         };
         
         if let Err(e) = sender.0.send(message) {
             error!("Failed to send metering data for '{}': {}", id.0, e);
         }
     }
-}
\ No newline at end of file
+
+    let (true, Some(totals_sender)) = (exported, totals_sender) else { return };
+    let mut totals = BalancerSiteTotalsMessage {
+        timestamp: Utc::now(),
+        assets_power_kw: 0.0,
+        power_kw_by_asset_type: HashMap::new(),
+        grid_power_kw: None,
+    };
+    for (_, asset_type, reading, _, source, _) in query.iter() {
+        if *asset_type == EAssetType::GridConnection {
+            // An unmetered grid connection has no reading of its own
+            if source.is_some() {
+                *totals.grid_power_kw.get_or_insert(0.0) += reading.power_kw;
+            }
+        } else {
+            totals.assets_power_kw += reading.power_kw;
+            *totals.power_kw_by_asset_type.entry(*asset_type).or_default() += reading.power_kw;
+        }
+    }
+    if let Err(e) = totals_sender.0.send(totals) {
+        error!("Failed to send site totals: {}", e);
+    }
+}
+
+/// Exports operational status when it changes, so the balancer stops counting on assets that went offline
+pub fn export_asset_status(
+    query: Query<(&ExternalId, &EOperationalStatus), Changed<EOperationalStatus>>,
+    sender: Option<Res<BalancerStatusSender>>,
+) {
+    let Some(sender) = sender else { return };
+
+    for (id, status) in query.iter() {
+        let message = BalancerAssetStatusMessage {
+            external_id: id.0.clone(),
+            status: *status,
+            timestamp: Utc::now(),
+        };
+
+        if let Err(e) = sender.0.send(message) {
+            error!("Failed to send status for '{}': {}", id.0, e);
+        }
+    }
+}
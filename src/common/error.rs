@@ -1,7 +1,56 @@
 use thiserror::Error;
+use crate::common::types::EAssetType;
 
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
+    #[error("Modbus transport error: {0}")]
+    ModbusTransport(#[from] tokio_modbus::Error),
+    #[error("Modbus exception response: {0}")]
+    ModbusException(tokio_modbus::ExceptionCode),
+    #[error("Modbus I/O timed out")]
+    ModbusTimeout,
+
+    // Site config validation; `path` locates the offending value, e.g. `assets[2].template_id`.
+    #[error("Asset '{asset_id}' at {path}: unknown template '{template_id}'")]
+    UnknownTemplate { asset_id: String, template_id: String, path: String },
+    #[error("Asset '{asset_id}' at {path}: external_id is already used by an earlier asset")]
+    DuplicateExternalId { asset_id: String, template_id: String, path: String },
+    #[error("Asset '{asset_id}' (template '{template_id}') at {path}: '{value}' is not one of {expected}")]
+    InvalidEnumValue { asset_id: String, template_id: String, path: String, value: String, expected: &'static str },
+    #[error("Asset '{asset_id}' (template '{template_id}') at {path}: invalid {component}: {reason}")]
+    InvalidComponent { asset_id: String, template_id: String, path: String, component: &'static str, reason: String },
+    #[error("Asset '{asset_id}' (template '{template_id}') at {path}: {component} is not allowed on a {asset_type:?}")]
+    ComponentNotAllowed { asset_id: String, template_id: String, path: String, component: &'static str, asset_type: EAssetType },
+    #[error("Asset '{asset_id}' (template '{template_id}') at {path}: a {asset_type:?} requires {component}")]
+    MissingComponent { asset_id: String, template_id: String, path: String, component: &'static str, asset_type: EAssetType },
+    #[error("Asset '{asset_id}' (template '{template_id}') at {path}: unknown register map '{key}'")]
+    UnknownRegisterMap { asset_id: String, template_id: String, path: String, key: String },
+    #[error("Asset '{asset_id}' (template '{template_id}') at {path}: the asset has no {component} to override")]
+    UnknownOverride { asset_id: String, template_id: String, path: String, component: String },
+    #[error("Template '{template_id}' at {path}: unknown parent template '{parent_id}'")]
+    UnknownParentTemplate { template_id: String, path: String, parent_id: String },
+    #[error("Template '{template_id}' at {path}: inheritance cycle {cycle}")]
+    TemplateCycle { template_id: String, path: String, cycle: String },
+    #[error("Template '{template_id}' at {path}: no asset_type on the template or its parents")]
+    MissingAssetType { template_id: String, path: String },
+    #[error("Asset group '{group_id}' at {path}: no asset '{asset_id}'")]
+    UnknownGroupMember { group_id: String, path: String, asset_id: String },
+    #[error("No asset '{asset_id}'")]
+    UnknownAsset { asset_id: String },
+    #[error("Asset '{asset_id}' comes from a file included by {path}; change it in that file")]
+    AssetInIncludedFile { asset_id: String, path: String },
+
+    // Site config files
+    #[error("Failed to read config file {path}: {reason}")]
+    ConfigFileUnreadable { path: String, reason: String },
+    #[error("Config file {path} has an unsupported extension; expected .json, .toml, .yaml or .yml")]
+    UnsupportedConfigFormat { path: String },
+    #[error("Failed to parse config file {path}: {reason}")]
+    ConfigFileInvalid { path: String, reason: String },
+    #[error("Config file {path} uses environment variable '{name}', which is not set and has no default")]
+    UndefinedEnvVar { path: String, name: String },
+    #[error("Config file {path} is included in a cycle: {cycle}")]
+    IncludeCycle { path: String, cycle: String },
 }
@@ -41,3 +41,27 @@ impl FromStr for EMeteringDataSource {
         }
     }
 }
+
+/// Conductor a measurement was taken on; phase-to-neutral values count as their phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub enum EPhase {
+    L1,
+    L2,
+    L3,
+    N,
+}
+
+impl FromStr for EPhase {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "L1" | "L1-N" => Ok(EPhase::L1),
+            "L2" | "L2-N" => Ok(EPhase::L2),
+            "L3" | "L3-N" => Ok(EPhase::L3),
+            "N" => Ok(EPhase::N),
+            _ => Err(()),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
-use crate::common::types::{EMeteringDataSource}; 
+use crate::common::types::{EMeteringDataSource, EPhase}; 
 use chrono::{DateTime, Utc};
 
 #[derive(Component, Debug, Clone, Reflect, Serialize, Deserialize)]
@@ -22,13 +22,124 @@ pub struct CurrentMeterReading {
     #[reflect(ignore)] 
     #[serde(with = "chrono::serde::ts_seconds")]
     pub timestamp: DateTime<Utc>, 
+    /// Per-connector split of the reading for assets metered connector by connector; empty otherwise.
+    pub connectors: Vec<ConnectorMeterReading>,
+    #[serde(flatten)]
+    pub measurements: ElectricalMeasurements,
 }
 
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize, Default, PartialEq)]
+#[reflect(Serialize, Deserialize, Default)]
+pub struct ConnectorMeterReading {
+    pub connector_id: u32,
+    pub power_kw: f32,
+    pub energy_kwh: f64,
+    /// Charger time of the newest sample applied to the connector.
+    #[reflect(ignore)]
+    #[serde(skip)]
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub measurements: ElectricalMeasurements,
+}
 
-#[derive(Component, Debug, Clone, Copy, Reflect, Serialize, Deserialize, Default)]
+/// Measurements beyond active power and energy; each stays `None` (or empty) until the asset reports it.
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize, Default, PartialEq)]
+#[reflect(Serialize, Deserialize, Default)]
+pub struct ElectricalMeasurements {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<PhaseMeterReading>,
+    /// Current and voltage reported without a phase.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_a: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voltage_v: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reactive_power_kvar: Option<f32>,
+    /// Battery or vehicle state of charge.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soc_percent: Option<f32>,
+    /// Current a charger offers the vehicle, i.e. the limit it is actually applying.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offered_current_a: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offered_power_kw: Option<f32>,
+    /// Exported active energy register, for assets that feed back into the site.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exported_energy_kwh: Option<f32>,
+    /// Net reactive energy register (import minus export).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reactive_energy_kvarh: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_hz: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_factor: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature_c: Option<f32>,
+}
+
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize, PartialEq)]
+#[reflect(Serialize, Deserialize)]
+pub struct PhaseMeterReading {
+    pub phase: EPhase,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_kw: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_a: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voltage_v: Option<f32>,
+}
+
+impl ElectricalMeasurements {
+    /// Entry for `phase`, added in phase order if the phase has not been reported before.
+    pub fn phase_mut(&mut self, phase: EPhase) -> &mut PhaseMeterReading {
+        let index = match self.phases.iter().position(|p| p.phase == phase) {
+            Some(index) => index,
+            None => {
+                self.phases.push(PhaseMeterReading { phase, power_kw: None, current_a: None, voltage_v: None });
+                self.phases.sort_by_key(|p| p.phase as u8);
+                self.phases.iter().position(|p| p.phase == phase).unwrap()
+            }
+        };
+        &mut self.phases[index]
+    }
+}
+
+
+/// Setpoint as the balancer asked for it, before site limits turn it into `TargetPowerSetpointKw`.
+#[derive(Component, Debug, Clone, Copy, Reflect, Serialize, Deserialize, Default, PartialEq)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+pub struct RequestedSetpointKw(pub f32);
+
+#[derive(Component, Debug, Clone, Copy, Reflect, Serialize, Deserialize, Default, PartialEq)]
 #[reflect(Component, Serialize, Deserialize, Default)]
 pub struct TargetPowerSetpointKw(pub f32);
 
+/// One slot of a setpoint schedule, running until the next slot starts; the last one runs on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchedulePeriod {
+    pub start: DateTime<Utc>,
+    /// `start` on the `Time::elapsed_secs_f64` clock, fixed when the schedule arrives.
+    pub start_secs: f64,
+    pub power_kw: f32,
+}
+
+/// Setpoint plan from the balancer, ordered by start. Replaced whole by each new schedule.
+#[derive(Component, Debug, Clone, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct SetpointSchedule {
+    #[reflect(ignore)]
+    pub periods: Vec<SchedulePeriod>,
+    /// Index of the slot last applied as the requested setpoint.
+    pub active_period: Option<usize>,
+}
+
+impl SetpointSchedule {
+    /// Index of the slot in force at `now_secs`, if the schedule has started.
+    pub fn period_at(&self, now_secs: f64) -> Option<usize> {
+        self.periods.iter().rposition(|period| period.start_secs <= now_secs)
+    }
+}
+
 #[derive(Component, Debug, Clone, Copy, Reflect, Serialize, Deserialize, Default)]
 #[reflect(Component, Serialize, Deserialize, Default)]
 pub struct LastAppliedSetpointKw(pub f32);
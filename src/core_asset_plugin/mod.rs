@@ -6,6 +6,15 @@ pub mod components;
 
 pub use components::*;
 
+/// Stages a setpoint passes through within one frame: requested by the balancer, constrained to
+/// site limits, then dispatched to the asset protocols.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ESetpointFlow {
+    Request,
+    Constrain,
+    Dispatch,
+}
+
 pub struct CoreAssetPlugin;
 
 impl Plugin for CoreAssetPlugin {
@@ -15,9 +24,15 @@ impl Plugin for CoreAssetPlugin {
             .register_type::<EAssetType>()
             .register_type::<EOperationalStatus>()
             .register_type::<components::CurrentMeterReading>()
+            .register_type::<components::ConnectorMeterReading>()
+            .register_type::<components::ElectricalMeasurements>()
+            .register_type::<components::PhaseMeterReading>()
+            .register_type::<components::RequestedSetpointKw>()
             .register_type::<components::TargetPowerSetpointKw>()
+            .register_type::<components::SetpointSchedule>()
             .register_type::<components::LastAppliedSetpointKw>()
-            .register_type::<components::MeteringSource>();
+            .register_type::<components::MeteringSource>()
+            .configure_sets(Update, (ESetpointFlow::Request, ESetpointFlow::Constrain, ESetpointFlow::Dispatch).chain());
         
         // Debug‐log setpoint changes only in debug mode
         #[cfg(debug_assertions)]
@@ -26,6 +41,7 @@ impl Plugin for CoreAssetPlugin {
 }
 
 #[cfg(debug_assertions)]
+#[allow(clippy::type_complexity)]
 fn debug_core_assets_system(
     query: Query<(
         Entity,
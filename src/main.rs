@@ -1,9 +1,40 @@
 use bevy::prelude::*;
 use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppMode};
+use ocpp_bevy_poc::asset_template_plugin::{load_site_config, watch_site_config_file, SiteConfigPersistPath};
+use ocpp_bevy_poc::common::error::AppError;
+use ocpp_bevy_poc::modbus_protocol_plugin::{ModbusBridge, ModbusRegisterMaps};
+use ocpp_bevy_poc::ocpp_protocol_plugin::{OcppChargePointVersions, OcppServer};
 use ocpp_bevy_poc::visualization_plugin::log_capture;
 use std::env;
-use std::fs;
-use tracing_subscriber;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Site config loaded at startup, unless `--config <path>` names another, and watched for changes while running.
+const DEFAULT_SITE_CONFIG_PATH: &str = "assets/site_config.json";
+/// How often the site config file is checked for changes.
+const SITE_CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Address the OCPP-J central system listens on; charge points connect to `ws://<addr>/<charge_point_id>`.
+const OCPP_SERVER_ADDR: &str = "0.0.0.0:9000";
+
+/// The site config named by `--config <path>` or `--config=<path>`, or the default one.
+fn site_config_path() -> PathBuf {
+    let args: Vec<String> = env::args().collect();
+    let path = args.iter().enumerate().find_map(|(i, arg)| match arg.strip_prefix("--config") {
+        Some("") => args.get(i + 1).cloned(),
+        Some(rest) => rest.strip_prefix('=').map(str::to_string),
+        None => None,
+    });
+    PathBuf::from(path.unwrap_or_else(|| DEFAULT_SITE_CONFIG_PATH.to_string()))
+}
+
+fn exit_with_config_errors(path: &Path, errors: &[AppError]) -> ! {
+    for e in errors {
+        error!("{}", e);
+    }
+    error!("{} has {} error(s); exiting", path.display(), errors.len());
+    std::process::exit(1);
+}
 
 fn main() {
     // Determine app mode from command-line arguments.
@@ -25,15 +56,59 @@ fn main() {
 
     info!("Starting Site Controller ECS POC...");
 
-    let config_json = fs::read_to_string("assets/site_config.json")
-        .expect("Failed to read site_config.json");
-    let (mut app, _app_external_channel_ends) = setup_bevy_app(config_json, app_mode, log_receiver);
-    
+    let config_path = site_config_path();
+    info!("Loading site config from {}", config_path.display());
+    let loaded_config = load_site_config(&config_path).unwrap_or_else(|errors| exit_with_config_errors(&config_path, &errors));
+    let is_headless = app_mode == AppMode::Headless;
+    let (mut app, app_external_channel_ends) = setup_bevy_app(loaded_config.json, app_mode, log_receiver)
+        .unwrap_or_else(|errors| exit_with_config_errors(&config_path, &errors));
+
+    // Edits to the site config are applied to the running app, and assets registered at runtime are saved back to it
+    app.insert_resource(SiteConfigPersistPath(config_path.clone()));
+    let site_config_reload_sender = app_external_channel_ends.site_config_reload_sender.clone();
+    std::thread::Builder::new()
+        .name("site-config-watcher".to_string())
+        .spawn(move || watch_site_config_file(config_path, site_config_reload_sender, SITE_CONFIG_POLL_INTERVAL))
+        .expect("Failed to spawn site config watcher thread");
+
+    // In visual mode the egui panel plays the part of the assets and drains the asset-facing queues itself.
+    if is_headless {
+        let ocpp_from_asset_sender = app_external_channel_ends.ocpp_from_asset_sender.clone();
+        let ocpp_to_asset_receiver = app_external_channel_ends.ocpp_to_asset_receiver.clone();
+        let ocpp_response_from_asset_sender = app_external_channel_ends.ocpp_response_from_asset_sender.clone();
+        let modbus_request_receiver = app_external_channel_ends.modbus_request_receiver.clone();
+        let modbus_response_sender = app_external_channel_ends.modbus_response_sender.clone();
+        let modbus_write_request_receiver = app_external_channel_ends.modbus_write_request_receiver.clone();
+        let modbus_write_response_sender = app_external_channel_ends.modbus_write_response_sender.clone();
+        let ocpp_versions = app.world().resource::<OcppChargePointVersions>().clone();
+        let register_maps = app.world().resource::<ModbusRegisterMaps>().clone();
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Runtime::new().expect("Failed to start tokio runtime");
+            runtime.block_on(async {
+                let modbus_bridge = ModbusBridge::new(register_maps);
+                tokio::spawn(modbus_bridge.run(
+                    modbus_request_receiver,
+                    modbus_response_sender,
+                    modbus_write_request_receiver,
+                    modbus_write_response_sender,
+                ));
+
+                match OcppServer::bind(OCPP_SERVER_ADDR, ocpp_versions).await {
+                    Ok(server) => server.run(ocpp_from_asset_sender, ocpp_response_from_asset_sender, ocpp_to_asset_receiver).await,
+                    Err(e) => error!("Failed to bind OCPP server on {}: {}", OCPP_SERVER_ADDR, e),
+                }
+            });
+        });
+    }
+
     // In a real production app, we would now spawn threads/tasks
-    // to manage app_external_channel_ends:
+    // to manage the remaining app_external_channel_ends:
     // - app_external_channel_ends.balancer_setpoint_sender_to_bevy:
     //   An external component (e.g., HTTP server, Kafka consumer) would use this
     //   to send setpoints into the Bevy app.
+    // - app_external_channel_ends.asset_registry_command_sender:
+    //   A commissioning tool would use this to add, remove and update assets on site,
+    //   reading the outcome of each command from asset_registry_result_receiver.
     // - app_external_channel_ends.metering_receiver_from_bevy:
     //   An external component (e.g., Kafka producer, database writer) would use this
     //   to receive metering data from the Bevy app.
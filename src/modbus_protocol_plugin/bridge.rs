@@ -0,0 +1,255 @@
+// Modbus TCP bridge.
+// Drains ModbusRequest / ModbusWriteRequest from ECS, performs the register reads or setpoint writes
+// against the device and pushes ModbusResponse / ModbusWriteResponse back.
+
+use bevy::log::{debug, info, warn};
+use chrono::Utc;
+use crossbeam_channel::{Receiver, Sender};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_modbus::client::{tcp, Context, Reader, Writer};
+use tokio_modbus::prelude::SlaveContext;
+use tokio_modbus::Slave;
+
+use super::components::{ModbusRequest, ModbusResponse, ModbusWriteRequest, ModbusWriteResponse};
+use super::register_map::{
+    decode_register_value, encode_setpoint, EModbusMeasurement, EModbusRegisterKind, ModbusRegisterMap, ModbusWriteRegisterMap,
+};
+use super::resources::ModbusRegisterMaps;
+use crate::common::error::AppError;
+
+/// Upper bound on a single connect or read before the connection is considered dead.
+const MODBUS_IO_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Work queued for a single device connection.
+enum DeviceJob {
+    Read(ModbusRequest),
+    Write(ModbusWriteRequest),
+}
+
+impl DeviceJob {
+    fn endpoint(&self) -> (String, u16) {
+        match self {
+            DeviceJob::Read(request) => (request.ip.clone(), request.port),
+            DeviceJob::Write(request) => (request.ip.clone(), request.port),
+        }
+    }
+}
+
+/// Where device tasks report results.
+#[derive(Clone)]
+struct ResponseSenders {
+    read: Sender<ModbusResponse>,
+    write: Sender<ModbusWriteResponse>,
+}
+
+pub struct ModbusBridge {
+    register_maps: ModbusRegisterMaps,
+}
+
+impl ModbusBridge {
+    /// Every device task looks its register maps up in `register_maps` per request, so changes reach it straight away.
+    pub fn new(register_maps: ModbusRegisterMaps) -> Self {
+        Self { register_maps }
+    }
+
+    /// Serve requests until every read and write request sender is dropped.
+    /// Each `ip:port` gets its own task holding a persistent TCP connection, so a slow device
+    /// never delays polling of the others, and reads and writes to one device never interleave.
+    pub async fn run(
+        self,
+        requests: Receiver<ModbusRequest>,
+        responses: Sender<ModbusResponse>,
+        write_requests: Receiver<ModbusWriteRequest>,
+        write_responses: Sender<ModbusWriteResponse>,
+    ) {
+        let (job_tx, mut job_rx) = mpsc::unbounded_channel::<DeviceJob>();
+        forward_to_jobs("modbus-read-forward", requests, job_tx.clone(), DeviceJob::Read);
+        forward_to_jobs("modbus-write-forward", write_requests, job_tx, DeviceJob::Write);
+
+        let senders = ResponseSenders { read: responses, write: write_responses };
+        let mut devices: HashMap<(String, u16), mpsc::UnboundedSender<DeviceJob>> = HashMap::new();
+        while let Some(job) = job_rx.recv().await {
+            let device = devices.entry(job.endpoint()).or_insert_with_key(|(ip, port)| {
+                let (device_tx, device_rx) = mpsc::unbounded_channel();
+                tokio::spawn(run_device_connection(
+                    ip.clone(),
+                    *port,
+                    device_rx,
+                    self.register_maps.clone(),
+                    senders.clone(),
+                ));
+                device_tx
+            });
+            let _ = device.send(job);
+        }
+        debug!("Modbus request channels closed; bridge stopping");
+    }
+}
+
+/// Pump a blocking crossbeam receiver into the async job queue on a dedicated thread.
+fn forward_to_jobs<T: Send + 'static>(
+    name: &str,
+    receiver: Receiver<T>,
+    jobs: mpsc::UnboundedSender<DeviceJob>,
+    wrap: fn(T) -> DeviceJob,
+) {
+    std::thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || {
+            while let Ok(item) = receiver.recv() {
+                if jobs.send(wrap(item)).is_err() {
+                    break;
+                }
+            }
+        })
+        .expect("Failed to spawn Modbus request forwarding thread");
+}
+
+async fn with_io_timeout<T>(operation: impl Future<Output = Result<T, AppError>>) -> Result<T, AppError> {
+    tokio::time::timeout(MODBUS_IO_TIMEOUT, operation)
+        .await
+        .unwrap_or(Err(AppError::ModbusTimeout))
+}
+
+/// Process jobs for one device in order, reconnecting lazily after any transport failure.
+async fn run_device_connection(
+    ip: String,
+    port: u16,
+    mut jobs: mpsc::UnboundedReceiver<DeviceJob>,
+    register_maps: ModbusRegisterMaps,
+    senders: ResponseSenders,
+) {
+    let mut connection: Option<Context> = None;
+
+    while let Some(job) = jobs.recv().await {
+        let (external_id, unit_id) = match &job {
+            DeviceJob::Read(request) => (request.external_id.clone(), request.unit_id),
+            DeviceJob::Write(request) => (request.external_id.clone(), request.unit_id),
+        };
+
+        if connection.is_none() {
+            match with_io_timeout(connect(&ip, port)).await {
+                Ok(ctx) => {
+                    info!("Modbus connected to {}:{}", ip, port);
+                    connection = Some(ctx);
+                }
+                Err(e) => {
+                    warn!("Modbus connect to {}:{} failed: {}", ip, port, e);
+                    if let DeviceJob::Write(request) = &job {
+                        let _ = senders.write.send(write_response(request, false));
+                    }
+                    continue;
+                }
+            }
+        }
+        let Some(ctx) = connection.as_mut() else { continue };
+        ctx.set_slave(Slave(unit_id));
+
+        let outcome = match job {
+            DeviceJob::Read(request) => {
+                let Some(register_map) = register_maps.read_map(&request.register_map_key) else {
+                    warn!("Unknown register map '{}' for '{}'", request.register_map_key, request.external_id);
+                    continue;
+                };
+                match with_io_timeout(read_register_map(ctx, &register_map)).await {
+                    Ok(values) => {
+                        let _ = senders.read.send(response_from_values(&request, &values));
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            DeviceJob::Write(request) => {
+                let Some(write_map) = register_maps.write_map(&request.write_register_map_key) else {
+                    warn!("Unknown write register map '{}' for '{}'", request.write_register_map_key, request.external_id);
+                    let _ = senders.write.send(write_response(&request, false));
+                    continue;
+                };
+                let result = with_io_timeout(write_setpoint(ctx, &write_map, request.setpoint_kw)).await;
+                let _ = senders.write.send(write_response(&request, result.is_ok()));
+                result
+            }
+        };
+
+        match outcome {
+            Ok(()) => {}
+            Err(AppError::ModbusException(code)) => {
+                warn!("Modbus device {}:{} unit {} rejected request for '{}': {}", ip, port, unit_id, external_id, code);
+            }
+            Err(e) => {
+                warn!("Modbus request to {}:{} failed, dropping connection: {}", ip, port, e);
+                connection = None;
+            }
+        }
+    }
+}
+
+fn response_from_values(request: &ModbusRequest, values: &HashMap<EModbusMeasurement, f64>) -> ModbusResponse {
+    let mut response = ModbusResponse::new(
+        request.external_id.clone(),
+        request.request_id,
+        values.get(&EModbusMeasurement::PowerKw).copied().unwrap_or_default() as f32,
+        values.get(&EModbusMeasurement::EnergyKwh).copied().unwrap_or_default(),
+        Utc::now(),
+    );
+    response.soc_percent = values.get(&EModbusMeasurement::Soc).map(|v| *v as f32);
+    response.voltage_v = values.get(&EModbusMeasurement::Voltage).map(|v| *v as f32);
+    response.current_a = values.get(&EModbusMeasurement::Current).map(|v| *v as f32);
+    response
+}
+
+fn write_response(request: &ModbusWriteRequest, success: bool) -> ModbusWriteResponse {
+    ModbusWriteResponse {
+        external_id: request.external_id.clone(),
+        request_id: request.request_id,
+        setpoint_kw: request.setpoint_kw,
+        success,
+    }
+}
+
+async fn connect(ip: &str, port: u16) -> Result<Context, AppError> {
+    let addr = tokio::net::lookup_host((ip, port))
+        .await
+        .map_err(|e| AppError::ModbusTransport(e.into()))?
+        .next()
+        .ok_or_else(|| AppError::ModbusTransport(std::io::Error::from(std::io::ErrorKind::AddrNotAvailable).into()))?;
+    tcp::connect(addr).await.map_err(|e| AppError::ModbusTransport(e.into()))
+}
+
+async fn read_register_map(
+    ctx: &mut Context,
+    register_map: &ModbusRegisterMap,
+) -> Result<HashMap<EModbusMeasurement, f64>, AppError> {
+    let mut values = HashMap::new();
+    for mapping in &register_map.registers {
+        let count = mapping.data_type.register_count();
+        let words = match mapping.kind {
+            EModbusRegisterKind::Holding => ctx.read_holding_registers(mapping.address, count).await,
+            EModbusRegisterKind::Input => ctx.read_input_registers(mapping.address, count).await,
+        }?
+        .map_err(AppError::ModbusException)?;
+
+        if let Some(value) = decode_register_value(mapping, &words) {
+            values.insert(mapping.field, value);
+        }
+    }
+    Ok(values)
+}
+
+/// Write the enable and run-mode registers (when configured), then the setpoint itself.
+async fn write_setpoint(ctx: &mut Context, write_map: &ModbusWriteRegisterMap, setpoint_kw: f32) -> Result<(), AppError> {
+    for fixed in [write_map.enable, write_map.run_mode].into_iter().flatten() {
+        ctx.write_single_register(fixed.address, fixed.value).await?.map_err(AppError::ModbusException)?;
+    }
+
+    let address = write_map.setpoint.address;
+    let words = encode_setpoint(&write_map.setpoint, setpoint_kw);
+    match words.as_slice() {
+        [word] => ctx.write_single_register(address, *word).await,
+        _ => ctx.write_multiple_registers(address, &words).await,
+    }?
+    .map_err(AppError::ModbusException)
+}
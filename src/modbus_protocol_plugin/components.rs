@@ -8,22 +8,76 @@ pub struct ModbusControlConfig {
     pub ip: String,
     pub port: u16,
     pub unit_id: u8,
+    pub write_register_map_key: String,
 }
 
-#[derive(Component, Debug, Copy, Clone, Reflect, Default)]
+/// Per-asset repeating timer set from the metering source's `poll_interval_ms`.
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct ModbusPollTimer(pub Timer);
+
+/// A read sent to the bridge that has not been answered yet.
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct ModbusOutstandingRequest {
+    pub request_id: u64,
+    /// `Time::elapsed_secs_f64` when the request was sent.
+    pub sent_at_secs: f64,
+}
+
+/// Per-asset read correlation, retry and failure state.
+#[derive(Component, Debug, Clone, Default, Reflect)]
 #[reflect(Component, Default)]
-pub struct ModbusAssetLastPoll(pub f32);
+pub struct ModbusRequestTracker {
+    pub outstanding: Option<ModbusOutstandingRequest>,
+    /// When the next retry of a timed-out read is due, if one is scheduled.
+    pub retry_at_secs: Option<f64>,
+    /// Retries already made for the current poll.
+    pub retries: u32,
+    pub consecutive_failures: u32,
+}
+
+impl ModbusRequestTracker {
+    /// True while a read is in flight or waiting to be retried.
+    pub fn is_busy(&self) -> bool {
+        self.outstanding.is_some() || self.retry_at_secs.is_some()
+    }
+}
+
+/// A setpoint write sent to the bridge that has not been acknowledged yet.
+#[derive(Debug, Clone, Copy, Reflect)]
+pub struct ModbusOutstandingWrite {
+    pub request_id: u64,
+    pub setpoint_kw: f32,
+    /// `Time::elapsed_secs_f64` when the write was sent.
+    pub sent_at_secs: f64,
+}
+
+/// Per-asset setpoint write state: the write in flight and the retry of a failed or unanswered one.
+#[derive(Component, Debug, Clone, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct ModbusWriteTracker {
+    pub outstanding: Option<ModbusOutstandingWrite>,
+    /// When the target is next re-sent after a failed write, if one is scheduled.
+    pub retry_at_secs: Option<f64>,
+    /// Failed writes in a row since the device last acknowledged a setpoint.
+    pub retries: u32,
+}
 
 /// Represents a Modbus read request emitted by ECS.
 #[derive(Debug, Clone)]
 pub struct ModbusRequest {
     pub external_id: String,
+    /// Echoed back in the `ModbusResponse` so late answers to timed-out reads can be discarded.
+    pub request_id: u64,
     pub register_map_key: String,
+    pub ip: String,
+    pub port: u16,
+    pub unit_id: u8,
 }
 
 impl ModbusRequest {
-    pub fn new(external_id: String, register_map_key: String) -> Self {
-        Self { external_id, register_map_key }
+    pub fn new(external_id: String, request_id: u64, register_map_key: String, ip: String, port: u16, unit_id: u8) -> Self {
+        Self { external_id, request_id, register_map_key, ip, port, unit_id }
     }
 }
 
@@ -31,13 +85,40 @@ impl ModbusRequest {
 #[derive(Debug, Clone)]
 pub struct ModbusResponse {
     pub external_id: String,
+    pub request_id: u64,
     pub power_kw: f32,
     pub energy_kwh: f64,
     pub timestamp: DateTime<Utc>,
+    /// Only present when the asset's register map includes the value.
+    pub soc_percent: Option<f32>,
+    pub voltage_v: Option<f32>,
+    pub current_a: Option<f32>,
 }
 
 impl ModbusResponse {
-    pub fn new(external_id: String, power_kw: f32, energy_kwh: f64, timestamp: DateTime<Utc>) -> Self {
-        Self { external_id, power_kw, energy_kwh, timestamp }
+    pub fn new(external_id: String, request_id: u64, power_kw: f32, energy_kwh: f64, timestamp: DateTime<Utc>) -> Self {
+        Self { external_id, request_id, power_kw, energy_kwh, timestamp, soc_percent: None, voltage_v: None, current_a: None }
     }
 }
+
+/// Represents a Modbus setpoint write emitted by ECS.
+#[derive(Debug, Clone)]
+pub struct ModbusWriteRequest {
+    pub external_id: String,
+    /// Echoed back in the `ModbusWriteResponse` so late acknowledgements of superseded writes can be discarded.
+    pub request_id: u64,
+    pub write_register_map_key: String,
+    pub ip: String,
+    pub port: u16,
+    pub unit_id: u8,
+    pub setpoint_kw: f32,
+}
+
+/// The bridge's acknowledgement of a `ModbusWriteRequest`.
+#[derive(Debug, Clone)]
+pub struct ModbusWriteResponse {
+    pub external_id: String,
+    pub request_id: u64,
+    pub setpoint_kw: f32,
+    pub success: bool,
+}
@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use super::components::{ModbusRequest, ModbusResponse};
+use super::components::{ModbusRequest, ModbusResponse, ModbusWriteRequest, ModbusWriteResponse};
 use chrono::DateTime;
 
 /// Resource holding the sender for ModbusRequest.
@@ -10,22 +10,56 @@ pub struct ModbusRequestChannel(pub crossbeam_channel::Sender<ModbusRequest>);
 #[derive(Resource)]
 pub struct ModbusResponseChannel(pub crossbeam_channel::Receiver<ModbusResponse>);
 
-/// Emit this on each timer tick; drives ModbusRequest production.
+/// Resource holding the sender for ModbusWriteRequest.
+#[derive(Resource)]
+pub struct ModbusWriteRequestChannel(pub crossbeam_channel::Sender<ModbusWriteRequest>);
+
+/// Resource holding the receiver for ModbusWriteResponse.
+#[derive(Resource)]
+pub struct ModbusWriteResponseChannel(pub crossbeam_channel::Receiver<ModbusWriteResponse>);
+
+/// Emitted when an asset's poll timer fires; drives ModbusRequest production.
 #[derive(Event)]
-pub struct ModbusPollEvent;
+pub struct ModbusPollEvent {
+    pub entity: Entity,
+}
 
 /// Internal event for scheduling a Modbus read
 #[derive(Event)]
 pub struct ModbusRequestEvent {
     pub entity: Entity,
     pub register_map_key: String,
+    pub ip: String,
+    pub port: u16,
+    pub unit_id: u8,
 }
 
 /// Internal event carrying an incoming Modbus response
 #[derive(Event)]
 pub struct ModbusResponseEvent {
     pub external_id: String,
+    pub request_id: u64,
     pub power_kw: f32,
     pub energy_kwh: f64,
     pub timestamp: DateTime<chrono::Utc>,
+    pub soc_percent: Option<f32>,
+    pub voltage_v: Option<f32>,
+    pub current_a: Option<f32>,
+}
+
+/// Internal event for scheduling a Modbus setpoint write
+#[derive(Event)]
+pub struct ModbusWriteRequestEvent {
+    pub entity: Entity,
+    pub request_id: u64,
+    pub setpoint_kw: f32,
+}
+
+/// Internal event carrying a write acknowledgement from the bridge
+#[derive(Event)]
+pub struct ModbusWriteResponseEvent {
+    pub external_id: String,
+    pub request_id: u64,
+    pub setpoint_kw: f32,
+    pub success: bool,
 }
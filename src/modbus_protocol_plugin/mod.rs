@@ -1,30 +1,50 @@
 use bevy::prelude::*;
+use crate::core_asset_plugin::ESetpointFlow;
 
 pub mod components;
 pub mod events;
+pub mod resources;
 pub mod systems;
+pub mod register_map;
+pub mod bridge;
 
 pub use components::*;
 pub use events::*;
+pub use resources::*;
 pub use systems::*;
+pub use register_map::*;
+pub use bridge::ModbusBridge;
 
 pub struct ModbusProtocolPlugin;
 
 impl Plugin for ModbusProtocolPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<ModbusControlConfig>()
-           .register_type::<ModbusAssetLastPoll>()
-           .insert_resource(ModbusPollTimer(Timer::from_seconds(5.0, TimerMode::Repeating)))
+           .register_type::<ModbusPollTimer>()
+           .register_type::<ModbusRequestTracker>()
+           .register_type::<ModbusWriteTracker>()
+           .init_resource::<ModbusRetryPolicy>()
+           .init_resource::<NextModbusRequestId>()
            .add_event::<ModbusPollEvent>()
            .add_event::<ModbusRequestEvent>()
            .add_event::<ModbusResponseEvent>()
+           .add_event::<ModbusWriteRequestEvent>()
+           .add_event::<ModbusWriteResponseEvent>()
            .add_systems(Update, (
-               systems::modbus_poll_timer_system,
+               systems::init_modbus_poll_timers,
+               systems::modbus_poll_timer_system.after(systems::init_modbus_poll_timers),
                systems::schedule_modbus_requests_on_event.after(systems::modbus_poll_timer_system),
-               systems::send_modbus_requests_to_channel.after(systems::schedule_modbus_requests_on_event),
+               systems::retry_modbus_requests.after(systems::schedule_modbus_requests_on_event),
+               systems::send_modbus_requests_to_channel.after(systems::retry_modbus_requests),
                systems::ingest_modbus_responses.after(systems::send_modbus_requests_to_channel),
                systems::apply_modbus_responses.after(systems::ingest_modbus_responses),
-               systems::placeholder_modbus_control_system.after(systems::apply_modbus_responses),
+               systems::expire_modbus_requests.after(systems::apply_modbus_responses),
+               systems::init_modbus_write_trackers.after(systems::expire_modbus_requests),
+               systems::schedule_modbus_setpoint_writes.after(systems::init_modbus_write_trackers).in_set(ESetpointFlow::Dispatch),
+               systems::send_modbus_write_requests_to_channel.after(systems::schedule_modbus_setpoint_writes),
+               systems::ingest_modbus_write_responses.after(systems::send_modbus_write_requests_to_channel),
+               systems::apply_modbus_write_responses.after(systems::ingest_modbus_write_responses),
+               systems::expire_modbus_writes.after(systems::apply_modbus_write_responses),
            ));
         info!("ModbusProtocolPlugin loaded");
     }
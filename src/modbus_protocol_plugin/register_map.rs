@@ -0,0 +1,191 @@
+use serde::Deserialize;
+
+/// Which Modbus table a value is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum EModbusRegisterKind {
+    /// Function code 0x03
+    Holding,
+    /// Function code 0x04
+    Input,
+}
+
+/// How the raw register contents are interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum EModbusDataType {
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+impl EModbusDataType {
+    /// Number of 16-bit registers a value of this type spans.
+    pub fn register_count(&self) -> u16 {
+        match self {
+            EModbusDataType::U16 | EModbusDataType::I16 => 1,
+            EModbusDataType::U32 | EModbusDataType::I32 | EModbusDataType::F32 => 2,
+        }
+    }
+}
+
+/// Order of the registers making up a 32-bit value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+pub enum EModbusWordOrder {
+    #[default]
+    HighWordFirst,
+    LowWordFirst,
+}
+
+/// Order of the two bytes inside each register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+pub enum EModbusByteOrder {
+    #[default]
+    BigEndian,
+    LittleEndian,
+}
+
+/// The `ModbusResponse` field a register feeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EModbusMeasurement {
+    PowerKw,
+    EnergyKwh,
+    Soc,
+    Voltage,
+    Current,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// One value in a register map. The decoded value is `raw * scale + offset`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModbusRegisterMapping {
+    pub field: EModbusMeasurement,
+    pub address: u16,
+    pub kind: EModbusRegisterKind,
+    pub data_type: EModbusDataType,
+    #[serde(default)]
+    pub word_order: EModbusWordOrder,
+    #[serde(default)]
+    pub byte_order: EModbusByteOrder,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub offset: f64,
+}
+
+/// The registers read for one `register_map_key`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModbusRegisterMap {
+    pub registers: Vec<ModbusRegisterMapping>,
+}
+
+/// Decode the registers read for `mapping` into an engineering value.
+/// Returns `None` if the number of registers does not match the mapping's data type.
+pub fn decode_register_value(mapping: &ModbusRegisterMapping, words: &[u16]) -> Option<f64> {
+    if words.len() != mapping.data_type.register_count() as usize {
+        return None;
+    }
+
+    let mut ordered: Vec<u16> = words
+        .iter()
+        .map(|word| match mapping.byte_order {
+            EModbusByteOrder::BigEndian => *word,
+            EModbusByteOrder::LittleEndian => word.swap_bytes(),
+        })
+        .collect();
+    if mapping.word_order == EModbusWordOrder::LowWordFirst {
+        ordered.reverse();
+    }
+
+    let combined = ordered.iter().fold(0u32, |acc, word| (acc << 16) | *word as u32);
+    let raw = match mapping.data_type {
+        EModbusDataType::U16 => combined as u16 as f64,
+        EModbusDataType::I16 => combined as u16 as i16 as f64,
+        EModbusDataType::U32 => combined as f64,
+        EModbusDataType::I32 => combined as i32 as f64,
+        EModbusDataType::F32 => f32::from_bits(combined) as f64,
+    };
+    Some(raw * mapping.scale + mapping.offset)
+}
+
+/// How the device interprets the sign of a power setpoint.
+/// Internally a positive `TargetPowerSetpointKw` means importing (charging).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Default)]
+pub enum EModbusSignConvention {
+    #[default]
+    ChargePositive,
+    DischargePositive,
+}
+
+/// The holding register a power setpoint is written to. The raw value is `kw * scale`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModbusSetpointRegister {
+    pub address: u16,
+    pub data_type: EModbusDataType,
+    #[serde(default)]
+    pub word_order: EModbusWordOrder,
+    #[serde(default)]
+    pub byte_order: EModbusByteOrder,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub sign_convention: EModbusSignConvention,
+}
+
+/// A fixed value written before each setpoint, e.g. an enable flag or run mode.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ModbusFixedRegisterWrite {
+    pub address: u16,
+    pub value: u16,
+}
+
+/// The registers written for one `write_register_map_key`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModbusWriteRegisterMap {
+    pub setpoint: ModbusSetpointRegister,
+    #[serde(default)]
+    pub enable: Option<ModbusFixedRegisterWrite>,
+    #[serde(default)]
+    pub run_mode: Option<ModbusFixedRegisterWrite>,
+}
+
+/// Encode an engineering value into registers; the inverse of `decode_register_value` without scale or offset.
+/// Integer types are rounded and saturate at their range.
+pub fn encode_register_value(
+    data_type: EModbusDataType,
+    word_order: EModbusWordOrder,
+    byte_order: EModbusByteOrder,
+    raw: f64,
+) -> Vec<u16> {
+    let mut words = match data_type {
+        EModbusDataType::U16 => vec![raw.round() as u16],
+        EModbusDataType::I16 => vec![raw.round() as i16 as u16],
+        EModbusDataType::U32 => split_words(raw.round() as u32),
+        EModbusDataType::I32 => split_words(raw.round() as i32 as u32),
+        EModbusDataType::F32 => split_words((raw as f32).to_bits()),
+    };
+    if word_order == EModbusWordOrder::LowWordFirst {
+        words.reverse();
+    }
+    if byte_order == EModbusByteOrder::LittleEndian {
+        words.iter_mut().for_each(|word| *word = word.swap_bytes());
+    }
+    words
+}
+
+fn split_words(value: u32) -> Vec<u16> {
+    vec![(value >> 16) as u16, value as u16]
+}
+
+/// Registers to write for a setpoint in kW, after applying the device's sign convention and scale.
+pub fn encode_setpoint(register: &ModbusSetpointRegister, setpoint_kw: f32) -> Vec<u16> {
+    let signed_kw = match register.sign_convention {
+        EModbusSignConvention::ChargePositive => setpoint_kw as f64,
+        EModbusSignConvention::DischargePositive => -(setpoint_kw as f64),
+    };
+    encode_register_value(register.data_type, register.word_order, register.byte_order, signed_kw * register.scale)
+}
@@ -0,0 +1,71 @@
+use bevy::prelude::Resource;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use super::register_map::{ModbusRegisterMap, ModbusWriteRegisterMap};
+
+/// How long to wait for a Modbus read, how often to retry it, and when to give up on the asset.
+#[derive(Resource, Debug, Clone)]
+pub struct ModbusRetryPolicy {
+    /// A read unanswered for this long counts as a failure.
+    pub request_timeout: Duration,
+    /// Retries per poll after the first attempt times out.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for each further retry.
+    pub retry_backoff: Duration,
+    /// Consecutive failed reads after which the asset is marked `Offline`.
+    pub offline_after_failures: u32,
+}
+
+impl Default for ModbusRetryPolicy {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(4),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(500),
+            offline_after_failures: 3,
+        }
+    }
+}
+
+impl ModbusRetryPolicy {
+    /// Delay before retry number `retry` (1-based).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.retry_backoff * 2u32.saturating_pow(retry.saturating_sub(1))
+    }
+}
+
+/// Register maps by key, as the site config's `register_maps` and `write_register_maps` define them.
+#[derive(Debug, Clone, Default)]
+pub struct RegisterMaps {
+    pub read: HashMap<String, ModbusRegisterMap>,
+    pub write: HashMap<String, ModbusWriteRegisterMap>,
+}
+
+/// The site's register maps, shared with the `ModbusBridge` so that maps added or changed by a reload are used for the
+/// next request; `switch_site_config` keeps it in step with the site config.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct ModbusRegisterMaps(pub Arc<RwLock<RegisterMaps>>);
+
+impl ModbusRegisterMaps {
+    pub fn new(read: HashMap<String, ModbusRegisterMap>, write: HashMap<String, ModbusWriteRegisterMap>) -> Self {
+        Self(Arc::new(RwLock::new(RegisterMaps { read, write })))
+    }
+
+    pub fn replace(&self, read: HashMap<String, ModbusRegisterMap>, write: HashMap<String, ModbusWriteRegisterMap>) {
+        *self.0.write().unwrap() = RegisterMaps { read, write };
+    }
+
+    pub fn read_map(&self, key: &str) -> Option<ModbusRegisterMap> {
+        self.0.read().unwrap().read.get(key).cloned()
+    }
+
+    pub fn write_map(&self, key: &str) -> Option<ModbusWriteRegisterMap> {
+        self.0.read().unwrap().write.get(key).cloned()
+    }
+}
+
+/// Source of ids correlating `ModbusRequest`s with their `ModbusResponse`s, and `ModbusWriteRequest`s with their
+/// `ModbusWriteResponse`s.
+#[derive(Resource, Debug, Default)]
+pub struct NextModbusRequestId(pub u64);
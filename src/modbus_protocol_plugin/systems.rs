@@ -1,46 +1,122 @@
 use bevy::prelude::*;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::Duration;
 use crate::core_asset_plugin::{ExternalId, CurrentMeterReading, TargetPowerSetpointKw, LastAppliedSetpointKw};
-use crate::modbus_protocol_plugin::components::{ModbusControlConfig, ModbusRequest};
+use crate::modbus_protocol_plugin::components::{
+    ModbusControlConfig, ModbusOutstandingRequest, ModbusOutstandingWrite, ModbusPollTimer, ModbusRequest, ModbusRequestTracker,
+    ModbusWriteRequest, ModbusWriteTracker,
+};
+use crate::modbus_protocol_plugin::resources::{ModbusRetryPolicy, NextModbusRequestId};
 use super::{
     ModbusPollEvent, ModbusRequestEvent, ModbusResponseEvent,
     ModbusRequestChannel, ModbusResponseChannel,
+    ModbusWriteRequestEvent, ModbusWriteResponseEvent,
+    ModbusWriteRequestChannel, ModbusWriteResponseChannel,
 };
+use crate::common::external_id_map::ExternalIdMap;
 use crate::core_asset_plugin::MeteringSource;
 use crate::core_asset_plugin::components::MeteringSourceDetails;
-use crate::common::types::EMeteringDataSource;
+use crate::common::types::{EMeteringDataSource, EOperationalStatus};
+
+/// Give each Modbus-metered asset its own poll timer, running at the asset's `poll_interval_ms`, and rebuild it whenever
+/// the asset's metering source changes; assets no longer metered over Modbus lose their timer and read tracker.
+/// The first poll is staggered by a per-asset phase offset so assets sharing an interval do not poll in bursts.
+#[allow(clippy::type_complexity)]
+pub fn init_modbus_poll_timers(
+    mut commands: Commands,
+    query: Query<(Entity, &ExternalId, &MeteringSource, Has<ModbusPollTimer>), Or<(Changed<MeteringSource>, Without<ModbusPollTimer>)>>,
+    mut removed_sources: RemovedComponents<MeteringSource>,
+) {
+    for entity in removed_sources.read() {
+        if let Ok(mut entity_commands) = commands.get_entity(entity) {
+            entity_commands.try_remove::<(ModbusPollTimer, ModbusRequestTracker)>();
+        }
+    }
+    for (entity, id, source, has_timer) in query.iter() {
+        if let (
+            EMeteringDataSource::Modbus,
+            Some(MeteringSourceDetails::Modbus { poll_interval_ms, .. })
+        ) = (source.source_type, &source.details)
+        {
+            let interval = Duration::from_millis((*poll_interval_ms).max(1) as u64);
+            let mut timer = Timer::new(interval, TimerMode::Repeating);
+            timer.set_elapsed(interval - poll_phase_offset(&id.0, interval));
+            commands.entity(entity).insert((ModbusPollTimer(timer), ModbusRequestTracker::default()));
+        } else if has_timer {
+            commands.entity(entity).remove::<(ModbusPollTimer, ModbusRequestTracker)>();
+        }
+    }
+}
 
-/// Timer resource that triggers a Modbus poll every 5 sec.
-#[derive(Resource)]
-pub struct ModbusPollTimer(pub Timer);
+/// Deterministic offset in `(0, interval]` derived from the asset's external id.
+fn poll_phase_offset(external_id: &str, interval: Duration) -> Duration {
+    let mut hasher = DefaultHasher::new();
+    external_id.hash(&mut hasher);
+    let interval_ms = interval.as_millis() as u64;
+    Duration::from_millis(hasher.finish() % interval_ms + 1)
+}
 
-/// Each time the timer finishes, fire a `ModbusPollEvent`.
+/// Each time an asset's timer finishes, fire a `ModbusPollEvent` for it.
 pub fn modbus_poll_timer_system(
     time: Res<Time>,
-    mut timer: ResMut<ModbusPollTimer>,
+    mut query: Query<(Entity, &mut ModbusPollTimer)>,
     mut writer: EventWriter<ModbusPollEvent>,
 ) {
-    if timer.0.tick(time.delta()).just_finished() {
-        writer.write(ModbusPollEvent);
+    for (entity, mut timer) in query.iter_mut() {
+        if timer.0.tick(time.delta()).just_finished() {
+            writer.write(ModbusPollEvent { entity });
+        }
     }
 }
 
-/// On each `ModbusPollEvent`, enqueue a ModbusRequest for every Modbus‐enabled asset.
+/// Build the read request for a Modbus metering source.
+fn modbus_request_event(entity: Entity, source: &MeteringSource) -> Option<ModbusRequestEvent> {
+    match (source.source_type, &source.details) {
+        (
+            EMeteringDataSource::Modbus,
+            Some(MeteringSourceDetails::Modbus { register_map_key, ip, port, unit_id, .. }),
+        ) => Some(ModbusRequestEvent {
+            entity,
+            register_map_key: register_map_key.clone(),
+            ip: ip.clone(),
+            port: *port,
+            unit_id: *unit_id,
+        }),
+        _ => None,
+    }
+}
+
+/// On each `ModbusPollEvent`, enqueue a ModbusRequest for the polled asset,
+/// unless its previous read is still in flight or being retried.
 pub fn schedule_modbus_requests_on_event(
     mut poll_reader: EventReader<ModbusPollEvent>,
     mut writer: EventWriter<ModbusRequestEvent>,
-    query: Query<(Entity, &ExternalId, &MeteringSource)>,
+    query: Query<(&ExternalId, &MeteringSource, &ModbusRequestTracker)>,
+) {
+    for ev in poll_reader.read() {
+        let Ok((id, source, tracker)) = query.get(ev.entity) else { continue };
+        if tracker.is_busy() {
+            debug!("Skipping Modbus poll of '{}'; previous read still pending", id.0);
+            continue;
+        }
+        if let Some(request) = modbus_request_event(ev.entity, source) {
+            writer.write(request);
+        }
+    }
+}
+
+/// Re-send reads whose retry backoff has elapsed.
+pub fn retry_modbus_requests(
+    time: Res<Time>,
+    mut query: Query<(Entity, &MeteringSource, &mut ModbusRequestTracker)>,
+    mut writer: EventWriter<ModbusRequestEvent>,
 ) {
-    for _ in poll_reader.read() {
-        for (entity, _id, source) in query.iter() {
-            if let (
-                EMeteringDataSource::Modbus, 
-                Some(MeteringSourceDetails::Modbus{ register_map_key, .. })
-            ) = (source.source_type, &source.details)
-            {
-                writer.write(ModbusRequestEvent {
-                    entity,
-                    register_map_key: register_map_key.clone(),
-                });
+    let now = time.elapsed_secs_f64();
+    for (entity, source, mut tracker) in query.iter_mut() {
+        if tracker.retry_at_secs.is_some_and(|retry_at| retry_at <= now) {
+            tracker.retry_at_secs = None;
+            if let Some(request) = modbus_request_event(entity, source) {
+                writer.write(request);
             }
         }
     }
@@ -54,49 +130,238 @@ pub fn ingest_modbus_responses(
     while let Ok(resp) = channel.0.try_recv() {
         writer.write(ModbusResponseEvent {
             external_id: resp.external_id.clone(),
+            request_id: resp.request_id,
             power_kw: resp.power_kw,
             energy_kwh: resp.energy_kwh,
             timestamp: resp.timestamp,
+            soc_percent: resp.soc_percent,
+            voltage_v: resp.voltage_v,
+            current_a: resp.current_a,
         });
     }
 }
 
-/// Internal request events -> channel send
+/// Internal request events -> channel send, recording each as the asset's outstanding read.
 pub fn send_modbus_requests_to_channel(
     mut reader: EventReader<ModbusRequestEvent>,
     channel: Res<ModbusRequestChannel>,
+    time: Res<Time>,
+    mut next_id: ResMut<NextModbusRequestId>,
+    mut query: Query<(&ExternalId, &mut ModbusRequestTracker)>,
 ) {
     for event in reader.read() {
+        let Ok((id, mut tracker)) = query.get_mut(event.entity) else { continue };
+        next_id.0 += 1;
+        tracker.outstanding = Some(ModbusOutstandingRequest {
+            request_id: next_id.0,
+            sent_at_secs: time.elapsed_secs_f64(),
+        });
         let _ = channel.0.send(ModbusRequest::new(
-            // lookup ExternalId if needed; here we embed ID in request
-            event.entity.to_string(),
+            id.0.clone(),
+            next_id.0,
             event.register_map_key.clone(),
+            event.ip.clone(),
+            event.port,
+            event.unit_id,
         ));
     }
 }
 
-/// 5. Internal response events → component updates
+/// Internal response events → component updates.
+/// Only the answer to the asset's outstanding read is applied; a success brings an offline asset back online.
 pub fn apply_modbus_responses(
     mut reader: EventReader<ModbusResponseEvent>,
-    mut query: Query<(&ExternalId, &mut CurrentMeterReading)>,
+    id_map: Res<ExternalIdMap>,
+    mut query: Query<(&mut CurrentMeterReading, &mut ModbusRequestTracker, &mut EOperationalStatus)>,
 ) {
     for ev in reader.read() {
-        if let Some((_, mut reading)) = query.iter_mut()
-            .find(|(id, _)| id.0 == ev.external_id)
-        {
-            reading.power_kw   = ev.power_kw;
-            reading.energy_kwh = ev.energy_kwh;
-            reading.timestamp  = ev.timestamp;
+        let Some((mut reading, mut tracker, mut status)) = id_map.0.get(&ev.external_id).and_then(|&entity| query.get_mut(entity).ok()) else {
+            warn!("Modbus response for unknown asset '{}'", ev.external_id);
+            continue;
+        };
+        if tracker.outstanding.map(|outstanding| outstanding.request_id) != Some(ev.request_id) {
+            debug!("Discarding stale Modbus response {} for '{}'", ev.request_id, ev.external_id);
+            continue;
+        }
+
+        reading.power_kw   = ev.power_kw;
+        reading.energy_kwh = ev.energy_kwh;
+        reading.timestamp  = ev.timestamp;
+        // Every poll reads the whole map, so a value missing from this one is no longer known
+        reading.measurements.soc_percent = ev.soc_percent;
+        reading.measurements.voltage_v   = ev.voltage_v;
+        reading.measurements.current_a   = ev.current_a;
+
+        *tracker = ModbusRequestTracker::default();
+        if *status != EOperationalStatus::Online {
+            info!("Modbus asset '{}' is online", ev.external_id);
+            *status = EOperationalStatus::Online;
         }
     }
 }
 
-/// Placeholder control system for Modbus‐controlled assets.
-pub fn placeholder_modbus_control_system(
-    mut query: Query<(&ExternalId, &TargetPowerSetpointKw, &ModbusControlConfig, &mut LastAppliedSetpointKw), Changed<TargetPowerSetpointKw>>
+/// Fail reads that outlived the request timeout, schedule retries with exponential backoff,
+/// and mark the asset offline after too many consecutive failures.
+pub fn expire_modbus_requests(
+    time: Res<Time>,
+    policy: Res<ModbusRetryPolicy>,
+    mut query: Query<(&ExternalId, &mut ModbusRequestTracker, &mut EOperationalStatus)>,
 ) {
-    for (id, target, cfg, mut last) in query.iter_mut() {
-        info!("Modbus Control: {} kW to {}:{} unit {}", target.0, cfg.ip, cfg.port, id.0);
-        last.0 = target.0;
+    let now = time.elapsed_secs_f64();
+    for (id, mut tracker, mut status) in query.iter_mut() {
+        let Some(outstanding) = tracker.outstanding else { continue };
+        if now - outstanding.sent_at_secs < policy.request_timeout.as_secs_f64() {
+            continue;
+        }
+
+        tracker.outstanding = None;
+        tracker.consecutive_failures += 1;
+        warn!("Modbus read {} for '{}' timed out ({} consecutive failures)", outstanding.request_id, id.0, tracker.consecutive_failures);
+
+        if tracker.consecutive_failures >= policy.offline_after_failures && *status != EOperationalStatus::Offline {
+            warn!("Modbus asset '{}' is offline", id.0);
+            *status = EOperationalStatus::Offline;
+        }
+
+        if tracker.retries < policy.max_retries {
+            tracker.retries += 1;
+            tracker.retry_at_secs = Some(now + policy.backoff(tracker.retries).as_secs_f64());
+        } else {
+            // Give up on this poll; the next timer tick starts afresh.
+            tracker.retries = 0;
+        }
+    }
+}
+
+/// Give each Modbus-controlled asset a write tracker.
+pub fn init_modbus_write_trackers(
+    mut commands: Commands,
+    query: Query<Entity, (With<ModbusControlConfig>, Without<ModbusWriteTracker>)>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(ModbusWriteTracker::default());
+    }
+}
+
+/// Queue a setpoint write when a Modbus-controlled asset's target changes and differs from what the device last
+/// acknowledged or has in flight, and re-send the target once the backoff after a failed or unanswered write has elapsed.
+pub fn schedule_modbus_setpoint_writes(
+    time: Res<Time>,
+    mut next_id: ResMut<NextModbusRequestId>,
+    mut query: Query<(Entity, Ref<TargetPowerSetpointKw>, &LastAppliedSetpointKw, &mut ModbusWriteTracker), With<ModbusControlConfig>>,
+    mut writer: EventWriter<ModbusWriteRequestEvent>,
+) {
+    let now = time.elapsed_secs_f64();
+    for (entity, target, last, mut tracker) in query.iter_mut() {
+        // A write of another value still in flight would leave the device there once acknowledged
+        let other_write_in_flight = tracker.outstanding.is_some_and(|outstanding| outstanding.setpoint_kw != target.0);
+        if target.0 == last.0 && !other_write_in_flight {
+            if tracker.retry_at_secs.is_some() {
+                *tracker = ModbusWriteTracker::default();
+            }
+            continue;
+        }
+        let retry_due = tracker.retry_at_secs.is_some_and(|retry_at| retry_at <= now);
+        if !target.is_changed() && !retry_due {
+            continue;
+        }
+        tracker.retry_at_secs = None;
+        next_id.0 += 1;
+        tracker.outstanding = Some(ModbusOutstandingWrite { request_id: next_id.0, setpoint_kw: target.0, sent_at_secs: now });
+        writer.write(ModbusWriteRequestEvent { entity, request_id: next_id.0, setpoint_kw: target.0 });
+    }
+}
+
+/// Schedule the next attempt after a failed or unanswered write, backing off as for reads.
+fn schedule_write_retry(tracker: &mut ModbusWriteTracker, policy: &ModbusRetryPolicy, now: f64) {
+    tracker.outstanding = None;
+    tracker.retries += 1;
+    // Retries go on for as long as the target is not applied, so the delay stops growing at the read policy's last one
+    let delay = policy.backoff(tracker.retries.min(policy.max_retries.max(1)));
+    tracker.retry_at_secs = Some(now + delay.as_secs_f64());
+}
+
+/// Internal write events -> channel send
+pub fn send_modbus_write_requests_to_channel(
+    mut reader: EventReader<ModbusWriteRequestEvent>,
+    channel: Res<ModbusWriteRequestChannel>,
+    query: Query<(&ExternalId, &ModbusControlConfig)>,
+) {
+    for event in reader.read() {
+        if let Ok((id, cfg)) = query.get(event.entity) {
+            info!("Modbus Control: writing {} kW to {}:{} unit {} for '{}'", event.setpoint_kw, cfg.ip, cfg.port, cfg.unit_id, id.0);
+            let _ = channel.0.send(ModbusWriteRequest {
+                external_id: id.0.clone(),
+                request_id: event.request_id,
+                write_register_map_key: cfg.write_register_map_key.clone(),
+                ip: cfg.ip.clone(),
+                port: cfg.port,
+                unit_id: cfg.unit_id,
+                setpoint_kw: event.setpoint_kw,
+            });
+        }
+    }
+}
+
+/// Pull write acknowledgements from the bridge.
+pub fn ingest_modbus_write_responses(
+    channel: Res<ModbusWriteResponseChannel>,
+    mut writer: EventWriter<ModbusWriteResponseEvent>,
+) {
+    while let Ok(resp) = channel.0.try_recv() {
+        writer.write(ModbusWriteResponseEvent {
+            external_id: resp.external_id,
+            request_id: resp.request_id,
+            setpoint_kw: resp.setpoint_kw,
+            success: resp.success,
+        });
+    }
+}
+
+/// Record a setpoint as applied only once the device has acknowledged it; a failed write of the setpoint in flight
+/// is retried.
+pub fn apply_modbus_write_responses(
+    time: Res<Time>,
+    policy: Res<ModbusRetryPolicy>,
+    mut reader: EventReader<ModbusWriteResponseEvent>,
+    id_map: Res<ExternalIdMap>,
+    mut query: Query<(&mut LastAppliedSetpointKw, &mut ModbusWriteTracker), With<ModbusControlConfig>>,
+) {
+    let now = time.elapsed_secs_f64();
+    for ev in reader.read() {
+        let Some((mut last, mut tracker)) = id_map.0.get(&ev.external_id).and_then(|&entity| query.get_mut(entity).ok()) else {
+            warn!("Modbus write acknowledgement for unknown asset '{}'", ev.external_id);
+            continue;
+        };
+        // An acknowledgement of a superseded or timed-out write says nothing about the setpoint the device ends up with
+        if tracker.outstanding.map(|outstanding| outstanding.request_id) != Some(ev.request_id) {
+            debug!("Discarding stale Modbus write acknowledgement {} ({} kW) for '{}'", ev.request_id, ev.setpoint_kw, ev.external_id);
+            continue;
+        }
+        if ev.success {
+            last.0 = ev.setpoint_kw;
+            *tracker = ModbusWriteTracker::default();
+        } else {
+            schedule_write_retry(&mut tracker, &policy, now);
+            warn!("Modbus write of {} kW to '{}' failed; last applied remains {} kW, retry {} in {:.1}s",
+                ev.setpoint_kw, ev.external_id, last.0, tracker.retries, tracker.retry_at_secs.unwrap_or(now) - now);
+        }
+    }
+}
+
+/// Treat writes left unacknowledged for the request timeout as failed, and schedule their retry.
+pub fn expire_modbus_writes(
+    time: Res<Time>,
+    policy: Res<ModbusRetryPolicy>,
+    mut query: Query<(&ExternalId, &mut ModbusWriteTracker)>,
+) {
+    let now = time.elapsed_secs_f64();
+    for (id, mut tracker) in query.iter_mut() {
+        let Some(outstanding) = tracker.outstanding else { continue };
+        if now - outstanding.sent_at_secs < policy.request_timeout.as_secs_f64() {
+            continue;
+        }
+        schedule_write_retry(&mut tracker, &policy, now);
+        warn!("Modbus write of {} kW to '{}' was not acknowledged; retry {} scheduled", outstanding.setpoint_kw, id.0, tracker.retries);
     }
 }
@@ -2,6 +2,8 @@ use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use crate::ocpp_protocol_plugin::types::{EOcppVersion, EChargingRateUnit}; 
 use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::str::FromStr;
 
 #[derive(Component, Debug, Clone, Reflect, Serialize, Deserialize)]
 #[reflect(Component, Serialize, Deserialize)]
@@ -18,6 +20,10 @@ pub struct OcppConnectionState {
     #[serde(with = "chrono::serde::ts_seconds_option")]
     pub last_heartbeat_rcvd: Option<DateTime<Utc>>,
     pub ocpp_message_id_counter: u32, 
+    /// Heartbeat interval accepted in the BootNotification reply; 0 until the charger has booted.
+    pub heartbeat_interval_secs: u32,
+    /// `Time::elapsed_secs_f64` of the last message of any kind from the charger.
+    pub last_seen_secs: Option<f64>,
 }
 
 #[derive(Component, Debug, Clone, Reflect, Serialize, Deserialize)]
@@ -25,6 +31,9 @@ pub struct OcppConnectionState {
 pub struct OcppProfileBehavior {
     pub rate_unit: EChargingRateUnit, 
     pub profile_phases_in_ocpp_message: u8, 
+    /// Send the balancer's whole setpoint schedule as a multi-period profile rather than only the current limit.
+    #[serde(default)]
+    pub send_schedules: bool,
 }
 
 #[derive(Component, Debug, Clone, Reflect, Serialize, Deserialize)]
@@ -49,12 +58,66 @@ pub enum EGunStatusOcpp {
     Faulted,
 }
 
+#[derive(Debug, Clone, Copy, Reflect, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize, Default)]
+pub enum EConnectorType {
+    #[default]
+    Unspecified,
+    Type1,
+    Type2,
+    Ccs1,
+    Ccs2,
+    Chademo,
+}
+
+impl FromStr for EConnectorType {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Type1" => Ok(EConnectorType::Type1),
+            "Type2" => Ok(EConnectorType::Type2),
+            "Ccs1" => Ok(EConnectorType::Ccs1),
+            "Ccs2" => Ok(EConnectorType::Ccs2),
+            "Chademo" => Ok(EConnectorType::Chademo),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A charging session on one gun, from StartTransaction to StopTransaction (TransactionEvent Started to Ended in 2.0.1).
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize, PartialEq)]
+#[reflect(Serialize, Deserialize)]
+pub struct GunTransaction {
+    pub transaction_id: i32,
+    pub id_tag: String,
+    pub meter_start_wh: i32,
+    pub meter_stop_wh: Option<i32>,
+    #[reflect(ignore)]
+    pub start_time: DateTime<Utc>,
+    #[reflect(ignore)]
+    pub stop_time: Option<DateTime<Utc>>,
+    /// The id an OCPP 2.0.1 station gave the transaction; `transaction_id` is then only the central system's own.
+    #[serde(default)]
+    pub station_transaction_id: Option<String>,
+}
+
 #[derive(Debug, Clone, Reflect, Serialize, Deserialize, Default)]
 #[reflect(Serialize, Deserialize, Default)]
 pub struct Gun {
     pub gun_id: u32,
     pub connector_id: u32,
     pub status: EGunStatusOcpp,
+    pub connector_type: EConnectorType,
+    /// Per-phase current limit of the connector; `None` leaves the share uncapped.
+    pub max_current_a: Option<f32>,
+    /// Phases wired to the connector; `None` uses the charger's `active_phase_count`.
+    pub phases: Option<u8>,
+    /// Share of the charger setpoint last sent for this gun's session; 0 while idle.
+    pub allocated_kw: f32,
+    /// The session in progress on this gun, if any.
+    pub transaction: Option<GunTransaction>,
+    /// The most recently stopped session, kept for reporting.
+    pub last_transaction: Option<GunTransaction>,
 }
 
 #[derive(Component, Debug, Clone, Reflect, Serialize, Deserialize, Default)]
@@ -91,3 +154,56 @@ pub enum GenericChargerInitProgress {
 #[derive(Component, Debug, Default, Clone, Copy, Reflect, Serialize, Deserialize)]
 #[reflect(Component, Serialize, Deserialize, Default)]
 pub struct GenericChargerInitializationStatus(pub GenericChargerInitProgress);
+
+/// How a charger answered a CALL the central system sent it.
+#[derive(Debug, Clone, Reflect, Serialize, Deserialize, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum EOcppCallOutcome {
+    Accepted,
+    Rejected,
+    NotSupported,
+    RebootRequired,
+    /// CALLERROR with this error code, or a CALLRESULT whose payload could not be parsed.
+    CallError(String),
+    /// No reply within `OcppCallTimeout`.
+    TimedOut,
+}
+
+/// Latest outcome of each kind of command sent to a charger.
+#[derive(Component, Debug, Clone, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+pub struct OcppCallOutcomes {
+    pub set_charging_profile: Option<EOcppCallOutcome>,
+    pub remote_start_transaction: Option<EOcppCallOutcome>,
+    /// Keyed by configuration key.
+    pub change_configuration: HashMap<String, EOcppCallOutcome>,
+}
+
+impl OcppCallOutcomes {
+    /// True if any configuration change only takes effect after a reboot.
+    pub fn reboot_required(&self) -> bool {
+        self.change_configuration.values().any(|outcome| *outcome == EOcppCallOutcome::RebootRequired)
+    }
+}
+
+/// The power setpoint carried by the charger's unanswered SetChargingProfile, if any.
+/// It becomes `LastAppliedSetpointKw` only once the charger accepts every profile it was split into.
+#[derive(Component, Debug, Clone, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+pub struct OcppPendingSetpoint {
+    /// SetChargingProfile requests still awaiting a reply, one per connector the setpoint was split across.
+    pub ocpp_message_ids: Vec<String>,
+    pub setpoint_kw: f32,
+    /// Set once any of those profiles is not accepted.
+    pub failed: bool,
+}
+
+/// Variables an OCPP 2.0.1 station reported in NotifyReport, with their `Actual` value. Keyed
+/// `Component[.instance][[evse]]/Variable[.instance]`, e.g. `OCPPCommCtrlr/HeartbeatInterval` or `EVSE[1]/Power`.
+#[derive(Component, Debug, Clone, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+pub struct Ocpp201DeviceModel {
+    pub variables: HashMap<String, String>,
+    /// Set once the last part of a report has arrived.
+    pub report_complete: bool,
+}
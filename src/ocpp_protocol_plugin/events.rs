@@ -1,6 +1,9 @@
 use bevy::prelude::*;
+use crate::ocpp_protocol_plugin::messages::EChargePointRequest;
+use crate::ocpp_protocol_plugin::types_v201::EChargingStationRequest;
 use crate::ocpp_protocol_plugin::types::EOutgoingOcppMessage; 
 
+/// A CALL from a charger as it arrived on the wire; decoded into an `OcppCallFromAsset`.
 #[derive(Event, Debug, Clone)]
 pub struct OcppRequestFromAsset {
     pub charge_point_id: String,
@@ -9,6 +12,38 @@ pub struct OcppRequestFromAsset {
     pub ocpp_message_id: String, 
 }
 
+/// A charger's CALL whose action and payload passed decoding.
+#[derive(Event, Debug, Clone)]
+pub struct OcppCallFromAsset {
+    pub charge_point_id: String,
+    pub ocpp_message_id: String,
+    pub request: EChargePointRequest,
+}
+
+/// An OCPP 2.0.1 station's CALL whose action and payload passed decoding.
+#[derive(Event, Debug, Clone)]
+pub struct Ocpp201CallFromAsset {
+    pub charge_point_id: String,
+    pub ocpp_message_id: String,
+    pub request: EChargingStationRequest,
+}
+
+/// A charger's reply to a CALL the central system sent it.
+#[derive(Event, Debug, Clone)]
+pub struct OcppResponseFromAsset {
+    pub charge_point_id: String,
+    pub ocpp_message_id: String,
+    pub response: EOcppCallResponse,
+}
+
+#[derive(Debug, Clone)]
+pub enum EOcppCallResponse {
+    /// CALLRESULT
+    Result { payload_json: String },
+    /// CALLERROR
+    Error { error_code: String, error_description: String },
+}
+
 #[derive(Event, Debug, Clone)]
 pub struct OcppCommandToAsset {
     pub charge_point_id: String,
@@ -19,5 +54,8 @@ pub struct OcppCommandToAsset {
 #[derive(Resource)]
 pub struct OcppFromAssetChannel(pub crossbeam_channel::Receiver<OcppRequestFromAsset>);
 
+#[derive(Resource)]
+pub struct OcppResponseFromAssetChannel(pub crossbeam_channel::Receiver<OcppResponseFromAsset>);
+
 #[derive(Resource)]
 pub struct OcppToAssetChannel(pub crossbeam_channel::Sender<OcppCommandToAsset>);
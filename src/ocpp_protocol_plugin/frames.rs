@@ -0,0 +1,142 @@
+// OCPP-J (JSON over WebSocket) RPC framing, as described in the OCPP 1.6J specification §4.
+
+use serde_json::{json, Value};
+
+pub const CALL_MESSAGE_TYPE_ID: u64 = 2;
+pub const CALL_RESULT_MESSAGE_TYPE_ID: u64 = 3;
+pub const CALL_ERROR_MESSAGE_TYPE_ID: u64 = 4;
+
+/// A single OCPP-J RPC frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EOcppJFrame {
+    /// `[2, "<messageId>", "<action>", {payload}]`
+    Call {
+        message_id: String,
+        action: String,
+        payload: Value,
+    },
+    /// `[3, "<messageId>", {payload}]`
+    CallResult {
+        message_id: String,
+        payload: Value,
+    },
+    /// `[4, "<messageId>", "<errorCode>", "<errorDescription>", {errorDetails}]`
+    CallError {
+        message_id: String,
+        error_code: String,
+        error_description: String,
+        error_details: Value,
+    },
+}
+
+/// Why a text frame could not be turned into an `EOcppJFrame`.
+/// Carries the message type and id when they could be recovered, so a malformed CALL can still be answered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OcppJFrameError {
+    pub message_type_id: Option<u64>,
+    pub message_id: Option<String>,
+    pub error_code: &'static str,
+    pub description: String,
+}
+
+impl EOcppJFrame {
+    pub fn message_id(&self) -> &str {
+        match self {
+            EOcppJFrame::Call { message_id, .. }
+            | EOcppJFrame::CallResult { message_id, .. }
+            | EOcppJFrame::CallError { message_id, .. } => message_id,
+        }
+    }
+
+    /// Parse a WebSocket text frame.
+    pub fn parse(text: &str) -> Result<Self, OcppJFrameError> {
+        let value: Value = serde_json::from_str(text).map_err(|e| OcppJFrameError {
+            message_type_id: None,
+            message_id: None,
+            error_code: "ProtocolError",
+            description: format!("Frame is not valid JSON: {}", e),
+        })?;
+
+        let Some(items) = value.as_array() else {
+            return Err(OcppJFrameError {
+                message_type_id: None,
+                message_id: None,
+                error_code: "ProtocolError",
+                description: "Frame is not a JSON array".to_string(),
+            });
+        };
+
+        let message_type_id = items.first().and_then(Value::as_u64);
+        let message_id = items.get(1).and_then(Value::as_str).map(str::to_string);
+        let frame_error = |error_code: &'static str, description: &str| OcppJFrameError {
+            message_type_id,
+            message_id: message_id.clone(),
+            error_code,
+            description: description.to_string(),
+        };
+
+        let Some(id) = message_id.clone() else {
+            return Err(frame_error("ProtocolError", "Missing or non-string message id"));
+        };
+
+        match message_type_id {
+            Some(CALL_MESSAGE_TYPE_ID) => match (items.get(2).and_then(Value::as_str), items.get(3)) {
+                (Some(action), Some(payload)) if items.len() == 4 => Ok(EOcppJFrame::Call {
+                    message_id: id,
+                    action: action.to_string(),
+                    payload: payload.clone(),
+                }),
+                _ => Err(frame_error("FormationViolation", "CALL must be [2, id, action, payload]")),
+            },
+            Some(CALL_RESULT_MESSAGE_TYPE_ID) => match items.get(2) {
+                Some(payload) if items.len() == 3 => Ok(EOcppJFrame::CallResult {
+                    message_id: id,
+                    payload: payload.clone(),
+                }),
+                _ => Err(frame_error("FormationViolation", "CALLRESULT must be [3, id, payload]")),
+            },
+            Some(CALL_ERROR_MESSAGE_TYPE_ID) => match (
+                items.get(2).and_then(Value::as_str),
+                items.get(3).and_then(Value::as_str),
+                items.get(4),
+            ) {
+                (Some(code), Some(description), Some(details)) if items.len() == 5 => Ok(EOcppJFrame::CallError {
+                    message_id: id,
+                    error_code: code.to_string(),
+                    error_description: description.to_string(),
+                    error_details: details.clone(),
+                }),
+                _ => Err(frame_error("FormationViolation", "CALLERROR must be [4, id, code, description, details]")),
+            },
+            _ => Err(frame_error("ProtocolError", "Unknown message type id")),
+        }
+    }
+
+    /// Serialize to the JSON array sent over the WebSocket.
+    pub fn to_text(&self) -> String {
+        let value = match self {
+            EOcppJFrame::Call { message_id, action, payload } => {
+                json!([CALL_MESSAGE_TYPE_ID, message_id, action, payload])
+            }
+            EOcppJFrame::CallResult { message_id, payload } => {
+                json!([CALL_RESULT_MESSAGE_TYPE_ID, message_id, payload])
+            }
+            EOcppJFrame::CallError { message_id, error_code, error_description, error_details } => {
+                json!([CALL_ERROR_MESSAGE_TYPE_ID, message_id, error_code, error_description, error_details])
+            }
+        };
+        value.to_string()
+    }
+}
+
+impl From<OcppJFrameError> for EOcppJFrame {
+    fn from(err: OcppJFrameError) -> Self {
+        EOcppJFrame::CallError {
+            // The spec allows "-1" when the id of the offending message cannot be read.
+            message_id: err.message_id.unwrap_or_else(|| "-1".to_string()),
+            error_code: err.error_code.to_string(),
+            error_description: err.description,
+            error_details: json!({}),
+        }
+    }
+}
@@ -0,0 +1,278 @@
+// Typed OCPP 1.6 actions, and decoding of charger-initiated CALLs with the schema's constraints checked.
+
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use super::types::*;
+
+/// Every OCPP 1.6 action, in both directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub enum EOcppAction {
+    // Core
+    Authorize,
+    BootNotification,
+    ChangeAvailability,
+    ChangeConfiguration,
+    ClearCache,
+    DataTransfer,
+    GetConfiguration,
+    Heartbeat,
+    MeterValues,
+    RemoteStartTransaction,
+    RemoteStopTransaction,
+    Reset,
+    StartTransaction,
+    StatusNotification,
+    StopTransaction,
+    UnlockConnector,
+    // Firmware Management
+    DiagnosticsStatusNotification,
+    FirmwareStatusNotification,
+    GetDiagnostics,
+    UpdateFirmware,
+    // Local Auth List Management
+    GetLocalListVersion,
+    SendLocalList,
+    // Reservation
+    CancelReservation,
+    ReserveNow,
+    // Smart Charging
+    ClearChargingProfile,
+    GetCompositeSchedule,
+    SetChargingProfile,
+    // Remote Trigger
+    TriggerMessage,
+}
+
+impl EOcppAction {
+    pub const ALL: [EOcppAction; 28] = [
+        EOcppAction::Authorize, EOcppAction::BootNotification, EOcppAction::ChangeAvailability, EOcppAction::ChangeConfiguration,
+        EOcppAction::ClearCache, EOcppAction::DataTransfer, EOcppAction::GetConfiguration, EOcppAction::Heartbeat,
+        EOcppAction::MeterValues, EOcppAction::RemoteStartTransaction, EOcppAction::RemoteStopTransaction, EOcppAction::Reset,
+        EOcppAction::StartTransaction, EOcppAction::StatusNotification, EOcppAction::StopTransaction, EOcppAction::UnlockConnector,
+        EOcppAction::DiagnosticsStatusNotification, EOcppAction::FirmwareStatusNotification, EOcppAction::GetDiagnostics,
+        EOcppAction::UpdateFirmware, EOcppAction::GetLocalListVersion, EOcppAction::SendLocalList, EOcppAction::CancelReservation,
+        EOcppAction::ReserveNow, EOcppAction::ClearChargingProfile, EOcppAction::GetCompositeSchedule, EOcppAction::SetChargingProfile,
+        EOcppAction::TriggerMessage,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EOcppAction::Authorize => "Authorize",
+            EOcppAction::BootNotification => "BootNotification",
+            EOcppAction::ChangeAvailability => "ChangeAvailability",
+            EOcppAction::ChangeConfiguration => "ChangeConfiguration",
+            EOcppAction::ClearCache => "ClearCache",
+            EOcppAction::DataTransfer => "DataTransfer",
+            EOcppAction::GetConfiguration => "GetConfiguration",
+            EOcppAction::Heartbeat => "Heartbeat",
+            EOcppAction::MeterValues => "MeterValues",
+            EOcppAction::RemoteStartTransaction => "RemoteStartTransaction",
+            EOcppAction::RemoteStopTransaction => "RemoteStopTransaction",
+            EOcppAction::Reset => "Reset",
+            EOcppAction::StartTransaction => "StartTransaction",
+            EOcppAction::StatusNotification => "StatusNotification",
+            EOcppAction::StopTransaction => "StopTransaction",
+            EOcppAction::UnlockConnector => "UnlockConnector",
+            EOcppAction::DiagnosticsStatusNotification => "DiagnosticsStatusNotification",
+            EOcppAction::FirmwareStatusNotification => "FirmwareStatusNotification",
+            EOcppAction::GetDiagnostics => "GetDiagnostics",
+            EOcppAction::UpdateFirmware => "UpdateFirmware",
+            EOcppAction::GetLocalListVersion => "GetLocalListVersion",
+            EOcppAction::SendLocalList => "SendLocalList",
+            EOcppAction::CancelReservation => "CancelReservation",
+            EOcppAction::ReserveNow => "ReserveNow",
+            EOcppAction::ClearChargingProfile => "ClearChargingProfile",
+            EOcppAction::GetCompositeSchedule => "GetCompositeSchedule",
+            EOcppAction::SetChargingProfile => "SetChargingProfile",
+            EOcppAction::TriggerMessage => "TriggerMessage",
+        }
+    }
+}
+
+impl FromStr for EOcppAction {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EOcppAction::ALL.into_iter().find(|action| action.as_str() == s).ok_or(())
+    }
+}
+
+impl fmt::Display for EOcppAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// OCPP-J CALLERROR codes (OCPP-J 1.6 §4.2.3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EOcppErrorCode {
+    NotImplemented,
+    NotSupported,
+    InternalError,
+    ProtocolError,
+    SecurityError,
+    FormationViolation,
+    PropertyConstraintViolation,
+    OccurenceConstraintViolation,
+    TypeConstraintViolation,
+    GenericError,
+}
+
+impl EOcppErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EOcppErrorCode::NotImplemented => "NotImplemented",
+            EOcppErrorCode::NotSupported => "NotSupported",
+            EOcppErrorCode::InternalError => "InternalError",
+            EOcppErrorCode::ProtocolError => "ProtocolError",
+            EOcppErrorCode::SecurityError => "SecurityError",
+            EOcppErrorCode::FormationViolation => "FormationViolation",
+            EOcppErrorCode::PropertyConstraintViolation => "PropertyConstraintViolation",
+            // Spelled as in the specification.
+            EOcppErrorCode::OccurenceConstraintViolation => "OccurenceConstraintViolation",
+            EOcppErrorCode::TypeConstraintViolation => "TypeConstraintViolation",
+            EOcppErrorCode::GenericError => "GenericError",
+        }
+    }
+
+    /// The code as named by `version`; OCPP 2.0.1 renamed two of them.
+    pub fn code_for(self, version: EOcppVersion) -> &'static str {
+        match (version, self) {
+            (EOcppVersion::V2_0_1, EOcppErrorCode::FormationViolation) => "FormatViolation",
+            (EOcppVersion::V2_0_1, EOcppErrorCode::OccurenceConstraintViolation) => "OccurrenceConstraintViolation",
+            _ => self.as_str(),
+        }
+    }
+}
+
+/// Why a charger's CALL was not accepted; sent back as a CALLERROR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcppMessageError {
+    pub error_code: EOcppErrorCode,
+    pub description: String,
+}
+
+impl From<OcppMessageError> for CallErrorPayload {
+    fn from(err: OcppMessageError) -> Self {
+        CallErrorPayload { error_code: err.error_code.as_str().to_string(), error_description: err.description }
+    }
+}
+
+/// A CALL a charge point may send the central system, with its payload decoded.
+#[derive(Debug, Clone)]
+pub enum EChargePointRequest {
+    Authorize(AuthorizeReqPayload),
+    BootNotification(BootNotificationReqPayload),
+    DataTransfer(DataTransferReqPayload),
+    DiagnosticsStatusNotification(DiagnosticsStatusNotificationReqPayload),
+    FirmwareStatusNotification(FirmwareStatusNotificationReqPayload),
+    Heartbeat(HeartbeatReqPayload),
+    MeterValues(MeterValuesReqPayload),
+    StartTransaction(StartTransactionReqPayload),
+    StatusNotification(StatusNotificationReqPayload),
+    StopTransaction(StopTransactionReqPayload),
+}
+
+impl EChargePointRequest {
+    pub fn action(&self) -> EOcppAction {
+        match self {
+            EChargePointRequest::Authorize(_) => EOcppAction::Authorize,
+            EChargePointRequest::BootNotification(_) => EOcppAction::BootNotification,
+            EChargePointRequest::DataTransfer(_) => EOcppAction::DataTransfer,
+            EChargePointRequest::DiagnosticsStatusNotification(_) => EOcppAction::DiagnosticsStatusNotification,
+            EChargePointRequest::FirmwareStatusNotification(_) => EOcppAction::FirmwareStatusNotification,
+            EChargePointRequest::Heartbeat(_) => EOcppAction::Heartbeat,
+            EChargePointRequest::MeterValues(_) => EOcppAction::MeterValues,
+            EChargePointRequest::StartTransaction(_) => EOcppAction::StartTransaction,
+            EChargePointRequest::StatusNotification(_) => EOcppAction::StatusNotification,
+            EChargePointRequest::StopTransaction(_) => EOcppAction::StopTransaction,
+        }
+    }
+
+    /// Decode a CALL from its action name and JSON payload.
+    /// Unknown actions are `NotImplemented`, actions only the central system sends are `NotSupported`,
+    /// payloads that do not match the message structure are a `FormationViolation`,
+    /// and values outside the schema's limits are a `PropertyConstraintViolation`.
+    pub fn decode(action: &str, payload_json: &str) -> Result<Self, OcppMessageError> {
+        let action: EOcppAction = action.parse().map_err(|_| OcppMessageError {
+            error_code: EOcppErrorCode::NotImplemented,
+            description: format!("Unknown action '{}'", action),
+        })?;
+        let request = match action {
+            EOcppAction::Authorize => EChargePointRequest::Authorize(payload(action, payload_json)?),
+            EOcppAction::BootNotification => EChargePointRequest::BootNotification(payload(action, payload_json)?),
+            EOcppAction::DataTransfer => EChargePointRequest::DataTransfer(payload(action, payload_json)?),
+            EOcppAction::DiagnosticsStatusNotification => EChargePointRequest::DiagnosticsStatusNotification(payload(action, payload_json)?),
+            EOcppAction::FirmwareStatusNotification => EChargePointRequest::FirmwareStatusNotification(payload(action, payload_json)?),
+            EOcppAction::Heartbeat => EChargePointRequest::Heartbeat(payload(action, payload_json)?),
+            EOcppAction::MeterValues => EChargePointRequest::MeterValues(payload(action, payload_json)?),
+            EOcppAction::StartTransaction => EChargePointRequest::StartTransaction(payload(action, payload_json)?),
+            EOcppAction::StatusNotification => EChargePointRequest::StatusNotification(payload(action, payload_json)?),
+            EOcppAction::StopTransaction => EChargePointRequest::StopTransaction(payload(action, payload_json)?),
+            other => return Err(OcppMessageError {
+                error_code: EOcppErrorCode::NotSupported,
+                description: format!("{} is not sent by a charge point", other),
+            }),
+        };
+        request.validate().map_err(|description| OcppMessageError {
+            error_code: EOcppErrorCode::PropertyConstraintViolation,
+            description: format!("{}: {}", action, description),
+        })?;
+        Ok(request)
+    }
+
+    /// Checks the constraints of the OCPP 1.6 JSON schemas that serde does not: string lengths.
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            EChargePointRequest::Authorize(p) => ci_string("idTag", &p.id_tag, 20),
+            EChargePointRequest::BootNotification(p) => {
+                ci_string("chargePointVendor", &p.charge_point_vendor, 20)?;
+                ci_string("chargePointModel", &p.charge_point_model, 20)?;
+                optional_ci_string("chargePointSerialNumber", &p.charge_point_serial_number, 25)?;
+                optional_ci_string("chargeBoxSerialNumber", &p.charge_box_serial_number, 25)?;
+                optional_ci_string("firmwareVersion", &p.firmware_version, 50)?;
+                optional_ci_string("iccid", &p.iccid, 20)?;
+                optional_ci_string("imsi", &p.imsi, 20)?;
+                optional_ci_string("meterType", &p.meter_type, 25)?;
+                optional_ci_string("meterSerialNumber", &p.meter_serial_number, 25)
+            }
+            EChargePointRequest::DataTransfer(p) => {
+                ci_string("vendorId", &p.vendor_id, 255)?;
+                optional_ci_string("messageId", &p.message_id, 50)
+            }
+            EChargePointRequest::StartTransaction(p) => ci_string("idTag", &p.id_tag, 20),
+            EChargePointRequest::StatusNotification(p) => {
+                optional_ci_string("info", &p.info, 50)?;
+                optional_ci_string("vendorId", &p.vendor_id, 255)?;
+                optional_ci_string("vendorErrorCode", &p.vendor_error_code, 50)
+            }
+            EChargePointRequest::StopTransaction(p) => optional_ci_string("idTag", &p.id_tag, 20),
+            EChargePointRequest::DiagnosticsStatusNotification(_)
+            | EChargePointRequest::FirmwareStatusNotification(_)
+            | EChargePointRequest::Heartbeat(_)
+            | EChargePointRequest::MeterValues(_) => Ok(()),
+        }
+    }
+}
+
+pub(super) fn payload<T: DeserializeOwned>(action: impl fmt::Display, payload_json: &str) -> Result<T, OcppMessageError> {
+    serde_json::from_str(payload_json).map_err(|e| OcppMessageError {
+        error_code: EOcppErrorCode::FormationViolation,
+        description: format!("Invalid {} payload: {}", action, e),
+    })
+}
+
+/// `CiStringNType`: at most `max_len` characters.
+pub(super) fn ci_string(field: &str, value: &str, max_len: usize) -> Result<(), String> {
+    if value.chars().count() > max_len {
+        return Err(format!("{} is longer than {} characters", field, max_len));
+    }
+    Ok(())
+}
+
+pub(super) fn optional_ci_string(field: &str, value: &Option<String>, max_len: usize) -> Result<(), String> {
+    value.as_deref().map_or(Ok(()), |value| ci_string(field, value, max_len))
+}
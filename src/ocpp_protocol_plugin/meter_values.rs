@@ -0,0 +1,355 @@
+// Parsing of OCPP 1.6 MeterValues samples into the crate's units (kW, kWh, kvar, kvarh, A, V, %, Hz, °C)
+
+use bevy::prelude::*;
+use chrono::{DateTime, Utc};
+use std::str::FromStr;
+use super::types::{MeterSample, MeterValueSampledValue};
+use crate::common::types::EPhase;
+use crate::core_asset_plugin::ElectricalMeasurements;
+
+/// OCPP 1.6 measurands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EMeasurand {
+    EnergyActiveExportRegister,
+    EnergyActiveImportRegister,
+    EnergyReactiveExportRegister,
+    EnergyReactiveImportRegister,
+    EnergyActiveExportInterval,
+    EnergyActiveImportInterval,
+    EnergyReactiveExportInterval,
+    EnergyReactiveImportInterval,
+    PowerActiveExport,
+    PowerActiveImport,
+    PowerOffered,
+    PowerReactiveExport,
+    PowerReactiveImport,
+    PowerFactor,
+    CurrentImport,
+    CurrentExport,
+    CurrentOffered,
+    Voltage,
+    Frequency,
+    Temperature,
+    SoC,
+    Rpm,
+}
+
+impl FromStr for EMeasurand {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Energy.Active.Export.Register" => Ok(EMeasurand::EnergyActiveExportRegister),
+            "Energy.Active.Import.Register" => Ok(EMeasurand::EnergyActiveImportRegister),
+            "Energy.Reactive.Export.Register" => Ok(EMeasurand::EnergyReactiveExportRegister),
+            "Energy.Reactive.Import.Register" => Ok(EMeasurand::EnergyReactiveImportRegister),
+            "Energy.Active.Export.Interval" => Ok(EMeasurand::EnergyActiveExportInterval),
+            "Energy.Active.Import.Interval" => Ok(EMeasurand::EnergyActiveImportInterval),
+            "Energy.Reactive.Export.Interval" => Ok(EMeasurand::EnergyReactiveExportInterval),
+            "Energy.Reactive.Import.Interval" => Ok(EMeasurand::EnergyReactiveImportInterval),
+            "Power.Active.Export" => Ok(EMeasurand::PowerActiveExport),
+            "Power.Active.Import" => Ok(EMeasurand::PowerActiveImport),
+            "Power.Offered" => Ok(EMeasurand::PowerOffered),
+            "Power.Reactive.Export" => Ok(EMeasurand::PowerReactiveExport),
+            "Power.Reactive.Import" => Ok(EMeasurand::PowerReactiveImport),
+            "Power.Factor" => Ok(EMeasurand::PowerFactor),
+            "Current.Import" => Ok(EMeasurand::CurrentImport),
+            "Current.Export" => Ok(EMeasurand::CurrentExport),
+            "Current.Offered" => Ok(EMeasurand::CurrentOffered),
+            "Voltage" => Ok(EMeasurand::Voltage),
+            "Frequency" => Ok(EMeasurand::Frequency),
+            "Temperature" => Ok(EMeasurand::Temperature),
+            "SoC" => Ok(EMeasurand::SoC),
+            "RPM" => Ok(EMeasurand::Rpm),
+            _ => Err(()),
+        }
+    }
+}
+
+impl EMeasurand {
+    /// Whether the measurand is a cumulative register, which stays meaningful in any reading context.
+    pub fn is_register(self) -> bool {
+        matches!(self,
+            EMeasurand::EnergyActiveExportRegister | EMeasurand::EnergyActiveImportRegister |
+            EMeasurand::EnergyReactiveExportRegister | EMeasurand::EnergyReactiveImportRegister)
+    }
+}
+
+/// OCPP 1.6 units of measure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EUnitOfMeasure {
+    Wh,
+    KWh,
+    Varh,
+    Kvarh,
+    W,
+    KW,
+    Va,
+    KVa,
+    Var,
+    Kvar,
+    A,
+    V,
+    Celsius,
+    Fahrenheit,
+    K,
+    Percent,
+}
+
+impl FromStr for EUnitOfMeasure {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Wh" => Ok(EUnitOfMeasure::Wh),
+            "kWh" => Ok(EUnitOfMeasure::KWh),
+            "varh" => Ok(EUnitOfMeasure::Varh),
+            "kvarh" => Ok(EUnitOfMeasure::Kvarh),
+            "W" => Ok(EUnitOfMeasure::W),
+            "kW" => Ok(EUnitOfMeasure::KW),
+            "VA" => Ok(EUnitOfMeasure::Va),
+            "kVA" => Ok(EUnitOfMeasure::KVa),
+            "var" => Ok(EUnitOfMeasure::Var),
+            "kvar" => Ok(EUnitOfMeasure::Kvar),
+            "A" => Ok(EUnitOfMeasure::A),
+            "V" => Ok(EUnitOfMeasure::V),
+            "Celsius" => Ok(EUnitOfMeasure::Celsius),
+            "Fahrenheit" => Ok(EUnitOfMeasure::Fahrenheit),
+            "K" => Ok(EUnitOfMeasure::K),
+            "Percent" => Ok(EUnitOfMeasure::Percent),
+            _ => Err(()),
+        }
+    }
+}
+
+/// OCPP 1.6 reading contexts; `Sample.Periodic` when absent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EReadingContext {
+    InterruptionBegin,
+    InterruptionEnd,
+    Other,
+    SampleClock,
+    #[default]
+    SamplePeriodic,
+    TransactionBegin,
+    TransactionEnd,
+    Trigger,
+}
+
+impl FromStr for EReadingContext {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Interruption.Begin" => Ok(EReadingContext::InterruptionBegin),
+            "Interruption.End" => Ok(EReadingContext::InterruptionEnd),
+            "Other" => Ok(EReadingContext::Other),
+            "Sample.Clock" => Ok(EReadingContext::SampleClock),
+            "Sample.Periodic" => Ok(EReadingContext::SamplePeriodic),
+            "Transaction.Begin" => Ok(EReadingContext::TransactionBegin),
+            "Transaction.End" => Ok(EReadingContext::TransactionEnd),
+            "Trigger" => Ok(EReadingContext::Trigger),
+            _ => Err(()),
+        }
+    }
+}
+
+impl EReadingContext {
+    /// Whether the sample describes the asset as it is now, rather than a snapshot at a transaction or interruption boundary.
+    pub fn is_live(self) -> bool {
+        matches!(self, EReadingContext::SamplePeriodic | EReadingContext::SampleClock | EReadingContext::Trigger | EReadingContext::Other)
+    }
+}
+
+/// A sampled value with its measurand, phase and context resolved and its value converted to the crate's units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedSampledValue {
+    pub measurand: EMeasurand,
+    pub phase: Option<EPhase>,
+    pub context: EReadingContext,
+    pub value: f64,
+}
+
+/// A sample with its charger timestamp; `None` when the charger sent none or an unreadable one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedMeterSample {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub values: Vec<ParsedSampledValue>,
+}
+
+/// Converts `value` in `unit` to the crate's unit for `measurand`, or `None` if the unit does not fit the measurand.
+fn to_crate_unit(measurand: EMeasurand, unit: EUnitOfMeasure, value: f64) -> Option<f64> {
+    use EMeasurand as M;
+    use EUnitOfMeasure as U;
+    match (measurand, unit) {
+        (M::EnergyActiveExportRegister | M::EnergyActiveImportRegister | M::EnergyActiveExportInterval | M::EnergyActiveImportInterval, U::Wh) => Some(value / 1000.0),
+        (M::EnergyActiveExportRegister | M::EnergyActiveImportRegister | M::EnergyActiveExportInterval | M::EnergyActiveImportInterval, U::KWh) => Some(value),
+        (M::EnergyReactiveExportRegister | M::EnergyReactiveImportRegister | M::EnergyReactiveExportInterval | M::EnergyReactiveImportInterval, U::Varh) => Some(value / 1000.0),
+        (M::EnergyReactiveExportRegister | M::EnergyReactiveImportRegister | M::EnergyReactiveExportInterval | M::EnergyReactiveImportInterval, U::Kvarh) => Some(value),
+        (M::PowerActiveExport | M::PowerActiveImport | M::PowerOffered, U::W | U::Va) => Some(value / 1000.0),
+        (M::PowerActiveExport | M::PowerActiveImport | M::PowerOffered, U::KW | U::KVa) => Some(value),
+        (M::PowerReactiveExport | M::PowerReactiveImport, U::Var) => Some(value / 1000.0),
+        (M::PowerReactiveExport | M::PowerReactiveImport, U::Kvar) => Some(value),
+        (M::CurrentImport | M::CurrentExport | M::CurrentOffered, U::A) => Some(value),
+        (M::Voltage, U::V) => Some(value),
+        (M::SoC, U::Percent) => Some(value),
+        (M::Temperature, U::Celsius) => Some(value),
+        (M::Temperature, U::Fahrenheit) => Some((value - 32.0) * 5.0 / 9.0),
+        (M::Temperature, U::K) => Some(value - 273.15),
+        // Power factor, frequency and RPM have no unit of their own in OCPP 1.6
+        (M::PowerFactor | M::Frequency | M::Rpm, _) => Some(value),
+        _ => None,
+    }
+}
+
+/// The unit a charger most likely meant when it left `unit` out.
+/// OCPP 1.6 says Wh, which only makes sense for energy, so other measurands take their natural unit.
+fn default_unit(measurand: EMeasurand) -> EUnitOfMeasure {
+    use EMeasurand as M;
+    match measurand {
+        M::EnergyReactiveExportRegister | M::EnergyReactiveImportRegister | M::EnergyReactiveExportInterval | M::EnergyReactiveImportInterval => EUnitOfMeasure::Varh,
+        M::PowerActiveExport | M::PowerActiveImport | M::PowerOffered => EUnitOfMeasure::W,
+        M::PowerReactiveExport | M::PowerReactiveImport => EUnitOfMeasure::Var,
+        M::CurrentImport | M::CurrentExport | M::CurrentOffered => EUnitOfMeasure::A,
+        M::Voltage => EUnitOfMeasure::V,
+        M::SoC => EUnitOfMeasure::Percent,
+        M::Temperature => EUnitOfMeasure::Celsius,
+        _ => EUnitOfMeasure::Wh,
+    }
+}
+
+fn parse_sampled_value(sv: &MeterValueSampledValue) -> Result<ParsedSampledValue, String> {
+    if sv.format.as_deref() == Some("SignedData") {
+        return Err("signed data is not decoded".to_string());
+    }
+    let measurand = match sv.measurand.as_deref() {
+        Some(measurand) => measurand.parse().map_err(|_| format!("unknown measurand '{}'", measurand))?,
+        None => EMeasurand::EnergyActiveImportRegister,
+    };
+    let unit = match sv.unit.as_deref() {
+        Some(unit) => unit.parse().map_err(|_| format!("unknown unit '{}'", unit))?,
+        None => default_unit(measurand),
+    };
+    let phase = match sv.phase.as_deref() {
+        Some(phase) => Some(phase.parse::<EPhase>().map_err(|_| format!("unsupported phase '{}'", phase))?),
+        None => None,
+    };
+    let context = match sv.context.as_deref() {
+        Some(context) => context.parse().map_err(|_| format!("unknown context '{}'", context))?,
+        None => EReadingContext::default(),
+    };
+    let raw: f64 = sv.value.trim().parse().map_err(|_| format!("unreadable value '{}'", sv.value))?;
+    let value = to_crate_unit(measurand, unit, raw).ok_or_else(|| format!("unit {:?} does not fit {:?}", unit, measurand))?;
+    Ok(ParsedSampledValue { measurand, phase, context, value })
+}
+
+/// Parses every sample, oldest first, so later samples win when applied in order.
+/// Values that cannot be read are dropped with a debug log rather than failing the whole message.
+pub fn parse_meter_values(samples: &[MeterSample]) -> Vec<ParsedMeterSample> {
+    let mut parsed: Vec<ParsedMeterSample> = samples.iter()
+        .map(|sample| ParsedMeterSample {
+            timestamp: sample.timestamp.as_deref().and_then(|ts| DateTime::parse_from_rfc3339(ts).ok()).map(|ts| ts.with_timezone(&Utc)),
+            values: sample.sampled_value.iter()
+                .filter_map(|sv| parse_sampled_value(sv).inspect_err(|e| debug!("Skipping sampled value: {}", e)).ok())
+                .collect(),
+        })
+        .collect();
+    // Stable, so samples without a timestamp keep their place relative to each other
+    parsed.sort_by_key(|sample| sample.timestamp);
+    parsed
+}
+
+impl ParsedMeterSample {
+    /// Value of `measurand` for the whole asset: the unphased value if sent, else the sum over L1-L3.
+    fn total(&self, measurand: EMeasurand) -> Option<f64> {
+        let values = || self.values.iter().filter(move |v| v.measurand == measurand);
+        values().find(|v| v.phase.is_none()).map(|v| v.value).or_else(|| {
+            let phases: Vec<f64> = values().filter(|v| matches!(v.phase, Some(EPhase::L1 | EPhase::L2 | EPhase::L3))).map(|v| v.value).collect();
+            (!phases.is_empty()).then(|| phases.iter().sum())
+        })
+    }
+
+    fn phase_value(&self, measurand: EMeasurand, phase: EPhase) -> Option<f64> {
+        self.values.iter().find(|v| v.measurand == measurand && v.phase == Some(phase)).map(|v| v.value)
+    }
+
+    fn unphased(&self, measurand: EMeasurand) -> Option<f64> {
+        self.values.iter().find(|v| v.measurand == measurand && v.phase.is_none()).map(|v| v.value)
+    }
+
+    /// Import minus export, when either was sent.
+    fn net(import: Option<f64>, export: Option<f64>) -> Option<f64> {
+        (import.is_some() || export.is_some()).then(|| import.unwrap_or(0.0) - export.unwrap_or(0.0))
+    }
+
+    /// The active import register for the whole asset, in kWh, if the sample carries one.
+    pub fn import_register_kwh(&self) -> Option<f64> {
+        self.total(EMeasurand::EnergyActiveImportRegister)
+    }
+
+    /// Writes the sample into a reading last stamped `reading_time`. Registers are taken from every context; instantaneous
+    /// values only from live contexts, since transaction and interruption samples are snapshots that may arrive late.
+    /// A sample older than the reading has been superseded, e.g. a replay after a reconnect, and is skipped.
+    pub fn apply(&self, power_kw: &mut f32, energy_kwh: &mut f64, measurements: &mut ElectricalMeasurements, reading_time: DateTime<Utc>) {
+        use EMeasurand as M;
+        if self.timestamp.is_some_and(|timestamp| timestamp < reading_time) {
+            debug!("Skipping meter sample from {:?}, older than the reading at {}", self.timestamp, reading_time);
+            return;
+        }
+        let live = ParsedMeterSample {
+            timestamp: self.timestamp,
+            values: self.values.iter().filter(|v| v.context.is_live()).cloned().collect(),
+        };
+        let registers = ParsedMeterSample {
+            timestamp: self.timestamp,
+            values: self.values.iter().filter(|v| v.measurand.is_register()).cloned().collect(),
+        };
+
+        if let Some(kwh) = registers.total(M::EnergyActiveImportRegister) {
+            *energy_kwh = kwh;
+        }
+        if let Some(kwh) = registers.total(M::EnergyActiveExportRegister) {
+            measurements.exported_energy_kwh = Some(kwh as f32);
+        }
+        if let Some(kvarh) = Self::net(registers.total(M::EnergyReactiveImportRegister), registers.total(M::EnergyReactiveExportRegister)) {
+            measurements.reactive_energy_kvarh = Some(kvarh as f32);
+        }
+
+        if let Some(kw) = Self::net(live.total(M::PowerActiveImport), live.total(M::PowerActiveExport)) {
+            *power_kw = kw as f32;
+        }
+        if let Some(kvar) = Self::net(live.total(M::PowerReactiveImport), live.total(M::PowerReactiveExport)) {
+            measurements.reactive_power_kvar = Some(kvar as f32);
+        }
+        if let Some(amps) = Self::net(live.unphased(M::CurrentImport), live.unphased(M::CurrentExport)) {
+            measurements.current_a = Some(amps as f32);
+        }
+        if let Some(volts) = live.unphased(M::Voltage) {
+            measurements.voltage_v = Some(volts as f32);
+        }
+        for phase in [EPhase::L1, EPhase::L2, EPhase::L3, EPhase::N] {
+            let power = Self::net(live.phase_value(M::PowerActiveImport, phase), live.phase_value(M::PowerActiveExport, phase));
+            let current = Self::net(live.phase_value(M::CurrentImport, phase), live.phase_value(M::CurrentExport, phase));
+            let voltage = live.phase_value(M::Voltage, phase);
+            if power.is_none() && current.is_none() && voltage.is_none() {
+                continue;
+            }
+            let entry = measurements.phase_mut(phase);
+            entry.power_kw = power.map(|kw| kw as f32).or(entry.power_kw);
+            entry.current_a = current.map(|a| a as f32).or(entry.current_a);
+            entry.voltage_v = voltage.map(|v| v as f32).or(entry.voltage_v);
+        }
+
+        let set = |measurand: M, target: &mut Option<f32>| {
+            if let Some(value) = live.total(measurand) {
+                *target = Some(value as f32);
+            }
+        };
+        set(M::CurrentOffered, &mut measurements.offered_current_a);
+        set(M::PowerOffered, &mut measurements.offered_power_kw);
+        set(M::SoC, &mut measurements.soc_percent);
+        set(M::Frequency, &mut measurements.frequency_hz);
+        set(M::PowerFactor, &mut measurements.power_factor);
+        set(M::Temperature, &mut measurements.temperature_c);
+    }
+}
@@ -1,18 +1,41 @@
 use bevy::prelude::*;
+use crate::core_asset_plugin::ESetpointFlow;
 pub mod components;
 pub mod events;
+pub mod frames;
+pub mod messages;
+pub mod meter_values;
+pub mod resources;
+pub mod server;
 pub mod systems;
 pub mod types;
+pub mod types_v201;
 
 pub use components::*;
-pub use events::{OcppRequestFromAsset, OcppCommandToAsset, OcppFromAssetChannel, OcppToAssetChannel};
+pub use events::{
+    OcppRequestFromAsset, OcppCallFromAsset, Ocpp201CallFromAsset, OcppCommandToAsset, OcppResponseFromAsset, EOcppCallResponse,
+    OcppFromAssetChannel, OcppToAssetChannel, OcppResponseFromAssetChannel,
+};
+pub use frames::EOcppJFrame;
+pub use messages::{EOcppAction, EChargePointRequest, EOcppErrorCode, OcppMessageError};
+pub use resources::*;
+pub use server::OcppServer;
+pub use types_v201::{EChargingStationRequest, EOcpp201Action, EOutgoingOcpp201Message};
 pub use systems::{
     ingest_ocpp_requests_from_channel_system,
+    sync_ocpp_charge_point_versions,
+    decode_ocpp_requests,
     ocpp_request_handler,
+    ocpp201_request_handler,
     generic_ocpp_charger_initialization_system,
     alfen_special_init_system,
     charger_control_to_ocpp_profile,
     export_ocpp_commands_to_channel_system,
+    ingest_ocpp_responses_from_channel_system,
+    ocpp_response_handler,
+    expire_pending_ocpp_requests,
+    refresh_ocpp_liveness,
+    supervise_ocpp_liveness,
 };
 
 pub struct OcppProtocolPlugin;
@@ -25,26 +48,55 @@ impl Plugin for OcppProtocolPlugin {
             .register_type::<ChargerElectricalConfig>()
             .register_type::<EGunStatusOcpp>()
             .register_type::<Gun>()
+            .register_type::<GunTransaction>()
             .register_type::<Guns>()
             .register_type::<AlfenSpecificConfig>() 
             .register_type::<AlfenSpecialInitStatus>() 
             .register_type::<AlfenSpecialInitState>() 
             .register_type::<GenericChargerInitializationStatus>()
             .register_type::<GenericChargerInitProgress>()
+            .register_type::<OcppCallOutcomes>()
+            .register_type::<OcppPendingSetpoint>()
+            .register_type::<Ocpp201DeviceModel>()
+            .init_resource::<OcppPendingRequests>()
+            .init_resource::<OcppCallTimeout>()
+            .init_resource::<OcppTransactionIdAllocator>()
+            .init_resource::<OcppHeartbeatPolicy>()
+            .init_resource::<OcppChargePointVersions>()
             .add_event::<OcppRequestFromAsset>()
+            .add_event::<OcppCallFromAsset>()
+            .add_event::<Ocpp201CallFromAsset>()
             .add_event::<OcppCommandToAsset>()
+            .add_event::<OcppResponseFromAsset>()
             .add_systems(Update, (
                 ingest_ocpp_requests_from_channel_system,
-                ocpp_request_handler
+                sync_ocpp_charge_point_versions,
+                decode_ocpp_requests
                     .after(ingest_ocpp_requests_from_channel_system),
+                ocpp_request_handler
+                    .after(decode_ocpp_requests),
+                ocpp201_request_handler
+                    .after(decode_ocpp_requests),
                 generic_ocpp_charger_initialization_system
-                    .after(ocpp_request_handler),
+                    .after(ocpp_request_handler)
+                    .after(ocpp201_request_handler),
                 alfen_special_init_system
                     .after(generic_ocpp_charger_initialization_system),
                 charger_control_to_ocpp_profile
-                    .after(alfen_special_init_system),
+                    .after(alfen_special_init_system)
+                    .in_set(ESetpointFlow::Dispatch),
                 export_ocpp_commands_to_channel_system
                     .after(charger_control_to_ocpp_profile),
+                ingest_ocpp_responses_from_channel_system
+                    .after(export_ocpp_commands_to_channel_system),
+                ocpp_response_handler
+                    .after(ingest_ocpp_responses_from_channel_system),
+                expire_pending_ocpp_requests
+                    .after(ocpp_response_handler),
+                refresh_ocpp_liveness
+                    .after(ocpp_response_handler),
+                supervise_ocpp_liveness
+                    .after(refresh_ocpp_liveness),
             ));
     }
 }
@@ -0,0 +1,79 @@
+use bevy::prelude::Resource;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use super::types::{EOcppVersion, EOutgoingOcppMessage};
+
+/// A CALL sent to a charger that has not been answered yet.
+#[derive(Debug, Clone)]
+pub struct OcppPendingRequest {
+    pub charge_point_id: String,
+    pub message: EOutgoingOcppMessage,
+    /// `Time::elapsed_secs_f64` when the CALL was sent.
+    pub sent_at_secs: f64,
+}
+
+/// Unanswered CALLs, keyed by `ocpp_message_id`.
+#[derive(Resource, Debug, Default)]
+pub struct OcppPendingRequests(pub HashMap<String, OcppPendingRequest>);
+
+/// Configured OCPP version of each charger, keyed by charge point id. Shared with the `OcppServer`, which offers a
+/// charger only the subprotocol of its version; `sync_ocpp_charge_point_versions` keeps it in step with the site.
+#[derive(Resource, Debug, Clone, Default)]
+pub struct OcppChargePointVersions(pub Arc<RwLock<HashMap<String, EOcppVersion>>>);
+
+impl OcppChargePointVersions {
+    pub fn get(&self, charge_point_id: &str) -> Option<EOcppVersion> {
+        self.0.read().unwrap().get(charge_point_id).copied()
+    }
+}
+
+/// How long a charger has to answer a CALL before it is recorded as `TimedOut`.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct OcppCallTimeout(pub Duration);
+
+impl Default for OcppCallTimeout {
+    fn default() -> Self {
+        Self(Duration::from_secs(30))
+    }
+}
+
+/// Allocates the `transactionId` returned in StartTransaction replies.
+/// Starts from the seconds elapsed since 2020 so that, after a restart, new ids do not collide with transactions
+/// chargers still hold open or have queued StopTransactions for, as long as fewer than one id per second was handed out.
+#[derive(Resource, Debug)]
+pub struct OcppTransactionIdAllocator(pub i32);
+
+impl Default for OcppTransactionIdAllocator {
+    fn default() -> Self {
+        // 2020-01-01T00:00:00Z as a Unix timestamp
+        const EPOCH_SECS: i64 = 1_577_836_800;
+        let elapsed_secs = chrono::Utc::now().timestamp() - EPOCH_SECS;
+        Self(elapsed_secs.clamp(0, i32::MAX as i64 / 2) as i32)
+    }
+}
+
+impl OcppTransactionIdAllocator {
+    pub fn next_id(&mut self) -> i32 {
+        // Ids must stay positive; wrap around long before i32 runs out
+        self.0 = if self.0 >= i32::MAX - 1 { 1 } else { self.0 + 1 };
+        self.0
+    }
+}
+
+/// Heartbeat interval offered to chargers, and how many intervals of silence take one `Offline`.
+#[derive(Resource, Debug, Clone)]
+pub struct OcppHeartbeatPolicy {
+    pub interval: Duration,
+    /// Multiple of the negotiated interval after which a silent charger is considered gone.
+    pub offline_after_intervals: f64,
+}
+
+impl Default for OcppHeartbeatPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(300),
+            offline_after_intervals: 2.0,
+        }
+    }
+}
@@ -0,0 +1,276 @@
+// OCPP-J 1.6 / 2.0.1 central-system WebSocket server.
+// Bridges charge point connections onto the OcppRequestFromAsset / OcppCommandToAsset channels.
+
+use bevy::log::{debug, error, info, warn};
+use crossbeam_channel::{Receiver, Sender};
+use futures_util::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::events::{EOcppCallResponse, OcppCommandToAsset, OcppRequestFromAsset, OcppResponseFromAsset};
+use super::frames::{EOcppJFrame, CALL_MESSAGE_TYPE_ID};
+use super::resources::OcppChargePointVersions;
+use super::types::{EOcppVersion, EOutgoingOcppMessage};
+
+/// WebSocket subprotocol negotiated with OCPP 1.6J charge points.
+pub const OCPP_1_6_SUBPROTOCOL: &str = "ocpp1.6";
+/// WebSocket subprotocol negotiated with OCPP 2.0.1 charging stations.
+pub const OCPP_2_0_1_SUBPROTOCOL: &str = "ocpp2.0.1";
+
+/// Outbound frame queues for each connected charge point, keyed by charge point id.
+type ConnectionRegistry = Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Message>>>>;
+
+pub struct OcppServer {
+    listener: TcpListener,
+    connections: ConnectionRegistry,
+    versions: OcppChargePointVersions,
+}
+
+impl OcppServer {
+    /// Bind the server; charge points are only accepted if `versions` knows their id, and must offer its subprotocol.
+    pub async fn bind(addr: impl tokio::net::ToSocketAddrs, versions: OcppChargePointVersions) -> std::io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr).await?,
+            connections: ConnectionRegistry::default(),
+            versions,
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept charge point connections until the task is dropped.
+    /// Inbound CALLs are pushed to `from_asset` and CALLRESULT/CALLERROR replies to `responses_from_asset`;
+    /// commands read from `to_asset` are framed and routed to the connection whose path matches their `charge_point_id`.
+    pub async fn run(
+        self,
+        from_asset: Sender<OcppRequestFromAsset>,
+        responses_from_asset: Sender<OcppResponseFromAsset>,
+        to_asset: Receiver<OcppCommandToAsset>,
+    ) {
+        let connections = self.connections.clone();
+        std::thread::Builder::new()
+            .name("ocpp-command-dispatch".to_string())
+            .spawn(move || dispatch_commands_to_connections(to_asset, connections))
+            .expect("Failed to spawn OCPP command dispatcher thread");
+
+        info!("OCPP server listening on {:?}", self.listener.local_addr());
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, peer)) => {
+                    let inbound = InboundChannels { requests: from_asset.clone(), responses: responses_from_asset.clone() };
+                    tokio::spawn(handle_connection(stream, peer, inbound, self.connections.clone(), self.versions.clone()));
+                }
+                Err(e) => error!("OCPP server accept failed: {}", e),
+            }
+        }
+    }
+}
+
+/// Convert an ECS command into the OCPP-J frame it is sent as.
+pub fn command_to_frame(cmd: &OcppCommandToAsset, fallback_message_id: impl FnOnce() -> String) -> serde_json::Result<EOcppJFrame> {
+    let payload = cmd.message_type.payload()?;
+    let message_id = cmd.ocpp_message_id.clone().unwrap_or_else(fallback_message_id);
+    Ok(match (&cmd.message_type, cmd.message_type.action_name()) {
+        (EOutgoingOcppMessage::CallError(error), _) => EOcppJFrame::CallError {
+            message_id,
+            error_code: error.error_code.clone(),
+            error_description: error.error_description.clone(),
+            error_details: payload,
+        },
+        (_, Some(action)) => EOcppJFrame::Call { message_id, action: action.to_string(), payload },
+        (_, None) => EOcppJFrame::CallResult { message_id, payload },
+    })
+}
+
+/// Blocking loop draining the ECS command channel; runs until every sender is dropped.
+fn dispatch_commands_to_connections(to_asset: Receiver<OcppCommandToAsset>, connections: ConnectionRegistry) {
+    let generated_ids = AtomicU64::new(0);
+    while let Ok(cmd) = to_asset.recv() {
+        let frame = match command_to_frame(&cmd, || format!("cs_{}", generated_ids.fetch_add(1, Ordering::Relaxed) + 1)) {
+            Ok(frame) => frame,
+            Err(e) => {
+                error!("Failed to serialize OCPP command for '{}': {}", cmd.charge_point_id, e);
+                continue;
+            }
+        };
+
+        let registry = connections.lock().unwrap();
+        match registry.get(&cmd.charge_point_id) {
+            Some(outbound) => {
+                if outbound.send(Message::Text(frame.to_text().into())).is_err() {
+                    warn!("Connection for '{}' closed before command {} was sent", cmd.charge_point_id, frame.message_id());
+                }
+            }
+            None => warn!("Dropping OCPP command {} for disconnected charger '{}'", frame.message_id(), cmd.charge_point_id),
+        }
+    }
+    debug!("OCPP command channel closed; dispatcher stopping");
+}
+
+/// The charge point id is the last path segment of the connection URL, e.g. `ws://host/ocpp/CH001`.
+fn charge_point_id_from_path(path: &str) -> Option<String> {
+    path.rsplit('/').find(|segment| !segment.is_empty()).map(str::to_string)
+}
+
+fn subprotocol(version: EOcppVersion) -> &'static str {
+    match version {
+        EOcppVersion::V1_6J => OCPP_1_6_SUBPROTOCOL,
+        EOcppVersion::V2_0_1 => OCPP_2_0_1_SUBPROTOCOL,
+    }
+}
+
+fn reject(status: StatusCode, reason: &str) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(reason.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+/// Where frames received from charge points are delivered.
+#[derive(Clone)]
+struct InboundChannels {
+    requests: Sender<OcppRequestFromAsset>,
+    responses: Sender<OcppResponseFromAsset>,
+}
+
+// The handshake callback's error type is fixed by tungstenite.
+#[allow(clippy::result_large_err)]
+async fn handle_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    inbound_channels: InboundChannels,
+    connections: ConnectionRegistry,
+    versions: OcppChargePointVersions,
+) {
+    let mut charge_point_id: Option<String> = None;
+    let handshake = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, mut response: Response| {
+        let Some(cp_id) = charge_point_id_from_path(request.uri().path()) else {
+            return Err(reject(StatusCode::NOT_FOUND, "Missing charge point id in path"));
+        };
+        let Some(version) = versions.get(&cp_id) else {
+            return Err(reject(StatusCode::NOT_FOUND, &format!("Unknown charge point '{cp_id}'")));
+        };
+        // Messages are decoded in the configured version, so the charger must offer that version's subprotocol.
+        let subprotocol = subprotocol(version);
+        let offered = request
+            .headers()
+            .get_all("Sec-WebSocket-Protocol")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .any(|protocol| protocol.trim() == subprotocol);
+        if !offered {
+            return Err(reject(StatusCode::BAD_REQUEST, &format!("Subprotocol {subprotocol} required for '{cp_id}'")));
+        }
+        response
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", HeaderValue::from_static(subprotocol));
+        charge_point_id = Some(cp_id);
+        Ok(response)
+    })
+    .await;
+
+    let (ws, cp_id) = match (handshake, charge_point_id) {
+        (Ok(ws), Some(cp_id)) => (ws, cp_id),
+        (Err(e), _) => {
+            warn!("OCPP handshake from {} rejected: {}", peer, e);
+            return;
+        }
+        (Ok(_), None) => return,
+    };
+
+    info!("Charge point '{}' connected from {}", cp_id, peer);
+    let (outbound_tx, mut outbound_rx) = mpsc::unbounded_channel::<Message>();
+    if connections.lock().unwrap().insert(cp_id.clone(), outbound_tx.clone()).is_some() {
+        warn!("Charge point '{}' reconnected; replacing previous connection", cp_id);
+    }
+
+    let (mut ws_tx, mut ws_rx) = ws.split();
+    loop {
+        tokio::select! {
+            inbound = ws_rx.next() => match inbound {
+                Some(Ok(Message::Text(text))) => {
+                    if let Some(reply) = handle_inbound_text(&cp_id, text.as_str(), &inbound_channels) {
+                        if ws_tx.send(Message::Text(reply.to_text().into())).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    warn!("WebSocket error on '{}': {}", cp_id, e);
+                    break;
+                }
+            },
+            Some(outbound) = outbound_rx.recv() => {
+                if ws_tx.send(outbound).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Only unregister if a newer connection has not already taken this id over.
+    let mut registry = connections.lock().unwrap();
+    if registry.get(&cp_id).is_some_and(|registered| registered.same_channel(&outbound_tx)) {
+        registry.remove(&cp_id);
+    }
+    info!("Charge point '{}' disconnected", cp_id);
+}
+
+/// Route an inbound text frame. Returns a frame to send straight back when the message cannot be handed to ECS.
+fn handle_inbound_text(cp_id: &str, text: &str, channels: &InboundChannels) -> Option<EOcppJFrame> {
+    match EOcppJFrame::parse(text) {
+        Ok(EOcppJFrame::Call { message_id, action, payload }) => {
+            let request = OcppRequestFromAsset {
+                charge_point_id: cp_id.to_string(),
+                action,
+                payload_json: payload.to_string(),
+                ocpp_message_id: message_id.clone(),
+            };
+            if channels.requests.send(request).is_err() {
+                error!("OCPP request channel closed; cannot deliver message {} from '{}'", message_id, cp_id);
+                return Some(EOcppJFrame::CallError {
+                    message_id,
+                    error_code: "InternalError".to_string(),
+                    error_description: "Central system unavailable".to_string(),
+                    error_details: serde_json::json!({}),
+                });
+            }
+            None
+        }
+        Ok(EOcppJFrame::CallResult { message_id, payload }) => {
+            forward_response(cp_id, message_id, EOcppCallResponse::Result { payload_json: payload.to_string() }, channels);
+            None
+        }
+        Ok(EOcppJFrame::CallError { message_id, error_code, error_description, .. }) => {
+            forward_response(cp_id, message_id, EOcppCallResponse::Error { error_code, error_description }, channels);
+            None
+        }
+        // OCPP-J only allows a CALLERROR in reply to a CALL; malformed replies and unreadable frames are dropped.
+        Err(e) if e.message_type_id == Some(CALL_MESSAGE_TYPE_ID) => {
+            warn!("Malformed OCPP CALL from '{}': {}", cp_id, e.description);
+            Some(e.into())
+        }
+        Err(e) => {
+            warn!("Dropping malformed OCPP frame from '{}': {}", cp_id, e.description);
+            None
+        }
+    }
+}
+
+fn forward_response(cp_id: &str, message_id: String, response: EOcppCallResponse, channels: &InboundChannels) {
+    let response = OcppResponseFromAsset { charge_point_id: cp_id.to_string(), ocpp_message_id: message_id, response };
+    if channels.responses.send(response).is_err() {
+        error!("OCPP response channel closed; dropping reply from '{}'", cp_id);
+    }
+}
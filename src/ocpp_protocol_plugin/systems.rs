@@ -1,11 +1,25 @@
 use bevy::prelude::*;
-use super::events::{OcppRequestFromAsset, OcppCommandToAsset};
+use super::events::{OcppRequestFromAsset, OcppCallFromAsset, Ocpp201CallFromAsset, OcppCommandToAsset, OcppResponseFromAsset, EOcppCallResponse};
 use super::components::*;
-use crate::core_asset_plugin::{TargetPowerSetpointKw, CurrentMeterReading, MeteringSource, ExternalId, LastAppliedSetpointKw};
+use super::messages::EChargePointRequest;
+use super::meter_values::{parse_meter_values, ParsedMeterSample};
+use super::types_v201::{
+    self as v201,
+    EChargingStationRequest,
+    EOutgoingOcpp201Message,
+    to_v16_meter_samples,
+    ChargingStateEnumType,
+    ConnectorStatusEnumType,
+    TransactionEventEnumType,
+    TransactionEventRequest,
+    AttributeEnumType,
+    AuthorizationStatusEnumType,
+    ChargingProfileStatusEnumType,
+    GenericDeviceModelStatusEnumType,
+    ReportBaseEnumType,
+};
+use crate::core_asset_plugin::{TargetPowerSetpointKw, SetpointSchedule, CurrentMeterReading, ConnectorMeterReading, ElectricalMeasurements, MeteringSource, ExternalId, LastAppliedSetpointKw};
 use super::types::{
-    BootNotificationReqPayload,
-    StatusNotificationReqPayload,
-    MeterValuesReqPayload,
     EOutgoingOcppMessage,
     ChargingSchedule,
     CsChargingProfiles,
@@ -14,38 +28,176 @@ use super::types::{
     MeterValuesConfPayload,
     StatusNotificationConfPayload,
     BootNotificationConfPayload,
+    HeartbeatConfPayload,
     RegistrationStatus,
     EChargingRateUnit,
     SetChargingProfileReqPayload,
+    SetChargingProfileConfPayload,
+    ChargingProfileStatus,
+    ChangeConfigurationConfPayload,
+    ConfigurationStatus,
+    RemoteStartTransactionConfPayload,
+    RemoteStartStopStatus,
+    AuthorizeConfPayload,
+    AuthorizationStatus,
+    IdTagInfo,
+    StartTransactionConfPayload,
+    StopTransactionConfPayload,
+    ChargePointErrorCode,
+    ChargingRateUnitType,
+    ChargingProfilePurposeType,
+    ChargingProfileKindType,
+    RecurrencyKindType,
+    DataTransferConfPayload,
+    DataTransferStatus,
+    DiagnosticsStatusNotificationConfPayload,
+    FirmwareStatusNotificationConfPayload,
+    ChangeAvailabilityConfPayload,
+    AvailabilityStatus,
+    ClearCacheConfPayload,
+    ClearCacheStatus,
+    ClearChargingProfileConfPayload,
+    ClearChargingProfileStatus,
+    GetCompositeScheduleConfPayload,
+    GetCompositeScheduleStatus,
+    GetConfigurationConfPayload,
+    GetDiagnosticsConfPayload,
+    RemoteStopTransactionConfPayload,
+    ReserveNowConfPayload,
+    ReservationStatus,
+    ResetConfPayload,
+    ResetStatus,
+    TriggerMessageConfPayload,
+    TriggerMessageStatus,
+    UnlockConnectorConfPayload,
+    UnlockStatus,
+    UpdateFirmwareConfPayload,
+    CancelReservationConfPayload,
+    CancelReservationStatus,
+    CallErrorPayload,
+    EOcppVersion,
 };
-use chrono::Utc;
-use crate::ocpp_protocol_plugin::events::{OcppFromAssetChannel, OcppToAssetChannel};
+use super::resources::{OcppPendingRequest, OcppPendingRequests, OcppCallTimeout, OcppTransactionIdAllocator, OcppHeartbeatPolicy, OcppChargePointVersions};
+use chrono::{DateTime, Utc};
+use crate::ocpp_protocol_plugin::events::{OcppFromAssetChannel, OcppResponseFromAssetChannel, OcppToAssetChannel};
 use crossbeam_channel::TryRecvError;
 use crate::common::external_id_map::ExternalIdMap;
 use crate::common::types::{EAssetType, EOperationalStatus, EMeteringDataSource};
 
-/// Translate OCPP status string to internal enum.
-fn map_status_to_gun_status(status: &str) -> EGunStatusOcpp {
-    match status {
-        "Available"       => EGunStatusOcpp::Available,
-        "Preparing"       => EGunStatusOcpp::Preparing,
-        "Charging"        => EGunStatusOcpp::Charging,
-        "SuspendedEV"     => EGunStatusOcpp::SuspendedEV,
-        "SuspendedEVSE"   => EGunStatusOcpp::SuspendedEVSE,
-        "Finishing"       => EGunStatusOcpp::Finishing,
-        "Reserved"        => EGunStatusOcpp::Reserved,
-        "Unavailable"     => EGunStatusOcpp::Unavailable,
-        "Faulted"         => EGunStatusOcpp::Faulted,
-        other             => {
-            warn!("Unknown OCPP status '{}', defaulting to Unavailable.", other);
-            EGunStatusOcpp::Unavailable
+/// Charger-reported times, falling back to now when the charger's clock string cannot be parsed.
+fn parse_ocpp_timestamp(timestamp: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+/// Republishes every charger's configured version to the `OcppServer` when an `OcppConfig` is added, changed or removed.
+pub fn sync_ocpp_charge_point_versions(
+    configs: Query<Ref<OcppConfig>>,
+    mut removed: RemovedComponents<OcppConfig>,
+    versions: Res<OcppChargePointVersions>,
+) {
+    let removed_any = removed.read().count() > 0;
+    if !removed_any && !configs.iter().any(|config| config.is_changed()) {
+        return;
+    }
+    *versions.0.write().unwrap() = configs.iter().map(|config| (config.charge_point_id.clone(), config.version)).collect();
+}
+
+/// Decode raw charger CALLs in the OCPP version configured for the charger;
+/// ones that cannot be decoded are answered with a CALLERROR straight away.
+pub fn decode_ocpp_requests(
+    mut reader: EventReader<OcppRequestFromAsset>,
+    id_map: Res<ExternalIdMap>,
+    configs: Query<&OcppConfig>,
+    mut call_writer: EventWriter<OcppCallFromAsset>,
+    mut call_201_writer: EventWriter<Ocpp201CallFromAsset>,
+    mut command_writer: EventWriter<OcppCommandToAsset>,
+) {
+    for request in reader.read() {
+        // Unknown chargers are decoded as 1.6 and reported by the handler.
+        let version = id_map.0.get(&request.charge_point_id)
+            .and_then(|&entity| configs.get(entity).ok())
+            .map_or(EOcppVersion::V1_6J, |config| config.version);
+        let decoded = match version {
+            EOcppVersion::V1_6J => EChargePointRequest::decode(&request.action, &request.payload_json).map(|decoded| {
+                call_writer.write(OcppCallFromAsset {
+                    charge_point_id: request.charge_point_id.clone(),
+                    ocpp_message_id: request.ocpp_message_id.clone(),
+                    request: decoded,
+                });
+            }),
+            EOcppVersion::V2_0_1 => EChargingStationRequest::decode(&request.action, &request.payload_json).map(|decoded| {
+                call_201_writer.write(Ocpp201CallFromAsset {
+                    charge_point_id: request.charge_point_id.clone(),
+                    ocpp_message_id: request.ocpp_message_id.clone(),
+                    request: decoded,
+                });
+            }),
+        };
+        if let Err(e) = decoded {
+            let error_code = e.error_code.code_for(version);
+            warn!("Rejecting {} {} from '{}' with {}: {}", request.action, request.ocpp_message_id, request.charge_point_id, error_code, e.description);
+            command_writer.write(OcppCommandToAsset {
+                charge_point_id: request.charge_point_id.clone(),
+                message_type:    EOutgoingOcppMessage::CallError(CallErrorPayload {
+                    error_code: error_code.to_string(),
+                    error_description: e.description,
+                }),
+                ocpp_message_id: Some(request.ocpp_message_id.clone()),
+            });
         }
     }
 }
 
-/// Handle incoming OCPP requests (BootNotification, StatusNotification, MeterValues).
+/// Write meter samples into the charger's reading. Connector (or EVSE) 0 is the charger's main meter;
+/// samples for a gun go to its connector reading, and the connector readings then make up the charger total.
+/// Readings keep the newest timestamp they have seen, and samples older than it are skipped.
+fn apply_meter_samples(reading: &mut CurrentMeterReading, guns: &Guns, connector_id: u32, samples: &[ParsedMeterSample], cp_id: &str) {
+    // The charger's clock for the newest sample; ours when it sent none
+    let sampled_at = samples.iter().rev().find_map(|sample| sample.timestamp).unwrap_or_else(Utc::now);
+    if samples.is_empty() {
+        debug!("MeterValues without samples from '{}'", cp_id);
+    } else if connector_id == 0 {
+        let reading_time = reading.timestamp;
+        for sample in samples {
+            sample.apply(&mut reading.power_kw, &mut reading.energy_kwh, &mut reading.measurements, reading_time);
+        }
+        reading.timestamp = reading_time.max(sampled_at);
+    } else if guns.0.iter().any(|g| g.connector_id == connector_id) {
+        if !reading.connectors.iter().any(|c| c.connector_id == connector_id) {
+            reading.connectors.push(ConnectorMeterReading { connector_id, ..Default::default() });
+            reading.connectors.sort_by_key(|c| c.connector_id);
+        }
+        let connector = reading.connectors.iter_mut().find(|c| c.connector_id == connector_id).unwrap();
+        for sample in samples {
+            sample.apply(&mut connector.power_kw, &mut connector.energy_kwh, &mut connector.measurements, connector.timestamp);
+        }
+        connector.timestamp = connector.timestamp.max(sampled_at);
+        reading.power_kw = reading.connectors.iter().map(|c| c.power_kw).sum();
+        reading.energy_kwh = reading.connectors.iter().map(|c| c.energy_kwh).sum();
+        reading.timestamp = reading.timestamp.max(sampled_at);
+    } else {
+        warn!("MeterValues for unknown connector {} on '{}'", connector_id, cp_id);
+    }
+}
+
+/// Forget what the vehicle on `connector_id` reported once its session ends, so the next one does not inherit its SoC,
+/// offered current or phase values; when no session is left open, the charger-wide vehicle values go as well.
+fn clear_session_measurements(reading: &mut CurrentMeterReading, guns: &Guns, connector_id: u32) {
+    if let Some(connector) = reading.connectors.iter_mut().find(|c| c.connector_id == connector_id) {
+        connector.measurements = ElectricalMeasurements::default();
+    }
+    if guns.0.iter().all(|gun| gun.transaction.is_none()) {
+        reading.measurements.soc_percent = None;
+        reading.measurements.offered_current_a = None;
+    }
+}
+
+/// Handle decoded charger CALLs (BootNotification, Heartbeat, StatusNotification, MeterValues, Authorize, Start/StopTransaction,
+/// DataTransfer and the firmware and diagnostics status notifications).
 pub fn ocpp_request_handler(
-    mut event_reader: EventReader<OcppRequestFromAsset>,
+    mut event_reader: EventReader<OcppCallFromAsset>,
     id_map: Res<ExternalIdMap>,
     mut query: Query<(
         &OcppConfig,
@@ -56,6 +208,8 @@ pub fn ocpp_request_handler(
         &mut EOperationalStatus,
     )>,
     mut command_writer: EventWriter<OcppCommandToAsset>,
+    mut transaction_ids: ResMut<OcppTransactionIdAllocator>,
+    heartbeat: Res<OcppHeartbeatPolicy>,
 ) {
     for request in event_reader.read() {
         if let Some(&entity) = id_map.0.get(&request.charge_point_id) {
@@ -65,90 +219,173 @@ pub fn ocpp_request_handler(
             {
                 let cp_id = &config.charge_point_id;
 
-                match request.action.as_str() {
-                    "BootNotification" => {
-                        if let Ok(_payload) = serde_json::from_str::<BootNotificationReqPayload>(&request.payload_json) {
-                            conn.is_connected = true;
-                            conn.last_heartbeat_rcvd = Some(Utc::now());
-                            *status = EOperationalStatus::Online;
+                match &request.request {
+                    EChargePointRequest::BootNotification(_payload) => {
+                        conn.is_connected = true;
+                        conn.heartbeat_interval_secs = heartbeat.interval.as_secs() as u32;
+                        *status = EOperationalStatus::Online;
 
-                            let response = BootNotificationConfPayload {
+                        let response = BootNotificationConfPayload {
+                            current_time: Utc::now().to_rfc3339(),
+                            interval:     conn.heartbeat_interval_secs,
+                            status:       RegistrationStatus::Accepted,
+                        };
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::BootNotificationResponse(response),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
+                    }
+
+                    EChargePointRequest::Heartbeat(_) => {
+                        // Liveness itself is refreshed by `refresh_ocpp_liveness` for every inbound message.
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::HeartbeatResponse(HeartbeatConfPayload {
                                 current_time: Utc::now().to_rfc3339(),
-                                interval:     300,
-                                status:       RegistrationStatus::Accepted,
-                            };
-                            command_writer.write(OcppCommandToAsset {
-                                charge_point_id: cp_id.clone(),
-                                message_type:    EOutgoingOcppMessage::BootNotificationResponse(response),
-                                ocpp_message_id: Some(request.ocpp_message_id.clone()),
-                            });
-                        } else {
-                            error!("Invalid BootNotification payload");
-                        }
+                            }),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
                     }
 
-                    "StatusNotification" => {
-                        if let Ok(payload) = serde_json::from_str::<StatusNotificationReqPayload>(&request.payload_json) {
-                            let status_enum = map_status_to_gun_status(&payload.status);
+                    EChargePointRequest::StatusNotification(payload) => {
+                        let status_enum = payload.status.clone();
 
-                            if payload.connector_id == 0 {
-                                if status_enum == EGunStatusOcpp::Faulted || payload.error_code != "NoError" {
-                                    *status = EOperationalStatus::Faulted;
-                                } else if *status == EOperationalStatus::Faulted {
-                                    *status = EOperationalStatus::Online;
-                                }
-                                for gun in guns.0.iter_mut() {
-                                    gun.status = status_enum.clone();
-                                }
-                            } else if let Some(gun) = guns.0.iter_mut().find(|g| g.connector_id == payload.connector_id) {
-                                gun.status = status_enum;
-                            } else {
-                                warn!("Unknown connector {}", payload.connector_id);
+                        if payload.connector_id == 0 {
+                            if status_enum == EGunStatusOcpp::Faulted || payload.error_code != ChargePointErrorCode::NoError {
+                                *status = EOperationalStatus::Faulted;
+                            } else if *status == EOperationalStatus::Faulted {
+                                *status = EOperationalStatus::Online;
                             }
-
-                            command_writer.write(OcppCommandToAsset {
-                                charge_point_id: cp_id.clone(),
-                                message_type:    EOutgoingOcppMessage::StatusNotificationResponse(StatusNotificationConfPayload {}),
-                                ocpp_message_id: Some(request.ocpp_message_id.clone()),
-                            });
+                            for gun in guns.0.iter_mut() {
+                                gun.status = status_enum.clone();
+                            }
+                        } else if let Some(gun) = guns.0.iter_mut().find(|g| g.connector_id == payload.connector_id) {
+                            gun.status = status_enum;
                         } else {
-                            error!("Invalid StatusNotification payload");
+                            warn!("Unknown connector {}", payload.connector_id);
+                        }
+
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::StatusNotificationResponse(StatusNotificationConfPayload {}),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
+                    }
+
+                    EChargePointRequest::MeterValues(payload) => {
+                        if source.source_type == EMeteringDataSource::Ocpp {
+                            apply_meter_samples(&mut reading, &guns, payload.connector_id, &parse_meter_values(&payload.meter_value), cp_id);
                         }
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::MeterValuesResponse(MeterValuesConfPayload {}),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
+                    }
+
+                    EChargePointRequest::Authorize(payload) => {
+                        // Any id tag may charge, but only one session per tag on a charger.
+                        let in_use = guns.0.iter().any(|gun| gun.transaction.as_ref().is_some_and(|tx| tx.id_tag == payload.id_tag));
+                        let status = if in_use { AuthorizationStatus::ConcurrentTx } else { AuthorizationStatus::Accepted };
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::AuthorizeResponse(AuthorizeConfPayload {
+                                id_tag_info: IdTagInfo::with_status(status),
+                            }),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
                     }
 
-                    "MeterValues" => {
-                        if let Ok(payload) = serde_json::from_str::<MeterValuesReqPayload>(&request.payload_json) {
-                            if source.source_type == EMeteringDataSource::Ocpp {
-                                if let Some(sample) = payload.meter_value.first() {
-                                    for sv in &sample.sampled_value {
-                                        match sv.measurand.as_deref() {
-                                            Some("Power.Active.Import") => {
-                                                if let Ok(val) = sv.value.parse::<f32>() {
-                                                    reading.power_kw = if sv.unit.as_deref() == Some("kW") { val } else { val / 1000.0 };
-                                                }
-                                            }
-                                            Some("Energy.Active.Import.Register") => {
-                                                if let Ok(val) = sv.value.parse::<f64>() {
-                                                    reading.energy_kwh = if sv.unit.as_deref() == Some("kWh") { val } else { val / 1000.0 };
-                                                }
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                    reading.timestamp = Utc::now();
+                    EChargePointRequest::StartTransaction(payload) => {
+                        // Only a transaction that is actually recorded consumes an id
+                        let (status, transaction_id) = match guns.0.iter_mut().find(|g| g.connector_id == payload.connector_id) {
+                            Some(gun) => {
+                                let transaction_id = transaction_ids.next_id();
+                                if let Some(previous) = &gun.transaction {
+                                    warn!("Connector {} on '{}' started transaction {} while {} was still open", payload.connector_id, cp_id, transaction_id, previous.transaction_id);
                                 }
+                                gun.transaction = Some(GunTransaction {
+                                    transaction_id,
+                                    id_tag: payload.id_tag.clone(),
+                                    meter_start_wh: payload.meter_start,
+                                    meter_stop_wh: None,
+                                    start_time: parse_ocpp_timestamp(&payload.timestamp),
+                                    stop_time: None,
+                                    station_transaction_id: None,
+                                });
+                                info!("Transaction {} started on '{}' connector {} for '{}'", transaction_id, cp_id, payload.connector_id, payload.id_tag);
+                                (AuthorizationStatus::Accepted, transaction_id)
                             }
-                            command_writer.write(OcppCommandToAsset {
-                                charge_point_id: cp_id.clone(),
-                                message_type:    EOutgoingOcppMessage::MeterValuesResponse(MeterValuesConfPayload {}),
-                                ocpp_message_id: Some(request.ocpp_message_id.clone()),
-                            });
-                        } else {
-                            error!("Invalid MeterValues payload");
+                            None => {
+                                warn!("StartTransaction for unknown connector {} on '{}'", payload.connector_id, cp_id);
+                                (AuthorizationStatus::Invalid, 0)
+                            }
+                        };
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::StartTransactionResponse(StartTransactionConfPayload {
+                                id_tag_info: IdTagInfo::with_status(status),
+                                transaction_id,
+                            }),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
+                    }
+
+                    EChargePointRequest::StopTransaction(payload) => {
+                        let gun = guns.0.iter_mut()
+                            .find(|g| g.transaction.as_ref().is_some_and(|tx| tx.transaction_id == payload.transaction_id));
+                        match gun.and_then(|gun| gun.transaction.take().map(|tx| (gun, tx))) {
+                            Some((gun, mut transaction)) => {
+                                transaction.meter_stop_wh = Some(payload.meter_stop);
+                                transaction.stop_time = Some(parse_ocpp_timestamp(&payload.timestamp));
+                                info!("Transaction {} stopped on '{}' connector {} after {} Wh", transaction.transaction_id, cp_id, gun.connector_id, payload.meter_stop - transaction.meter_start_wh);
+                                gun.last_transaction = Some(transaction);
+                                let connector_id = gun.connector_id;
+                                clear_session_measurements(&mut reading, &guns, connector_id);
+                            }
+                            None => warn!("StopTransaction for unknown transaction {} on '{}'", payload.transaction_id, cp_id),
                         }
+                        // The charger must always get a reply, or it will keep resending the stop.
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::StopTransactionResponse(StopTransactionConfPayload {
+                                id_tag_info: payload.id_tag.as_ref().map(|_| IdTagInfo::with_status(AuthorizationStatus::Accepted)),
+                            }),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
+                    }
+
+                    EChargePointRequest::DataTransfer(payload) => {
+                        // No vendor extensions are understood.
+                        debug!("DataTransfer from '{}' for vendor '{}'", cp_id, payload.vendor_id);
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::DataTransferResponse(DataTransferConfPayload {
+                                status: DataTransferStatus::UnknownVendorId,
+                                data: None,
+                            }),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
                     }
 
-                    other => warn!("Unhandled OCPP action '{}'", other),
+                    EChargePointRequest::DiagnosticsStatusNotification(payload) => {
+                        info!("Diagnostics upload on '{}': {:?}", cp_id, payload.status);
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::DiagnosticsStatusNotificationResponse(DiagnosticsStatusNotificationConfPayload {}),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
+                    }
+
+                    EChargePointRequest::FirmwareStatusNotification(payload) => {
+                        info!("Firmware update on '{}': {:?}", cp_id, payload.status);
+                        command_writer.write(OcppCommandToAsset {
+                            charge_point_id: cp_id.clone(),
+                            message_type:    EOutgoingOcppMessage::FirmwareStatusNotificationResponse(FirmwareStatusNotificationConfPayload {}),
+                            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+                        });
+                    }
                 }
             }
         } else {
@@ -157,98 +394,405 @@ pub fn ocpp_request_handler(
     }
 }
 
-/// Send SetChargingProfile requests when target power changes.
+/// The 1.6 gun status for a 2.0.1 connector status. `Occupied` keeps the finer state a running transaction reported.
+fn gun_status_from_connector_status(status: ConnectorStatusEnumType, current: &EGunStatusOcpp) -> EGunStatusOcpp {
+    match status {
+        ConnectorStatusEnumType::Available => EGunStatusOcpp::Available,
+        ConnectorStatusEnumType::Occupied => match current {
+            EGunStatusOcpp::Preparing | EGunStatusOcpp::Charging | EGunStatusOcpp::SuspendedEV | EGunStatusOcpp::SuspendedEVSE | EGunStatusOcpp::Finishing => current.clone(),
+            _ => EGunStatusOcpp::Preparing,
+        },
+        ConnectorStatusEnumType::Reserved => EGunStatusOcpp::Reserved,
+        ConnectorStatusEnumType::Unavailable => EGunStatusOcpp::Unavailable,
+        ConnectorStatusEnumType::Faulted => EGunStatusOcpp::Faulted,
+    }
+}
+
+/// The 1.6 gun status for the charging state a 2.0.1 transaction reports.
+fn gun_status_from_charging_state(state: ChargingStateEnumType) -> EGunStatusOcpp {
+    match state {
+        ChargingStateEnumType::Charging => EGunStatusOcpp::Charging,
+        ChargingStateEnumType::SuspendedEV => EGunStatusOcpp::SuspendedEV,
+        ChargingStateEnumType::SuspendedEVSE => EGunStatusOcpp::SuspendedEVSE,
+        ChargingStateEnumType::EVConnected | ChargingStateEnumType::Idle => EGunStatusOcpp::Preparing,
+    }
+}
+
+/// Follow a 2.0.1 transaction on its gun: the first event opens it with an id from `OcppTransactionIdAllocator`,
+/// later ones update the gun status from the charging state, and `Ended` closes it. Returns the gun's connector id.
+fn apply_transaction_event(
+    payload: &TransactionEventRequest,
+    samples: &[ParsedMeterSample],
+    guns: &mut Guns,
+    transaction_ids: &mut OcppTransactionIdAllocator,
+    cp_id: &str,
+) -> Option<u32> {
+    let station_transaction_id = &payload.transaction_info.transaction_id;
+    let register_wh = samples.iter().rev().find_map(|sample| sample.import_register_kwh()).map(|kwh| (kwh * 1000.0).round() as i32);
+    let timestamp = parse_ocpp_timestamp(&payload.timestamp);
+    let is_this = |tx: &GunTransaction| tx.station_transaction_id.as_ref() == Some(station_transaction_id);
+
+    // Only the first event of a transaction has to name the EVSE.
+    let gun = match guns.0.iter().position(|gun| gun.transaction.as_ref().is_some_and(is_this)) {
+        Some(index) => Some(&mut guns.0[index]),
+        None => payload.evse.as_ref().and_then(|evse| guns.0.iter_mut().find(|gun| gun.connector_id == evse.id)),
+    };
+    let Some(gun) = gun else {
+        warn!("TransactionEvent {:?} for transaction '{}' on '{}' names no known EVSE", payload.event_type, station_transaction_id, cp_id);
+        return None;
+    };
+
+    // A station that was offline may report a transaction first as Updated, or only once it has Ended.
+    if !gun.transaction.as_ref().is_some_and(is_this) {
+        let transaction_id = transaction_ids.next_id();
+        if let Some(previous) = &gun.transaction {
+            warn!("EVSE {} on '{}' started transaction '{}' while {} was still open", gun.connector_id, cp_id, station_transaction_id, previous.transaction_id);
+        }
+        gun.transaction = Some(GunTransaction {
+            transaction_id,
+            id_tag: payload.id_token.as_ref().map(|token| token.id_token.clone()).unwrap_or_default(),
+            meter_start_wh: register_wh.unwrap_or(0),
+            meter_stop_wh: None,
+            start_time: timestamp,
+            stop_time: None,
+            station_transaction_id: Some(station_transaction_id.clone()),
+        });
+        info!("Transaction '{}' ({}) started on '{}' EVSE {}", station_transaction_id, transaction_id, cp_id, gun.connector_id);
+    }
+    let transaction = gun.transaction.as_mut().unwrap();
+    // The driver may authorize after plugging in.
+    if let (true, Some(token)) = (transaction.id_tag.is_empty(), &payload.id_token) {
+        transaction.id_tag = token.id_token.clone();
+    }
+    if let Some(state) = payload.transaction_info.charging_state {
+        gun.status = gun_status_from_charging_state(state);
+    }
+
+    if payload.event_type == TransactionEventEnumType::Ended {
+        let mut transaction = gun.transaction.take().unwrap();
+        transaction.meter_stop_wh = Some(register_wh.unwrap_or(transaction.meter_start_wh));
+        transaction.stop_time = Some(timestamp);
+        info!("Transaction '{}' ({}) ended on '{}' EVSE {} after {} Wh ({:?})", station_transaction_id, transaction.transaction_id, cp_id, gun.connector_id,
+            transaction.meter_stop_wh.unwrap() - transaction.meter_start_wh, payload.transaction_info.stopped_reason);
+        gun.last_transaction = Some(transaction);
+        // Idle: the EV has already gone.
+        gun.status = match payload.transaction_info.charging_state {
+            Some(ChargingStateEnumType::Idle) => EGunStatusOcpp::Available,
+            _ => EGunStatusOcpp::Finishing,
+        };
+    }
+    Some(gun.connector_id)
+}
+
+/// Handle decoded OCPP 2.0.1 station CALLs (BootNotification, Heartbeat, StatusNotification, MeterValues, Authorize,
+/// TransactionEvent and NotifyReport). EVSE ids address guns the way 1.6 connector ids do.
+pub fn ocpp201_request_handler(
+    mut event_reader: EventReader<Ocpp201CallFromAsset>,
+    id_map: Res<ExternalIdMap>,
+    mut query: Query<(
+        &OcppConfig,
+        &mut OcppConnectionState,
+        &mut Guns,
+        &mut CurrentMeterReading,
+        &MeteringSource,
+        &mut EOperationalStatus,
+        &mut Ocpp201DeviceModel,
+    )>,
+    mut command_writer: EventWriter<OcppCommandToAsset>,
+    mut transaction_ids: ResMut<OcppTransactionIdAllocator>,
+    heartbeat: Res<OcppHeartbeatPolicy>,
+) {
+    for request in event_reader.read() {
+        let Some((config, mut conn, mut guns, mut reading, source, mut status, mut device_model)) =
+            id_map.0.get(&request.charge_point_id).and_then(|&entity| query.get_mut(entity).ok())
+        else {
+            warn!("No OCPP 2.0.1 charger found for '{}'", request.charge_point_id);
+            continue;
+        };
+        let cp_id = &config.charge_point_id;
+
+        let reply = match &request.request {
+            EChargingStationRequest::BootNotification(payload) => {
+                info!("'{}' booted ({:?}): {} {}", cp_id, payload.reason, payload.charging_station.vendor_name, payload.charging_station.model);
+                conn.is_connected = true;
+                conn.heartbeat_interval_secs = heartbeat.interval.as_secs() as u32;
+                *status = EOperationalStatus::Online;
+                // In 2.0.1 the interval here is also the heartbeat interval the station adopts.
+                EOutgoingOcpp201Message::BootNotificationResponse(v201::BootNotificationResponse {
+                    current_time: Utc::now().to_rfc3339(),
+                    interval:     conn.heartbeat_interval_secs,
+                    status:       RegistrationStatus::Accepted,
+                    status_info:  None,
+                })
+            }
+
+            EChargingStationRequest::Heartbeat(_) => EOutgoingOcpp201Message::HeartbeatResponse(v201::HeartbeatResponse {
+                current_time: Utc::now().to_rfc3339(),
+            }),
+
+            EChargingStationRequest::StatusNotification(payload) => {
+                match guns.0.iter_mut().find(|g| g.connector_id == payload.evse_id) {
+                    Some(gun) => gun.status = gun_status_from_connector_status(payload.connector_status, &gun.status),
+                    None => warn!("Unknown EVSE {} on '{}'", payload.evse_id, cp_id),
+                }
+                EOutgoingOcpp201Message::StatusNotificationResponse(v201::StatusNotificationResponse {})
+            }
+
+            EChargingStationRequest::MeterValues(payload) => {
+                if source.source_type == EMeteringDataSource::Ocpp {
+                    let samples = parse_meter_values(&to_v16_meter_samples(&payload.meter_value));
+                    apply_meter_samples(&mut reading, &guns, payload.evse_id, &samples, cp_id);
+                }
+                EOutgoingOcpp201Message::MeterValuesResponse(v201::MeterValuesResponse {})
+            }
+
+            EChargingStationRequest::Authorize(payload) => {
+                // Any token may charge, but only one session per token on a charger.
+                let in_use = guns.0.iter().any(|gun| gun.transaction.as_ref().is_some_and(|tx| tx.id_tag == payload.id_token.id_token));
+                let status = if in_use { AuthorizationStatusEnumType::ConcurrentTx } else { AuthorizationStatusEnumType::Accepted };
+                EOutgoingOcpp201Message::AuthorizeResponse(v201::AuthorizeResponse {
+                    id_token_info: v201::IdTokenInfo { status },
+                })
+            }
+
+            EChargingStationRequest::TransactionEvent(payload) => {
+                let samples = parse_meter_values(&to_v16_meter_samples(payload.meter_value.as_deref().unwrap_or_default()));
+                let connector_id = apply_transaction_event(payload, &samples, &mut guns, &mut transaction_ids, cp_id);
+                if let (Some(connector_id), false, EMeteringDataSource::Ocpp) = (connector_id, samples.is_empty(), source.source_type) {
+                    apply_meter_samples(&mut reading, &guns, connector_id, &samples, cp_id);
+                }
+                if let (Some(connector_id), TransactionEventEnumType::Ended) = (connector_id, payload.event_type) {
+                    clear_session_measurements(&mut reading, &guns, connector_id);
+                }
+                EOutgoingOcpp201Message::TransactionEventResponse(v201::TransactionEventResponse {
+                    id_token_info: payload.id_token.as_ref().map(|_| v201::IdTokenInfo { status: AuthorizationStatusEnumType::Accepted }),
+                })
+            }
+
+            EChargingStationRequest::NotifyReport(payload) => {
+                for data in payload.report_data.iter().flatten() {
+                    let actual = data.variable_attribute.iter()
+                        .find(|attribute| attribute.attribute_type.unwrap_or_default() == AttributeEnumType::Actual)
+                        .and_then(|attribute| attribute.value.clone());
+                    let Some(value) = actual else { continue };
+                    let component = &data.component;
+                    let mut key = component.name.clone();
+                    if let Some(instance) = &component.instance {
+                        key += &format!(".{}", instance);
+                    }
+                    if let Some(evse) = &component.evse {
+                        key += &format!("[{}]", evse.id);
+                    }
+                    key += &format!("/{}", data.variable.name);
+                    if let Some(instance) = &data.variable.instance {
+                        key += &format!(".{}", instance);
+                    }
+                    device_model.variables.insert(key, value);
+                }
+                device_model.report_complete = !payload.tbc;
+                if device_model.report_complete {
+                    info!("'{}' reported {} device model variables", cp_id, device_model.variables.len());
+                }
+                EOutgoingOcpp201Message::NotifyReportResponse(v201::NotifyReportResponse {})
+            }
+        };
+        command_writer.write(OcppCommandToAsset {
+            charge_point_id: cp_id.clone(),
+            message_type:    EOutgoingOcppMessage::V201(reply),
+            ocpp_message_id: Some(request.ocpp_message_id.clone()),
+        });
+    }
+}
+
+/// Profile id base for setpoints scoped to a transaction (plus the connector id); distinct from the TxDefaultProfile ids used at initialization.
+const TX_PROFILE_ID: i32 = 1000;
+
+/// Split `target_kw` evenly across sessions, capping each at `caps_kw` and sharing what capped sessions cannot take among the rest.
+fn split_setpoint(target_kw: f32, caps_kw: &[f32]) -> Vec<f32> {
+    let mut shares = vec![0.0; caps_kw.len()];
+    let mut open: Vec<usize> = (0..caps_kw.len()).collect();
+    let mut remaining = target_kw.max(0.0);
+    while !open.is_empty() {
+        let share = remaining / open.len() as f32;
+        let (capped, uncapped): (Vec<usize>, Vec<usize>) = open.iter().partition(|&&i| caps_kw[i] < share);
+        if capped.is_empty() {
+            uncapped.into_iter().for_each(|i| shares[i] = share);
+            break;
+        }
+        for i in capped {
+            shares[i] = caps_kw[i];
+            remaining -= caps_kw[i];
+        }
+        open = uncapped;
+    }
+    shares
+}
+
+/// Translate power (kW) to the profile limit, in Amps per phase or Watts as the charger's `OcppProfileBehavior` asks.
+fn profile_limit(power_kw: f32, behavior: &OcppProfileBehavior, elec_cfg: &ChargerElectricalConfig, phases: u8) -> f32 {
+    match behavior.rate_unit {
+        // Amps = (Power in Watts) / (Voltage * Number of Phases)
+        EChargingRateUnit::Amps => ((power_kw * 1000.0) / (elec_cfg.nominal_voltage_ln * phases as f32)).max(0.0),
+        EChargingRateUnit::Watts => (power_kw * 1000.0).max(0.0),
+    }
+}
+
+/// Send SetChargingProfile requests when target power or the set of sessions on the charger changes.
+/// Profiles are built in 1.6 terms and restated per EVSE for 2.0.1 stations.
+#[allow(clippy::type_complexity)]
 pub fn charger_control_to_ocpp_profile(
     mut query: Query<(
         &ExternalId,
         &OcppConfig,
-        &Guns,
+        &mut Guns,
         &ChargerElectricalConfig,
         &OcppProfileBehavior,
         &TargetPowerSetpointKw,
         &OcppConnectionState,
-        &mut LastAppliedSetpointKw,
-    ), (With<EAssetType>, Changed<TargetPowerSetpointKw>)>,
+        &LastAppliedSetpointKw,
+        &mut OcppPendingSetpoint,
+        Option<Ref<SetpointSchedule>>,
+    ), (With<EAssetType>, Or<(Changed<TargetPowerSetpointKw>, Changed<Guns>, Changed<SetpointSchedule>)>)>,
     mut command_writer: EventWriter<OcppCommandToAsset>,
     mut message_id_counter: Local<u32>,
 ) {
-    for (external_id, config, _guns, elec_cfg, behavior, target_kw, conn, mut last_kw) in query.iter_mut() {
+    for (external_id, config, mut guns, elec_cfg, behavior, target_kw, conn, last_kw, mut pending, schedule) in query.iter_mut() {
         debug!(
             "Processing charger '{}' with target setpoint: {} kW",
             external_id.0, target_kw.0
         );
 
-        if target_kw.0 == last_kw.0 || !conn.is_connected {
-            info!(
-                "Skipping charger '{}' because setpoint is unchanged (last: {} kW) or charger is not connected (is_connected: {}).",
-                external_id.0, last_kw.0, conn.is_connected
-            );
+        if !conn.is_connected {
+            info!("Skipping charger '{}' because it is not connected.", external_id.0);
             continue;
         }
 
-        *message_id_counter += 1;
-        let msg_id = format!("sc_{}", *message_id_counter);
-        let cp_id = config.charge_point_id.clone();
-
-        // translation from power (kW) to the profile limit (Amps or Watts) 
-        // unit is determined by the `OcppProfileBehavior` component from the asset configuration.
-        let limit = match behavior.rate_unit {
-            EChargingRateUnit::Amps => {
-                // For Amps, we calculate per-phase current:
-                // Amps = (Power in Watts) / (Voltage * Number of Phases)
-                let per_phase = (target_kw.0 * 1000.0)
-                    / (elec_cfg.nominal_voltage_ln * elec_cfg.active_phase_count as f32);
-                per_phase.max(0.0)
+        // Each open session gets a share of the charger setpoint, capped by its connector's rating.
+        let gun_phases = |gun: &Gun| gun.phases.unwrap_or(elec_cfg.active_phase_count);
+        let caps_kw: Vec<f32> = guns.0.iter()
+            .filter(|gun| gun.transaction.is_some())
+            .map(|gun| gun.max_current_a
+                .map(|amps| amps * elec_cfg.nominal_voltage_ln * gun_phases(gun) as f32 / 1000.0)
+                .unwrap_or(f32::INFINITY))
+            .collect();
+        let mut shares = split_setpoint(target_kw.0, &caps_kw).into_iter();
+        let allocation: Vec<f32> = guns.0.iter()
+            .map(|gun| if gun.transaction.is_some() { shares.next().unwrap_or(0.0) } else { 0.0 })
+            .collect();
+
+        // The profile starts with the current target, which site limits may have cut below the slot in force;
+        // chargers taking schedules also get the slots still to come, as offsets from that slot's start.
+        let schedule = schedule.filter(|_| behavior.send_schedules);
+        let mut origin = Utc::now();
+        let mut plan: Vec<(u32, f32)> = vec![(0, target_kw.0)];
+        if let Some(schedule) = &schedule {
+            if let Some(current) = schedule.active_period {
+                origin = schedule.periods[current].start;
             }
-            EChargingRateUnit::Watts => (target_kw.0 * 1000.0).max(0.0),
-        };
+            plan.extend(schedule.periods.iter()
+                .skip(schedule.active_period.map_or(0, |current| current + 1))
+                .filter(|period| period.start > origin)
+                .map(|period| ((period.start - origin).num_seconds() as u32, period.power_kw)));
+        }
+        let plan_shares: Vec<Vec<f32>> = plan.iter().map(|&(_, kw)| split_setpoint(kw, &caps_kw)).collect();
 
-        debug!(
-            "Charger '{}' computed limit: {} {}",
-            external_id.0,
-            limit,
-            if behavior.rate_unit == EChargingRateUnit::Amps { "A" } else { "W" }
-        );
+        let allocation_unchanged = guns.0.iter().zip(&allocation).all(|(gun, kw)| gun.allocated_kw == *kw);
+        // While profiles are awaiting a reply, their setpoint is the one the charger ends up with, not the last applied one
+        let already_requested = if pending.ocpp_message_ids.is_empty() { target_kw.0 == last_kw.0 } else { pending.setpoint_kw == target_kw.0 };
+        let schedule_changed = schedule.as_ref().is_some_and(|schedule| schedule.is_changed());
+        if allocation_unchanged && already_requested && !schedule_changed {
+            debug!("Charger '{}' already has {} kW applied or awaiting a reply", external_id.0, target_kw.0);
+            continue;
+        }
 
-        let schedule = ChargingSchedule {
-            duration: Some(86400),
-            start_schedule: Some(Utc::now().to_rfc3339()),
-            charging_rate_unit: if behavior.rate_unit == EChargingRateUnit::Amps { "A" } else { "W" }.to_string(),
-            charging_schedule_period: vec![ChargingSchedulePeriod {
-                start_period: 0,
-                limit,
+        // The last slot holds for a day, as the single slot of an unscheduled profile does
+        let duration = plan.last().map_or(0, |&(start_period, _)| start_period) + 86400;
+        let schedule = |power_kw: &dyn Fn(usize) -> f32, phases: u8| ChargingSchedule {
+            duration: Some(duration),
+            start_schedule: Some(origin.to_rfc3339()),
+            charging_rate_unit: behavior.rate_unit.into(),
+            charging_schedule_period: plan.iter().enumerate().map(|(i, &(start_period, _))| ChargingSchedulePeriod {
+                start_period,
+                limit: profile_limit(power_kw(i), behavior, elec_cfg, phases),
                 number_phases: Some(behavior.profile_phases_in_ocpp_message),
-            }],
+            }).collect(),
             min_charging_rate: Some(0.0),
         };
 
-        let profiles = CsChargingProfiles {
-            charging_profile_id: 1,
-            transaction_id: None,
-            stack_level: 1,
-            charging_profile_purpose: "TxDefaultProfile".to_string(),
-            charging_profile_kind: "Absolute".to_string(),
-            recurrency_kind: Some("Daily".to_string()),
-            valid_from: Some(Utc::now().to_rfc3339()),
-            valid_to: Some((Utc::now() + chrono::Duration::days(1)).to_rfc3339()),
-            charging_schedule: schedule,
-        };
-
-        command_writer.write(OcppCommandToAsset {
-            charge_point_id: cp_id.clone(),
-            message_type: EOutgoingOcppMessage::SetChargingProfileRequest(SetChargingProfileReqPayload {
+        // Sessions in progress get a TxProfile each on their own connector;
+        // with none, the limit becomes the charger-wide default for the next session (TxDefaultProfile on connector 0).
+        let requests: Vec<SetChargingProfileReqPayload> = if caps_kw.is_empty() {
+            vec![SetChargingProfileReqPayload {
                 connector_id: 0,
-                cs_charging_profiles: profiles,
-            }),
-            ocpp_message_id: Some(msg_id.clone()),
-        });
+                cs_charging_profiles: CsChargingProfiles {
+                    charging_profile_id: 1,
+                    transaction_id: None,
+                    stack_level: 1,
+                    charging_profile_purpose: ChargingProfilePurposeType::TxDefaultProfile,
+                    charging_profile_kind: ChargingProfileKindType::Absolute,
+                    recurrency_kind: Some(RecurrencyKindType::Daily),
+                    valid_from: Some(Utc::now().to_rfc3339()),
+                    valid_to: Some((Utc::now() + chrono::Duration::days(1)).to_rfc3339()),
+                    charging_schedule: schedule(&|i| plan[i].1, elec_cfg.active_phase_count),
+                },
+            }]
+        } else {
+            guns.0.iter()
+                .filter_map(|gun| gun.transaction.as_ref().map(|tx| (gun, tx)))
+                .enumerate()
+                .map(|(session, (gun, tx))| SetChargingProfileReqPayload {
+                    connector_id: gun.connector_id,
+                    cs_charging_profiles: CsChargingProfiles {
+                        charging_profile_id: TX_PROFILE_ID + gun.connector_id as i32,
+                        transaction_id: Some(tx.transaction_id),
+                        stack_level: 1,
+                        charging_profile_purpose: ChargingProfilePurposeType::TxProfile,
+                        charging_profile_kind: ChargingProfileKindType::Absolute,
+                        recurrency_kind: None,
+                        valid_from: None,
+                        valid_to: None,
+                        charging_schedule: schedule(&|i| plan_shares[i][session], gun_phases(gun)),
+                    },
+                })
+                .collect()
+        };
 
-        debug!(
-            "Charger '{}' sent SetChargingProfileRequest with message ID: {}",
-            external_id.0, msg_id
-        );
+        pending.ocpp_message_ids.clear();
+        pending.failed = false;
+        pending.setpoint_kw = target_kw.0;
+        for request in requests {
+            *message_id_counter += 1;
+            let msg_id = format!("sc_{}", *message_id_counter);
+            debug!(
+                "Charger '{}' sending SetChargingProfileRequest {} for connector {}: limit {}",
+                external_id.0, msg_id, request.connector_id, request.cs_charging_profiles.charging_schedule.charging_schedule_period[0].limit
+            );
+            let message_type = match config.version {
+                EOcppVersion::V1_6J => EOutgoingOcppMessage::SetChargingProfileRequest(request),
+                EOcppVersion::V2_0_1 => {
+                    // The station knows its transactions by its own ids.
+                    let station_transaction_id = request.cs_charging_profiles.transaction_id
+                        .and_then(|id| guns.0.iter().filter_map(|gun| gun.transaction.as_ref()).find(|tx| tx.transaction_id == id))
+                        .and_then(|tx| tx.station_transaction_id.clone());
+                    EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::SetChargingProfileRequest(
+                        v201::SetChargingProfileRequest::from_v16(&request, station_transaction_id),
+                    ))
+                }
+            };
+            command_writer.write(OcppCommandToAsset {
+                charge_point_id: config.charge_point_id.clone(),
+                message_type,
+                ocpp_message_id: Some(msg_id.clone()),
+            });
+            pending.ocpp_message_ids.push(msg_id);
+        }
 
-        last_kw.0 = target_kw.0;
+        // Only touch `Guns` when the split moved, so this system does not retrigger itself.
+        if !allocation_unchanged {
+            for (gun, kw) in guns.0.iter_mut().zip(allocation) {
+                gun.allocated_kw = kw;
+            }
+        }
     }
 }
 
@@ -270,6 +814,7 @@ fn send_ocpp_command_helper(
 }
 
 /// Generic initialization for OCPP chargers.
+#[allow(clippy::type_complexity)]
 pub fn generic_ocpp_charger_initialization_system(
     mut generic_chargers_query: Query<(
         &ExternalId,
@@ -290,6 +835,7 @@ pub fn generic_ocpp_charger_initialization_system(
     )>,
     mut ocpp_command_writer: EventWriter<OcppCommandToAsset>,
     mut ocpp_message_id_generator_local: Local<u32>,
+    heartbeat: Res<OcppHeartbeatPolicy>,
 ) {
     let ocpp_message_id_generator_ref_mut = &mut *ocpp_message_id_generator_local;
 
@@ -304,8 +850,19 @@ pub fn generic_ocpp_charger_initialization_system(
         if connection_state_comp.is_connected && initialization_status_comp.0 == GenericChargerInitProgress::Pending {
             info!("Generic OCPP Init for {} (ExtID: {}): Starting", ocpp_config_comp.charge_point_id, external_id_comp.0);
 
+            if ocpp_config_comp.version == EOcppVersion::V2_0_1 {
+                // 2.0.1 stations take the heartbeat interval from the BootNotification reply and have no 1.6 configuration keys;
+                // ask for their configuration instead, which arrives as NotifyReport.
+                send_ocpp_command_helper(&ocpp_config_comp.charge_point_id, EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::GetBaseReportRequest(v201::GetBaseReportRequest {
+                    request_id: 1, report_base: ReportBaseEnumType::ConfigurationInventory,
+                })), &mut ocpp_command_writer, ocpp_message_id_generator_ref_mut, "generic_init");
+                initialization_status_comp.0 = GenericChargerInitProgress::Complete;
+                info!("Generic OCPP Init for {} (ExtID: {}): Requested base report.", ocpp_config_comp.charge_point_id, external_id_comp.0);
+                return;
+            }
+
             send_ocpp_command_helper(&ocpp_config_comp.charge_point_id, EOutgoingOcppMessage::ChangeConfigurationRequest(ChangeConfigurationReqPayload {
-                key: "HeartbeatInterval".to_string(), value: "300".to_string()
+                key: "HeartbeatInterval".to_string(), value: heartbeat.interval.as_secs().to_string()
             }), &mut ocpp_command_writer, ocpp_message_id_generator_ref_mut, "generic_init");
             send_ocpp_command_helper(&ocpp_config_comp.charge_point_id, EOutgoingOcppMessage::ChangeConfigurationRequest(ChangeConfigurationReqPayload {
                 key: "MeterValueSampleInterval".to_string(), value: "60".to_string()
@@ -318,10 +875,10 @@ pub fn generic_ocpp_charger_initialization_system(
                 let clear_profile_data = CsChargingProfiles {
                     charging_profile_id: gun_configuration_item.connector_id as i32,
                     stack_level: 0,
-                    charging_profile_purpose: "TxDefaultProfile".to_string(),
-                    charging_profile_kind: "Recurring".to_string(),
+                    charging_profile_purpose: ChargingProfilePurposeType::TxDefaultProfile,
+                    charging_profile_kind: ChargingProfileKindType::Recurring,
                     charging_schedule: ChargingSchedule {
-                        charging_rate_unit: "W".to_string(),
+                        charging_rate_unit: ChargingRateUnitType::W,
                         charging_schedule_period: vec![ChargingSchedulePeriod{start_period:0, limit: 0.0, number_phases: Some(0)}],
                         ..Default::default()
                     },
@@ -337,13 +894,13 @@ pub fn generic_ocpp_charger_initialization_system(
                     charging_profile_id: gun_configuration_item.connector_id as i32,
                     transaction_id: None,
                     stack_level: 1,
-                    charging_profile_purpose: "TxDefaultProfile".to_string(),
-                    charging_profile_kind: "Recurring".to_string(),
-                    recurrency_kind: Some("Daily".to_string()),
+                    charging_profile_purpose: ChargingProfilePurposeType::TxDefaultProfile,
+                    charging_profile_kind: ChargingProfileKindType::Recurring,
+                    recurrency_kind: Some(RecurrencyKindType::Daily),
                     charging_schedule: ChargingSchedule {
                         duration: Some(86400),
                         start_schedule: Some("00:00:00".to_string()),
-                        charging_rate_unit: "W".to_string(),
+                        charging_rate_unit: ChargingRateUnitType::W,
                         charging_schedule_period: vec![ChargingSchedulePeriod {
                             start_period: 0,
                             limit: 0.0, // Default to 0W, actual power set by balancer
@@ -389,6 +946,10 @@ pub fn alfen_special_init_system(
     let ocpp_message_id_generator_ref_mut = &mut *ocpp_message_id_generator_local;
 
     for (external_id_comp, ocpp_config_comp, electrical_config_comp, guns_comp, alfen_specific_config_comp, generic_init_status_comp, mut alfen_init_status_comp) in alfen_chargers_special_init_query.iter_mut() {
+        // The Alfen sequence sets OCPP 1.6 configuration keys.
+        if ocpp_config_comp.version != EOcppVersion::V1_6J {
+            continue;
+        }
         if generic_init_status_comp.0 == GenericChargerInitProgress::Complete && alfen_init_status_comp.0 == AlfenSpecialInitState::Pending {
             info!("Alfen Charger {} (ExtID: {}) generic init complete. Performing special Alfen initialization.", ocpp_config_comp.charge_point_id, external_id_comp.0);
             alfen_init_status_comp.0 = AlfenSpecialInitState::InProgress;
@@ -415,7 +976,7 @@ pub fn alfen_special_init_system(
                 let schedule_data = ChargingSchedule {
                     duration: None,
                     start_schedule: None,
-                    charging_rate_unit: "W".to_string(),
+                    charging_rate_unit: ChargingRateUnitType::W,
                     charging_schedule_period: vec![schedule_period_data],
                     min_charging_rate: Some(0.0),
                 };
@@ -423,9 +984,9 @@ pub fn alfen_special_init_system(
                 let charging_profiles_data = CsChargingProfiles {
                     charging_profile_id: gun_configuration_item.connector_id as i32 * 100 + 2,
                     transaction_id: None,
-                    stack_level: gun_configuration_item.connector_id as u32 * 10 + 2,
-                    charging_profile_purpose: "TxDefaultProfile".to_string(),
-                    charging_profile_kind: "Relative".to_string(),
+                    stack_level: gun_configuration_item.connector_id * 10 + 2,
+                    charging_profile_purpose: ChargingProfilePurposeType::TxDefaultProfile,
+                    charging_profile_kind: ChargingProfileKindType::Relative,
                     recurrency_kind: None,
                     valid_from: None,
                     valid_to: None,
@@ -461,11 +1022,274 @@ pub fn ingest_ocpp_requests_from_channel_system(
 }
 
 /// Drain Bevy‐generated `SendOcppToChargerCommand` events and push them into the channel resource.
+/// CALLs are recorded as pending until the charger answers or they time out.
 pub fn export_ocpp_commands_to_channel_system(
     mut reader: EventReader<OcppCommandToAsset>,
     channel: Res<OcppToAssetChannel>,
+    time: Res<Time>,
+    mut pending: ResMut<OcppPendingRequests>,
 ) {
     for cmd in reader.read() {
+        if let (Some(_), Some(message_id)) = (cmd.message_type.action_name(), &cmd.ocpp_message_id) {
+            pending.0.insert(message_id.clone(), OcppPendingRequest {
+                charge_point_id: cmd.charge_point_id.clone(),
+                message: cmd.message_type.clone(),
+                sent_at_secs: time.elapsed_secs_f64(),
+            });
+        }
         let _ = channel.0.send(cmd.clone());
     }
 }
+
+/// Pull charger replies to central-system CALLs from the channel resource and fire Bevy events.
+pub fn ingest_ocpp_responses_from_channel_system(
+    channel: Res<OcppResponseFromAssetChannel>,
+    mut writer: EventWriter<OcppResponseFromAsset>,
+) {
+    while let Ok(response) = channel.0.try_recv() {
+        writer.write(response);
+    }
+}
+
+/// Interpret a charger's reply in terms of the CALL it answers.
+fn call_outcome(message: &EOutgoingOcppMessage, response: &EOcppCallResponse) -> EOcppCallOutcome {
+    let payload_json = match response {
+        EOcppCallResponse::Result { payload_json } => payload_json,
+        EOcppCallResponse::Error { error_code, .. } => return EOcppCallOutcome::CallError(error_code.clone()),
+    };
+    let outcome = match message {
+        EOutgoingOcppMessage::SetChargingProfileRequest(_) => serde_json::from_str::<SetChargingProfileConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                ChargingProfileStatus::Accepted => EOcppCallOutcome::Accepted,
+                ChargingProfileStatus::Rejected => EOcppCallOutcome::Rejected,
+                ChargingProfileStatus::NotSupported => EOcppCallOutcome::NotSupported,
+            }),
+        EOutgoingOcppMessage::ChangeConfigurationRequest(_) => serde_json::from_str::<ChangeConfigurationConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                ConfigurationStatus::Accepted => EOcppCallOutcome::Accepted,
+                ConfigurationStatus::Rejected => EOcppCallOutcome::Rejected,
+                ConfigurationStatus::RebootRequired => EOcppCallOutcome::RebootRequired,
+                ConfigurationStatus::NotSupported => EOcppCallOutcome::NotSupported,
+            }),
+        EOutgoingOcppMessage::RemoteStartTransactionRequest(_) => serde_json::from_str::<RemoteStartTransactionConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                RemoteStartStopStatus::Accepted => EOcppCallOutcome::Accepted,
+                RemoteStartStopStatus::Rejected => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::RemoteStopTransactionRequest(_) => serde_json::from_str::<RemoteStopTransactionConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                RemoteStartStopStatus::Accepted => EOcppCallOutcome::Accepted,
+                RemoteStartStopStatus::Rejected => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::ChangeAvailabilityRequest(_) => serde_json::from_str::<ChangeAvailabilityConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                // Scheduled: applied once the running transaction ends.
+                AvailabilityStatus::Accepted | AvailabilityStatus::Scheduled => EOcppCallOutcome::Accepted,
+                AvailabilityStatus::Rejected => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::ClearCacheRequest(_) => serde_json::from_str::<ClearCacheConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                ClearCacheStatus::Accepted => EOcppCallOutcome::Accepted,
+                ClearCacheStatus::Rejected => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::ClearChargingProfileRequest(_) => serde_json::from_str::<ClearChargingProfileConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                ClearChargingProfileStatus::Accepted => EOcppCallOutcome::Accepted,
+                ClearChargingProfileStatus::Unknown => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::GetCompositeScheduleRequest(_) => serde_json::from_str::<GetCompositeScheduleConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                GetCompositeScheduleStatus::Accepted => EOcppCallOutcome::Accepted,
+                GetCompositeScheduleStatus::Rejected => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::ResetRequest(_) => serde_json::from_str::<ResetConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                ResetStatus::Accepted => EOcppCallOutcome::Accepted,
+                ResetStatus::Rejected => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::UnlockConnectorRequest(_) => serde_json::from_str::<UnlockConnectorConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                UnlockStatus::Unlocked => EOcppCallOutcome::Accepted,
+                UnlockStatus::UnlockFailed => EOcppCallOutcome::Rejected,
+                UnlockStatus::NotSupported => EOcppCallOutcome::NotSupported,
+            }),
+        EOutgoingOcppMessage::TriggerMessageRequest(_) => serde_json::from_str::<TriggerMessageConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                TriggerMessageStatus::Accepted => EOcppCallOutcome::Accepted,
+                TriggerMessageStatus::Rejected => EOcppCallOutcome::Rejected,
+                TriggerMessageStatus::NotImplemented => EOcppCallOutcome::NotSupported,
+            }),
+        EOutgoingOcppMessage::ReserveNowRequest(_) => serde_json::from_str::<ReserveNowConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                ReservationStatus::Accepted => EOcppCallOutcome::Accepted,
+                ReservationStatus::Faulted | ReservationStatus::Occupied | ReservationStatus::Rejected | ReservationStatus::Unavailable => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::CancelReservationRequest(_) => serde_json::from_str::<CancelReservationConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                CancelReservationStatus::Accepted => EOcppCallOutcome::Accepted,
+                CancelReservationStatus::Rejected => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::DataTransferRequest(_) => serde_json::from_str::<DataTransferConfPayload>(payload_json)
+            .map(|conf| match conf.status {
+                DataTransferStatus::Accepted => EOcppCallOutcome::Accepted,
+                DataTransferStatus::Rejected => EOcppCallOutcome::Rejected,
+                DataTransferStatus::UnknownMessageId | DataTransferStatus::UnknownVendorId => EOcppCallOutcome::NotSupported,
+            }),
+        // Replies that carry data rather than a status are accepted once they parse.
+        EOutgoingOcppMessage::GetConfigurationRequest(_) => serde_json::from_str::<GetConfigurationConfPayload>(payload_json).map(|_| EOcppCallOutcome::Accepted),
+        EOutgoingOcppMessage::GetDiagnosticsRequest(_) => serde_json::from_str::<GetDiagnosticsConfPayload>(payload_json).map(|_| EOcppCallOutcome::Accepted),
+        EOutgoingOcppMessage::UpdateFirmwareRequest(_) => serde_json::from_str::<UpdateFirmwareConfPayload>(payload_json).map(|_| EOcppCallOutcome::Accepted),
+        EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::SetChargingProfileRequest(_)) => serde_json::from_str::<v201::SetChargingProfileResponse>(payload_json)
+            .map(|conf| match conf.status {
+                ChargingProfileStatusEnumType::Accepted => EOcppCallOutcome::Accepted,
+                ChargingProfileStatusEnumType::Rejected => EOcppCallOutcome::Rejected,
+            }),
+        EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::GetBaseReportRequest(_)) => serde_json::from_str::<v201::GetBaseReportResponse>(payload_json)
+            .map(|conf| match conf.status {
+                GenericDeviceModelStatusEnumType::Accepted | GenericDeviceModelStatusEnumType::EmptyResultSet => EOcppCallOutcome::Accepted,
+                GenericDeviceModelStatusEnumType::Rejected => EOcppCallOutcome::Rejected,
+                GenericDeviceModelStatusEnumType::NotSupported => EOcppCallOutcome::NotSupported,
+            }),
+        // Replies to charger-initiated CALLs are never pending.
+        _ => return EOcppCallOutcome::Accepted,
+    };
+    outcome.unwrap_or_else(|e| {
+        warn!("Invalid CALLRESULT payload for {:?}: {}", message.action_name(), e);
+        EOcppCallOutcome::CallError("FormationViolation".to_string())
+    })
+}
+
+/// Record `outcome` against the command it answers; a setpoint becomes the last applied one once all its profiles are accepted.
+fn record_call_outcome(
+    message_id: &str,
+    message: &EOutgoingOcppMessage,
+    outcome: EOcppCallOutcome,
+    outcomes: &mut OcppCallOutcomes,
+    pending_setpoint: &mut OcppPendingSetpoint,
+    last_applied: &mut LastAppliedSetpointKw,
+) {
+    match message {
+        EOutgoingOcppMessage::SetChargingProfileRequest(_)
+        | EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::SetChargingProfileRequest(_)) => {
+            if let Some(index) = pending_setpoint.ocpp_message_ids.iter().position(|id| id == message_id) {
+                pending_setpoint.ocpp_message_ids.remove(index);
+                pending_setpoint.failed |= outcome != EOcppCallOutcome::Accepted;
+                if pending_setpoint.ocpp_message_ids.is_empty() && !pending_setpoint.failed {
+                    last_applied.0 = pending_setpoint.setpoint_kw;
+                }
+            }
+            outcomes.set_charging_profile = Some(outcome);
+        }
+        EOutgoingOcppMessage::ChangeConfigurationRequest(req) => {
+            outcomes.change_configuration.insert(req.key.clone(), outcome);
+        }
+        EOutgoingOcppMessage::RemoteStartTransactionRequest(_) => {
+            outcomes.remote_start_transaction = Some(outcome);
+        }
+        _ => {}
+    }
+}
+
+/// Match charger replies to their pending CALLs and record the outcome on the charger.
+pub fn ocpp_response_handler(
+    mut reader: EventReader<OcppResponseFromAsset>,
+    mut pending: ResMut<OcppPendingRequests>,
+    id_map: Res<ExternalIdMap>,
+    mut query: Query<(&mut OcppCallOutcomes, &mut OcppPendingSetpoint, &mut LastAppliedSetpointKw)>,
+) {
+    for response in reader.read() {
+        let Some(request) = pending.0.get(&response.ocpp_message_id) else {
+            warn!("Reply {} from '{}' matches no pending request", response.ocpp_message_id, response.charge_point_id);
+            continue;
+        };
+        if request.charge_point_id != response.charge_point_id {
+            warn!("Reply {} from '{}' answers a request sent to '{}'", response.ocpp_message_id, response.charge_point_id, request.charge_point_id);
+            continue;
+        }
+        let Some(request) = pending.0.remove(&response.ocpp_message_id) else { continue };
+
+        let outcome = call_outcome(&request.message, &response.response);
+        if outcome != EOcppCallOutcome::Accepted {
+            warn!("Charger '{}' answered {:?} {} with {:?}", response.charge_point_id, request.message.action_name(), response.ocpp_message_id, outcome);
+        }
+        let Some((mut outcomes, mut pending_setpoint, mut last_applied)) =
+            id_map.0.get(&response.charge_point_id).and_then(|&entity| query.get_mut(entity).ok())
+        else {
+            continue;
+        };
+        record_call_outcome(&response.ocpp_message_id, &request.message, outcome, &mut outcomes, &mut pending_setpoint, &mut last_applied);
+    }
+}
+
+/// Record CALLs the charger has not answered within `OcppCallTimeout` as `TimedOut`.
+pub fn expire_pending_ocpp_requests(
+    time: Res<Time>,
+    timeout: Res<OcppCallTimeout>,
+    mut pending: ResMut<OcppPendingRequests>,
+    id_map: Res<ExternalIdMap>,
+    mut query: Query<(&mut OcppCallOutcomes, &mut OcppPendingSetpoint, &mut LastAppliedSetpointKw)>,
+) {
+    let now = time.elapsed_secs_f64();
+    let expired: Vec<String> = pending.0.iter()
+        .filter(|(_, request)| now - request.sent_at_secs >= timeout.0.as_secs_f64())
+        .map(|(message_id, _)| message_id.clone())
+        .collect();
+
+    for message_id in expired {
+        let Some(request) = pending.0.remove(&message_id) else { continue };
+        warn!("Charger '{}' did not answer {:?} {} in time", request.charge_point_id, request.message.action_name(), message_id);
+        if let Some((mut outcomes, mut pending_setpoint, mut last_applied)) =
+            id_map.0.get(&request.charge_point_id).and_then(|&entity| query.get_mut(entity).ok())
+        {
+            record_call_outcome(&message_id, &request.message, EOcppCallOutcome::TimedOut, &mut outcomes, &mut pending_setpoint, &mut last_applied);
+        }
+    }
+}
+
+/// Any message from a charger counts as a sign of life; a booted charger that had gone silent comes back `Online`.
+pub fn refresh_ocpp_liveness(
+    time: Res<Time>,
+    mut requests: EventReader<OcppRequestFromAsset>,
+    mut responses: EventReader<OcppResponseFromAsset>,
+    id_map: Res<ExternalIdMap>,
+    mut query: Query<(&mut OcppConnectionState, &mut EOperationalStatus)>,
+) {
+    let senders = requests.read().map(|request| &request.charge_point_id)
+        .chain(responses.read().map(|response| &response.charge_point_id));
+    for charge_point_id in senders {
+        let Some((mut conn, mut status)) = id_map.0.get(charge_point_id).and_then(|&entity| query.get_mut(entity).ok()) else {
+            continue;
+        };
+        conn.last_seen_secs = Some(time.elapsed_secs_f64());
+        conn.last_heartbeat_rcvd = Some(Utc::now());
+        // Before BootNotification there is no negotiated interval to supervise against.
+        if !conn.is_connected && conn.heartbeat_interval_secs > 0 {
+            info!("Charger '{}' is reachable again", charge_point_id);
+            conn.is_connected = true;
+            if *status == EOperationalStatus::Offline {
+                *status = EOperationalStatus::Online;
+            }
+        }
+    }
+}
+
+/// Mark chargers silent for `OcppHeartbeatPolicy::offline_after_intervals` heartbeat intervals as disconnected and `Offline`.
+pub fn supervise_ocpp_liveness(
+    time: Res<Time>,
+    policy: Res<OcppHeartbeatPolicy>,
+    mut query: Query<(&OcppConfig, &mut OcppConnectionState, &mut EOperationalStatus)>,
+) {
+    let now = time.elapsed_secs_f64();
+    for (config, mut conn, mut status) in query.iter_mut() {
+        let Some(last_seen) = conn.last_seen_secs else { continue };
+        if !conn.is_connected || conn.heartbeat_interval_secs == 0 {
+            continue;
+        }
+        let deadline = conn.heartbeat_interval_secs as f64 * policy.offline_after_intervals;
+        if now - last_seen > deadline {
+            warn!("Charger '{}' silent for {:.0} s (deadline {:.0} s); marking it Offline", config.charge_point_id, now - last_seen, deadline);
+            conn.is_connected = false;
+            *status = EOperationalStatus::Offline;
+        }
+    }
+}
@@ -1,11 +1,15 @@
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use super::components::EGunStatusOcpp;
+use super::messages::EOcppAction;
+use super::types_v201::EOutgoingOcpp201Message;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
 #[reflect(Serialize, Deserialize)]
 pub enum EOcppVersion {
     V1_6J,
+    V2_0_1,
 }
 
 impl FromStr for EOcppVersion {
@@ -13,6 +17,7 @@ impl FromStr for EOcppVersion {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "V1_6J" => Ok(EOcppVersion::V1_6J),
+            "V2_0_1" => Ok(EOcppVersion::V2_0_1),
             _ => Err(()),
         }
     }
@@ -39,8 +44,24 @@ impl FromStr for EChargingRateUnit {
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
 #[reflect(Default, Serialize, Deserialize)]
 pub struct BootNotificationReqPayload {
+    #[serde(rename = "chargePointVendor")]
     pub charge_point_vendor: String,
+    #[serde(rename = "chargePointModel")]
     pub charge_point_model: String,
+    #[serde(rename = "chargePointSerialNumber", skip_serializing_if = "Option::is_none")]
+    pub charge_point_serial_number: Option<String>,
+    #[serde(rename = "chargeBoxSerialNumber", skip_serializing_if = "Option::is_none")]
+    pub charge_box_serial_number: Option<String>,
+    #[serde(rename = "firmwareVersion", skip_serializing_if = "Option::is_none")]
+    pub firmware_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iccid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imsi: Option<String>,
+    #[serde(rename = "meterType", skip_serializing_if = "Option::is_none")]
+    pub meter_type: Option<String>,
+    #[serde(rename = "meterSerialNumber", skip_serializing_if = "Option::is_none")]
+    pub meter_serial_number: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
@@ -60,14 +81,56 @@ pub struct BootNotificationConfPayload {
     pub status: RegistrationStatus,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct HeartbeatReqPayload {
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct HeartbeatConfPayload {
+    #[serde(rename = "currentTime")]
+    pub current_time: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
 #[reflect(Default, Serialize, Deserialize)]
 pub struct StatusNotificationReqPayload {
     #[serde(rename = "connectorId")]
     pub connector_id: u32,
     #[serde(rename = "errorCode")]
-    pub error_code: String, 
-    pub status: String, 
+    pub error_code: ChargePointErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<String>,
+    pub status: EGunStatusOcpp,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+    #[serde(rename = "vendorId", skip_serializing_if = "Option::is_none")]
+    pub vendor_id: Option<String>,
+    #[serde(rename = "vendorErrorCode", skip_serializing_if = "Option::is_none")]
+    pub vendor_error_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum ChargePointErrorCode {
+    ConnectorLockFailure,
+    EVCommunicationError,
+    GroundFailure,
+    HighTemperature,
+    InternalError,
+    LocalListConflict,
+    #[default]
+    NoError,
+    OtherError,
+    OverCurrentFailure,
+    PowerMeterFailure,
+    PowerSwitchFailure,
+    ReaderFailure,
+    ResetFailure,
+    UnderVoltage,
+    OverVoltage,
+    WeakSignal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
@@ -84,6 +147,8 @@ pub struct MeterSample {
     pub sampled_value: Vec<MeterValueSampledValue>,
 }
 
+/// The enumerated fields are kept as strings and decoded by `meter_values`,
+/// so one value a charger labels oddly is skipped rather than rejecting the whole message.
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
 #[reflect(Default, Serialize, Deserialize)]
 pub struct MeterValueSampledValue {
@@ -102,8 +167,8 @@ pub struct MeterValueSampledValue {
 pub struct MeterValuesReqPayload {
     #[serde(rename = "connectorId")]
     pub connector_id: u32,
-    #[serde(rename = "transactionId")]
-    pub transaction_id: Option<i32>, 
+    #[serde(rename = "transactionId", skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<i32>,
     #[serde(rename = "meterValue")]
     pub meter_value: Vec<MeterSample>,
 }
@@ -119,21 +184,64 @@ pub struct ChargingSchedulePeriod {
     #[serde(rename = "startPeriod")]
     pub start_period: u32, 
     pub limit: f32,        
-    #[serde(rename = "numberPhases")]
+    #[serde(rename = "numberPhases", skip_serializing_if = "Option::is_none")]
     pub number_phases: Option<u8>,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum ChargingRateUnitType {
+    #[default]
+    W,
+    A,
+}
+
+impl From<EChargingRateUnit> for ChargingRateUnitType {
+    fn from(unit: EChargingRateUnit) -> Self {
+        match unit {
+            EChargingRateUnit::Watts => ChargingRateUnitType::W,
+            EChargingRateUnit::Amps => ChargingRateUnitType::A,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum ChargingProfilePurposeType {
+    ChargePointMaxProfile,
+    #[default]
+    TxDefaultProfile,
+    TxProfile,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum ChargingProfileKindType {
+    #[default]
+    Absolute,
+    Recurring,
+    Relative,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum RecurrencyKindType {
+    Daily,
+    Weekly,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
 #[reflect(Default, Serialize, Deserialize)]
 pub struct ChargingSchedule {
-    pub duration: Option<u32>, 
-    #[serde(rename = "startSchedule")]
-    pub start_schedule: Option<String>, 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    #[serde(rename = "startSchedule", skip_serializing_if = "Option::is_none")]
+    pub start_schedule: Option<String>,
     #[serde(rename = "chargingRateUnit")]
-    pub charging_rate_unit: String, 
+    pub charging_rate_unit: ChargingRateUnitType,
     #[serde(rename = "chargingSchedulePeriod")]
     pub charging_schedule_period: Vec<ChargingSchedulePeriod>,
-    #[serde(rename = "minChargingRate")]
+    #[serde(rename = "minChargingRate", skip_serializing_if = "Option::is_none")]
     pub min_charging_rate: Option<f32>,
 }
 
@@ -142,20 +250,20 @@ pub struct ChargingSchedule {
 pub struct CsChargingProfiles {
     #[serde(rename = "chargingProfileId")]
     pub charging_profile_id: i32,
-    #[serde(rename = "transactionId")]
+    #[serde(rename = "transactionId", skip_serializing_if = "Option::is_none")]
     pub transaction_id: Option<i32>,
     #[serde(rename = "stackLevel")]
     pub stack_level: u32,
     #[serde(rename = "chargingProfilePurpose")]
-    pub charging_profile_purpose: String, 
+    pub charging_profile_purpose: ChargingProfilePurposeType,
     #[serde(rename = "chargingProfileKind")]
-    pub charging_profile_kind: String, 
-    #[serde(rename = "recurrencyKind")]
-    pub recurrency_kind: Option<String>, 
-    #[serde(rename = "validFrom")]
-    pub valid_from: Option<String>, 
-    #[serde(rename = "validTo")]
-    pub valid_to: Option<String>, 
+    pub charging_profile_kind: ChargingProfileKindType,
+    #[serde(rename = "recurrencyKind", skip_serializing_if = "Option::is_none")]
+    pub recurrency_kind: Option<RecurrencyKindType>,
+    #[serde(rename = "validFrom", skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<String>,
+    #[serde(rename = "validTo", skip_serializing_if = "Option::is_none")]
+    pub valid_to: Option<String>,
     #[serde(rename = "chargingSchedule")]
     pub charging_schedule: ChargingSchedule,
 }
@@ -208,21 +316,637 @@ pub struct ChangeConfigurationConfPayload {
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
 #[reflect(Default, Serialize, Deserialize)]
 pub struct RemoteStartTransactionReqPayload {
-    #[serde(rename = "connectorId")]
+    #[serde(rename = "connectorId", skip_serializing_if = "Option::is_none")]
     pub connector_id: Option<u32>,
     #[serde(rename = "idTag")]
     pub id_tag: String,
-    #[serde(rename = "chargingProfile")]
+    #[serde(rename = "chargingProfile", skip_serializing_if = "Option::is_none")]
     pub charging_profile: Option<CsChargingProfiles>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum AuthorizationStatus {
+    Accepted,
+    Blocked,
+    Expired,
+    Invalid,
+    ConcurrentTx,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct IdTagInfo {
+    #[serde(rename = "expiryDate", skip_serializing_if = "Option::is_none")]
+    pub expiry_date: Option<String>,
+    #[serde(rename = "parentIdTag", skip_serializing_if = "Option::is_none")]
+    pub parent_id_tag: Option<String>,
+    pub status: AuthorizationStatus,
+}
+
+impl IdTagInfo {
+    pub fn with_status(status: AuthorizationStatus) -> Self {
+        Self { expiry_date: None, parent_id_tag: None, status }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct AuthorizeReqPayload {
+    #[serde(rename = "idTag")]
+    pub id_tag: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct AuthorizeConfPayload {
+    #[serde(rename = "idTagInfo")]
+    pub id_tag_info: IdTagInfo,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct StartTransactionReqPayload {
+    #[serde(rename = "connectorId")]
+    pub connector_id: u32,
+    #[serde(rename = "idTag")]
+    pub id_tag: String,
+    /// Energy register at the start of the transaction, in Wh.
+    #[serde(rename = "meterStart")]
+    pub meter_start: i32,
+    #[serde(rename = "reservationId", skip_serializing_if = "Option::is_none")]
+    pub reservation_id: Option<i32>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct StartTransactionConfPayload {
+    #[serde(rename = "idTagInfo")]
+    pub id_tag_info: IdTagInfo,
+    #[serde(rename = "transactionId")]
+    pub transaction_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct StopTransactionReqPayload {
+    #[serde(rename = "transactionId")]
+    pub transaction_id: i32,
+    #[serde(rename = "idTag", skip_serializing_if = "Option::is_none")]
+    pub id_tag: Option<String>,
+    /// Energy register at the end of the transaction, in Wh.
+    #[serde(rename = "meterStop")]
+    pub meter_stop: i32,
+    pub timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<Reason>,
+    #[serde(rename = "transactionData", skip_serializing_if = "Option::is_none")]
+    pub transaction_data: Option<Vec<MeterSample>>,
+}
+
+/// Why a charger stopped a transaction; `Local` when the charger does not say.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum Reason {
+    DeAuthorized,
+    EmergencyStop,
+    EVDisconnected,
+    HardReset,
+    Local,
+    Other,
+    PowerLoss,
+    Reboot,
+    Remote,
+    SoftReset,
+    UnlockCommand,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct StopTransactionConfPayload {
+    #[serde(rename = "idTagInfo", skip_serializing_if = "Option::is_none")]
+    pub id_tag_info: Option<IdTagInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub enum RemoteStartStopStatus {
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct RemoteStartTransactionConfPayload {
+    pub status: RemoteStartStopStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct DataTransferReqPayload {
+    #[serde(rename = "vendorId")]
+    pub vendor_id: String,
+    #[serde(rename = "messageId", skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum DataTransferStatus {
+    Accepted,
+    Rejected,
+    UnknownMessageId,
+    UnknownVendorId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct DataTransferConfPayload {
+    pub status: DataTransferStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum AvailabilityType {
+    Inoperative,
+    Operative,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct ChangeAvailabilityReqPayload {
+    #[serde(rename = "connectorId")]
+    pub connector_id: u32,
+    #[serde(rename = "type")]
+    pub availability_type: AvailabilityType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum AvailabilityStatus {
+    Accepted,
+    Rejected,
+    Scheduled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct ChangeAvailabilityConfPayload {
+    pub status: AvailabilityStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ClearCacheReqPayload {
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum ClearCacheStatus {
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct ClearCacheConfPayload {
+    pub status: ClearCacheStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct GetConfigurationReqPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct KeyValue {
+    pub key: String,
+    pub readonly: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct GetConfigurationConfPayload {
+    #[serde(rename = "configurationKey", skip_serializing_if = "Option::is_none")]
+    pub configuration_key: Option<Vec<KeyValue>>,
+    #[serde(rename = "unknownKey", skip_serializing_if = "Option::is_none")]
+    pub unknown_key: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct RemoteStopTransactionReqPayload {
+    #[serde(rename = "transactionId")]
+    pub transaction_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct RemoteStopTransactionConfPayload {
+    pub status: RemoteStartStopStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum ResetType {
+    Hard,
+    Soft,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct ResetReqPayload {
+    #[serde(rename = "type")]
+    pub reset_type: ResetType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum ResetStatus {
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct ResetConfPayload {
+    pub status: ResetStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct UnlockConnectorReqPayload {
+    #[serde(rename = "connectorId")]
+    pub connector_id: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum UnlockStatus {
+    Unlocked,
+    UnlockFailed,
+    NotSupported,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct UnlockConnectorConfPayload {
+    pub status: UnlockStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ClearChargingProfileReqPayload {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i32>,
+    #[serde(rename = "connectorId", skip_serializing_if = "Option::is_none")]
+    pub connector_id: Option<u32>,
+    #[serde(rename = "chargingProfilePurpose", skip_serializing_if = "Option::is_none")]
+    pub charging_profile_purpose: Option<ChargingProfilePurposeType>,
+    #[serde(rename = "stackLevel", skip_serializing_if = "Option::is_none")]
+    pub stack_level: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum ClearChargingProfileStatus {
+    Accepted,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct ClearChargingProfileConfPayload {
+    pub status: ClearChargingProfileStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct GetCompositeScheduleReqPayload {
+    #[serde(rename = "connectorId")]
+    pub connector_id: u32,
+    pub duration: u32,
+    #[serde(rename = "chargingRateUnit", skip_serializing_if = "Option::is_none")]
+    pub charging_rate_unit: Option<ChargingRateUnitType>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum GetCompositeScheduleStatus {
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct GetCompositeScheduleConfPayload {
+    pub status: GetCompositeScheduleStatus,
+    #[serde(rename = "connectorId", skip_serializing_if = "Option::is_none")]
+    pub connector_id: Option<u32>,
+    #[serde(rename = "scheduleStart", skip_serializing_if = "Option::is_none")]
+    pub schedule_start: Option<String>,
+    #[serde(rename = "chargingSchedule", skip_serializing_if = "Option::is_none")]
+    pub charging_schedule: Option<ChargingSchedule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum MessageTrigger {
+    BootNotification,
+    DiagnosticsStatusNotification,
+    FirmwareStatusNotification,
+    Heartbeat,
+    MeterValues,
+    StatusNotification,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct TriggerMessageReqPayload {
+    #[serde(rename = "requestedMessage")]
+    pub requested_message: MessageTrigger,
+    #[serde(rename = "connectorId", skip_serializing_if = "Option::is_none")]
+    pub connector_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum TriggerMessageStatus {
+    Accepted,
+    Rejected,
+    NotImplemented,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct TriggerMessageConfPayload {
+    pub status: TriggerMessageStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct GetDiagnosticsReqPayload {
+    pub location: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    #[serde(rename = "retryInterval", skip_serializing_if = "Option::is_none")]
+    pub retry_interval: Option<u32>,
+    #[serde(rename = "startTime", skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(rename = "stopTime", skip_serializing_if = "Option::is_none")]
+    pub stop_time: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct GetDiagnosticsConfPayload {
+    #[serde(rename = "fileName", skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum DiagnosticsStatus {
+    Idle,
+    Uploaded,
+    UploadFailed,
+    Uploading,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct DiagnosticsStatusNotificationReqPayload {
+    pub status: DiagnosticsStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct DiagnosticsStatusNotificationConfPayload {
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum FirmwareStatus {
+    Downloaded,
+    DownloadFailed,
+    Downloading,
+    Idle,
+    InstallationFailed,
+    Installing,
+    Installed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct FirmwareStatusNotificationReqPayload {
+    pub status: FirmwareStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct FirmwareStatusNotificationConfPayload {
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct UpdateFirmwareReqPayload {
+    pub location: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    #[serde(rename = "retrieveDate")]
+    pub retrieve_date: String,
+    #[serde(rename = "retryInterval", skip_serializing_if = "Option::is_none")]
+    pub retry_interval: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct UpdateFirmwareConfPayload {
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ReserveNowReqPayload {
+    #[serde(rename = "connectorId")]
+    pub connector_id: u32,
+    #[serde(rename = "expiryDate")]
+    pub expiry_date: String,
+    #[serde(rename = "idTag")]
+    pub id_tag: String,
+    #[serde(rename = "parentIdTag", skip_serializing_if = "Option::is_none")]
+    pub parent_id_tag: Option<String>,
+    #[serde(rename = "reservationId")]
+    pub reservation_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum ReservationStatus {
+    Accepted,
+    Faulted,
+    Occupied,
+    Rejected,
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct ReserveNowConfPayload {
+    pub status: ReservationStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct CancelReservationReqPayload {
+    #[serde(rename = "reservationId")]
+    pub reservation_id: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum CancelReservationStatus {
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct CancelReservationConfPayload {
+    pub status: CancelReservationStatus,
+}
+
+/// A CALLERROR answering a charger's CALL the central system could not accept.
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct CallErrorPayload {
+    pub error_code: String,
+    pub error_description: String,
+}
+
+/// Everything the central system sends: replies to charger-initiated CALLs and its own CALLs.
 #[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
 #[reflect(Serialize, Deserialize)]
 pub enum EOutgoingOcppMessage {
     BootNotificationResponse(BootNotificationConfPayload),
+    HeartbeatResponse(HeartbeatConfPayload),
     StatusNotificationResponse(StatusNotificationConfPayload),
     MeterValuesResponse(MeterValuesConfPayload),
+    AuthorizeResponse(AuthorizeConfPayload),
+    StartTransactionResponse(StartTransactionConfPayload),
+    StopTransactionResponse(StopTransactionConfPayload),
+    DataTransferResponse(DataTransferConfPayload),
+    DiagnosticsStatusNotificationResponse(DiagnosticsStatusNotificationConfPayload),
+    FirmwareStatusNotificationResponse(FirmwareStatusNotificationConfPayload),
+    /// Sent as a CALLERROR frame instead of a CALLRESULT.
+    CallError(CallErrorPayload),
     SetChargingProfileRequest(SetChargingProfileReqPayload),
     RemoteStartTransactionRequest(RemoteStartTransactionReqPayload),
     ChangeConfigurationRequest(ChangeConfigurationReqPayload),
-}
\ No newline at end of file
+    CancelReservationRequest(CancelReservationReqPayload),
+    ChangeAvailabilityRequest(ChangeAvailabilityReqPayload),
+    ClearCacheRequest(ClearCacheReqPayload),
+    ClearChargingProfileRequest(ClearChargingProfileReqPayload),
+    DataTransferRequest(DataTransferReqPayload),
+    GetCompositeScheduleRequest(GetCompositeScheduleReqPayload),
+    GetConfigurationRequest(GetConfigurationReqPayload),
+    GetDiagnosticsRequest(GetDiagnosticsReqPayload),
+    RemoteStopTransactionRequest(RemoteStopTransactionReqPayload),
+    ReserveNowRequest(ReserveNowReqPayload),
+    ResetRequest(ResetReqPayload),
+    TriggerMessageRequest(TriggerMessageReqPayload),
+    UnlockConnectorRequest(UnlockConnectorReqPayload),
+    UpdateFirmwareRequest(UpdateFirmwareReqPayload),
+    /// Messages to OCPP 2.0.1 charging stations.
+    V201(EOutgoingOcpp201Message),
+}
+impl EOutgoingOcppMessage {
+    /// OCPP action when this message is a CALL initiated by the central system.
+    /// Replies to charger-initiated CALLs (sent as CALLRESULT or CALLERROR) return `None`.
+    pub fn call_action(&self) -> Option<EOcppAction> {
+        match self {
+            EOutgoingOcppMessage::BootNotificationResponse(_)
+            | EOutgoingOcppMessage::HeartbeatResponse(_)
+            | EOutgoingOcppMessage::StatusNotificationResponse(_)
+            | EOutgoingOcppMessage::MeterValuesResponse(_)
+            | EOutgoingOcppMessage::AuthorizeResponse(_)
+            | EOutgoingOcppMessage::StartTransactionResponse(_)
+            | EOutgoingOcppMessage::StopTransactionResponse(_)
+            | EOutgoingOcppMessage::DataTransferResponse(_)
+            | EOutgoingOcppMessage::DiagnosticsStatusNotificationResponse(_)
+            | EOutgoingOcppMessage::FirmwareStatusNotificationResponse(_)
+            | EOutgoingOcppMessage::CallError(_)
+            | EOutgoingOcppMessage::V201(_) => None,
+            EOutgoingOcppMessage::SetChargingProfileRequest(_) => Some(EOcppAction::SetChargingProfile),
+            EOutgoingOcppMessage::RemoteStartTransactionRequest(_) => Some(EOcppAction::RemoteStartTransaction),
+            EOutgoingOcppMessage::ChangeConfigurationRequest(_) => Some(EOcppAction::ChangeConfiguration),
+            EOutgoingOcppMessage::CancelReservationRequest(_) => Some(EOcppAction::CancelReservation),
+            EOutgoingOcppMessage::ChangeAvailabilityRequest(_) => Some(EOcppAction::ChangeAvailability),
+            EOutgoingOcppMessage::ClearCacheRequest(_) => Some(EOcppAction::ClearCache),
+            EOutgoingOcppMessage::ClearChargingProfileRequest(_) => Some(EOcppAction::ClearChargingProfile),
+            EOutgoingOcppMessage::DataTransferRequest(_) => Some(EOcppAction::DataTransfer),
+            EOutgoingOcppMessage::GetCompositeScheduleRequest(_) => Some(EOcppAction::GetCompositeSchedule),
+            EOutgoingOcppMessage::GetConfigurationRequest(_) => Some(EOcppAction::GetConfiguration),
+            EOutgoingOcppMessage::GetDiagnosticsRequest(_) => Some(EOcppAction::GetDiagnostics),
+            EOutgoingOcppMessage::RemoteStopTransactionRequest(_) => Some(EOcppAction::RemoteStopTransaction),
+            EOutgoingOcppMessage::ReserveNowRequest(_) => Some(EOcppAction::ReserveNow),
+            EOutgoingOcppMessage::ResetRequest(_) => Some(EOcppAction::Reset),
+            EOutgoingOcppMessage::TriggerMessageRequest(_) => Some(EOcppAction::TriggerMessage),
+            EOutgoingOcppMessage::UnlockConnectorRequest(_) => Some(EOcppAction::UnlockConnector),
+            EOutgoingOcppMessage::UpdateFirmwareRequest(_) => Some(EOcppAction::UpdateFirmware),
+        }
+    }
+
+    /// The wire payload, without the enum variant wrapper.
+    pub fn payload(&self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            EOutgoingOcppMessage::BootNotificationResponse(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::HeartbeatResponse(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::StatusNotificationResponse(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::MeterValuesResponse(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::AuthorizeResponse(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::StartTransactionResponse(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::StopTransactionResponse(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::DataTransferResponse(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::DiagnosticsStatusNotificationResponse(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::FirmwareStatusNotificationResponse(p) => serde_json::to_value(p),
+            // CALLERROR details are always an empty object here.
+            EOutgoingOcppMessage::CallError(_) => Ok(serde_json::json!({})),
+            EOutgoingOcppMessage::SetChargingProfileRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::RemoteStartTransactionRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::ChangeConfigurationRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::CancelReservationRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::ChangeAvailabilityRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::ClearCacheRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::ClearChargingProfileRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::DataTransferRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::GetCompositeScheduleRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::GetConfigurationRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::GetDiagnosticsRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::RemoteStopTransactionRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::ReserveNowRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::ResetRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::TriggerMessageRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::UnlockConnectorRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::UpdateFirmwareRequest(p) => serde_json::to_value(p),
+            EOutgoingOcppMessage::V201(message) => message.payload(),
+        }
+    }
+
+    /// Action name of a CALL initiated by the central system, in whichever OCPP version it belongs to.
+    pub fn action_name(&self) -> Option<&'static str> {
+        match self {
+            EOutgoingOcppMessage::V201(message) => message.call_action().map(|action| action.as_str()),
+            other => other.call_action().map(|action| action.as_str()),
+        }
+    }
+}
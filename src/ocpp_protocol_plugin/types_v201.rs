@@ -0,0 +1,873 @@
+// OCPP 2.0.1 messages: the charging station side that differs from 1.6 (EVSE/connector addressing, TransactionEvent,
+// the device model), decoding of station-initiated CALLs, and conversions to and from the 1.6 types the systems share.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use super::messages::{ci_string, optional_ci_string, payload, EOcppErrorCode, OcppMessageError};
+use super::types::{
+    ChargingProfileKindType, ChargingProfilePurposeType, ChargingRateUnitType, MeterSample, MeterValueSampledValue,
+    RecurrencyKindType, RegistrationStatus, SetChargingProfileReqPayload,
+};
+
+/// The OCPP 2.0.1 actions this central system exchanges with charging stations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub enum EOcpp201Action {
+    // Sent by the charging station
+    Authorize,
+    BootNotification,
+    Heartbeat,
+    MeterValues,
+    NotifyReport,
+    StatusNotification,
+    TransactionEvent,
+    // Sent by the central system
+    GetBaseReport,
+    SetChargingProfile,
+}
+
+impl EOcpp201Action {
+    pub const ALL: [EOcpp201Action; 9] = [
+        EOcpp201Action::Authorize, EOcpp201Action::BootNotification, EOcpp201Action::Heartbeat, EOcpp201Action::MeterValues,
+        EOcpp201Action::NotifyReport, EOcpp201Action::StatusNotification, EOcpp201Action::TransactionEvent,
+        EOcpp201Action::GetBaseReport, EOcpp201Action::SetChargingProfile,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EOcpp201Action::Authorize => "Authorize",
+            EOcpp201Action::BootNotification => "BootNotification",
+            EOcpp201Action::Heartbeat => "Heartbeat",
+            EOcpp201Action::MeterValues => "MeterValues",
+            EOcpp201Action::NotifyReport => "NotifyReport",
+            EOcpp201Action::StatusNotification => "StatusNotification",
+            EOcpp201Action::TransactionEvent => "TransactionEvent",
+            EOcpp201Action::GetBaseReport => "GetBaseReport",
+            EOcpp201Action::SetChargingProfile => "SetChargingProfile",
+        }
+    }
+}
+
+impl FromStr for EOcpp201Action {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EOcpp201Action::ALL.into_iter().find(|action| action.as_str() == s).ok_or(())
+    }
+}
+
+impl fmt::Display for EOcpp201Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct StatusInfo {
+    #[serde(rename = "reasonCode")]
+    pub reason_code: String,
+    #[serde(rename = "additionalInfo", skip_serializing_if = "Option::is_none")]
+    pub additional_info: Option<String>,
+}
+
+/// An EVSE, optionally narrowed to one of its connectors.
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct Evse {
+    pub id: u32,
+    #[serde(rename = "connectorId", skip_serializing_if = "Option::is_none")]
+    pub connector_id: Option<u32>,
+}
+
+// BootNotification
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct Modem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iccid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imsi: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ChargingStation {
+    pub model: String,
+    #[serde(rename = "vendorName")]
+    pub vendor_name: String,
+    #[serde(rename = "serialNumber", skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    #[serde(rename = "firmwareVersion", skip_serializing_if = "Option::is_none")]
+    pub firmware_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modem: Option<Modem>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum BootReasonEnumType {
+    ApplicationReset,
+    FirmwareUpdate,
+    LocalReset,
+    #[default]
+    PowerUp,
+    RemoteReset,
+    ScheduledReset,
+    Triggered,
+    Unknown,
+    Watchdog,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct BootNotificationRequest {
+    #[serde(rename = "chargingStation")]
+    pub charging_station: ChargingStation,
+    pub reason: BootReasonEnumType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct BootNotificationResponse {
+    #[serde(rename = "currentTime")]
+    pub current_time: String,
+    pub interval: u32,
+    pub status: RegistrationStatus,
+    #[serde(rename = "statusInfo", skip_serializing_if = "Option::is_none")]
+    pub status_info: Option<StatusInfo>,
+}
+
+// Heartbeat
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct HeartbeatRequest {
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct HeartbeatResponse {
+    #[serde(rename = "currentTime")]
+    pub current_time: String,
+}
+
+// StatusNotification
+
+/// Connector state in 2.0.1; the charging detail 1.6 put here moved to `TransactionEvent`'s `chargingState`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum ConnectorStatusEnumType {
+    #[default]
+    Available,
+    Occupied,
+    Reserved,
+    Unavailable,
+    Faulted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct StatusNotificationRequest {
+    pub timestamp: String,
+    #[serde(rename = "connectorStatus")]
+    pub connector_status: ConnectorStatusEnumType,
+    #[serde(rename = "evseId")]
+    pub evse_id: u32,
+    #[serde(rename = "connectorId")]
+    pub connector_id: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct StatusNotificationResponse {
+}
+
+// Authorize
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum IdTokenEnumType {
+    Central,
+    #[serde(rename = "eMAID")]
+    EMaid,
+    #[default]
+    ISO14443,
+    ISO15693,
+    KeyCode,
+    Local,
+    MacAddress,
+    NoAuthorization,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct IdToken {
+    #[serde(rename = "idToken")]
+    pub id_token: String,
+    #[serde(rename = "type")]
+    pub token_type: IdTokenEnumType,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum AuthorizationStatusEnumType {
+    Accepted,
+    Blocked,
+    ConcurrentTx,
+    Expired,
+    Invalid,
+    NoCredit,
+    NotAllowedTypeEVSE,
+    NotAtThisLocation,
+    NotAtThisTime,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct IdTokenInfo {
+    pub status: AuthorizationStatusEnumType,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct AuthorizeRequest {
+    #[serde(rename = "idToken")]
+    pub id_token: IdToken,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct AuthorizeResponse {
+    #[serde(rename = "idTokenInfo")]
+    pub id_token_info: IdTokenInfo,
+}
+
+// MeterValues
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct UnitOfMeasure {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// Power of ten the value is scaled by.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiplier: Option<i32>,
+}
+
+/// As in 1.6, the enumerated fields are kept as strings and decoded by `meter_values`.
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct SampledValue {
+    pub value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub measurand: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(rename = "unitOfMeasure", skip_serializing_if = "Option::is_none")]
+    pub unit_of_measure: Option<UnitOfMeasure>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct MeterValue {
+    pub timestamp: String,
+    #[serde(rename = "sampledValue")]
+    pub sampled_value: Vec<SampledValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct MeterValuesRequest {
+    /// 0 is the station's main meter.
+    #[serde(rename = "evseId")]
+    pub evse_id: u32,
+    #[serde(rename = "meterValue")]
+    pub meter_value: Vec<MeterValue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct MeterValuesResponse {
+}
+
+/// Restates 2.0.1 meter values in the 1.6 form, scaling by each value's multiplier, so `parse_meter_values` reads both.
+pub fn to_v16_meter_samples(meter_values: &[MeterValue]) -> Vec<MeterSample> {
+    meter_values.iter()
+        .map(|meter_value| MeterSample {
+            timestamp: Some(meter_value.timestamp.clone()),
+            sampled_value: meter_value.sampled_value.iter()
+                .map(|sv| {
+                    let unit = sv.unit_of_measure.as_ref();
+                    let multiplier = unit.and_then(|u| u.multiplier).unwrap_or(0);
+                    MeterValueSampledValue {
+                        value: (sv.value * 10f64.powi(multiplier)).to_string(),
+                        context: sv.context.clone(),
+                        format: None,
+                        measurand: sv.measurand.clone(),
+                        phase: sv.phase.clone(),
+                        location: sv.location.clone(),
+                        unit: unit.and_then(|u| u.unit.clone()),
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+// TransactionEvent
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum TransactionEventEnumType {
+    Ended,
+    #[default]
+    Started,
+    Updated,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum TriggerReasonEnumType {
+    #[default]
+    Authorized,
+    CablePluggedIn,
+    ChargingRateChanged,
+    ChargingStateChanged,
+    Deauthorized,
+    EnergyLimitReached,
+    EVCommunicationLost,
+    EVConnectTimeout,
+    MeterValueClock,
+    MeterValuePeriodic,
+    TimeLimitReached,
+    Trigger,
+    UnlockCommand,
+    StopAuthorized,
+    EVDeparted,
+    EVDetected,
+    RemoteStop,
+    RemoteStart,
+    AbnormalCondition,
+    SignedDataReceived,
+    ResetCommand,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum ChargingStateEnumType {
+    Charging,
+    EVConnected,
+    SuspendedEV,
+    SuspendedEVSE,
+    Idle,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum ReasonEnumType {
+    DeAuthorized,
+    EmergencyStop,
+    EnergyLimitReached,
+    EVDisconnected,
+    GroundFault,
+    ImmediateReset,
+    Local,
+    LocalOutOfCredit,
+    MasterPass,
+    Other,
+    OvercurrentFault,
+    PowerLoss,
+    PowerQuality,
+    Reboot,
+    Remote,
+    SOCLimitReached,
+    StoppedByEV,
+    TimeLimitReached,
+    Timeout,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct Transaction {
+    /// Assigned by the station, unlike the central system's integer ids in 1.6.
+    #[serde(rename = "transactionId")]
+    pub transaction_id: String,
+    #[serde(rename = "chargingState", skip_serializing_if = "Option::is_none")]
+    pub charging_state: Option<ChargingStateEnumType>,
+    #[serde(rename = "timeSpentCharging", skip_serializing_if = "Option::is_none")]
+    pub time_spent_charging: Option<i32>,
+    #[serde(rename = "stoppedReason", skip_serializing_if = "Option::is_none")]
+    pub stopped_reason: Option<ReasonEnumType>,
+    #[serde(rename = "remoteStartId", skip_serializing_if = "Option::is_none")]
+    pub remote_start_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct TransactionEventRequest {
+    #[serde(rename = "eventType")]
+    pub event_type: TransactionEventEnumType,
+    pub timestamp: String,
+    #[serde(rename = "triggerReason")]
+    pub trigger_reason: TriggerReasonEnumType,
+    #[serde(rename = "seqNo")]
+    pub seq_no: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offline: Option<bool>,
+    #[serde(rename = "numberOfPhasesUsed", skip_serializing_if = "Option::is_none")]
+    pub number_of_phases_used: Option<u8>,
+    #[serde(rename = "cableMaxCurrent", skip_serializing_if = "Option::is_none")]
+    pub cable_max_current: Option<i32>,
+    #[serde(rename = "reservationId", skip_serializing_if = "Option::is_none")]
+    pub reservation_id: Option<i32>,
+    #[serde(rename = "transactionInfo")]
+    pub transaction_info: Transaction,
+    #[serde(rename = "idToken", skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<IdToken>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evse: Option<Evse>,
+    #[serde(rename = "meterValue", skip_serializing_if = "Option::is_none")]
+    pub meter_value: Option<Vec<MeterValue>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct TransactionEventResponse {
+    #[serde(rename = "idTokenInfo", skip_serializing_if = "Option::is_none")]
+    pub id_token_info: Option<IdTokenInfo>,
+}
+
+// NotifyReport and GetBaseReport
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ReportComponent {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evse: Option<Evse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ReportVariable {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum AttributeEnumType {
+    #[default]
+    Actual,
+    Target,
+    MinSet,
+    MaxSet,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum MutabilityEnumType {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct VariableAttribute {
+    /// `Actual` when absent.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub attribute_type: Option<AttributeEnumType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mutability: Option<MutabilityEnumType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub persistent: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub constant: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum DataEnumType {
+    #[serde(rename = "string")]
+    String,
+    #[serde(rename = "decimal")]
+    Decimal,
+    #[serde(rename = "integer")]
+    Integer,
+    #[serde(rename = "dateTime")]
+    DateTime,
+    #[serde(rename = "boolean")]
+    Boolean,
+    OptionList,
+    SequenceList,
+    MemberList,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct VariableCharacteristics {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(rename = "dataType")]
+    pub data_type: DataEnumType,
+    #[serde(rename = "minLimit", skip_serializing_if = "Option::is_none")]
+    pub min_limit: Option<f64>,
+    #[serde(rename = "maxLimit", skip_serializing_if = "Option::is_none")]
+    pub max_limit: Option<f64>,
+    #[serde(rename = "valuesList", skip_serializing_if = "Option::is_none")]
+    pub values_list: Option<String>,
+    #[serde(rename = "supportsMonitoring")]
+    pub supports_monitoring: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ReportData {
+    pub component: ReportComponent,
+    pub variable: ReportVariable,
+    #[serde(rename = "variableAttribute")]
+    pub variable_attribute: Vec<VariableAttribute>,
+    #[serde(rename = "variableCharacteristics", skip_serializing_if = "Option::is_none")]
+    pub variable_characteristics: Option<VariableCharacteristics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct NotifyReportRequest {
+    #[serde(rename = "requestId")]
+    pub request_id: i32,
+    #[serde(rename = "generatedAt")]
+    pub generated_at: String,
+    /// "To be continued": more parts of this report follow.
+    #[serde(default)]
+    pub tbc: bool,
+    #[serde(rename = "seqNo")]
+    pub seq_no: u32,
+    #[serde(rename = "reportData", skip_serializing_if = "Option::is_none")]
+    pub report_data: Option<Vec<ReportData>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct NotifyReportResponse {
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum ReportBaseEnumType {
+    #[default]
+    ConfigurationInventory,
+    FullInventory,
+    SummaryInventory,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct GetBaseReportRequest {
+    #[serde(rename = "requestId")]
+    pub request_id: i32,
+    #[serde(rename = "reportBase")]
+    pub report_base: ReportBaseEnumType,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum GenericDeviceModelStatusEnumType {
+    Accepted,
+    Rejected,
+    NotSupported,
+    EmptyResultSet,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct GetBaseReportResponse {
+    pub status: GenericDeviceModelStatusEnumType,
+    #[serde(rename = "statusInfo", skip_serializing_if = "Option::is_none")]
+    pub status_info: Option<StatusInfo>,
+}
+
+// SetChargingProfile
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, Default, PartialEq, Eq)]
+#[reflect(Default, Serialize, Deserialize)]
+pub enum ChargingProfilePurposeEnumType {
+    ChargingStationExternalConstraints,
+    ChargingStationMaxProfile,
+    #[default]
+    TxDefaultProfile,
+    TxProfile,
+}
+
+impl From<ChargingProfilePurposeType> for ChargingProfilePurposeEnumType {
+    fn from(purpose: ChargingProfilePurposeType) -> Self {
+        match purpose {
+            ChargingProfilePurposeType::ChargePointMaxProfile => ChargingProfilePurposeEnumType::ChargingStationMaxProfile,
+            ChargingProfilePurposeType::TxDefaultProfile => ChargingProfilePurposeEnumType::TxDefaultProfile,
+            ChargingProfilePurposeType::TxProfile => ChargingProfilePurposeEnumType::TxProfile,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ChargingSchedulePeriod {
+    #[serde(rename = "startPeriod")]
+    pub start_period: u32,
+    pub limit: f32,
+    #[serde(rename = "numberPhases", skip_serializing_if = "Option::is_none")]
+    pub number_phases: Option<u8>,
+    #[serde(rename = "phaseToUse", skip_serializing_if = "Option::is_none")]
+    pub phase_to_use: Option<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ChargingSchedule {
+    pub id: i32,
+    #[serde(rename = "startSchedule", skip_serializing_if = "Option::is_none")]
+    pub start_schedule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    #[serde(rename = "chargingRateUnit")]
+    pub charging_rate_unit: ChargingRateUnitType,
+    #[serde(rename = "chargingSchedulePeriod")]
+    pub charging_schedule_period: Vec<ChargingSchedulePeriod>,
+    #[serde(rename = "minChargingRate", skip_serializing_if = "Option::is_none")]
+    pub min_charging_rate: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct ChargingProfile {
+    pub id: i32,
+    #[serde(rename = "stackLevel")]
+    pub stack_level: u32,
+    #[serde(rename = "chargingProfilePurpose")]
+    pub charging_profile_purpose: ChargingProfilePurposeEnumType,
+    #[serde(rename = "chargingProfileKind")]
+    pub charging_profile_kind: ChargingProfileKindType,
+    #[serde(rename = "recurrencyKind", skip_serializing_if = "Option::is_none")]
+    pub recurrency_kind: Option<RecurrencyKindType>,
+    #[serde(rename = "validFrom", skip_serializing_if = "Option::is_none")]
+    pub valid_from: Option<String>,
+    #[serde(rename = "validTo", skip_serializing_if = "Option::is_none")]
+    pub valid_to: Option<String>,
+    /// The station's id for the transaction; required for a TxProfile.
+    #[serde(rename = "transactionId", skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<String>,
+    #[serde(rename = "chargingSchedule")]
+    pub charging_schedule: Vec<ChargingSchedule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect, Default)]
+#[reflect(Default, Serialize, Deserialize)]
+pub struct SetChargingProfileRequest {
+    /// 0 addresses the whole station.
+    #[serde(rename = "evseId")]
+    pub evse_id: u32,
+    #[serde(rename = "chargingProfile")]
+    pub charging_profile: ChargingProfile,
+}
+
+impl SetChargingProfileRequest {
+    /// The 2.0.1 form of a 1.6 profile: the connector becomes the EVSE,
+    /// and `transaction_id` is the station's id for the transaction the profile is scoped to.
+    pub fn from_v16(request: &SetChargingProfileReqPayload, transaction_id: Option<String>) -> Self {
+        let profile = &request.cs_charging_profiles;
+        let schedule = &profile.charging_schedule;
+        SetChargingProfileRequest {
+            evse_id: request.connector_id,
+            charging_profile: ChargingProfile {
+                id: profile.charging_profile_id,
+                stack_level: profile.stack_level,
+                charging_profile_purpose: profile.charging_profile_purpose.into(),
+                charging_profile_kind: profile.charging_profile_kind,
+                recurrency_kind: profile.recurrency_kind,
+                valid_from: profile.valid_from.clone(),
+                valid_to: profile.valid_to.clone(),
+                transaction_id,
+                charging_schedule: vec![ChargingSchedule {
+                    id: profile.charging_profile_id,
+                    start_schedule: schedule.start_schedule.clone(),
+                    duration: schedule.duration,
+                    charging_rate_unit: schedule.charging_rate_unit,
+                    charging_schedule_period: schedule.charging_schedule_period.iter()
+                        .map(|period| ChargingSchedulePeriod {
+                            start_period: period.start_period,
+                            limit: period.limit,
+                            number_phases: period.number_phases,
+                            phase_to_use: None,
+                        })
+                        .collect(),
+                    min_charging_rate: schedule.min_charging_rate,
+                }],
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Reflect, PartialEq, Eq)]
+#[reflect(Serialize, Deserialize)]
+pub enum ChargingProfileStatusEnumType {
+    Accepted,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub struct SetChargingProfileResponse {
+    pub status: ChargingProfileStatusEnumType,
+    #[serde(rename = "statusInfo", skip_serializing_if = "Option::is_none")]
+    pub status_info: Option<StatusInfo>,
+}
+
+/// A CALL a charging station may send the central system, with its payload decoded.
+#[derive(Debug, Clone)]
+pub enum EChargingStationRequest {
+    Authorize(AuthorizeRequest),
+    BootNotification(BootNotificationRequest),
+    Heartbeat(HeartbeatRequest),
+    MeterValues(MeterValuesRequest),
+    NotifyReport(NotifyReportRequest),
+    StatusNotification(StatusNotificationRequest),
+    TransactionEvent(TransactionEventRequest),
+}
+
+impl EChargingStationRequest {
+    pub fn action(&self) -> EOcpp201Action {
+        match self {
+            EChargingStationRequest::Authorize(_) => EOcpp201Action::Authorize,
+            EChargingStationRequest::BootNotification(_) => EOcpp201Action::BootNotification,
+            EChargingStationRequest::Heartbeat(_) => EOcpp201Action::Heartbeat,
+            EChargingStationRequest::MeterValues(_) => EOcpp201Action::MeterValues,
+            EChargingStationRequest::NotifyReport(_) => EOcpp201Action::NotifyReport,
+            EChargingStationRequest::StatusNotification(_) => EOcpp201Action::StatusNotification,
+            EChargingStationRequest::TransactionEvent(_) => EOcpp201Action::TransactionEvent,
+        }
+    }
+
+    /// Decode a CALL from its action name and JSON payload, with the same error codes as
+    /// `EChargePointRequest::decode`; they are renamed for the wire by `EOcppErrorCode::code_for`.
+    pub fn decode(action: &str, payload_json: &str) -> Result<Self, OcppMessageError> {
+        let action: EOcpp201Action = action.parse().map_err(|_| OcppMessageError {
+            error_code: EOcppErrorCode::NotImplemented,
+            description: format!("Unknown action '{}'", action),
+        })?;
+        let request = match action {
+            EOcpp201Action::Authorize => EChargingStationRequest::Authorize(payload(action, payload_json)?),
+            EOcpp201Action::BootNotification => EChargingStationRequest::BootNotification(payload(action, payload_json)?),
+            EOcpp201Action::Heartbeat => EChargingStationRequest::Heartbeat(payload(action, payload_json)?),
+            EOcpp201Action::MeterValues => EChargingStationRequest::MeterValues(payload(action, payload_json)?),
+            EOcpp201Action::NotifyReport => EChargingStationRequest::NotifyReport(payload(action, payload_json)?),
+            EOcpp201Action::StatusNotification => EChargingStationRequest::StatusNotification(payload(action, payload_json)?),
+            EOcpp201Action::TransactionEvent => EChargingStationRequest::TransactionEvent(payload(action, payload_json)?),
+            other => return Err(OcppMessageError {
+                error_code: EOcppErrorCode::NotSupported,
+                description: format!("{} is not sent by a charging station", other),
+            }),
+        };
+        request.validate().map_err(|description| OcppMessageError {
+            error_code: EOcppErrorCode::PropertyConstraintViolation,
+            description: format!("{}: {}", action, description),
+        })?;
+        Ok(request)
+    }
+
+    /// Checks the string lengths of the OCPP 2.0.1 JSON schemas.
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            EChargingStationRequest::Authorize(p) => ci_string("idToken", &p.id_token.id_token, 36),
+            EChargingStationRequest::BootNotification(p) => {
+                let station = &p.charging_station;
+                ci_string("model", &station.model, 20)?;
+                ci_string("vendorName", &station.vendor_name, 50)?;
+                optional_ci_string("serialNumber", &station.serial_number, 25)?;
+                optional_ci_string("firmwareVersion", &station.firmware_version, 50)?;
+                if let Some(modem) = &station.modem {
+                    optional_ci_string("iccid", &modem.iccid, 20)?;
+                    optional_ci_string("imsi", &modem.imsi, 20)?;
+                }
+                Ok(())
+            }
+            EChargingStationRequest::NotifyReport(p) => {
+                for data in p.report_data.iter().flatten() {
+                    ci_string("component.name", &data.component.name, 50)?;
+                    ci_string("variable.name", &data.variable.name, 50)?;
+                    for attribute in &data.variable_attribute {
+                        optional_ci_string("variableAttribute.value", &attribute.value, 2500)?;
+                    }
+                }
+                Ok(())
+            }
+            EChargingStationRequest::TransactionEvent(p) => {
+                ci_string("transactionId", &p.transaction_info.transaction_id, 36)?;
+                p.id_token.as_ref().map_or(Ok(()), |token| ci_string("idToken", &token.id_token, 36))
+            }
+            EChargingStationRequest::Heartbeat(_)
+            | EChargingStationRequest::MeterValues(_)
+            | EChargingStationRequest::StatusNotification(_) => Ok(()),
+        }
+    }
+}
+
+/// Everything the central system sends a 2.0.1 charging station.
+#[derive(Debug, Clone, Serialize, Deserialize, Reflect)]
+#[reflect(Serialize, Deserialize)]
+pub enum EOutgoingOcpp201Message {
+    AuthorizeResponse(AuthorizeResponse),
+    BootNotificationResponse(BootNotificationResponse),
+    HeartbeatResponse(HeartbeatResponse),
+    MeterValuesResponse(MeterValuesResponse),
+    NotifyReportResponse(NotifyReportResponse),
+    StatusNotificationResponse(StatusNotificationResponse),
+    TransactionEventResponse(TransactionEventResponse),
+    GetBaseReportRequest(GetBaseReportRequest),
+    SetChargingProfileRequest(SetChargingProfileRequest),
+}
+
+impl EOutgoingOcpp201Message {
+    /// OCPP action when this message is a CALL initiated by the central system; `None` for replies.
+    pub fn call_action(&self) -> Option<EOcpp201Action> {
+        match self {
+            EOutgoingOcpp201Message::AuthorizeResponse(_)
+            | EOutgoingOcpp201Message::BootNotificationResponse(_)
+            | EOutgoingOcpp201Message::HeartbeatResponse(_)
+            | EOutgoingOcpp201Message::MeterValuesResponse(_)
+            | EOutgoingOcpp201Message::NotifyReportResponse(_)
+            | EOutgoingOcpp201Message::StatusNotificationResponse(_)
+            | EOutgoingOcpp201Message::TransactionEventResponse(_) => None,
+            EOutgoingOcpp201Message::GetBaseReportRequest(_) => Some(EOcpp201Action::GetBaseReport),
+            EOutgoingOcpp201Message::SetChargingProfileRequest(_) => Some(EOcpp201Action::SetChargingProfile),
+        }
+    }
+
+    /// The wire payload, without the enum variant wrapper.
+    pub fn payload(&self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            EOutgoingOcpp201Message::AuthorizeResponse(p) => serde_json::to_value(p),
+            EOutgoingOcpp201Message::BootNotificationResponse(p) => serde_json::to_value(p),
+            EOutgoingOcpp201Message::HeartbeatResponse(p) => serde_json::to_value(p),
+            EOutgoingOcpp201Message::MeterValuesResponse(p) => serde_json::to_value(p),
+            EOutgoingOcpp201Message::NotifyReportResponse(p) => serde_json::to_value(p),
+            EOutgoingOcpp201Message::StatusNotificationResponse(p) => serde_json::to_value(p),
+            EOutgoingOcpp201Message::TransactionEventResponse(p) => serde_json::to_value(p),
+            EOutgoingOcpp201Message::GetBaseReportRequest(p) => serde_json::to_value(p),
+            EOutgoingOcpp201Message::SetChargingProfileRequest(p) => serde_json::to_value(p),
+        }
+    }
+}
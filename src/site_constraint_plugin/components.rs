@@ -0,0 +1,10 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Capacity of the site's grid connection, both as positive kW figures.
+#[derive(Component, Debug, Clone, Copy, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+pub struct GridConnectionLimits {
+    pub import_capacity_kw: f32,
+    pub export_capacity_kw: f32,
+}
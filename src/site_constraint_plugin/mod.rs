@@ -0,0 +1,20 @@
+use bevy::prelude::*;
+use crate::core_asset_plugin::ESetpointFlow;
+
+pub mod components;
+pub mod systems;
+
+pub use components::*;
+pub use systems::*;
+
+/// Keeps the sum of all asset setpoints, plus the site's base load, within the grid connection's capacity.
+pub struct SiteConstraintPlugin;
+
+impl Plugin for SiteConstraintPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GridConnectionLimits>()
+           .add_systems(Update, enforce_site_limits.in_set(ESetpointFlow::Constrain));
+
+        info!("SiteConstraintPlugin loaded.");
+    }
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use super::components::GridConnectionLimits;
+use crate::core_asset_plugin::{ExternalId, CurrentMeterReading, MeteringSource, RequestedSetpointKw, TargetPowerSetpointKw};
+use crate::common::types::EAssetType;
+
+/// Turns each asset's `RequestedSetpointKw` into its `TargetPowerSetpointKw`.
+///
+/// When the requests plus the site's base load would import or export more than the grid connection
+/// allows, the importing (or exporting) requests are scaled down proportionally, to zero if the base
+/// load alone exceeds the limit. Without a grid connection limit requests pass through unchanged.
+pub fn enforce_site_limits(
+    grid_query: Query<(&ExternalId, &GridConnectionLimits, &CurrentMeterReading, Option<&MeteringSource>)>,
+    mut asset_query: Query<(&ExternalId, &EAssetType, &RequestedSetpointKw, &mut TargetPowerSetpointKw, &CurrentMeterReading)>,
+) {
+    let Some((grid_id, limits, grid_reading, grid_source)) = grid_query.iter().next() else {
+        for (_, _, requested, mut target, _) in asset_query.iter_mut() {
+            target.set_if_neq(TargetPowerSetpointKw(requested.0));
+        }
+        return;
+    };
+
+    // Base load is whatever the grid meter sees beyond the controllable assets' own consumption.
+    let controllable_kw: f32 = asset_query.iter()
+        .filter(|(_, asset_type, ..)| **asset_type != EAssetType::GridConnection)
+        .map(|(.., reading)| reading.power_kw)
+        .sum();
+    let base_load_kw = if grid_source.is_some() { grid_reading.power_kw - controllable_kw } else { 0.0 };
+
+    let (mut importing_kw, mut exporting_kw) = (0.0, 0.0);
+    for (_, asset_type, requested, ..) in asset_query.iter() {
+        if *asset_type == EAssetType::GridConnection {
+            continue;
+        }
+        if requested.0 >= 0.0 { importing_kw += requested.0 } else { exporting_kw += requested.0 }
+    }
+
+    let site_kw = base_load_kw + importing_kw + exporting_kw;
+    let (import_factor, export_factor) = if site_kw > limits.import_capacity_kw && importing_kw > 0.0 {
+        (((limits.import_capacity_kw - base_load_kw - exporting_kw) / importing_kw).clamp(0.0, 1.0), 1.0)
+    } else if site_kw < -limits.export_capacity_kw && exporting_kw < 0.0 {
+        (1.0, ((-limits.export_capacity_kw - base_load_kw - importing_kw) / exporting_kw).clamp(0.0, 1.0))
+    } else {
+        (1.0, 1.0)
+    };
+
+    for (id, asset_type, requested, mut target, _) in asset_query.iter_mut() {
+        if *asset_type == EAssetType::GridConnection {
+            continue;
+        }
+        let allowed_kw = requested.0 * if requested.0 >= 0.0 { import_factor } else { export_factor };
+        if target.0 == allowed_kw {
+            continue;
+        }
+        if allowed_kw != requested.0 {
+            warn!(
+                "Curtailing '{}' from {} kW to {:.2} kW: site would reach {:.2} kW against '{}' limits of +{} / -{} kW (base load {:.2} kW)",
+                id.0, requested.0, allowed_kw, site_kw, grid_id.0, limits.import_capacity_kw, limits.export_capacity_kw, base_load_kw
+            );
+        }
+        target.0 = allowed_kw;
+    }
+}
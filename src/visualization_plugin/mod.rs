@@ -1,9 +1,9 @@
 use bevy::prelude::*;
 use bevy_pancam::PanCamPlugin;
 use std::collections::HashMap;
-use crate::balancer_comms_plugin::balancer_messages::{BalancerMeteringMessage, BalancerSetpointMessage};
-use crate::modbus_protocol_plugin::{ModbusRequest, ModbusResponse};
-use crate::ocpp_protocol_plugin::events::{OcppCommandToAsset, OcppRequestFromAsset};
+use crate::balancer_comms_plugin::balancer_messages::{BalancerAssetStatusMessage, BalancerMeteringMessage, BalancerSiteTotalsMessage, BalancerScheduleMessage, BalancerSetpointBatchMessage, BalancerSetpointMessage};
+use crate::modbus_protocol_plugin::{ModbusRequest, ModbusResponse, ModbusWriteRequest, ModbusWriteResponse};
+use crate::ocpp_protocol_plugin::events::{OcppCommandToAsset, OcppRequestFromAsset, OcppResponseFromAsset};
 
 pub mod components;
 pub mod log_capture;
@@ -29,12 +29,40 @@ impl Plugin for VisualizationPlugin {
             )],
         );
 
+        templates.0.insert(
+            "Balancer Setpoint Batch".to_string(),
+            vec![(
+                "Chargers share 40kW".to_string(),
+                "{\n  \"setpoints\": [\n    { \"external_id\": \"BAT001\", \"target_power_kw\": -10.0 }\n  ],\n  \"group_setpoints\": [\n    { \"group_id\": \"chargers\", \"target_power_kw\": 40.0 }\n  ]\n}".to_string(),
+            )],
+        );
+
+        templates.0.insert(
+            "Balancer Schedule".to_string(),
+            vec![(
+                "Two 15-minute slots".to_string(),
+                "{\n  \"external_id\": \"CH001\",\n  \"periods\": [\n    { \"start\": \"2025-01-01T10:00:00Z\", \"power_kw\": 11.0 },\n    { \"start\": \"2025-01-01T10:15:00Z\", \"power_kw\": 4.0 }\n  ]\n}".to_string(),
+            )],
+        );
+
         templates.0.insert(
             "OCPP Request from Asset".to_string(),
             vec![
                 (
                     "BootNotification".to_string(),
-                    "{\n  \"charge_point_id\": \"CH001\",\n  \"action\": \"BootNotification\",\n  \"payload_json\": \"{\\\"charge_point_vendor\\\":\\\"Zenobe\\\",\\\"charge_point_model\\\":\\\"VirtualCharger\\\"}\"\n}".to_string(),
+                    "{\n  \"charge_point_id\": \"CH001\",\n  \"action\": \"BootNotification\",\n  \"payload_json\": \"{\\\"chargePointVendor\\\":\\\"Zenobe\\\",\\\"chargePointModel\\\":\\\"VirtualCharger\\\"}\"\n}".to_string(),
+                ),
+                (
+                    "Heartbeat".to_string(),
+                    "{\n  \"charge_point_id\": \"CH001\",\n  \"action\": \"Heartbeat\",\n  \"payload_json\": \"{}\"\n}".to_string(),
+                ),
+                (
+                    "StartTransaction".to_string(),
+                    "{\n  \"charge_point_id\": \"CH001\",\n  \"action\": \"StartTransaction\",\n  \"payload_json\": \"{\\\"connectorId\\\":1,\\\"idTag\\\":\\\"TAG001\\\",\\\"meterStart\\\":0,\\\"timestamp\\\":\\\"2025-01-01T00:00:00Z\\\"}\"\n}".to_string(),
+                ),
+                (
+                    "StopTransaction".to_string(),
+                    "{\n  \"charge_point_id\": \"CH001\",\n  \"action\": \"StopTransaction\",\n  \"payload_json\": \"{\\\"transactionId\\\":1,\\\"meterStop\\\":5000,\\\"timestamp\\\":\\\"2025-01-01T01:00:00Z\\\"}\"\n}".to_string(),
                 ),
                 (
                     "MeterValues".to_string(),
@@ -43,11 +71,33 @@ impl Plugin for VisualizationPlugin {
             ],
         );
 
+        templates.0.insert(
+            "OCPP Response from Asset".to_string(),
+            vec![
+                (
+                    "SetChargingProfile Accepted".to_string(),
+                    "{\n  \"charge_point_id\": \"CH001\",\n  \"ocpp_message_id\": \"sc_1\",\n  \"payload_json\": \"{\\\"status\\\":\\\"Accepted\\\"}\"\n}".to_string(),
+                ),
+                (
+                    "CALLERROR".to_string(),
+                    "{\n  \"charge_point_id\": \"CH001\",\n  \"ocpp_message_id\": \"sc_1\",\n  \"error_code\": \"NotImplemented\",\n  \"error_description\": \"\"\n}".to_string(),
+                ),
+            ],
+        );
+
         templates.0.insert(
             "Modbus Response from Asset".to_string(),
             vec![(
                 "Active Power Reading".to_string(),
-                "{\n  \"external_id\": \"BAT001\",\n  \"power_kw\": 5.0,\n  \"energy_kwh\": 1234.5\n}".to_string(),
+                "{\n  \"external_id\": \"BAT001\",\n  \"request_id\": 1,\n  \"power_kw\": 5.0,\n  \"energy_kwh\": 1234.5\n}".to_string(),
+            )],
+        );
+
+        templates.0.insert(
+            "Modbus Write Ack from Asset".to_string(),
+            vec![(
+                "Setpoint Accepted".to_string(),
+                "{\n  \"external_id\": \"BAT001\",\n  \"request_id\": 1,\n  \"setpoint_kw\": 5.0,\n  \"success\": true\n}".to_string(),
             )],
         );
 
@@ -64,7 +114,7 @@ impl Plugin for VisualizationPlugin {
            .insert_resource(SelectedQueue(default_queue))
            .insert_resource(SelectedTemplate(default_template_name))
            .insert_resource(MessageInput(default_template_json))
-           .add_plugins(PanCamPlugin::default())
+           .add_plugins(PanCamPlugin)
            .add_systems(Startup, setup_camera)
            .add_systems(Update, (
                attach_positions_system.run_if(positions_not_attached),
@@ -82,20 +132,35 @@ impl Plugin for VisualizationPlugin {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn setup_visualization_channels(
     balancer_setpoint_sender: crossbeam_channel::Sender<BalancerSetpointMessage>,
+    balancer_setpoint_batch_sender: crossbeam_channel::Sender<BalancerSetpointBatchMessage>,
+    balancer_schedule_sender: crossbeam_channel::Sender<BalancerScheduleMessage>,
     ocpp_from_asset_sender: crossbeam_channel::Sender<OcppRequestFromAsset>,
+    ocpp_response_from_asset_sender: crossbeam_channel::Sender<OcppResponseFromAsset>,
     modbus_response_sender: crossbeam_channel::Sender<ModbusResponse>,
+    modbus_write_response_sender: crossbeam_channel::Sender<ModbusWriteResponse>,
     balancer_metering_receiver: crossbeam_channel::Receiver<BalancerMeteringMessage>,
+    balancer_site_totals_receiver: crossbeam_channel::Receiver<BalancerSiteTotalsMessage>,
+    balancer_status_receiver: crossbeam_channel::Receiver<BalancerAssetStatusMessage>,
     ocpp_to_asset_receiver: crossbeam_channel::Receiver<OcppCommandToAsset>,
     modbus_request_receiver: crossbeam_channel::Receiver<ModbusRequest>,
+    modbus_write_request_receiver: crossbeam_channel::Receiver<ModbusWriteRequest>,
 ) -> MessageChannels {
     MessageChannels {
         balancer_setpoint_sender,
+        balancer_setpoint_batch_sender,
+        balancer_schedule_sender,
         ocpp_from_asset_sender,
+        ocpp_response_from_asset_sender,
         modbus_response_sender,
+        modbus_write_response_sender,
         balancer_metering_receiver,
+        balancer_site_totals_receiver,
+        balancer_status_receiver,
         ocpp_to_asset_receiver,
         modbus_request_receiver,
+        modbus_write_request_receiver,
     }
 }
\ No newline at end of file
@@ -1,8 +1,8 @@
 use bevy::prelude::*;
 use std::collections::HashMap;
-use crate::balancer_comms_plugin::balancer_messages::{BalancerSetpointMessage, BalancerMeteringMessage};
-use crate::modbus_protocol_plugin::{ModbusRequest, ModbusResponse};
-use crate::ocpp_protocol_plugin::events::{OcppRequestFromAsset, OcppCommandToAsset};
+use crate::balancer_comms_plugin::balancer_messages::{BalancerSetpointMessage, BalancerSetpointBatchMessage, BalancerScheduleMessage, BalancerMeteringMessage, BalancerAssetStatusMessage, BalancerSiteTotalsMessage};
+use crate::modbus_protocol_plugin::{ModbusRequest, ModbusResponse, ModbusWriteRequest, ModbusWriteResponse};
+use crate::ocpp_protocol_plugin::events::{OcppRequestFromAsset, OcppCommandToAsset, OcppResponseFromAsset};
 
 #[derive(Resource, Default)]
 pub struct PositionsAttached(pub bool);
@@ -13,18 +13,28 @@ pub struct LogMessages(pub Vec<String>);
 #[derive(Resource, Default)]
 pub struct OutputMessages {
     pub balancer_metering: Vec<String>,
+    pub balancer_site_totals: Vec<String>,
+    pub balancer_status: Vec<String>,
     pub ocpp_commands: Vec<String>,
     pub modbus_requests: Vec<String>,
+    pub modbus_write_requests: Vec<String>,
 }
 
 #[derive(Resource)]
 pub struct MessageChannels {
     pub balancer_setpoint_sender:   crossbeam_channel::Sender<BalancerSetpointMessage>,
+    pub balancer_setpoint_batch_sender: crossbeam_channel::Sender<BalancerSetpointBatchMessage>,
+    pub balancer_schedule_sender:   crossbeam_channel::Sender<BalancerScheduleMessage>,
     pub ocpp_from_asset_sender:     crossbeam_channel::Sender<OcppRequestFromAsset>,
+    pub ocpp_response_from_asset_sender: crossbeam_channel::Sender<OcppResponseFromAsset>,
     pub modbus_response_sender:     crossbeam_channel::Sender<ModbusResponse>,
+    pub modbus_write_response_sender: crossbeam_channel::Sender<ModbusWriteResponse>,
     pub balancer_metering_receiver: crossbeam_channel::Receiver<BalancerMeteringMessage>,
+    pub balancer_site_totals_receiver: crossbeam_channel::Receiver<BalancerSiteTotalsMessage>,
+    pub balancer_status_receiver: crossbeam_channel::Receiver<BalancerAssetStatusMessage>,
     pub ocpp_to_asset_receiver:     crossbeam_channel::Receiver<OcppCommandToAsset>,
     pub modbus_request_receiver:    crossbeam_channel::Receiver<ModbusRequest>,
+    pub modbus_write_request_receiver: crossbeam_channel::Receiver<ModbusWriteRequest>,
 }
 
 #[derive(Resource)]
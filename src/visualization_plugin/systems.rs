@@ -15,10 +15,10 @@ use super::resources::{
     PositionsAttached, LogMessages, OutputMessages, MessageChannels,
     MessageTemplateLibrary, SelectedQueue, SelectedTemplate, MessageInput,
 };
-use crate::balancer_comms_plugin::balancer_messages::{BalancerSetpointMessage};
+use crate::balancer_comms_plugin::balancer_messages::{BalancerSetpointMessage, BalancerSetpointBatchMessage, BalancerScheduleMessage};
 use crate::asset_template_plugin::TotalAssets;
-use crate::modbus_protocol_plugin::ModbusResponse;
-use crate::ocpp_protocol_plugin::events::OcppRequestFromAsset;
+use crate::modbus_protocol_plugin::{ModbusResponse, ModbusWriteResponse};
+use crate::ocpp_protocol_plugin::events::{OcppRequestFromAsset, OcppResponseFromAsset, EOcppCallResponse};
 
 // constants for layout and sizes
 const ASSET_SIZE: Vec2           = Vec2::new(50.0, 50.0);
@@ -56,7 +56,7 @@ pub fn setup_camera(mut commands: Commands) {
     // Spawn the camera entity with its core components.
     commands.spawn((
         Camera::default(),
-        Camera2d::default(),
+        Camera2d,
         Projection::Orthographic(projection),
         PanCam {
             grab_buttons: vec![MouseButton::Middle],
@@ -331,6 +331,18 @@ pub fn pull_output_messages_system(
                 output_messages.balancer_metering.remove(0);
             }
         }
+        while let Ok(msg) = channels.balancer_site_totals_receiver.try_recv() {
+            output_messages.balancer_site_totals.push(format!("{:?}", msg));
+            if output_messages.balancer_site_totals.len() > 50 {
+                output_messages.balancer_site_totals.remove(0);
+            }
+        }
+        while let Ok(msg) = channels.balancer_status_receiver.try_recv() {
+            output_messages.balancer_status.push(format!("{:?}", msg));
+            if output_messages.balancer_status.len() > 50 {
+                output_messages.balancer_status.remove(0);
+            }
+        }
         while let Ok(msg) = channels.ocpp_to_asset_receiver.try_recv() {
             output_messages.ocpp_commands.push(format!("{:?}", msg));
             if output_messages.ocpp_commands.len() > 50 {
@@ -343,9 +355,16 @@ pub fn pull_output_messages_system(
                 output_messages.modbus_requests.remove(0);
             }
         }
+        while let Ok(msg) = channels.modbus_write_request_receiver.try_recv() {
+            output_messages.modbus_write_requests.push(format!("{:?}", msg));
+            if output_messages.modbus_write_requests.len() > 50 {
+                output_messages.modbus_write_requests.remove(0);
+            }
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn ui_system(
     mut contexts: EguiContexts,
     log_messages: Res<LogMessages>,
@@ -421,6 +440,14 @@ pub fn ui_system(
                     ui.label(output_messages.balancer_metering.join("\n"));
                 });
                 ui.separator();
+                ui.collapsing("Output: Balancer Site Totals", |ui| {
+                    ui.label(output_messages.balancer_site_totals.join("\n"));
+                });
+                ui.separator();
+                ui.collapsing("Output: Balancer Asset Status", |ui| {
+                    ui.label(output_messages.balancer_status.join("\n"));
+                });
+                ui.separator();
                 ui.collapsing("Output: OCPP Command to Asset", |ui| {
                     ui.label(output_messages.ocpp_commands.join("\n"));
                 });
@@ -428,6 +455,10 @@ pub fn ui_system(
                 ui.collapsing("Output: Modbus Request to Asset", |ui| {
                     ui.label(output_messages.modbus_requests.join("\n"));
                 });
+                ui.separator();
+                ui.collapsing("Output: Modbus Write Request to Asset", |ui| {
+                    ui.label(output_messages.modbus_write_requests.join("\n"));
+                });
             });
         });
 
@@ -455,6 +486,20 @@ fn send_message(
                 }
             }
         }
+        "Balancer Setpoint Batch" => {
+            if let Ok(data) = serde_json::from_str::<BalancerSetpointBatchMessage>(message) {
+                if let Some(channels) = channels {
+                    let _ = channels.balancer_setpoint_batch_sender.send(data);
+                }
+            }
+        }
+        "Balancer Schedule" => {
+            if let Ok(data) = serde_json::from_str::<BalancerScheduleMessage>(message) {
+                if let Some(channels) = channels {
+                    let _ = channels.balancer_schedule_sender.send(data);
+                }
+            }
+        }
         "OCPP Request from Asset" => {
             if let Ok(data) = serde_json::from_str::<serde_json::Value>(message) {
                 if let (Some(cp_id), Some(action), Some(payload)) = (
@@ -485,15 +530,50 @@ fn send_message(
                 error!("Invalid JSON format");
             }
         }
+        "OCPP Response from Asset" => {
+            if let Ok(data) = serde_json::from_str::<serde_json::Value>(message) {
+                let field = |name: &str| data.get(name).and_then(|v| v.as_str()).map(str::to_string);
+                let response = match (field("payload_json"), field("error_code")) {
+                    (Some(payload_json), _) => Some(EOcppCallResponse::Result { payload_json }),
+                    (None, Some(error_code)) => Some(EOcppCallResponse::Error {
+                        error_code,
+                        error_description: field("error_description").unwrap_or_default(),
+                    }),
+                    (None, None) => None,
+                };
+                if let (Some(cp_id), Some(message_id), Some(response)) = (field("charge_point_id"), field("ocpp_message_id"), response) {
+                    let response = OcppResponseFromAsset {
+                        charge_point_id: cp_id.clone(),
+                        ocpp_message_id: message_id.clone(),
+                        response,
+                    };
+                    if let Some(channels) = channels {
+                        if let Err(e) = channels.ocpp_response_from_asset_sender.send(response) {
+                            error!("Failed to send OCPP response: {}", e);
+                        } else {
+                            info!("Sent OCPP response {} from {}", message_id, cp_id);
+                        }
+                    } else {
+                        warn!("Channels not available - simulation mode");
+                    }
+                } else {
+                    error!("Invalid OCPP response format");
+                }
+            } else {
+                error!("Invalid JSON format");
+            }
+        }
         "Modbus Response from Asset" => {
             if let Ok(data) = serde_json::from_str::<serde_json::Value>(message) {
-                if let (Some(external_id), Some(power_kw), Some(energy_kwh)) = (
+                if let (Some(external_id), Some(request_id), Some(power_kw), Some(energy_kwh)) = (
                     data.get("external_id").and_then(|v| v.as_str()),
+                    data.get("request_id").and_then(|v| v.as_u64()),
                     data.get("power_kw").and_then(|v| v.as_f64()),
                     data.get("energy_kwh").and_then(|v| v.as_f64()),
                 ) {
                     let response = ModbusResponse::new(
                         external_id.to_string(),
+                        request_id,
                         power_kw as f32,
                         energy_kwh,
                         chrono::Utc::now(),
@@ -514,6 +594,36 @@ fn send_message(
                 error!("Invalid JSON format");
             }
         }
+        "Modbus Write Ack from Asset" => {
+            if let Ok(data) = serde_json::from_str::<serde_json::Value>(message) {
+                if let (Some(external_id), Some(request_id), Some(setpoint_kw), Some(success)) = (
+                    data.get("external_id").and_then(|v| v.as_str()),
+                    data.get("request_id").and_then(|v| v.as_u64()),
+                    data.get("setpoint_kw").and_then(|v| v.as_f64()),
+                    data.get("success").and_then(|v| v.as_bool()),
+                ) {
+                    let response = ModbusWriteResponse {
+                        external_id: external_id.to_string(),
+                        request_id,
+                        setpoint_kw: setpoint_kw as f32,
+                        success,
+                    };
+                    if let Some(channels) = channels {
+                        if let Err(e) = channels.modbus_write_response_sender.send(response) {
+                            error!("Failed to send Modbus write ack: {}", e);
+                        } else {
+                            info!("Sent Modbus write ack for {}", external_id);
+                        }
+                    } else {
+                        warn!("Channels not available - simulation mode");
+                    }
+                } else {
+                    error!("Invalid Modbus write ack format");
+                }
+            } else {
+                error!("Invalid JSON format");
+            }
+        }
         _ => error!("Unknown queue selected: {}", selected_queue),
     }
 }
\ No newline at end of file
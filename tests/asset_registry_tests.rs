@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppExternalChannelEnds, AppMode};
+use ocpp_bevy_poc::asset_template_plugin::config::ComponentConfig;
+use ocpp_bevy_poc::asset_template_plugin::{EAssetRegistryCommand, SiteConfig, SiteConfigPersistPath, TotalAssets};
+use ocpp_bevy_poc::balancer_comms_plugin::FallbackWeight;
+use ocpp_bevy_poc::common::error::AppError;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::ExternalId;
+use ocpp_bevy_poc::ocpp_protocol_plugin::{OcppConfig, OcppConnectionState, OcppRequestFromAsset};
+use std::time::Duration;
+
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Watts", "profile_phases_in_ocpp_message": 3 },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        },
+        "Battery_Template": {
+            "asset_type": "Battery",
+            "components": [
+                { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" }
+            ]
+        }
+    },
+    "assets": [
+        { "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [
+            { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" }
+        ] },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [
+            { "type": "fallback_weight", "weight": 1.0 }
+        ] }
+    ],
+    "asset_groups": { "chargers": ["CH001"] }
+}"#;
+
+fn started_site() -> (App, AppExternalChannelEnds) {
+    let (mut app, channels) = setup_bevy_app(SITE_CONFIG_JSON.to_string(), AppMode::Headless, None).expect("valid site config");
+    app.update();
+    (app, channels)
+}
+
+/// Send one command and return its outcome, updating until it arrives; with a persist path it waits for the save.
+fn run_command(app: &mut App, channels: &AppExternalChannelEnds, command: EAssetRegistryCommand) -> Result<(), Vec<AppError>> {
+    channels.asset_registry_command_sender.send(command).unwrap();
+    for _ in 0..100 {
+        app.update();
+        if let Ok(outcome) = channels.asset_registry_result_receiver.try_recv() {
+            return outcome.result;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    panic!("command not answered");
+}
+
+fn add_charger(external_id: &str) -> EAssetRegistryCommand {
+    EAssetRegistryCommand::AddAsset {
+        external_id: external_id.into(),
+        template_id: "Charger_Template".into(),
+        instance_components: vec![ComponentConfig::OcppConfig { version: "V1_6J".into(), charge_point_id: external_id.into() }],
+    }
+}
+
+fn external_ids(app: &mut App) -> Vec<String> {
+    let mut ids: Vec<String> = app.world_mut().query::<&ExternalId>().iter(app.world()).map(|id| id.0.clone()).collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_added_charger_is_spawned_and_accepts_ocpp() {
+    let (mut app, channels) = started_site();
+
+    assert!(run_command(&mut app, &channels, add_charger("CH002")).is_ok());
+    app.update();
+
+    assert_eq!(external_ids(&mut app), vec!["BAT001", "CH001", "CH002"]);
+    assert_eq!(app.world().resource::<TotalAssets>().0, 3);
+    assert!(app.world().resource::<SiteConfig>().assets.iter().any(|instance| instance.external_id == "CH002"));
+    let entity = app.world().resource::<ExternalIdMap>().0["CH002"];
+    assert_eq!(app.world().get::<OcppConfig>(entity).unwrap().charge_point_id, "CH002");
+
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: "CH002".into(),
+        action: "BootNotification".into(),
+        payload_json: r#"{"chargePointVendor":"Alfen","chargePointModel":"Eve"}"#.into(),
+        ocpp_message_id: "boot-1".into(),
+    }).unwrap();
+    app.update();
+    app.update();
+    assert!(app.world().get::<OcppConnectionState>(entity).unwrap().is_connected);
+}
+
+#[test]
+fn test_invalid_additions_are_rejected_without_changes() {
+    let (mut app, channels) = started_site();
+
+    let errors = run_command(&mut app, &channels, add_charger("CH001")).unwrap_err();
+    assert!(matches!(errors.as_slice(), [AppError::DuplicateExternalId { asset_id, .. }] if asset_id == "CH001"));
+
+    let errors = run_command(&mut app, &channels, EAssetRegistryCommand::AddAsset {
+        external_id: "PV001".into(),
+        template_id: "Solar_Template".into(),
+        instance_components: vec![],
+    }).unwrap_err();
+    assert!(matches!(errors.as_slice(), [AppError::UnknownTemplate { template_id, .. }] if template_id == "Solar_Template"));
+
+    let errors = run_command(&mut app, &channels, EAssetRegistryCommand::AddAsset {
+        external_id: "BAT002".into(),
+        template_id: "Battery_Template".into(),
+        instance_components: vec![ComponentConfig::ModbusControlConfig {
+            ip: "127.0.0.1".into(), port: 502, unit_id: 1, write_register_map_key: "missing_regs".into(),
+        }],
+    }).unwrap_err();
+    assert!(matches!(errors.as_slice(), [AppError::UnknownRegisterMap { key, .. }] if key == "missing_regs"));
+
+    app.update();
+    assert_eq!(external_ids(&mut app), vec!["BAT001", "CH001"]);
+    assert_eq!(app.world().resource::<SiteConfig>().assets.len(), 2);
+    assert_eq!(app.world().resource::<TotalAssets>().0, 2);
+}
+
+#[test]
+fn test_update_components_in_place() {
+    let (mut app, channels) = started_site();
+    let entity = app.world().resource::<ExternalIdMap>().0["BAT001"];
+
+    let result = run_command(&mut app, &channels, EAssetRegistryCommand::UpdateAssetComponents {
+        external_id: "BAT001".into(),
+        instance_components: vec![ComponentConfig::FallbackWeight { weight: 4.0 }],
+    });
+    assert!(result.is_ok());
+    app.update();
+
+    assert_eq!(app.world().resource::<ExternalIdMap>().0["BAT001"], entity);
+    assert_eq!(app.world().get::<FallbackWeight>(entity).unwrap().0, 4.0);
+
+    let errors = run_command(&mut app, &channels, EAssetRegistryCommand::UpdateAssetComponents {
+        external_id: "BAT001".into(),
+        instance_components: vec![ComponentConfig::OcppConfig { version: "V1_6J".into(), charge_point_id: "BAT001".into() }],
+    }).unwrap_err();
+    assert!(matches!(errors.as_slice(), [AppError::ComponentNotAllowed { component: "ocpp_config", .. }]));
+    assert!(app.world().get::<OcppConfig>(entity).is_none());
+}
+
+#[test]
+fn test_remove_asset() {
+    let (mut app, channels) = started_site();
+
+    assert!(run_command(&mut app, &channels, EAssetRegistryCommand::RemoveAsset { external_id: "CH001".into() }).is_ok());
+    app.update();
+    assert_eq!(external_ids(&mut app), vec!["BAT001"]);
+    assert!(!app.world().resource::<ExternalIdMap>().0.contains_key("CH001"));
+    assert_eq!(app.world().resource::<TotalAssets>().0, 1);
+
+    let errors = run_command(&mut app, &channels, EAssetRegistryCommand::RemoveAsset { external_id: "CH001".into() }).unwrap_err();
+    assert!(matches!(errors.as_slice(), [AppError::UnknownAsset { asset_id }] if asset_id == "CH001"));
+}
+
+#[test]
+fn test_changes_are_persisted_to_config_file() {
+    let path = std::env::temp_dir().join(format!("asset_registry_test_{}.json", std::process::id()));
+    std::fs::write(&path, SITE_CONFIG_JSON).unwrap();
+    let (mut app, channels) = started_site();
+    app.insert_resource(SiteConfigPersistPath(path.clone()));
+
+    assert!(run_command(&mut app, &channels, add_charger("CH002")).is_ok());
+    assert!(run_command(&mut app, &channels, EAssetRegistryCommand::RemoveAsset { external_id: "BAT001".into() }).is_ok());
+
+    let saved = SiteConfig::from_json(&std::fs::read_to_string(&path).unwrap()).expect("saved config is valid");
+    let ids: Vec<&str> = saved.assets.iter().map(|instance| instance.external_id.as_str()).collect();
+    assert_eq!(ids, vec!["CH001", "CH002"]);
+    assert_eq!(saved.asset_templates.len(), 2);
+    assert_eq!(saved.asset_groups["chargers"], vec!["CH001"]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_command_whose_save_fails_changes_nothing() {
+    let (mut app, channels) = started_site();
+    let path = std::env::temp_dir().join(format!("asset_registry_missing_{}", std::process::id())).join("site.json");
+    app.insert_resource(SiteConfigPersistPath(path));
+
+    let errors = run_command(&mut app, &channels, add_charger("CH002")).unwrap_err();
+    assert!(matches!(errors.as_slice(), [AppError::ConfigFileUnreadable { .. }]), "{errors:?}");
+    app.update();
+    assert_eq!(external_ids(&mut app), vec!["BAT001", "CH001"]);
+    assert_eq!(app.world().resource::<SiteConfig>().assets.len(), 2);
+}
+
+#[test]
+fn test_changes_to_included_assets_are_rejected_when_persisting() {
+    let dir = std::env::temp_dir().join(format!("asset_registry_include_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let included = SITE_CONFIG_JSON.replace(r#""asset_groups": { "chargers": ["CH001"] }"#, r#""asset_groups": {}"#);
+    std::fs::write(dir.join("shared.json"), included).unwrap();
+    std::fs::write(dir.join("site.json"), r#"{ "include": "shared.json", "asset_groups": { "chargers": ["CH001"] } }"#).unwrap();
+    let (mut app, channels) = started_site();
+    app.insert_resource(SiteConfigPersistPath(dir.join("site.json")));
+
+    let update = EAssetRegistryCommand::UpdateAssetComponents { external_id: "BAT001".into(), instance_components: vec![] };
+    let remove = EAssetRegistryCommand::RemoveAsset { external_id: "CH001".into() };
+    for command in [update, remove] {
+        let errors = run_command(&mut app, &channels, command).unwrap_err();
+        assert!(matches!(errors.as_slice(), [AppError::AssetInIncludedFile { .. }]), "{errors:?}");
+    }
+    app.update();
+    assert_eq!(external_ids(&mut app), vec!["BAT001", "CH001"]);
+    let bat001 = app.world().resource::<ExternalIdMap>().0["BAT001"];
+    assert_eq!(app.world().get::<FallbackWeight>(bat001).unwrap().0, 1.0);
+
+    // Assets added at runtime belong to the site file, so they can still be changed
+    assert!(run_command(&mut app, &channels, add_charger("CH002")).is_ok());
+    assert!(run_command(&mut app, &channels, EAssetRegistryCommand::RemoveAsset { external_id: "CH002".into() }).is_ok());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
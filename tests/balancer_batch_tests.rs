@@ -0,0 +1,125 @@
+mod common;
+
+use bevy::prelude::*;
+use chrono::{Duration as ChronoDuration, Utc};
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::balancer_comms_plugin::balancer_messages::{BalancerGroupSetpointMessage, BalancerSetpointBatchMessage, BalancerSetpointMessage};
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::TargetPowerSetpointKw;
+
+/// Three chargers grouped as "chargers", CH003 also in "fast_chargers", and a battery.
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Charger_Template": {
+            "asset_type": "Charger",
+            "components": [ { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" } ]
+        },
+        "Battery_Template": {
+            "asset_type": "Battery",
+            "components": [ { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" } ]
+        }
+    },
+    "assets": [
+        { "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [] },
+        { "external_id": "CH002", "template_id": "Charger_Template", "instance_components": [] },
+        { "external_id": "CH003", "template_id": "Charger_Template", "instance_components": [] },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] }
+    ],
+    "asset_groups": {
+        "chargers": ["CH001", "CH002", "CH003"],
+        "fast_chargers": ["CH003"]
+    }
+}"#;
+
+fn send_batch(app: &mut App, channels: &AppExternalChannelEnds, batch: BalancerSetpointBatchMessage) {
+    channels.balancer_setpoint_batch_sender.send(batch).unwrap();
+    app.update();
+}
+
+fn setpoint(external_id: &str, target_power_kw: f32) -> BalancerSetpointMessage {
+    BalancerSetpointMessage { external_id: external_id.into(), target_power_kw, ..Default::default() }
+}
+
+fn group_setpoint(group_id: &str, target_power_kw: f32) -> BalancerGroupSetpointMessage {
+    BalancerGroupSetpointMessage { group_id: group_id.into(), target_power_kw, ..Default::default() }
+}
+
+fn targets(app: &App) -> [f32; 4] {
+    let id_map = app.world().resource::<ExternalIdMap>();
+    ["CH001", "CH002", "CH003", "BAT001"].map(|id| app.world().get::<TargetPowerSetpointKw>(id_map.0[id]).unwrap().0)
+}
+
+#[test]
+fn test_batch_applies_all_setpoints_in_one_frame() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage {
+        setpoints: vec![setpoint("CH001", 7.0), setpoint("CH002", 3.0), setpoint("BAT001", -5.0)],
+        ..Default::default()
+    });
+    assert_eq!(targets(&app), [7.0, 3.0, 0.0, -5.0]);
+}
+
+#[test]
+fn test_group_target_shared_among_members_without_their_own_setpoint() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage {
+        group_setpoints: vec![group_setpoint("chargers", 30.0)],
+        ..Default::default()
+    });
+    assert_eq!(targets(&app), [10.0, 10.0, 10.0, 0.0]);
+
+    // An explicit setpoint takes its asset out of the group share.
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage {
+        setpoints: vec![setpoint("CH003", 4.0)],
+        group_setpoints: vec![group_setpoint("chargers", 40.0)],
+    });
+    assert_eq!(targets(&app), [20.0, 20.0, 4.0, 0.0]);
+}
+
+#[test]
+fn test_batch_with_unknown_asset_or_group_is_rejected_whole() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage {
+        setpoints: vec![setpoint("CH001", 7.0), setpoint("CH999", 3.0)],
+        ..Default::default()
+    });
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage {
+        setpoints: vec![setpoint("BAT001", -5.0)],
+        group_setpoints: vec![group_setpoint("heat_pumps", 10.0)],
+    });
+    assert_eq!(targets(&app), [0.0; 4]);
+}
+
+#[test]
+fn test_batch_with_lapsed_stale_or_repeated_entries_is_rejected_whole() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    let sequenced = |external_id: &str, target_power_kw: f32, sequence: u64| BalancerSetpointMessage {
+        sequence: Some(sequence),
+        ..setpoint(external_id, target_power_kw)
+    };
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage { setpoints: vec![sequenced("CH001", 2.0, 5)], ..Default::default() });
+    assert_eq!(targets(&app), [2.0, 0.0, 0.0, 0.0]);
+
+    // One lapsed entry
+    let lapsed = BalancerSetpointMessage { valid_until: Some(Utc::now() - ChronoDuration::seconds(1)), ..setpoint("CH002", 3.0) };
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage { setpoints: vec![setpoint("BAT001", -5.0), lapsed], ..Default::default() });
+    // One entry older than the asset's last setpoint
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage {
+        setpoints: vec![setpoint("BAT001", -5.0), sequenced("CH001", 7.0, 4)],
+        ..Default::default()
+    });
+    // CH003 is in both groups
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage {
+        group_setpoints: vec![group_setpoint("chargers", 9.0), group_setpoint("fast_chargers", 4.0)],
+        ..Default::default()
+    });
+    assert_eq!(targets(&app), [2.0, 0.0, 0.0, 0.0]);
+
+    // Ordering also holds between batches received in the same frame
+    channels.balancer_setpoint_batch_sender.send(BalancerSetpointBatchMessage { setpoints: vec![sequenced("CH001", 6.0, 7)], ..Default::default() }).unwrap();
+    send_batch(&mut app, &channels, BalancerSetpointBatchMessage {
+        setpoints: vec![sequenced("CH001", 8.0, 6), setpoint("BAT001", -5.0)],
+        ..Default::default()
+    });
+    assert_eq!(targets(&app), [6.0, 0.0, 0.0, 0.0]);
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use bevy::prelude::*;
+use common::{run_secs, send_setpoint};
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::balancer_comms_plugin::EBalancerMode;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::TargetPowerSetpointKw;
+
+/// Two chargers (CH002 weighted double) and a battery with a -1 kW safe setpoint; the fallback shares 12 kW after 2 s of silence.
+fn site_config(strategy: &str) -> String {
+    format!(r#"{{
+        "asset_templates": {{
+            "Charger_Template": {{
+                "asset_type": "Charger",
+                "components": [ {{ "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" }} ]
+            }},
+            "Battery_Template": {{
+                "asset_type": "Battery",
+                "components": [ {{ "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" }} ]
+            }}
+        }},
+        "assets": [
+            {{ "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [] }},
+            {{ "external_id": "CH002", "template_id": "Charger_Template", "instance_components": [
+                {{ "type": "fallback_weight", "weight": 2.0 }}
+            ] }},
+            {{ "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [
+                {{ "type": "safe_setpoint", "power_kw": -1.0 }}
+            ] }}
+        ],
+        "balancer_fallback": {{ "timeout_secs": 2.0, "site_power_kw": 12.0, "strategy": "{strategy}" }}
+    }}"#)
+}
+
+fn start_app(strategy: &str) -> (App, AppExternalChannelEnds) {
+    common::start_app(site_config(strategy))
+}
+
+fn targets(app: &App) -> [f32; 3] {
+    let id_map = app.world().resource::<ExternalIdMap>();
+    ["CH001", "CH002", "BAT001"].map(|id| app.world().get::<TargetPowerSetpointKw>(id_map.0[id]).unwrap().0)
+}
+
+fn mode(app: &App) -> EBalancerMode {
+    *app.world().resource::<EBalancerMode>()
+}
+
+#[test]
+fn test_fallback_takes_over_after_silence_and_hands_back() {
+    let (mut app, channels) = start_app("EqualShare");
+
+    send_setpoint(&mut app, &channels, "CH001", 3.0);
+    send_setpoint(&mut app, &channels, "BAT001", -5.0);
+    run_secs(&mut app, 1);
+    assert_eq!(mode(&app), EBalancerMode::External);
+    assert_eq!(targets(&app), [3.0, 0.0, -5.0]);
+
+    // Two seconds after the last message the chargers split 12 kW and the battery is held at its safe setpoint.
+    run_secs(&mut app, 2);
+    assert_eq!(mode(&app), EBalancerMode::Fallback);
+    assert_eq!(targets(&app), [6.0, 6.0, -1.0]);
+
+    // The next balancer message takes control back; assets it does not address drop their fallback share.
+    send_setpoint(&mut app, &channels, "CH001", 1.0);
+    assert_eq!(mode(&app), EBalancerMode::External);
+    run_secs(&mut app, 1);
+    assert_eq!(mode(&app), EBalancerMode::External);
+    assert_eq!(targets(&app), [1.0, 0.0, -1.0]);
+}
+
+#[test]
+fn test_weighted_fallback_shares_by_weight() {
+    let (mut app, _channels) = start_app("Weighted");
+    run_secs(&mut app, 3);
+    assert_eq!(mode(&app), EBalancerMode::Fallback);
+    assert_eq!(targets(&app), [4.0, 8.0, -1.0]);
+}
+
+#[test]
+fn test_no_fallback_without_config() {
+    let config = site_config("EqualShare").replace(r#""balancer_fallback""#, r#""unused_fallback""#);
+    let (mut app, channels) = common::start_app(config);
+    send_setpoint(&mut app, &channels, "CH001", 3.0);
+    run_secs(&mut app, 10);
+    assert_eq!(mode(&app), EBalancerMode::External);
+    assert_eq!(targets(&app), [3.0, 0.0, 0.0]);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use bevy::prelude::*;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use common::run_secs;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::balancer_comms_plugin::balancer_messages::{BalancerSchedulePeriod, BalancerScheduleMessage, BalancerSetpointMessage};
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::TargetPowerSetpointKw;
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::SetChargingProfileReqPayload;
+
+/// A battery and an OCPP charger taking profiles in Watts; `SEND_SCHEDULES` is replaced per test.
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Watts", "profile_phases_in_ocpp_message": 3, "send_schedules": SEND_SCHEDULES },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        },
+        "Battery_Template": {
+            "asset_type": "Battery",
+            "components": [ { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" } ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH001",
+            "template_id": "Charger_Template",
+            "instance_components": [ { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" } ]
+        },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] }
+    ]
+}"#;
+
+fn start_app(send_schedules: bool) -> (App, AppExternalChannelEnds) {
+    let config = SITE_CONFIG_JSON.replace("SEND_SCHEDULES", &send_schedules.to_string());
+    common::start_app(config)
+}
+
+fn send_schedule(app: &mut App, channels: &AppExternalChannelEnds, external_id: &str, periods: &[(DateTime<Utc>, f32)]) {
+    channels.balancer_schedule_sender.send(BalancerScheduleMessage {
+        external_id: external_id.into(),
+        periods: periods.iter().map(|&(start, power_kw)| BalancerSchedulePeriod { start, power_kw }).collect(),
+    }).unwrap();
+    app.update();
+    app.update();
+}
+
+fn target(app: &App, external_id: &str) -> f32 {
+    let entity = app.world().resource::<ExternalIdMap>().0[external_id];
+    app.world().get::<TargetPowerSetpointKw>(entity).unwrap().0
+}
+
+fn profiles_sent(channels: &AppExternalChannelEnds) -> Vec<SetChargingProfileReqPayload> {
+    common::charging_profiles(channels.ocpp_to_asset_receiver.try_iter().collect()).into_iter().map(|(_, req)| req).collect()
+}
+
+#[test]
+fn test_schedule_slots_applied_as_they_start() {
+    let (mut app, channels) = start_app(false);
+    let now = Utc::now();
+    send_schedule(&mut app, &channels, "BAT001", &[
+        (now - ChronoDuration::seconds(10), 8.0),
+        (now + ChronoDuration::seconds(2), -5.0),
+        (now + ChronoDuration::seconds(4), 3.0),
+    ]);
+    assert_eq!(target(&app, "BAT001"), 8.0);
+
+    run_secs(&mut app, 3);
+    assert_eq!(target(&app, "BAT001"), -5.0);
+
+    // An immediate setpoint holds until the next slot starts.
+    channels.balancer_setpoint_sender.send(BalancerSetpointMessage { external_id: "BAT001".into(), target_power_kw: 1.0, ..Default::default() }).unwrap();
+    app.update();
+    assert_eq!(target(&app, "BAT001"), 1.0);
+
+    run_secs(&mut app, 2);
+    assert_eq!(target(&app, "BAT001"), 3.0);
+
+    // The last slot runs on.
+    run_secs(&mut app, 5);
+    assert_eq!(target(&app, "BAT001"), 3.0);
+}
+
+#[test]
+fn test_schedule_sent_to_charger_as_multi_period_profile() {
+    let (mut app, channels) = start_app(true);
+    common::boot_charger(&mut app, &channels, "CH001");
+
+    let slot_start = Utc::now() - ChronoDuration::minutes(5);
+    send_schedule(&mut app, &channels, "CH001", &[
+        (slot_start, 11.0),
+        (slot_start + ChronoDuration::minutes(15), 4.0),
+        (slot_start + ChronoDuration::minutes(30), 7.0),
+    ]);
+
+    let profiles = profiles_sent(&channels);
+    assert_eq!(profiles.len(), 1);
+    let schedule = &profiles[0].cs_charging_profiles.charging_schedule;
+    assert_eq!(schedule.start_schedule, Some(slot_start.to_rfc3339()));
+    let periods: Vec<(u32, f32)> = schedule.charging_schedule_period.iter().map(|p| (p.start_period, p.limit)).collect();
+    assert_eq!(periods, vec![(0, 11000.0), (900, 4000.0), (1800, 7000.0)]);
+    // The last slot holds for a day, not just to the end of the first day
+    assert_eq!(schedule.duration, Some(1800 + 86400));
+}
+
+#[test]
+fn test_charger_without_schedule_support_gets_current_slot_only() {
+    let (mut app, channels) = start_app(false);
+    common::boot_charger(&mut app, &channels, "CH001");
+
+    let slot_start = Utc::now() - ChronoDuration::minutes(5);
+    send_schedule(&mut app, &channels, "CH001", &[(slot_start, 11.0), (slot_start + ChronoDuration::minutes(15), 4.0)]);
+
+    let profiles = profiles_sent(&channels);
+    assert_eq!(profiles.len(), 1);
+    let periods = &profiles[0].cs_charging_profiles.charging_schedule.charging_schedule_period;
+    assert_eq!(periods.len(), 1);
+    assert_eq!(periods[0].limit, 11000.0);
+    assert_eq!(profiles[0].cs_charging_profiles.charging_schedule.duration, Some(86400));
+}
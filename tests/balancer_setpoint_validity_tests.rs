@@ -0,0 +1,120 @@
+mod common;
+
+use bevy::prelude::*;
+use chrono::{Duration as ChronoDuration, Utc};
+use common::run_secs;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::balancer_comms_plugin::balancer_messages::BalancerSetpointMessage;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::TargetPowerSetpointKw;
+
+/// A charger without a safe setpoint and a battery that falls back to charging at 2 kW.
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Charger_Template": {
+            "asset_type": "Charger",
+            "components": [ { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" } ]
+        },
+        "Battery_Template": {
+            "asset_type": "Battery",
+            "components": [
+                { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" },
+                { "type": "safe_setpoint", "power_kw": 2.0 }
+            ]
+        }
+    },
+    "assets": [
+        { "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [] },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] }
+    ]
+}"#;
+
+fn start_app() -> (App, AppExternalChannelEnds) {
+    common::start_app(SITE_CONFIG_JSON.to_string())
+}
+
+fn send(app: &mut App, channels: &AppExternalChannelEnds, message: BalancerSetpointMessage) {
+    channels.balancer_setpoint_sender.send(message).unwrap();
+    app.update();
+}
+
+fn target(app: &App, external_id: &str) -> f32 {
+    let entity = app.world().resource::<ExternalIdMap>().0[external_id];
+    app.world().get::<TargetPowerSetpointKw>(entity).unwrap().0
+}
+
+#[test]
+fn test_setpoint_reverts_to_safe_default_once_lapsed() {
+    let (mut app, channels) = start_app();
+    let valid_until = Some(Utc::now() + ChronoDuration::seconds(3));
+    send(&mut app, &channels, BalancerSetpointMessage { external_id: "CH001".into(), target_power_kw: 7.0, valid_until, ..Default::default() });
+    send(&mut app, &channels, BalancerSetpointMessage { external_id: "BAT001".into(), target_power_kw: -5.0, valid_until, ..Default::default() });
+
+    run_secs(&mut app, 2);
+    assert_eq!((target(&app, "CH001"), target(&app, "BAT001")), (7.0, -5.0));
+
+    run_secs(&mut app, 2);
+    assert_eq!((target(&app, "CH001"), target(&app, "BAT001")), (0.0, 2.0));
+}
+
+#[test]
+fn test_setpoints_without_validity_hold_and_lapsed_ones_are_ignored() {
+    let (mut app, channels) = start_app();
+    send(&mut app, &channels, BalancerSetpointMessage { external_id: "CH001".into(), target_power_kw: 7.0, ..Default::default() });
+
+    let valid_until = Some(Utc::now() - ChronoDuration::seconds(1));
+    send(&mut app, &channels, BalancerSetpointMessage { external_id: "CH001".into(), target_power_kw: 3.0, valid_until, ..Default::default() });
+
+    run_secs(&mut app, 10);
+    assert_eq!(target(&app, "CH001"), 7.0);
+}
+
+#[test]
+fn test_older_sequence_numbers_are_rejected() {
+    let (mut app, channels) = start_app();
+    let setpoint = |target_power_kw: f32, sequence: u64| BalancerSetpointMessage {
+        external_id: "CH001".into(),
+        target_power_kw,
+        sequence: Some(sequence),
+        ..Default::default()
+    };
+
+    send(&mut app, &channels, setpoint(7.0, 5));
+    send(&mut app, &channels, setpoint(3.0, 4));
+    send(&mut app, &channels, setpoint(4.0, 5));
+    assert_eq!(target(&app, "CH001"), 7.0);
+
+    send(&mut app, &channels, setpoint(9.0, 6));
+    assert_eq!(target(&app, "CH001"), 9.0);
+}
+
+#[test]
+fn test_issue_time_orders_messages_without_sequence() {
+    let (mut app, channels) = start_app();
+    let now = Utc::now();
+    let setpoint = |target_power_kw: f32, issued_at| BalancerSetpointMessage {
+        external_id: "CH001".into(),
+        target_power_kw,
+        issued_at: Some(issued_at),
+        ..Default::default()
+    };
+
+    send(&mut app, &channels, setpoint(7.0, now));
+    send(&mut app, &channels, setpoint(3.0, now - ChronoDuration::seconds(1)));
+    assert_eq!(target(&app, "CH001"), 7.0);
+
+    send(&mut app, &channels, setpoint(5.0, now + ChronoDuration::seconds(1)));
+    assert_eq!(target(&app, "CH001"), 5.0);
+}
+
+#[test]
+fn test_setpoint_message_fields_are_optional_on_the_wire() {
+    let message: BalancerSetpointMessage = serde_json::from_str(r#"{ "external_id": "CH001", "target_power_kw": 5.0 }"#).unwrap();
+    assert!(message.valid_until.is_none() && message.sequence.is_none() && message.issued_at.is_none());
+
+    let message: BalancerSetpointMessage = serde_json::from_str(
+        r#"{ "external_id": "CH001", "target_power_kw": 5.0, "valid_until": "2025-01-01T10:05:00Z", "sequence": 42, "issued_at": "2025-01-01T10:00:00Z" }"#,
+    ).unwrap();
+    assert_eq!(message.sequence, Some(42));
+    assert_eq!(message.valid_until.unwrap() - message.issued_at.unwrap(), ChronoDuration::minutes(5));
+}
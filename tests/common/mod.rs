@@ -0,0 +1,59 @@
+// Fixture shared by the integration tests: a manual-clock app and the charger traffic most of them start with.
+// Each test crate uses its own part of it.
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppExternalChannelEnds, AppMode};
+use ocpp_bevy_poc::balancer_comms_plugin::balancer_messages::BalancerSetpointMessage;
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::{EOutgoingOcppMessage, SetChargingProfileReqPayload};
+use ocpp_bevy_poc::ocpp_protocol_plugin::{OcppCommandToAsset, OcppRequestFromAsset};
+use std::time::Duration;
+
+/// Updates per second of simulated time.
+pub const STEPS_PER_SEC: usize = 5;
+
+/// Headless app for `site_config_json` whose clock advances `1 / STEPS_PER_SEC` s per update, after its first update.
+pub fn start_app(site_config_json: String) -> (App, AppExternalChannelEnds) {
+    let (mut app, channels) = setup_bevy_app(site_config_json, AppMode::Headless, None).expect("valid site config");
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(1) / STEPS_PER_SEC as u32));
+    app.update();
+    (app, channels)
+}
+
+pub fn run_secs(app: &mut App, secs: usize) {
+    for _ in 0..secs * STEPS_PER_SEC {
+        app.update();
+    }
+}
+
+/// Boot `cp_id` as an OCPP 1.6 charger; returns the commands sent to charge points meanwhile.
+pub fn boot_charger(app: &mut App, channels: &AppExternalChannelEnds, cp_id: &str) -> Vec<OcppCommandToAsset> {
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: cp_id.into(),
+        action: "BootNotification".into(),
+        payload_json: r#"{"chargePointVendor":"Alfen","chargePointModel":"Eve"}"#.into(),
+        ocpp_message_id: format!("boot-{cp_id}"),
+    }).unwrap();
+    app.update();
+    app.update();
+    channels.ocpp_to_asset_receiver.try_iter().collect()
+}
+
+/// Send the balancer's setpoint for `external_id`; returns the commands sent to charge points meanwhile.
+pub fn send_setpoint(app: &mut App, channels: &AppExternalChannelEnds, external_id: &str, target_power_kw: f32) -> Vec<OcppCommandToAsset> {
+    channels.balancer_setpoint_sender.send(BalancerSetpointMessage { external_id: external_id.into(), target_power_kw, ..Default::default() }).unwrap();
+    app.update();
+    app.update();
+    channels.ocpp_to_asset_receiver.try_iter().collect()
+}
+
+/// The OCPP 1.6 profiles among `commands`, with their message ids.
+pub fn charging_profiles(commands: Vec<OcppCommandToAsset>) -> Vec<(String, SetChargingProfileReqPayload)> {
+    commands.into_iter()
+        .filter_map(|cmd| match cmd.message_type {
+            EOutgoingOcppMessage::SetChargingProfileRequest(req) => Some((cmd.ocpp_message_id.unwrap(), req)),
+            _ => None,
+        })
+        .collect()
+}
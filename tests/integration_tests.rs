@@ -4,11 +4,14 @@ use ocpp_bevy_poc::balancer_comms_plugin::balancer_messages::BalancerSetpointMes
 use ocpp_bevy_poc::ocpp_protocol_plugin::events::{
     OcppRequestFromAsset,
 };
+use ocpp_bevy_poc::ocpp_protocol_plugin::EGunStatusOcpp;
 use ocpp_bevy_poc::ocpp_protocol_plugin::types::{
     BootNotificationReqPayload,
     StatusNotificationReqPayload,
     EOutgoingOcppMessage,
     RegistrationStatus,
+    ChargePointErrorCode,
+    ChargingRateUnitType,
 };
 use crossbeam_channel::Receiver;
 use std::time::Duration;
@@ -54,7 +57,7 @@ fn test_charger_connect_setpoint_update() {
     }"#.to_string();
 
     // 2. Standard app setup with custom config
-    let (mut bevy_app, channels) = setup_bevy_app(site_config_json, AppMode::Headless, None);
+    let (mut bevy_app, channels) = setup_bevy_app(site_config_json, AppMode::Headless, None).expect("valid site config");
 
     // 3. Grab OCPP and balancer channels
     let ocpp_from_asset_sender    = channels.ocpp_from_asset_sender.clone();
@@ -68,6 +71,7 @@ fn test_charger_connect_setpoint_update() {
     let boot_notification = BootNotificationReqPayload {
         charge_point_vendor: "TestVendor".into(),
         charge_point_model:  "TestModel".into(),
+        ..Default::default()
     };
     ocpp_from_asset_sender.send(OcppRequestFromAsset {
         charge_point_id: asset_external_id.clone(),
@@ -101,8 +105,9 @@ fn test_charger_connect_setpoint_update() {
     // 6. Simulate StatusNotification
     let status_notification = StatusNotificationReqPayload {
         connector_id: 1,
-        error_code:   "NoError".into(),
-        status:       "Available".into(),
+        error_code:   ChargePointErrorCode::NoError,
+        status:       EGunStatusOcpp::Available,
+        ..Default::default()
     };
     ocpp_from_asset_sender.send(OcppRequestFromAsset {
         charge_point_id: asset_external_id.clone(),
@@ -125,6 +130,7 @@ fn test_charger_connect_setpoint_update() {
     balancer_setpoint_sender.send(BalancerSetpointMessage {
         external_id:     asset_external_id.clone(),
         target_power_kw: 10.0,
+        ..Default::default()
     }).unwrap();
 
     // Call update three times to ensure all systems (including export_ocpp_commands_to_channel_system) run
@@ -137,7 +143,7 @@ fn test_charger_connect_setpoint_update() {
     assert_eq!(profile10.charge_point_id, asset_external_id.clone());
     if let EOutgoingOcppMessage::SetChargingProfileRequest(req) = profile10.message_type {
         // The profile behavior is configured for "Amps", so we assert the unit is "A"
-        assert_eq!(req.cs_charging_profiles.charging_schedule.charging_rate_unit, "A");
+        assert_eq!(req.cs_charging_profiles.charging_schedule.charging_rate_unit, ChargingRateUnitType::A);
         let limit = req.cs_charging_profiles.charging_schedule.charging_schedule_period[0].limit;
         // The limit is now in Amps: (10000W) / (230V * 3 phases) = 14.49A
         assert!((limit - 14.49).abs() < 0.1, "Limit was {}", limit);
@@ -149,6 +155,7 @@ fn test_charger_connect_setpoint_update() {
     balancer_setpoint_sender.send(BalancerSetpointMessage {
         external_id:     asset_external_id.clone(),
         target_power_kw: 5.0,
+        ..Default::default()
     }).unwrap();
     bevy_app.update(); bevy_app.update(); bevy_app.update();
 
@@ -159,7 +166,7 @@ fn test_charger_connect_setpoint_update() {
     assert_eq!(profile5.charge_point_id, asset_external_id);
     if let EOutgoingOcppMessage::SetChargingProfileRequest(req) = profile5.message_type {
         // The profile behavior is configured for "Amps", so we assert the unit is "A"
-        assert_eq!(req.cs_charging_profiles.charging_schedule.charging_rate_unit, "A");
+        assert_eq!(req.cs_charging_profiles.charging_schedule.charging_rate_unit, ChargingRateUnitType::A);
         let limit = req.cs_charging_profiles.charging_schedule.charging_schedule_period[0].limit;
         // The limit is now in Amps: (5000W) / (230V * 3 phases) = 7.24A
         assert!((limit - 7.24).abs() < 0.1, "Limit was {}", limit);
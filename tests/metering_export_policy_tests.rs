@@ -0,0 +1,116 @@
+mod common;
+
+use bevy::prelude::*;
+use common::STEPS_PER_SEC;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::common::types::EAssetType;
+use ocpp_bevy_poc::core_asset_plugin::CurrentMeterReading;
+
+/// Batteries report every 2 s, chargers on a 1 kW deadband with a 3 s max silence, and the metered grid on every change.
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Charger_Template": {
+            "asset_type": "Charger",
+            "components": [ { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" } ]
+        },
+        "Battery_Template": {
+            "asset_type": "Battery",
+            "components": [ { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" } ]
+        },
+        "Grid_Template": {
+            "asset_type": "GridConnection",
+            "components": [
+                { "type": "asset_info", "make": "Generic", "model": "Grid Meter" },
+                { "type": "metering_source", "source_type": "InternalCalculation", "details": { "internal_calculation": {} } }
+            ]
+        }
+    },
+    "assets": [
+        { "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [] },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] },
+        { "external_id": "GRID", "template_id": "Grid_Template", "instance_components": [] }
+    ],
+    "metering_export": {
+        "Battery": { "interval_secs": 2.0 },
+        "Charger": { "deadband_kw": 1.0, "max_silence_secs": 3.0 }
+    }
+}"#;
+
+fn start_app() -> (App, AppExternalChannelEnds) {
+    let (app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    channels.balancer_metering_receiver.try_iter().for_each(drop);
+    channels.balancer_site_totals_receiver.try_iter().for_each(drop);
+    (app, channels)
+}
+
+fn set_power(app: &mut App, external_id: &str, power_kw: f32) {
+    let entity = app.world().resource::<ExternalIdMap>().0[external_id];
+    app.world_mut().get_mut::<CurrentMeterReading>(entity).unwrap().power_kw = power_kw;
+    app.update();
+}
+
+fn sent_for(channels: &AppExternalChannelEnds, external_id: &str) -> Vec<f32> {
+    channels.balancer_metering_receiver.try_iter()
+        .filter(|message| message.external_id == external_id)
+        .map(|message| message.power_kw)
+        .collect()
+}
+
+#[test]
+fn test_interval_policy_sends_on_fixed_cadence() {
+    let (mut app, channels) = start_app();
+
+    // Changing every update still only goes out every 2 s.
+    for step in 0..6 * STEPS_PER_SEC {
+        set_power(&mut app, "BAT001", step as f32);
+    }
+    assert_eq!(sent_for(&channels, "BAT001").len(), 3);
+
+    // Unchanged readings keep going out on the cadence.
+    for _ in 0..4 * STEPS_PER_SEC {
+        app.update();
+    }
+    assert_eq!(sent_for(&channels, "BAT001").len(), 2);
+}
+
+#[test]
+fn test_deadband_and_max_silence() {
+    let (mut app, channels) = start_app();
+
+    for power_kw in [0.3, 0.6, 0.9, 1.2, 1.5, 0.1] {
+        set_power(&mut app, "CH001", power_kw);
+    }
+    assert_eq!(sent_for(&channels, "CH001"), vec![1.2, 0.1]);
+
+    // Quiet for 3 s, then the last value goes out again.
+    for _ in 0..3 * STEPS_PER_SEC {
+        app.update();
+    }
+    assert_eq!(sent_for(&channels, "CH001"), vec![0.1]);
+
+    // Without a policy every change is sent.
+    set_power(&mut app, "GRID", 4.0);
+    set_power(&mut app, "GRID", 4.1);
+    assert_eq!(sent_for(&channels, "GRID"), vec![4.0, 4.1]);
+}
+
+#[test]
+fn test_site_totals_sent_in_export_cycles() {
+    let (mut app, channels) = start_app();
+    let entity = |app: &App, id: &str| app.world().resource::<ExternalIdMap>().0[id];
+    let bat = entity(&app, "BAT001");
+    app.world_mut().get_mut::<CurrentMeterReading>(bat).unwrap().power_kw = -2.0;
+    set_power(&mut app, "GRID", 9.0);
+    set_power(&mut app, "CH001", 5.0);
+
+    let totals = channels.balancer_site_totals_receiver.try_iter().last().expect("No site totals sent");
+    assert_eq!(totals.assets_power_kw, 3.0);
+    assert_eq!(totals.power_kw_by_asset_type[&EAssetType::Charger], 5.0);
+    assert_eq!(totals.power_kw_by_asset_type[&EAssetType::Battery], -2.0);
+    assert_eq!(totals.grid_power_kw, Some(9.0));
+
+    // Nothing exported, no totals.
+    app.update();
+    assert!(channels.balancer_site_totals_receiver.try_iter().next().is_none());
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::balancer_comms_plugin::balancer_messages::{BalancerConnectorStatus, BalancerMeteringMessage};
+use ocpp_bevy_poc::common::types::EPhase;
+use ocpp_bevy_poc::core_asset_plugin::PhaseMeterReading;
+use ocpp_bevy_poc::modbus_protocol_plugin::ModbusResponse;
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::EOutgoingOcppMessage;
+use ocpp_bevy_poc::ocpp_protocol_plugin::{EGunStatusOcpp, OcppRequestFromAsset};
+use serde_json::json;
+use std::time::Duration;
+
+/// A dual-connector OCPP charger and a Modbus-metered battery.
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Phihong", "model": "AC_EU_Dual_V2" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+                { "type": "connectors", "count": 2, "max_current_a": 32.0, "phases": 3, "connector_type": "Type2" },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Amps", "profile_phases_in_ocpp_message": 3 },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        },
+        "Battery_Template": {
+            "asset_type": "Battery",
+            "components": [
+                { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" },
+                { "type": "metering_source", "source_type": "Modbus", "details": { "modbus": {
+                    "ip": "127.0.0.1", "port": 502, "unit_id": 1, "poll_interval_ms": 1000, "register_map_key": "regs" } } }
+            ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH001",
+            "template_id": "Charger_Template",
+            "instance_components": [ { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" } ]
+        },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] }
+    ],
+    "register_maps": {
+        "regs": { "registers": [ { "field": "power_kw", "address": 100, "kind": "Holding", "data_type": "F32" } ] }
+    }
+}"#;
+
+fn send(app: &mut App, channels: &AppExternalChannelEnds, action: &str, payload: serde_json::Value) {
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: "CH001".into(),
+        action: action.into(),
+        payload_json: payload.to_string(),
+        ocpp_message_id: format!("{action}-1"),
+    }).unwrap();
+    app.update();
+    app.update();
+}
+
+fn last_metering(channels: &AppExternalChannelEnds, external_id: &str) -> BalancerMeteringMessage {
+    channels.balancer_metering_receiver.try_iter()
+        .filter(|message| message.external_id == external_id)
+        .last()
+        .expect("No metering sent to the balancer")
+}
+
+#[test]
+fn test_phases_and_extra_measurands_exported_from_all_samples() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    send(&mut app, &channels, "BootNotification", json!({ "chargePointVendor": "Phihong", "chargePointModel": "AC_EU_Dual_V2" }));
+
+    let phase = |measurand: &str, phase: &str, value: &str, unit: &str| json!({ "value": value, "measurand": measurand, "phase": phase, "unit": unit });
+    send(&mut app, &channels, "MeterValues", json!({
+        "connectorId": 0,
+        "meterValue": [
+            { "timestamp": "2025-01-01T10:00:00Z", "sampledValue": [
+                { "value": "11000", "measurand": "Power.Active.Import", "unit": "W" },
+                phase("Current.Import", "L1", "16.1", "A"),
+                phase("Current.Import", "L2", "15.9", "A"),
+                phase("Current.Import", "L3", "16.0", "A")
+            ] },
+            { "timestamp": "2025-01-01T10:00:00Z", "sampledValue": [
+                phase("Voltage", "L1-N", "231.0", "V"),
+                phase("Voltage", "L2-N", "229.5", "V"),
+                phase("Voltage", "L3-N", "230.2", "V"),
+                { "value": "1200", "measurand": "Power.Reactive.Import", "unit": "var" },
+                { "value": "12345", "unit": "Wh" }
+            ] }
+        ]
+    }));
+
+    let metering = last_metering(&channels, "CH001");
+    assert_eq!((metering.power_kw, metering.energy_kwh), (11.0, 12.345));
+    assert_eq!(metering.measurements.reactive_power_kvar, Some(1.2));
+    assert_eq!(metering.measurements.phases, vec![
+        PhaseMeterReading { phase: EPhase::L1, power_kw: None, current_a: Some(16.1), voltage_v: Some(231.0) },
+        PhaseMeterReading { phase: EPhase::L2, power_kw: None, current_a: Some(15.9), voltage_v: Some(229.5) },
+        PhaseMeterReading { phase: EPhase::L3, power_kw: None, current_a: Some(16.0), voltage_v: Some(230.2) },
+    ]);
+
+    // Vehicle state of charge and the offered current belong to the connector.
+    send(&mut app, &channels, "MeterValues", json!({
+        "connectorId": 2,
+        "meterValue": [{ "timestamp": "2025-01-01T10:01:00Z", "sampledValue": [
+            { "value": "7.4", "measurand": "Power.Active.Import", "unit": "kW" },
+            { "value": "62", "measurand": "SoC", "unit": "Percent" },
+            { "value": "32", "measurand": "Current.Offered", "unit": "A" }
+        ] }]
+    }));
+    let metering = last_metering(&channels, "CH001");
+    assert_eq!(metering.connectors.len(), 1);
+    assert_eq!(metering.connectors[0].measurements.soc_percent, Some(62.0));
+    assert_eq!(metering.connectors[0].measurements.offered_current_a, Some(32.0));
+}
+
+#[test]
+fn test_vehicle_measurements_cleared_when_the_session_ends() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    send(&mut app, &channels, "BootNotification", json!({ "chargePointVendor": "Phihong", "chargePointModel": "AC_EU_Dual_V2" }));
+    send(&mut app, &channels, "StartTransaction", json!({ "connectorId": 2, "idTag": "TAG001", "meterStart": 0, "timestamp": "2025-01-01T10:00:00Z" }));
+    let transaction_id = channels.ocpp_to_asset_receiver.try_iter()
+        .find_map(|cmd| match cmd.message_type {
+            EOutgoingOcppMessage::StartTransactionResponse(conf) => Some(conf.transaction_id),
+            _ => None,
+        })
+        .expect("No StartTransaction reply");
+
+    send(&mut app, &channels, "MeterValues", json!({
+        "connectorId": 2,
+        "transactionId": transaction_id,
+        "meterValue": [{ "timestamp": chrono::Utc::now().to_rfc3339(), "sampledValue": [
+            { "value": "62", "measurand": "SoC", "unit": "Percent" },
+            { "value": "32", "measurand": "Current.Offered", "unit": "A" },
+            { "value": "16", "measurand": "Current.Import", "phase": "L1", "unit": "A" }
+        ] }]
+    }));
+    assert_eq!(last_metering(&channels, "CH001").connectors[0].measurements.soc_percent, Some(62.0));
+
+    send(&mut app, &channels, "StopTransaction", json!({ "transactionId": transaction_id, "meterStop": 5000, "timestamp": "2025-01-01T11:00:00Z" }));
+    let metering = last_metering(&channels, "CH001");
+    assert_eq!(metering.connectors[0].measurements, Default::default(), "the next vehicle must not inherit the last one's values");
+}
+
+#[test]
+fn test_connector_status_exported_with_metering() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    send(&mut app, &channels, "BootNotification", json!({ "chargePointVendor": "Phihong", "chargePointModel": "AC_EU_Dual_V2" }));
+    send(&mut app, &channels, "StatusNotification", json!({ "connectorId": 1, "errorCode": "NoError", "status": "Charging" }));
+
+    let metering = last_metering(&channels, "CH001");
+    assert_eq!(metering.connector_status, vec![
+        BalancerConnectorStatus { connector_id: 1, status: EGunStatusOcpp::Charging },
+        BalancerConnectorStatus { connector_id: 2, status: EGunStatusOcpp::Available },
+    ]);
+
+    let json = serde_json::to_value(&metering).unwrap();
+    assert_eq!(json["connector_status"][0], json!({ "connector_id": 1, "status": "Charging" }));
+    assert!(json.get("soc_percent").is_none(), "unreported measurements are left out");
+}
+
+#[test]
+fn test_modbus_soc_voltage_and_current_exported() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(250)));
+    let request = (0..20)
+        .find_map(|_| {
+            app.update();
+            channels.modbus_request_receiver.try_iter().next()
+        })
+        .expect("No Modbus poll");
+
+    let mut response = ModbusResponse::new(request.external_id.clone(), request.request_id, -20.0, 500.0, chrono::Utc::now());
+    response.soc_percent = Some(81.5);
+    response.voltage_v = Some(400.0);
+    response.current_a = Some(-50.0);
+    channels.modbus_response_sender.send(response).unwrap();
+    app.update();
+    app.update();
+
+    let metering = last_metering(&channels, "BAT001");
+    assert_eq!(metering.power_kw, -20.0);
+    assert_eq!(metering.measurements.soc_percent, Some(81.5));
+    assert_eq!(metering.measurements.voltage_v, Some(400.0));
+    assert_eq!(metering.measurements.current_a, Some(-50.0));
+}
@@ -0,0 +1,230 @@
+use crossbeam_channel::{unbounded, Receiver};
+use ocpp_bevy_poc::modbus_protocol_plugin::{
+    ModbusBridge, ModbusRegisterMap, ModbusRegisterMaps, ModbusRequest, ModbusResponse, ModbusWriteRegisterMap, ModbusWriteRequest, ModbusWriteResponse,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+use tokio_modbus::server::tcp::{accept_tcp_connection, Server};
+use tokio_modbus::server::Service;
+use tokio_modbus::{ExceptionCode, Request, Response};
+
+/// In-process stand-in for a battery's Modbus TCP interface.
+#[derive(Clone, Default)]
+struct DeviceStandIn {
+    holding_registers: Arc<Mutex<HashMap<u16, u16>>>,
+    input_registers: Arc<Mutex<HashMap<u16, u16>>>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl DeviceStandIn {
+    fn set_f32(table: &Mutex<HashMap<u16, u16>>, address: u16, value: f32) {
+        let bits = value.to_bits();
+        let mut registers = table.lock().unwrap();
+        registers.insert(address, (bits >> 16) as u16);
+        registers.insert(address + 1, bits as u16);
+    }
+
+    fn read(table: &Mutex<HashMap<u16, u16>>, address: u16, count: u16) -> Result<Vec<u16>, ExceptionCode> {
+        let registers = table.lock().unwrap();
+        (address..address + count)
+            .map(|a| registers.get(&a).copied().ok_or(ExceptionCode::IllegalDataAddress))
+            .collect()
+    }
+}
+
+impl Service for DeviceStandIn {
+    type Request = Request<'static>;
+    type Response = Response;
+    type Exception = ExceptionCode;
+    type Future = std::future::Ready<Result<Response, ExceptionCode>>;
+
+    fn call(&self, req: Self::Request) -> Self::Future {
+        std::future::ready(match req {
+            Request::ReadHoldingRegisters(address, count) => {
+                Self::read(&self.holding_registers, address, count).map(Response::ReadHoldingRegisters)
+            }
+            Request::ReadInputRegisters(address, count) => {
+                Self::read(&self.input_registers, address, count).map(Response::ReadInputRegisters)
+            }
+            Request::WriteSingleRegister(address, value) => {
+                self.holding_registers.lock().unwrap().insert(address, value);
+                Ok(Response::WriteSingleRegister(address, value))
+            }
+            Request::WriteMultipleRegisters(address, values) => {
+                let mut registers = self.holding_registers.lock().unwrap();
+                for (offset, value) in values.iter().enumerate() {
+                    registers.insert(address + offset as u16, *value);
+                }
+                Ok(Response::WriteMultipleRegisters(address, values.len() as u16))
+            }
+            _ => Err(ExceptionCode::IllegalFunction),
+        })
+    }
+}
+
+fn start_device(rt: &Runtime, device: DeviceStandIn) -> SocketAddr {
+    let listener = rt.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+    let addr = listener.local_addr().unwrap();
+    rt.spawn(async move {
+        let server = Server::new(listener);
+        let new_service = move |_peer| {
+            device.connections.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(device.clone()))
+        };
+        let on_connected = |stream, peer| {
+            let new_service = new_service.clone();
+            async move { accept_tcp_connection(stream, peer, new_service) }
+        };
+        let _ = server.serve(&on_connected, |err| eprintln!("stand-in error: {err}")).await;
+    });
+    addr
+}
+
+fn battery_register_maps() -> HashMap<String, ModbusRegisterMap> {
+    let map: ModbusRegisterMap = serde_json::from_str(r#"{
+        "registers": [
+            { "field": "power_kw", "address": 100, "kind": "Holding", "data_type": "F32" },
+            { "field": "energy_kwh", "address": 200, "kind": "Input", "data_type": "F32" },
+            { "field": "soc", "address": 300, "kind": "Input", "data_type": "U16", "scale": 0.1 }
+        ]
+    }"#).unwrap();
+    HashMap::from([("generic_battery_read_regs".to_string(), map)])
+}
+
+fn battery_write_register_maps() -> HashMap<String, ModbusWriteRegisterMap> {
+    let map: ModbusWriteRegisterMap = serde_json::from_str(r#"{
+        "setpoint": { "address": 400, "data_type": "I32", "scale": 1000.0, "sign_convention": "DischargePositive" },
+        "enable": { "address": 410, "value": 1 },
+        "run_mode": { "address": 411, "value": 2 }
+    }"#).unwrap();
+    HashMap::from([("generic_battery_write_regs".to_string(), map)])
+}
+
+/// Channel ends for a bridge running on `rt`.
+struct BridgeHarness {
+    request_tx: crossbeam_channel::Sender<ModbusRequest>,
+    response_rx: Receiver<ModbusResponse>,
+    write_request_tx: crossbeam_channel::Sender<ModbusWriteRequest>,
+    write_response_rx: Receiver<ModbusWriteResponse>,
+}
+
+fn start_bridge(rt: &Runtime) -> BridgeHarness {
+    let (request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    let (write_request_tx, write_request_rx) = unbounded();
+    let (write_response_tx, write_response_rx) = unbounded();
+    let bridge = ModbusBridge::new(ModbusRegisterMaps::new(battery_register_maps(), battery_write_register_maps()));
+    rt.spawn(bridge.run(request_rx, response_tx, write_request_rx, write_response_tx));
+    BridgeHarness { request_tx, response_rx, write_request_tx, write_response_rx }
+}
+
+fn battery_write(addr: SocketAddr, setpoint_kw: f32) -> ModbusWriteRequest {
+    ModbusWriteRequest {
+        external_id: "BAT001".to_string(),
+        request_id: 1,
+        write_register_map_key: "generic_battery_write_regs".to_string(),
+        ip: addr.ip().to_string(),
+        port: addr.port(),
+        unit_id: 1,
+        setpoint_kw,
+    }
+}
+
+fn battery_request(addr: SocketAddr, register_map_key: &str) -> ModbusRequest {
+    ModbusRequest::new("BAT001".to_string(), 1, register_map_key.to_string(), addr.ip().to_string(), addr.port(), 1)
+}
+
+fn recv(rx: &Receiver<ModbusResponse>) -> Option<ModbusResponse> {
+    rx.recv_timeout(Duration::from_secs(3)).ok()
+}
+
+#[test]
+fn test_bridge_reads_mapped_registers() {
+    let rt = Runtime::new().unwrap();
+    let device = DeviceStandIn::default();
+    DeviceStandIn::set_f32(&device.holding_registers, 100, -12.5);
+    DeviceStandIn::set_f32(&device.input_registers, 200, 1234.5);
+    device.input_registers.lock().unwrap().insert(300, 655);
+    let addr = start_device(&rt, device.clone());
+
+    let BridgeHarness { request_tx, response_rx, .. } = start_bridge(&rt);
+
+    request_tx.send(battery_request(addr, "generic_battery_read_regs")).unwrap();
+    let response = recv(&response_rx).expect("No Modbus response");
+    assert_eq!(response.external_id, "BAT001");
+    assert_eq!(response.request_id, 1);
+    assert_eq!(response.power_kw, -12.5);
+    assert_eq!(response.energy_kwh, 1234.5);
+    assert!((response.soc_percent.unwrap() - 65.5).abs() < 1e-4);
+    assert_eq!(response.voltage_v, None);
+
+    // A second poll reuses the open connection and sees the updated value.
+    DeviceStandIn::set_f32(&device.holding_registers, 100, 7.0);
+    request_tx.send(battery_request(addr, "generic_battery_read_regs")).unwrap();
+    let response = recv(&response_rx).expect("No second Modbus response");
+    assert_eq!(response.power_kw, 7.0);
+    assert_eq!(device.connections.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_bridge_skips_unknown_register_map_and_unreadable_registers() {
+    let rt = Runtime::new().unwrap();
+    let device = DeviceStandIn::default();
+    let addr = start_device(&rt, device.clone());
+
+    let BridgeHarness { request_tx, response_rx, .. } = start_bridge(&rt);
+
+    request_tx.send(battery_request(addr, "no_such_map")).unwrap();
+    // Registers not populated, so the device answers with an exception.
+    request_tx.send(battery_request(addr, "generic_battery_read_regs")).unwrap();
+    assert!(response_rx.recv_timeout(Duration::from_millis(500)).is_err());
+
+    // The exception does not tear the connection down.
+    DeviceStandIn::set_f32(&device.holding_registers, 100, 3.0);
+    DeviceStandIn::set_f32(&device.input_registers, 200, 10.0);
+    device.input_registers.lock().unwrap().insert(300, 500);
+    request_tx.send(battery_request(addr, "generic_battery_read_regs")).unwrap();
+    assert_eq!(recv(&response_rx).expect("No Modbus response").power_kw, 3.0);
+    assert_eq!(device.connections.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_bridge_writes_setpoint_and_acknowledges() {
+    let rt = Runtime::new().unwrap();
+    let device = DeviceStandIn::default();
+    let addr = start_device(&rt, device.clone());
+    let bridge = start_bridge(&rt);
+
+    // 5 kW charge; the device treats positive as discharge and takes watts.
+    bridge.write_request_tx.send(battery_write(addr, 5.0)).unwrap();
+    let ack = bridge.write_response_rx.recv_timeout(Duration::from_secs(3)).expect("No write ack");
+    assert_eq!(ack.external_id, "BAT001");
+    assert_eq!(ack.request_id, 1);
+    assert_eq!(ack.setpoint_kw, 5.0);
+    assert!(ack.success);
+
+    let registers = device.holding_registers.lock().unwrap();
+    assert_eq!(registers.get(&410), Some(&1));
+    assert_eq!(registers.get(&411), Some(&2));
+    let raw = ((registers[&400] as u32) << 16 | registers[&401] as u32) as i32;
+    assert_eq!(raw, -5000);
+}
+
+#[test]
+fn test_bridge_reports_failed_write_when_device_unreachable() {
+    let rt = Runtime::new().unwrap();
+    // Bind and immediately drop a listener to get a port nothing is listening on.
+    let addr = rt.block_on(TcpListener::bind("127.0.0.1:0")).unwrap().local_addr().unwrap();
+    let bridge = start_bridge(&rt);
+
+    bridge.write_request_tx.send(battery_write(addr, 5.0)).unwrap();
+    let ack = bridge.write_response_rx.recv_timeout(Duration::from_secs(5)).expect("No write ack");
+    assert!(!ack.success);
+    assert!(bridge.request_tx.send(battery_request(addr, "generic_battery_read_regs")).is_ok());
+    assert!(bridge.response_rx.recv_timeout(Duration::from_millis(200)).is_err());
+}
@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppExternalChannelEnds, AppMode};
+use ocpp_bevy_poc::balancer_comms_plugin::balancer_messages::BalancerSetpointMessage;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::LastAppliedSetpointKw;
+use ocpp_bevy_poc::modbus_protocol_plugin::{ModbusWriteRequest, ModbusWriteResponse};
+use std::time::Duration;
+
+const BATTERY_SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Generic_Battery_Template": {
+            "asset_type": "Battery",
+            "components": [
+                { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" },
+                { "type": "modbus_control_config", "ip": "127.0.0.1", "port": 5021, "unit_id": 1, "write_register_map_key": "battery_write_regs" }
+            ]
+        }
+    },
+    "assets": [
+        { "external_id": "BAT001", "template_id": "Generic_Battery_Template", "instance_components": [] }
+    ],
+    "write_register_maps": {
+        "battery_write_regs": { "setpoint": { "address": 200, "data_type": "I32", "scale": 1000.0 } }
+    }
+}"#;
+
+fn last_applied(app: &mut App, external_id: &str) -> f32 {
+    let entity = app.world().resource::<ExternalIdMap>().0[external_id];
+    app.world().get::<LastAppliedSetpointKw>(entity).unwrap().0
+}
+
+/// Update `steps` times with a clock advancing 100 ms per update, returning the writes sent meanwhile.
+fn run_steps(app: &mut App, writes: &crossbeam_channel::Receiver<ModbusWriteRequest>, steps: usize) -> Vec<ModbusWriteRequest> {
+    (0..steps).flat_map(|_| {
+        app.update();
+        writes.try_iter().collect::<Vec<_>>()
+    }).collect()
+}
+
+fn setpoints(writes: &[ModbusWriteRequest]) -> Vec<f32> {
+    writes.iter().map(|write| write.setpoint_kw).collect()
+}
+
+fn acknowledge(channels: &AppExternalChannelEnds, write: &ModbusWriteRequest, success: bool) {
+    channels.modbus_write_response_sender.send(ModbusWriteResponse {
+        external_id: write.external_id.clone(),
+        request_id: write.request_id,
+        setpoint_kw: write.setpoint_kw,
+        success,
+    }).unwrap();
+}
+
+/// The battery site with a manual clock, after startup.
+fn started_site() -> (App, AppExternalChannelEnds) {
+    let (mut app, channels) = setup_bevy_app(BATTERY_SITE_CONFIG_JSON.to_string(), AppMode::Headless, None).expect("valid site config");
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(100)));
+    app.update();
+    (app, channels)
+}
+
+fn send_target(channels: &AppExternalChannelEnds, target_power_kw: f32) {
+    channels.balancer_setpoint_sender.send(BalancerSetpointMessage {
+        external_id: "BAT001".into(),
+        target_power_kw,
+        ..Default::default()
+    }).unwrap();
+}
+
+#[test]
+fn test_setpoint_applied_only_after_write_acknowledged() {
+    let (mut app, channels) = started_site();
+    let writes = &channels.modbus_write_request_receiver;
+
+    send_target(&channels, -20.0);
+    let sent = run_steps(&mut app, writes, 2);
+    let [write] = sent.as_slice() else { panic!("Expected one ModbusWriteRequest, got {sent:?}") };
+    assert_eq!(write.external_id, "BAT001");
+    assert_eq!(write.write_register_map_key, "battery_write_regs");
+    assert_eq!((write.ip.as_str(), write.port, write.unit_id), ("127.0.0.1", 5021, 1));
+    assert_eq!(write.setpoint_kw, -20.0);
+    assert_eq!(last_applied(&mut app, "BAT001"), 0.0);
+
+    // A failed write leaves the last applied value alone.
+    acknowledge(&channels, write, false);
+    app.update();
+    assert_eq!(last_applied(&mut app, "BAT001"), 0.0);
+
+    let retried = run_steps(&mut app, writes, 10);
+    acknowledge(&channels, &retried[0], true);
+    app.update();
+    assert_eq!(last_applied(&mut app, "BAT001"), -20.0);
+}
+
+#[test]
+fn test_failed_and_unanswered_writes_are_retried_until_acknowledged() {
+    let (mut app, channels) = started_site();
+    let writes = &channels.modbus_write_request_receiver;
+
+    send_target(&channels, -20.0);
+    let first = run_steps(&mut app, writes, 2);
+    assert_eq!(setpoints(&first), vec![-20.0]);
+
+    // A failed write is re-sent after the first retry backoff (500 ms), not straight away
+    acknowledge(&channels, &first[0], false);
+    assert!(run_steps(&mut app, writes, 4).is_empty());
+    assert_eq!(setpoints(&run_steps(&mut app, writes, 2)), vec![-20.0]);
+
+    // An unanswered write is re-sent once the request timeout (4 s) and the next backoff (1 s) have passed
+    assert!(run_steps(&mut app, writes, 45).is_empty());
+    let last = run_steps(&mut app, writes, 10);
+    assert_eq!(setpoints(&last), vec![-20.0]);
+
+    acknowledge(&channels, &last[0], true);
+    assert!(run_steps(&mut app, writes, 100).is_empty());
+    assert_eq!(last_applied(&mut app, "BAT001"), -20.0);
+}
+
+#[test]
+fn test_target_returning_to_last_applied_while_write_in_flight_is_written() {
+    let (mut app, channels) = started_site();
+    let writes = &channels.modbus_write_request_receiver;
+
+    send_target(&channels, -20.0);
+    let first = run_steps(&mut app, writes, 2);
+    assert_eq!(setpoints(&first), vec![-20.0]);
+    acknowledge(&channels, &first[0], true);
+    assert!(run_steps(&mut app, writes, 2).is_empty());
+
+    // The target goes to -10 kW and back to -20 kW before the -10 kW write is acknowledged
+    send_target(&channels, -10.0);
+    let second = run_steps(&mut app, writes, 2);
+    assert_eq!(setpoints(&second), vec![-10.0]);
+    send_target(&channels, -20.0);
+    let third = run_steps(&mut app, writes, 2);
+    assert_eq!(setpoints(&third), vec![-20.0]);
+
+    acknowledge(&channels, &second[0], true);
+    acknowledge(&channels, &third[0], true);
+    assert!(run_steps(&mut app, writes, 100).is_empty());
+    assert_eq!(last_applied(&mut app, "BAT001"), -20.0);
+}
+
+#[test]
+fn test_late_acknowledgement_of_superseded_write_is_discarded() {
+    let (mut app, channels) = started_site();
+    let writes = &channels.modbus_write_request_receiver;
+
+    send_target(&channels, -20.0);
+    let stale = run_steps(&mut app, writes, 2);
+    // The -20 kW write times out, and the target moves on to -10 kW before it is retried
+    assert!(run_steps(&mut app, writes, 42).is_empty());
+    send_target(&channels, -10.0);
+    let current = run_steps(&mut app, writes, 2);
+    assert_eq!(setpoints(&current), vec![-10.0]);
+    acknowledge(&channels, &current[0], true);
+    app.update();
+    assert_eq!(last_applied(&mut app, "BAT001"), -10.0);
+
+    // The device acknowledged -10 kW last, so a late acknowledgement of -20 kW must not roll the setpoint back
+    acknowledge(&channels, &stale[0], true);
+    assert!(run_steps(&mut app, writes, 100).is_empty());
+    assert_eq!(last_applied(&mut app, "BAT001"), -10.0);
+}
@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppMode};
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::common::types::EOperationalStatus;
+use ocpp_bevy_poc::core_asset_plugin::CurrentMeterReading;
+use ocpp_bevy_poc::modbus_protocol_plugin::{ModbusRequest, ModbusResponse};
+use std::time::Duration;
+
+const STEP: Duration = Duration::from_millis(100);
+
+/// Two batteries polled every second and a meter polled every 3 seconds, told apart by unit id.
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Battery_Template": {
+            "asset_type": "Battery",
+            "components": [
+                { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" }
+            ]
+        },
+        "Meter_Template": {
+            "asset_type": "GridConnection",
+            "components": [
+                { "type": "asset_info", "make": "Generic", "model": "Grid Meter" },
+                { "type": "metering_source", "source_type": "Modbus", "details": { "modbus": {
+                    "ip": "127.0.0.1", "port": 502, "unit_id": 30, "poll_interval_ms": 3000, "register_map_key": "regs" } } }
+            ]
+        }
+    },
+    "assets": [
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [
+            { "type": "metering_source", "source_type": "Modbus", "details": { "modbus": {
+                "ip": "127.0.0.1", "port": 502, "unit_id": 1, "poll_interval_ms": 1000, "register_map_key": "regs" } } }
+        ] },
+        { "external_id": "BAT002", "template_id": "Battery_Template", "instance_components": [
+            { "type": "metering_source", "source_type": "Modbus", "details": { "modbus": {
+                "ip": "127.0.0.1", "port": 502, "unit_id": 2, "poll_interval_ms": 1000, "register_map_key": "regs" } } }
+        ] },
+        { "external_id": "MTR001", "template_id": "Meter_Template", "instance_components": [] }
+    ],
+    "register_maps": {
+        "regs": { "registers": [ { "field": "power_kw", "address": 100, "kind": "Holding", "data_type": "F32" } ] }
+    }
+}"#;
+
+/// Start the app with a manual clock advancing `STEP` per update.
+fn start_app() -> (App, Receiver<ModbusRequest>, Sender<ModbusResponse>) {
+    let (mut app, channels) = setup_bevy_app(SITE_CONFIG_JSON.to_string(), AppMode::Headless, None).expect("valid site config");
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(STEP));
+    app.update();
+    (app, channels.modbus_request_receiver.clone(), channels.modbus_response_sender.clone())
+}
+
+/// Update until a read for `unit_id` is sent, returning it and the number of updates taken.
+fn next_request(app: &mut App, requests: &Receiver<ModbusRequest>, unit_id: u8) -> (ModbusRequest, usize) {
+    for step in 1..=200 {
+        app.update();
+        if let Some(request) = requests.try_iter().find(|request| request.unit_id == unit_id) {
+            return (request, step);
+        }
+    }
+    panic!("No Modbus request for unit {unit_id}");
+}
+
+fn answer(responses: &Sender<ModbusResponse>, request: &ModbusRequest, request_id: u64, power_kw: f32) {
+    responses.send(ModbusResponse::new(request.external_id.clone(), request_id, power_kw, 10.0, chrono::Utc::now())).unwrap();
+}
+
+fn asset_state(app: &App, external_id: &str) -> (f32, EOperationalStatus) {
+    let entity = app.world().resource::<ExternalIdMap>().0[external_id];
+    let reading = app.world().get::<CurrentMeterReading>(entity).unwrap();
+    (reading.power_kw, *app.world().get::<EOperationalStatus>(entity).unwrap())
+}
+
+/// Advance the clock by `steps` updates, answering every read, and return the unit ids polled on each update.
+fn run_steps(app: &mut App, requests: &Receiver<ModbusRequest>, responses: &Sender<ModbusResponse>, steps: usize) -> Vec<Vec<u8>> {
+    (0..steps)
+        .map(|_| {
+            app.update();
+            requests
+                .try_iter()
+                .map(|request| {
+                    answer(responses, &request, request.request_id, 1.0);
+                    request.unit_id
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn poll_count(polls: &[Vec<u8>], unit_id: u8) -> usize {
+    polls.iter().flatten().filter(|&&id| id == unit_id).count()
+}
+
+#[test]
+fn test_assets_polled_at_their_own_interval() {
+    let (mut app, requests, responses) = start_app();
+
+    // Twelve seconds of simulated time.
+    let polls = run_steps(&mut app, &requests, &responses, 120);
+    assert_eq!(poll_count(&polls, 1), 12);
+    assert_eq!(poll_count(&polls, 2), 12);
+    assert_eq!(poll_count(&polls, 30), 4);
+}
+
+#[test]
+fn test_assets_sharing_an_interval_are_staggered() {
+    let (mut app, requests, responses) = start_app();
+
+    let polls = run_steps(&mut app, &requests, &responses, 20);
+    let first_poll = |unit_id: u8| polls.iter().position(|step| step.contains(&unit_id)).expect("Asset never polled");
+    assert_ne!(first_poll(1), first_poll(2));
+    // The first poll always lands within one interval.
+    assert!(first_poll(1) < 10 && first_poll(2) < 10);
+    assert!(first_poll(30) < 30);
+
+    // Once staggered, the two batteries keep polling on separate updates.
+    let polls = run_steps(&mut app, &requests, &responses, 50);
+    assert!(polls.iter().all(|step| !(step.contains(&1) && step.contains(&2))));
+}
+
+#[test]
+fn test_no_polls_without_time_passing() {
+    let (mut app, channels) = setup_bevy_app(SITE_CONFIG_JSON.to_string(), AppMode::Headless, None).expect("valid site config");
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+    for _ in 0..20 {
+        app.update();
+    }
+    assert!(channels.modbus_request_receiver.try_recv().is_err());
+}
+
+#[test]
+fn test_responses_correlated_by_external_id_and_request_id() {
+    let (mut app, requests, responses) = start_app();
+
+    let (request, _) = next_request(&mut app, &requests, 1);
+    assert_eq!(request.external_id, "BAT001");
+
+    // An answer to some other read is discarded.
+    answer(&responses, &request, request.request_id + 100, 99.0);
+    app.update();
+    assert_eq!(asset_state(&app, "BAT001"), (0.0, EOperationalStatus::Initializing));
+
+    answer(&responses, &request, request.request_id, 7.5);
+    app.update();
+    assert_eq!(asset_state(&app, "BAT001"), (7.5, EOperationalStatus::Online));
+
+    // The next poll gets a fresh id.
+    let (next, _) = next_request(&mut app, &requests, 1);
+    assert!(next.request_id > request.request_id);
+}
+
+#[test]
+fn test_timed_out_reads_retried_with_backoff_then_asset_goes_offline_and_recovers() {
+    let (mut app, requests, responses) = start_app();
+
+    let (request, _) = next_request(&mut app, &requests, 1);
+    answer(&responses, &request, request.request_id, 1.0);
+    app.update();
+
+    // Leave the next read unanswered: 4 s timeout, then retries after 0.5 s and 1 s backoff.
+    let (first, _) = next_request(&mut app, &requests, 1);
+    let (retry_1, after_first) = next_request(&mut app, &requests, 1);
+    assert!((44..=46).contains(&after_first), "first retry after {after_first} steps");
+    let (retry_2, after_retry_1) = next_request(&mut app, &requests, 1);
+    assert!((49..=51).contains(&after_retry_1), "second retry after {after_retry_1} steps");
+    assert!(first.request_id < retry_1.request_id && retry_1.request_id < retry_2.request_id);
+    assert_eq!(asset_state(&app, "BAT001").1, EOperationalStatus::Online);
+
+    // The third consecutive timeout takes the asset offline; polling carries on at the normal interval.
+    let (next_poll, _) = next_request(&mut app, &requests, 1);
+    assert_eq!(asset_state(&app, "BAT001").1, EOperationalStatus::Offline);
+
+    // A late answer to a timed-out read does not count as recovery.
+    answer(&responses, &retry_2, retry_2.request_id, 2.0);
+    app.update();
+    assert_eq!(asset_state(&app, "BAT001"), (1.0, EOperationalStatus::Offline));
+
+    answer(&responses, &next_poll, next_poll.request_id, 3.0);
+    app.update();
+    assert_eq!(asset_state(&app, "BAT001"), (3.0, EOperationalStatus::Online));
+}
@@ -0,0 +1,111 @@
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppMode};
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::modbus_protocol_plugin::{
+    decode_register_value, encode_register_value, encode_setpoint, ModbusRegisterMapping, ModbusSetpointRegister,
+};
+
+fn mapping(json: &str) -> ModbusRegisterMapping {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn test_decode_16_bit_values() {
+    let unsigned = mapping(r#"{ "field": "soc", "address": 0, "kind": "Input", "data_type": "U16", "scale": 0.1 }"#);
+    assert!((decode_register_value(&unsigned, &[0x0293]).unwrap() - 65.9).abs() < 1e-9);
+
+    let signed = mapping(r#"{ "field": "power_kw", "address": 0, "kind": "Holding", "data_type": "I16" }"#);
+    assert_eq!(decode_register_value(&signed, &[0xFFFE]), Some(-2.0));
+}
+
+#[test]
+fn test_decode_32_bit_word_and_byte_orders() {
+    // 0x0001_E240 = 123456
+    let high_first = mapping(r#"{ "field": "energy_kwh", "address": 0, "kind": "Holding", "data_type": "U32" }"#);
+    assert_eq!(decode_register_value(&high_first, &[0x0001, 0xE240]), Some(123456.0));
+
+    let low_first = mapping(r#"{ "field": "energy_kwh", "address": 0, "kind": "Holding", "data_type": "U32", "word_order": "LowWordFirst" }"#);
+    assert_eq!(decode_register_value(&low_first, &[0xE240, 0x0001]), Some(123456.0));
+
+    let swapped_bytes = mapping(r#"{ "field": "energy_kwh", "address": 0, "kind": "Holding", "data_type": "U32", "byte_order": "LittleEndian" }"#);
+    assert_eq!(decode_register_value(&swapped_bytes, &[0x0100, 0x40E2]), Some(123456.0));
+
+    // -5000 W reported in watts, converted to kW
+    let signed = mapping(r#"{ "field": "power_kw", "address": 0, "kind": "Holding", "data_type": "I32", "scale": 0.001 }"#);
+    let bits = (-5000i32) as u32;
+    assert_eq!(decode_register_value(&signed, &[(bits >> 16) as u16, bits as u16]), Some(-5.0));
+}
+
+#[test]
+fn test_decode_float_with_offset() {
+    let float = mapping(r#"{ "field": "voltage", "address": 0, "kind": "Input", "data_type": "F32", "offset": -0.5 }"#);
+    let bits = 230.5f32.to_bits();
+    assert_eq!(decode_register_value(&float, &[(bits >> 16) as u16, bits as u16]), Some(230.0));
+}
+
+#[test]
+fn test_decode_rejects_wrong_register_count() {
+    let float = mapping(r#"{ "field": "voltage", "address": 0, "kind": "Input", "data_type": "F32" }"#);
+    assert_eq!(decode_register_value(&float, &[0x4366]), None);
+}
+
+#[test]
+fn test_assets_with_unknown_register_map_are_not_spawned() {
+    let site_config_json = r#"{
+        "asset_templates": {
+            "Battery_Template": {
+                "asset_type": "Battery",
+                "components": [
+                    { "type": "asset_info", "make": "Generic", "model": "ESS" }
+                ]
+            }
+        },
+        "assets": [
+            {
+                "external_id": "BAT001",
+                "template_id": "Battery_Template",
+                "instance_components": [
+                    { "type": "metering_source", "source_type": "Modbus", "details": { "modbus": {
+                        "ip": "127.0.0.1", "port": 502, "unit_id": 1, "poll_interval_ms": 1000, "register_map_key": "known_regs" } } }
+                ]
+            },
+            {
+                "external_id": "BAT002",
+                "template_id": "Battery_Template",
+                "instance_components": [
+                    { "type": "metering_source", "source_type": "Modbus", "details": { "modbus": {
+                        "ip": "127.0.0.1", "port": 502, "unit_id": 2, "poll_interval_ms": 1000, "register_map_key": "missing_regs" } } }
+                ]
+            }
+        ],
+        "register_maps": {
+            "known_regs": { "registers": [ { "field": "power_kw", "address": 0, "kind": "Holding", "data_type": "I16" } ] }
+        }
+    }"#.to_string();
+
+    let (mut app, _channels) = setup_bevy_app(site_config_json, AppMode::Headless, None).expect("valid site config");
+    app.update();
+
+    let id_map = app.world().resource::<ExternalIdMap>();
+    assert!(id_map.0.contains_key("BAT001"));
+    assert!(!id_map.0.contains_key("BAT002"));
+}
+
+#[test]
+fn test_encode_setpoint_applies_sign_convention_and_scale() {
+    let discharge_positive: ModbusSetpointRegister =
+        serde_json::from_str(r#"{ "address": 0, "data_type": "I16", "scale": 10.0, "sign_convention": "DischargePositive" }"#).unwrap();
+    // 2.5 kW charge -> -25 in 0.1 kW units
+    assert_eq!(encode_setpoint(&discharge_positive, 2.5), vec![(-25i16) as u16]);
+
+    let charge_positive: ModbusSetpointRegister =
+        serde_json::from_str(r#"{ "address": 0, "data_type": "I32", "scale": 1000.0, "word_order": "LowWordFirst" }"#).unwrap();
+    let bits = (-7500i32) as u32;
+    assert_eq!(encode_setpoint(&charge_positive, -7.5), vec![bits as u16, (bits >> 16) as u16]);
+}
+
+#[test]
+fn test_encode_round_trips_through_decode() {
+    let mapping = mapping(r#"{ "field": "power_kw", "address": 0, "kind": "Holding", "data_type": "F32", "byte_order": "LittleEndian", "word_order": "LowWordFirst" }"#);
+    let words = encode_register_value(mapping.data_type, mapping.word_order, mapping.byte_order, -12.25);
+    assert_eq!(decode_register_value(&mapping, &words), Some(-12.25));
+}
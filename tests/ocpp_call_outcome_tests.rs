@@ -0,0 +1,148 @@
+mod common;
+
+use bevy::prelude::*;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::LastAppliedSetpointKw;
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::EOutgoingOcppMessage;
+use ocpp_bevy_poc::ocpp_protocol_plugin::{
+    EOcppCallOutcome, EOcppCallResponse, OcppCallOutcomes, OcppCallTimeout, OcppCommandToAsset, OcppPendingRequests,
+    OcppResponseFromAsset,
+};
+use std::time::Duration;
+
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Phihong_AC_EU_Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Phihong", "model": "AC_EU_Dual_V2" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Amps", "profile_phases_in_ocpp_message": 3 },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH001",
+            "template_id": "Phihong_AC_EU_Charger_Template",
+            "instance_components": [
+                { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" }
+            ]
+        }
+    ]
+}"#;
+
+/// Start the app and boot CH001, returning the commands sent during initialization.
+fn boot_charger() -> (App, AppExternalChannelEnds, Vec<OcppCommandToAsset>) {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    let init_commands = common::boot_charger(&mut app, &channels, "CH001");
+    (app, channels, init_commands)
+}
+
+fn send_setpoint(app: &mut App, channels: &AppExternalChannelEnds, target_power_kw: f32) -> String {
+    common::charging_profiles(common::send_setpoint(app, channels, "CH001", target_power_kw))
+        .into_iter()
+        .next()
+        .expect("Expected a SetChargingProfileRequest").0
+}
+
+fn reply(app: &mut App, channels: &AppExternalChannelEnds, ocpp_message_id: &str, response: EOcppCallResponse) {
+    channels.ocpp_response_from_asset_sender.send(OcppResponseFromAsset {
+        charge_point_id: "CH001".into(),
+        ocpp_message_id: ocpp_message_id.into(),
+        response,
+    }).unwrap();
+    app.update();
+}
+
+fn status(status: &str) -> EOcppCallResponse {
+    EOcppCallResponse::Result { payload_json: format!(r#"{{"status":"{status}"}}"#) }
+}
+
+fn charger_state(app: &App) -> (OcppCallOutcomes, f32) {
+    let entity = app.world().resource::<ExternalIdMap>().0["CH001"];
+    let outcomes = app.world().get::<OcppCallOutcomes>(entity).unwrap().clone();
+    (outcomes, app.world().get::<LastAppliedSetpointKw>(entity).unwrap().0)
+}
+
+#[test]
+fn test_last_applied_setpoint_follows_profile_acceptance() {
+    let (mut app, channels, _) = boot_charger();
+
+    let rejected_id = send_setpoint(&mut app, &channels, 10.0);
+    assert_eq!(charger_state(&app).1, 0.0);
+    reply(&mut app, &channels, &rejected_id, status("Rejected"));
+    let (outcomes, last_applied) = charger_state(&app);
+    assert_eq!(outcomes.set_charging_profile, Some(EOcppCallOutcome::Rejected));
+    assert_eq!(last_applied, 0.0);
+
+    let accepted_id = send_setpoint(&mut app, &channels, 7.0);
+    reply(&mut app, &channels, &accepted_id, status("Accepted"));
+    let (outcomes, last_applied) = charger_state(&app);
+    assert_eq!(outcomes.set_charging_profile, Some(EOcppCallOutcome::Accepted));
+    assert_eq!(last_applied, 7.0);
+
+    // A duplicate reply no longer matches a pending request.
+    reply(&mut app, &channels, &rejected_id, status("Accepted"));
+    assert_eq!(charger_state(&app).1, 7.0);
+}
+
+#[test]
+fn test_target_returning_to_last_applied_while_profile_pending_is_sent() {
+    let (mut app, channels, _) = boot_charger();
+    let first_id = send_setpoint(&mut app, &channels, 7.0);
+    reply(&mut app, &channels, &first_id, status("Accepted"));
+    assert_eq!(charger_state(&app).1, 7.0);
+
+    // The target goes to 10 kW and back to 7 kW before the 10 kW profile is answered
+    let superseded_id = send_setpoint(&mut app, &channels, 10.0);
+    let restored_id = send_setpoint(&mut app, &channels, 7.0);
+    reply(&mut app, &channels, &superseded_id, status("Accepted"));
+    assert_eq!(charger_state(&app).1, 7.0);
+    reply(&mut app, &channels, &restored_id, status("Accepted"));
+    assert_eq!(charger_state(&app).1, 7.0);
+}
+
+#[test]
+fn test_configuration_outcomes_recorded_per_key() {
+    let (mut app, channels, init_commands) = boot_charger();
+    let config_id = |key: &str| init_commands.iter()
+        .find(|cmd| matches!(&cmd.message_type, EOutgoingOcppMessage::ChangeConfigurationRequest(req) if req.key == key))
+        .and_then(|cmd| cmd.ocpp_message_id.clone())
+        .unwrap();
+
+    reply(&mut app, &channels, &config_id("HeartbeatInterval"), status("Accepted"));
+    reply(&mut app, &channels, &config_id("MeterValueSampleInterval"), status("RebootRequired"));
+    reply(&mut app, &channels, &config_id("LocalAuthorizeOffline"), EOcppCallResponse::Error {
+        error_code: "NotSupported".into(),
+        error_description: "Unknown key".into(),
+    });
+
+    let (outcomes, _) = charger_state(&app);
+    assert_eq!(outcomes.change_configuration["HeartbeatInterval"], EOcppCallOutcome::Accepted);
+    assert_eq!(outcomes.change_configuration["MeterValueSampleInterval"], EOcppCallOutcome::RebootRequired);
+    assert_eq!(outcomes.change_configuration["LocalAuthorizeOffline"], EOcppCallOutcome::CallError("NotSupported".into()));
+    assert!(outcomes.reboot_required());
+}
+
+#[test]
+fn test_unanswered_calls_time_out() {
+    let (mut app, channels, _) = boot_charger();
+    app.insert_resource(OcppCallTimeout(Duration::from_secs(2)));
+
+    let message_id = send_setpoint(&mut app, &channels, 10.0);
+    for _ in 0..12 {
+        app.update();
+    }
+
+    let (outcomes, last_applied) = charger_state(&app);
+    assert_eq!(outcomes.set_charging_profile, Some(EOcppCallOutcome::TimedOut));
+    assert_eq!(last_applied, 0.0);
+    assert!(app.world().resource::<OcppPendingRequests>().0.is_empty());
+
+    // A reply after the timeout is not applied.
+    reply(&mut app, &channels, &message_id, status("Accepted"));
+    assert_eq!(charger_state(&app).1, 0.0);
+}
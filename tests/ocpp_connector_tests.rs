@@ -0,0 +1,162 @@
+mod common;
+
+use bevy::prelude::*;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::{ConnectorMeterReading, LastAppliedSetpointKw};
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::SetChargingProfileReqPayload;
+use ocpp_bevy_poc::ocpp_protocol_plugin::{
+    EConnectorType, EGunStatusOcpp, EOcppCallResponse, Guns, OcppCommandToAsset, OcppRequestFromAsset, OcppResponseFromAsset,
+};
+use serde_json::json;
+
+/// A dual-socket charger whose Type 2 connectors are limited to 16 A on three phases (11.04 kW each).
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Phihong_AC_EU_Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Phihong", "model": "AC_EU_Dual_V2" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+                { "type": "connectors", "count": 2, "max_current_a": 16.0, "phases": 3, "connector_type": "Type2" },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Amps", "profile_phases_in_ocpp_message": 3 },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH001",
+            "template_id": "Phihong_AC_EU_Charger_Template",
+            "instance_components": [
+                { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" }
+            ]
+        }
+    ]
+}"#;
+
+fn send(app: &mut App, channels: &AppExternalChannelEnds, message_id: &str, action: &str, payload: serde_json::Value) {
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: "CH001".into(),
+        action: action.into(),
+        payload_json: payload.to_string(),
+        ocpp_message_id: message_id.into(),
+    }).unwrap();
+    app.update();
+    app.update();
+}
+
+fn boot_charger() -> (App, AppExternalChannelEnds) {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    common::boot_charger(&mut app, &channels, "CH001");
+    (app, channels)
+}
+
+fn start_transaction(app: &mut App, channels: &AppExternalChannelEnds, connector_id: u32, id_tag: &str) {
+    let start = json!({ "connectorId": connector_id, "idTag": id_tag, "meterStart": 0, "timestamp": "2025-01-01T10:00:00Z" });
+    send(app, channels, &format!("start-{connector_id}"), "StartTransaction", start);
+}
+
+/// `commands`' profiles, with their message ids, ordered by connector.
+fn by_connector(commands: Vec<OcppCommandToAsset>) -> Vec<(String, SetChargingProfileReqPayload)> {
+    let mut profiles = common::charging_profiles(commands);
+    profiles.sort_by_key(|(_, req)| req.connector_id);
+    profiles
+}
+
+/// Profiles sent since the last call, with their message ids, ordered by connector.
+fn profiles_sent(channels: &AppExternalChannelEnds) -> Vec<(String, SetChargingProfileReqPayload)> {
+    by_connector(channels.ocpp_to_asset_receiver.try_iter().collect())
+}
+
+fn limit(profile: &SetChargingProfileReqPayload) -> f32 {
+    profile.cs_charging_profiles.charging_schedule.charging_schedule_period[0].limit
+}
+
+fn send_setpoint(app: &mut App, channels: &AppExternalChannelEnds, target_power_kw: f32) -> Vec<(String, SetChargingProfileReqPayload)> {
+    by_connector(common::send_setpoint(app, channels, "CH001", target_power_kw))
+}
+
+fn accept(app: &mut App, channels: &AppExternalChannelEnds, message_id: &str, status: &str) {
+    channels.ocpp_response_from_asset_sender.send(OcppResponseFromAsset {
+        charge_point_id: "CH001".into(),
+        ocpp_message_id: message_id.into(),
+        response: EOcppCallResponse::Result { payload_json: format!(r#"{{"status":"{status}"}}"#) },
+    }).unwrap();
+    app.update();
+}
+
+fn charger<T: Component + Clone>(app: &App) -> T {
+    let entity = app.world().resource::<ExternalIdMap>().0["CH001"];
+    app.world().get::<T>(entity).unwrap().clone()
+}
+
+#[test]
+fn test_guns_built_from_connectors_config_and_tracked_per_connector() {
+    let (mut app, channels) = boot_charger();
+
+    let guns = charger::<Guns>(&app).0;
+    assert_eq!(guns.iter().map(|gun| gun.connector_id).collect::<Vec<_>>(), vec![1, 2]);
+    assert!(guns.iter().all(|gun| gun.connector_type == EConnectorType::Type2 && gun.max_current_a == Some(16.0) && gun.phases == Some(3)));
+
+    send(&mut app, &channels, "status-2", "StatusNotification", json!({ "connectorId": 2, "errorCode": "NoError", "status": "Charging" }));
+    let guns = charger::<Guns>(&app).0;
+    assert_eq!((guns[0].status.clone(), guns[1].status.clone()), (EGunStatusOcpp::Available, EGunStatusOcpp::Charging));
+
+    let meter_values = |connector_id: u32, watts: u32, wh: u32| json!({
+        "connectorId": connector_id,
+        "meterValue": [{ "timestamp": "2025-01-01T10:00:00Z", "sampledValue": [
+            { "value": watts.to_string(), "measurand": "Power.Active.Import", "unit": "W" },
+            { "value": wh.to_string(), "measurand": "Energy.Active.Import.Register", "unit": "Wh" }
+        ] }]
+    });
+    send(&mut app, &channels, "mv-1", "MeterValues", meter_values(1, 4000, 1000));
+    send(&mut app, &channels, "mv-2", "MeterValues", meter_values(2, 7000, 3000));
+
+    let metering = channels.balancer_metering_receiver.try_iter().last().expect("No metering sent to the balancer");
+    assert_eq!(metering.power_kw, 11.0);
+    assert_eq!(metering.energy_kwh, 4.0);
+    let timestamp = "2025-01-01T10:00:00Z".parse().unwrap();
+    assert_eq!(metering.connectors, vec![
+        ConnectorMeterReading { connector_id: 1, power_kw: 4.0, energy_kwh: 1.0, timestamp, ..Default::default() },
+        ConnectorMeterReading { connector_id: 2, power_kw: 7.0, energy_kwh: 3.0, timestamp, ..Default::default() },
+    ]);
+}
+
+#[test]
+fn test_setpoint_split_across_active_connectors_within_their_rating() {
+    let (mut app, channels) = boot_charger();
+
+    // No sessions: the whole setpoint is the charger-wide default.
+    let profiles = send_setpoint(&mut app, &channels, 15.0);
+    assert_eq!(profiles.len(), 1);
+    assert_eq!(profiles[0].1.connector_id, 0);
+
+    // One session takes the setpoint up to its connector's 16 A.
+    start_transaction(&mut app, &channels, 1, "TAG001");
+    let profiles = profiles_sent(&channels);
+    assert_eq!(profiles.len(), 1);
+    assert_eq!((profiles[0].1.connector_id, limit(&profiles[0].1)), (1, 16.0));
+
+    // A second session splits it evenly.
+    start_transaction(&mut app, &channels, 2, "TAG002");
+    let profiles = profiles_sent(&channels);
+    assert_eq!(profiles.iter().map(|(_, req)| req.connector_id).collect::<Vec<_>>(), vec![1, 2]);
+    let expected_amps = 7500.0 / (230.0 * 3.0);
+    assert!(profiles.iter().all(|(_, req)| (limit(req) - expected_amps).abs() < 0.01));
+    let allocated: Vec<f32> = charger::<Guns>(&app).0.iter().map(|gun| gun.allocated_kw).collect();
+    assert_eq!(allocated, vec![7.5, 7.5]);
+
+    // The setpoint counts as applied only once every connector's profile is accepted.
+    accept(&mut app, &channels, &profiles[0].0, "Accepted");
+    assert_eq!(charger::<LastAppliedSetpointKw>(&app).0, 0.0);
+    accept(&mut app, &channels, &profiles[1].0, "Accepted");
+    assert_eq!(charger::<LastAppliedSetpointKw>(&app).0, 15.0);
+
+    // Above both ratings each connector is capped at 16 A.
+    let profiles = send_setpoint(&mut app, &channels, 40.0);
+    assert!(profiles.iter().all(|(_, req)| limit(req) == 16.0));
+    accept(&mut app, &channels, &profiles[0].0, "Accepted");
+    accept(&mut app, &channels, &profiles[1].0, "Rejected");
+    assert_eq!(charger::<LastAppliedSetpointKw>(&app).0, 15.0);
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use bevy::prelude::*;
+use common::run_secs;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::common::types::EOperationalStatus;
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::EOutgoingOcppMessage;
+use ocpp_bevy_poc::ocpp_protocol_plugin::{
+    EOcppCallResponse, OcppCommandToAsset, OcppConnectionState, OcppHeartbeatPolicy, OcppRequestFromAsset, OcppResponseFromAsset,
+};
+use std::time::Duration;
+
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Alfen_AC_EU_Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 1 },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Watts", "profile_phases_in_ocpp_message": 1 },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH003",
+            "template_id": "Alfen_AC_EU_Charger_Template",
+            "instance_components": [
+                { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH003" }
+            ]
+        }
+    ]
+}"#;
+
+fn send_request(channels: &AppExternalChannelEnds, message_id: &str, action: &str, payload_json: &str) {
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: "CH003".into(),
+        action: action.into(),
+        payload_json: payload_json.into(),
+        ocpp_message_id: message_id.into(),
+    }).unwrap();
+}
+
+/// Boot CH003 with a 2 s heartbeat interval that goes offline after two missed intervals.
+fn boot_charger() -> (App, AppExternalChannelEnds, Vec<OcppCommandToAsset>) {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    app.insert_resource(OcppHeartbeatPolicy { interval: Duration::from_secs(2), offline_after_intervals: 2.0 });
+    let boot_commands = common::boot_charger(&mut app, &channels, "CH003");
+    (app, channels, boot_commands)
+}
+
+fn charger_state(app: &App) -> (bool, EOperationalStatus) {
+    let entity = app.world().resource::<ExternalIdMap>().0["CH003"];
+    let conn = app.world().get::<OcppConnectionState>(entity).unwrap();
+    (conn.is_connected, *app.world().get::<EOperationalStatus>(entity).unwrap())
+}
+
+fn status_updates(channels: &AppExternalChannelEnds) -> Vec<EOperationalStatus> {
+    channels.balancer_status_receiver.try_iter()
+        .filter(|message| message.external_id == "CH003")
+        .map(|message| message.status)
+        .collect()
+}
+
+#[test]
+fn test_heartbeat_answered_with_server_time_and_negotiated_interval() {
+    let (mut app, channels, boot_commands) = boot_charger();
+
+    let boot_reply = boot_commands.into_iter()
+        .find_map(|cmd| match cmd.message_type {
+            EOutgoingOcppMessage::BootNotificationResponse(conf) => Some(conf),
+            _ => None,
+        })
+        .expect("Expected a BootNotificationResponse");
+    assert_eq!(boot_reply.interval, 2);
+
+    send_request(&channels, "hb-1", "Heartbeat", "{}");
+    app.update();
+    let reply = channels.ocpp_to_asset_receiver.try_iter()
+        .find(|cmd| cmd.ocpp_message_id.as_deref() == Some("hb-1"))
+        .expect("No reply to Heartbeat");
+    let EOutgoingOcppMessage::HeartbeatResponse(conf) = reply.message_type else {
+        panic!("Expected HeartbeatResponse");
+    };
+    assert!(chrono::DateTime::parse_from_rfc3339(&conf.current_time).is_ok());
+}
+
+#[test]
+fn test_silent_charger_goes_offline_and_recovers_on_next_message() {
+    let (mut app, channels, _) = boot_charger();
+    status_updates(&channels);
+
+    // Heartbeats every 2 s keep the charger online well past the 4 s deadline.
+    for beat in 0..4 {
+        run_secs(&mut app, 2);
+        send_request(&channels, &format!("hb-{beat}"), "Heartbeat", "{}");
+    }
+    app.update();
+    assert_eq!(charger_state(&app), (true, EOperationalStatus::Online));
+    assert!(status_updates(&channels).is_empty());
+
+    run_secs(&mut app, 3);
+    assert_eq!(charger_state(&app), (true, EOperationalStatus::Online));
+    run_secs(&mut app, 2);
+    assert_eq!(charger_state(&app), (false, EOperationalStatus::Offline));
+    assert_eq!(status_updates(&channels), vec![EOperationalStatus::Offline]);
+
+    // Any message, not only a Heartbeat, proves the charger is back.
+    send_request(&channels, "status-1", "StatusNotification", r#"{"connectorId":1,"errorCode":"NoError","status":"Available"}"#);
+    app.update();
+    app.update();
+    assert_eq!(charger_state(&app), (true, EOperationalStatus::Online));
+    assert_eq!(status_updates(&channels), vec![EOperationalStatus::Online]);
+}
+
+#[test]
+fn test_replies_to_central_system_calls_count_as_liveness() {
+    let (mut app, channels, _) = boot_charger();
+
+    for reply in 0..4 {
+        run_secs(&mut app, 3);
+        channels.ocpp_response_from_asset_sender.send(OcppResponseFromAsset {
+            charge_point_id: "CH003".into(),
+            ocpp_message_id: format!("unsolicited-{reply}"),
+            response: EOcppCallResponse::Result { payload_json: "{}".into() },
+        }).unwrap();
+    }
+    app.update();
+    assert_eq!(charger_state(&app), (true, EOperationalStatus::Online));
+}
+
+#[test]
+fn test_chargers_that_never_booted_are_not_supervised() {
+    let (mut app, _channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    app.insert_resource(OcppHeartbeatPolicy { interval: Duration::from_secs(2), offline_after_intervals: 2.0 });
+    run_secs(&mut app, 10);
+    assert_eq!(charger_state(&app), (false, EOperationalStatus::Initializing));
+}
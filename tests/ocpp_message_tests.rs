@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppExternalChannelEnds, AppMode};
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::ocpp_protocol_plugin::server::command_to_frame;
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::{ChargePointErrorCode, EOutgoingOcppMessage, Reason};
+use ocpp_bevy_poc::ocpp_protocol_plugin::{
+    EChargePointRequest, EGunStatusOcpp, EOcppAction, EOcppErrorCode, EOcppJFrame, Guns, OcppCommandToAsset, OcppRequestFromAsset,
+};
+use serde_json::json;
+
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" },
+                { "type": "connectors", "count": 1, "max_current_a": 32.0, "phases": 3, "connector_type": "Type2" },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH001",
+            "template_id": "Charger_Template",
+            "instance_components": [ { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" } ]
+        }
+    ]
+}"#;
+
+fn send(app: &mut App, channels: &AppExternalChannelEnds, action: &str, payload: serde_json::Value) -> Vec<OcppCommandToAsset> {
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: "CH001".into(),
+        action: action.into(),
+        payload_json: payload.to_string(),
+        ocpp_message_id: format!("{action}-1"),
+    }).unwrap();
+    app.update();
+    channels.ocpp_to_asset_receiver.try_iter().collect()
+}
+
+fn error_code(action: &str, payload: serde_json::Value) -> EOcppErrorCode {
+    EChargePointRequest::decode(action, &payload.to_string()).expect_err("Expected the CALL to be rejected").error_code
+}
+
+#[test]
+fn test_charger_calls_decoded_into_typed_requests() {
+    let status = EChargePointRequest::decode("StatusNotification", &json!({
+        "connectorId": 1, "errorCode": "GroundFailure", "status": "Faulted", "vendorErrorCode": "E-17"
+    }).to_string()).unwrap();
+    let EChargePointRequest::StatusNotification(payload) = &status else { panic!("Expected StatusNotification, got {:?}", status) };
+    assert_eq!((payload.error_code, payload.status.clone()), (ChargePointErrorCode::GroundFailure, EGunStatusOcpp::Faulted));
+    assert_eq!(status.action(), EOcppAction::StatusNotification);
+
+    let stop = EChargePointRequest::decode("StopTransaction", &json!({
+        "transactionId": 4, "meterStop": 1200, "timestamp": "2025-03-04T08:15:00Z", "reason": "EVDisconnected"
+    }).to_string()).unwrap();
+    let EChargePointRequest::StopTransaction(payload) = stop else { panic!("Expected StopTransaction") };
+    assert_eq!(payload.reason, Some(Reason::EVDisconnected));
+
+    assert!(matches!(EChargePointRequest::decode("Heartbeat", "{}"), Ok(EChargePointRequest::Heartbeat(_))));
+    assert!(EOcppAction::ALL.iter().all(|action| action.as_str().parse::<EOcppAction>() == Ok(*action)));
+}
+
+#[test]
+fn test_invalid_calls_rejected_with_matching_error_code() {
+    // Enumerations outside the schema, wrong types and missing required fields.
+    assert_eq!(error_code("StatusNotification", json!({ "connectorId": 1, "errorCode": "NoError", "status": "Idle" })), EOcppErrorCode::FormationViolation);
+    assert_eq!(error_code("MeterValues", json!({ "connectorId": "1", "meterValue": [] })), EOcppErrorCode::FormationViolation);
+    assert_eq!(error_code("BootNotification", json!({ "chargePointVendor": "Alfen" })), EOcppErrorCode::FormationViolation);
+    // Strings longer than their CiString type allows.
+    assert_eq!(error_code("Authorize", json!({ "idTag": "0123456789ABCDEF01234" })), EOcppErrorCode::PropertyConstraintViolation);
+    // Actions that do not exist, or that only the central system sends.
+    assert_eq!(error_code("Hello", json!({})), EOcppErrorCode::NotImplemented);
+    assert_eq!(error_code("SetChargingProfile", json!({})), EOcppErrorCode::NotSupported);
+}
+
+#[test]
+fn test_malformed_call_answered_with_call_error() {
+    let (mut app, channels) = setup_bevy_app(SITE_CONFIG_JSON.to_string(), AppMode::Headless, None).expect("valid site config");
+    app.update();
+    send(&mut app, &channels, "BootNotification", json!({ "chargePointVendor": "Alfen", "chargePointModel": "Eve Single Pro-Line" }));
+
+    let replies = send(&mut app, &channels, "StatusNotification", json!({ "connectorId": 1, "errorCode": "NoError", "status": "Plugged" }));
+    assert_eq!(replies.len(), 1);
+    let frame = command_to_frame(&replies[0], || unreachable!()).unwrap();
+    let EOcppJFrame::CallError { message_id, error_code, .. } = frame else { panic!("Expected CALLERROR, got {:?}", frame) };
+    assert_eq!((message_id.as_str(), error_code.as_str()), ("StatusNotification-1", "FormationViolation"));
+
+    // The connector keeps its last valid status.
+    let entity = app.world().resource::<ExternalIdMap>().0["CH001"];
+    assert!(app.world().get::<Guns>(entity).unwrap().0.iter().all(|gun| gun.status == EGunStatusOcpp::Available));
+
+    // A well-formed vendor extension gets a CALLRESULT saying it is not understood.
+    let replies = send(&mut app, &channels, "DataTransfer", json!({ "vendorId": "com.alfen", "messageId": "GetSessionInfo" }));
+    assert!(matches!(&replies[0].message_type, EOutgoingOcppMessage::DataTransferResponse(conf) if serde_json::to_value(conf).unwrap() == json!({ "status": "UnknownVendorId" })));
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppExternalChannelEnds, AppMode};
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::common::types::EPhase;
+use ocpp_bevy_poc::core_asset_plugin::{CurrentMeterReading, ElectricalMeasurements, PhaseMeterReading};
+use ocpp_bevy_poc::ocpp_protocol_plugin::meter_values::{parse_meter_values, EMeasurand, EReadingContext, ParsedMeterSample};
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::MeterValuesReqPayload;
+use ocpp_bevy_poc::ocpp_protocol_plugin::OcppRequestFromAsset;
+use serde_json::json;
+
+/// A single-connector OCPP charger.
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "ABB", "model": "Terra AC W22-T-R-0" },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH001",
+            "template_id": "Charger_Template",
+            "instance_components": [ { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" } ]
+        }
+    ]
+}"#;
+
+fn parse(payload: serde_json::Value) -> Vec<ParsedMeterSample> {
+    let payload: MeterValuesReqPayload = serde_json::from_value(payload).unwrap();
+    parse_meter_values(&payload.meter_value)
+}
+
+/// Applies every sample to an empty reading, returning power, energy and the other measurements.
+fn apply(samples: &[ParsedMeterSample]) -> (f32, f64, ElectricalMeasurements) {
+    let (mut power_kw, mut energy_kwh, mut measurements) = (0.0, 0.0, ElectricalMeasurements::default());
+    for sample in samples {
+        sample.apply(&mut power_kw, &mut energy_kwh, &mut measurements, DateTime::UNIX_EPOCH);
+    }
+    (power_kw, energy_kwh, measurements)
+}
+
+#[test]
+fn test_phase_split_payload_aggregated() {
+    // Terra AC style: power only per phase, with the neutral current alongside the line currents.
+    let samples = parse(json!({
+        "connectorId": 1,
+        "transactionId": 17,
+        "meterValue": [{
+            "timestamp": "2025-03-04T08:15:00.000Z",
+            "sampledValue": [
+                { "value": "3680", "context": "Sample.Periodic", "measurand": "Power.Active.Import", "phase": "L1", "unit": "W" },
+                { "value": "3650", "context": "Sample.Periodic", "measurand": "Power.Active.Import", "phase": "L2", "unit": "W" },
+                { "value": "3670", "context": "Sample.Periodic", "measurand": "Power.Active.Import", "phase": "L3", "unit": "W" },
+                { "value": "16.0", "context": "Sample.Periodic", "measurand": "Current.Import", "phase": "L1", "unit": "A" },
+                { "value": "15.9", "context": "Sample.Periodic", "measurand": "Current.Import", "phase": "L2", "unit": "A" },
+                { "value": "16.0", "context": "Sample.Periodic", "measurand": "Current.Import", "phase": "L3", "unit": "A" },
+                { "value": "0.3", "context": "Sample.Periodic", "measurand": "Current.Import", "phase": "N", "unit": "A" },
+                { "value": "230.1", "context": "Sample.Periodic", "measurand": "Voltage", "phase": "L1-N", "unit": "V" },
+                { "value": "1520.4", "context": "Sample.Periodic", "measurand": "Energy.Active.Import.Register", "unit": "kWh" }
+            ]
+        }]
+    }));
+    assert_eq!(samples[0].timestamp, Some("2025-03-04T08:15:00Z".parse::<DateTime<Utc>>().unwrap()));
+
+    let (power_kw, energy_kwh, measurements) = apply(&samples);
+    assert!((power_kw - 11.0).abs() < 1e-4);
+    assert_eq!(energy_kwh, 1520.4);
+    assert_eq!(measurements.current_a, None, "per-phase currents are not summed into a total");
+    assert_eq!(measurements.phases, vec![
+        PhaseMeterReading { phase: EPhase::L1, power_kw: Some(3.68), current_a: Some(16.0), voltage_v: Some(230.1) },
+        PhaseMeterReading { phase: EPhase::L2, power_kw: Some(3.65), current_a: Some(15.9), voltage_v: None },
+        PhaseMeterReading { phase: EPhase::L3, power_kw: Some(3.67), current_a: Some(16.0), voltage_v: None },
+        PhaseMeterReading { phase: EPhase::N, power_kw: None, current_a: Some(0.3), voltage_v: None },
+    ]);
+}
+
+#[test]
+fn test_units_and_measurands_converted() {
+    // Mixed units as sent by different firmware, including missing units and a signed meter value.
+    let samples = parse(json!({
+        "connectorId": 0,
+        "meterValue": [{
+            "timestamp": "2025-03-04T08:15:00+01:00",
+            "sampledValue": [
+                { "value": "7.4", "measurand": "Power.Active.Import", "unit": "kW" },
+                { "value": "1200", "measurand": "Power.Active.Export", "unit": "W" },
+                { "value": "2500", "measurand": "Power.Reactive.Import" },
+                { "value": "48213" },
+                { "value": "350", "measurand": "Energy.Active.Export.Register", "unit": "Wh" },
+                { "value": "812500", "measurand": "Energy.Reactive.Import.Register", "unit": "varh" },
+                { "value": "11", "measurand": "Power.Offered", "unit": "kW" },
+                { "value": "49.98", "measurand": "Frequency" },
+                { "value": "0.97", "measurand": "Power.Factor" },
+                { "value": "104", "measurand": "Temperature", "location": "Body", "unit": "Fahrenheit" },
+                { "value": "80", "measurand": "SoC", "location": "EV", "unit": "Percent" },
+                { "value": "oCMBAAABAEFTRUZGQgAAAA", "format": "SignedData", "measurand": "Energy.Active.Import.Register" },
+                { "value": "12", "measurand": "Voltage", "unit": "A" },
+                { "value": "n/a", "measurand": "Current.Import", "unit": "A" }
+            ]
+        }]
+    }));
+    assert_eq!(samples[0].timestamp, Some("2025-03-04T07:15:00Z".parse::<DateTime<Utc>>().unwrap()));
+    assert_eq!(samples[0].values.len(), 11, "signed, mismatched and unreadable values are dropped");
+
+    let (power_kw, energy_kwh, m) = apply(&samples);
+    assert!((power_kw - 6.2).abs() < 1e-4, "export is netted off import");
+    assert_eq!(energy_kwh, 48.213);
+    assert_eq!(m.reactive_power_kvar, Some(2.5));
+    assert_eq!(m.exported_energy_kwh, Some(0.35));
+    assert_eq!(m.reactive_energy_kvarh, Some(812.5));
+    assert_eq!(m.offered_power_kw, Some(11.0));
+    assert_eq!(m.frequency_hz, Some(49.98));
+    assert_eq!(m.power_factor, Some(0.97));
+    assert_eq!(m.temperature_c, Some(40.0));
+    assert_eq!(m.soc_percent, Some(80.0));
+    assert_eq!(m.voltage_v, None);
+    assert_eq!(m.current_a, None);
+}
+
+#[test]
+fn test_transaction_context_only_updates_registers() {
+    // A Transaction.Begin snapshot sent late, after a newer periodic sample, listed first.
+    let samples = parse(json!({
+        "connectorId": 1,
+        "meterValue": [
+            { "timestamp": "2025-03-04T08:16:00Z", "sampledValue": [
+                { "value": "7200", "context": "Sample.Periodic", "measurand": "Power.Active.Import", "unit": "W" },
+                { "value": "10250", "context": "Sample.Periodic", "measurand": "Energy.Active.Import.Register", "unit": "Wh" }
+            ] },
+            { "timestamp": "2025-03-04T08:15:00Z", "sampledValue": [
+                { "value": "0", "context": "Transaction.Begin", "measurand": "Power.Active.Import", "unit": "W" },
+                { "value": "10000", "context": "Transaction.Begin", "measurand": "Energy.Active.Import.Register", "unit": "Wh" }
+            ] }
+        ]
+    }));
+    assert_eq!(samples.iter().map(|s| s.values[0].context).collect::<Vec<_>>(), vec![EReadingContext::TransactionBegin, EReadingContext::SamplePeriodic]);
+    assert_eq!(samples[1].values[1].measurand, EMeasurand::EnergyActiveImportRegister);
+
+    // Applied oldest first, the periodic sample wins.
+    let (power_kw, energy_kwh, _) = apply(&samples);
+    assert_eq!((power_kw, energy_kwh), (7.2, 10.25));
+
+    // On its own, the snapshot moves the register but leaves live power alone.
+    let (power_kw, energy_kwh, _) = apply(&samples[..1]);
+    assert_eq!((power_kw, energy_kwh), (0.0, 10.0));
+}
+
+#[test]
+fn test_reading_stamped_with_charger_time() {
+    let (mut app, channels): (_, AppExternalChannelEnds) = setup_bevy_app(SITE_CONFIG_JSON.to_string(), AppMode::Headless, None).expect("valid site config");
+    app.update();
+    let send = |app: &mut bevy::prelude::App, action: &str, payload: serde_json::Value| {
+        channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+            charge_point_id: "CH001".into(),
+            action: action.into(),
+            payload_json: payload.to_string(),
+            ocpp_message_id: format!("{action}-1"),
+        }).unwrap();
+        app.update();
+    };
+    send(&mut app, "BootNotification", json!({ "chargePointVendor": "ABB", "chargePointModel": "Terra AC W22-T-R-0" }));
+    let reading = |app: &bevy::prelude::App| {
+        let entity = app.world().resource::<ExternalIdMap>().0["CH001"];
+        app.world().get::<CurrentMeterReading>(entity).unwrap().clone()
+    };
+
+    send(&mut app, "MeterValues", json!({
+        "connectorId": 0,
+        "meterValue": [
+            { "timestamp": "2025-03-04T08:16:00Z", "sampledValue": [{ "value": "5000", "measurand": "Power.Active.Import", "unit": "W" }] },
+            { "timestamp": "2025-03-04T08:15:00Z", "sampledValue": [{ "value": "4000", "measurand": "Power.Active.Import", "unit": "W" }] }
+        ]
+    }));
+    let current = reading(&app);
+    assert_eq!(current.power_kw, 5.0, "the newest sample wins regardless of order in the message");
+    assert_eq!(current.timestamp, "2025-03-04T08:16:00Z".parse::<DateTime<Utc>>().unwrap());
+
+    // Without a readable charger timestamp the reading is stamped on receipt.
+    let before = Utc::now();
+    send(&mut app, "MeterValues", json!({
+        "connectorId": 0,
+        "meterValue": [{ "timestamp": "not a time", "sampledValue": [{ "value": "3", "measurand": "Power.Active.Import", "unit": "kW" }] }]
+    }));
+    let current = reading(&app);
+    assert_eq!(current.power_kw, 3.0);
+    assert!(current.timestamp >= before);
+}
+
+#[test]
+fn test_replayed_old_sample_does_not_overwrite_newer_reading() {
+    let (mut app, channels): (_, AppExternalChannelEnds) = setup_bevy_app(SITE_CONFIG_JSON.to_string(), AppMode::Headless, None).expect("valid site config");
+    app.update();
+    let send = |app: &mut bevy::prelude::App, action: &str, payload: serde_json::Value| {
+        channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+            charge_point_id: "CH001".into(),
+            action: action.into(),
+            payload_json: payload.to_string(),
+            ocpp_message_id: format!("{action}-1"),
+        }).unwrap();
+        app.update();
+    };
+    send(&mut app, "BootNotification", json!({ "chargePointVendor": "ABB", "chargePointModel": "Terra AC W22-T-R-0" }));
+    let reading = |app: &bevy::prelude::App| {
+        let entity = app.world().resource::<ExternalIdMap>().0["CH001"];
+        app.world().get::<CurrentMeterReading>(entity).unwrap().clone()
+    };
+    let sample = |connector_id: u32, timestamp: &str, watts: &str, wh: &str| json!({
+        "connectorId": connector_id,
+        "meterValue": [{ "timestamp": timestamp, "sampledValue": [
+            { "value": watts, "measurand": "Power.Active.Import", "unit": "W" },
+            { "value": "80", "measurand": "SoC", "unit": "Percent" },
+            { "value": wh, "measurand": "Energy.Active.Import.Register", "unit": "Wh" }
+        ] }]
+    });
+
+    for connector_id in [0, 1] {
+        send(&mut app, "MeterValues", sample(connector_id, "2025-03-04T08:16:00Z", "7000", "12000"));
+        // The charger replays a sample from before the one already applied.
+        send(&mut app, "MeterValues", sample(connector_id, "2025-03-04T08:10:00Z", "0", "11000"));
+
+        let current = reading(&app);
+        assert_eq!((current.power_kw, current.energy_kwh), (7.0, 12.0), "connector {connector_id}: newer values kept");
+        assert_eq!(current.timestamp, "2025-03-04T08:16:00Z".parse::<DateTime<Utc>>().unwrap(), "connector {connector_id}: timestamp kept");
+    }
+    let connector = &reading(&app).connectors[0];
+    assert_eq!((connector.power_kw, connector.measurements.soc_percent), (7.0, Some(80.0)));
+}
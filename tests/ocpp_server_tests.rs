@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+use futures_util::{SinkExt, StreamExt};
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppMode};
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::ocpp_protocol_plugin::{EOcppCallOutcome, EOcppJFrame, OcppCallOutcomes, OcppChargePointVersions, OcppServer};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::runtime::Runtime;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type ChargePointSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Phihong_AC_EU_Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Phihong", "model": "AC_EU_Dual_V2" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Amps", "profile_phases_in_ocpp_message": 3 },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH001",
+            "template_id": "Phihong_AC_EU_Charger_Template",
+            "instance_components": [
+                { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" }
+            ]
+        },
+        {
+            "external_id": "CH002",
+            "template_id": "Phihong_AC_EU_Charger_Template",
+            "instance_components": [
+                { "type": "ocpp_config", "version": "V2_0_1", "charge_point_id": "CH002" }
+            ]
+        }
+    ]
+}"#;
+
+/// Start the app and an OCPP server bound to an ephemeral local port.
+fn start_app_with_server(rt: &Runtime) -> (App, SocketAddr) {
+    let (mut app, channels) = setup_bevy_app(SITE_CONFIG_JSON.to_string(), AppMode::Headless, None).expect("valid site config");
+    app.update();
+
+    let versions = app.world().resource::<OcppChargePointVersions>().clone();
+    let server = rt.block_on(OcppServer::bind("127.0.0.1:0", versions)).unwrap();
+    let addr = server.local_addr().unwrap();
+    rt.spawn(server.run(
+        channels.ocpp_from_asset_sender.clone(),
+        channels.ocpp_response_from_asset_sender.clone(),
+        channels.ocpp_to_asset_receiver.clone(),
+    ));
+    (app, addr)
+}
+
+/// Connect a simulated charge point to `ws://<addr>/<cp_id>`, offering `subprotocol`; returns the socket and the subprotocol the server chose.
+fn connect_with_subprotocol(rt: &Runtime, addr: SocketAddr, cp_id: &str, subprotocol: Option<&str>) -> Result<(ChargePointSocket, String), String> {
+    let mut request = format!("ws://{}/{}", addr, cp_id).into_client_request().unwrap();
+    if let Some(protocol) = subprotocol {
+        request.headers_mut().insert("Sec-WebSocket-Protocol", protocol.parse().unwrap());
+    }
+    rt.block_on(tokio_tungstenite::connect_async(request))
+        .map(|(ws, response)| (ws, response.headers().get("Sec-WebSocket-Protocol").unwrap().to_str().unwrap().to_string()))
+        .map_err(|e| e.to_string())
+}
+
+/// Connect a simulated OCPP 1.6 charge point to `ws://<addr>/<cp_id>`.
+fn connect_charge_point(rt: &Runtime, addr: SocketAddr, cp_id: &str, subprotocol: Option<&str>) -> Result<ChargePointSocket, String> {
+    connect_with_subprotocol(rt, addr, cp_id, subprotocol).map(|(ws, negotiated)| {
+        assert_eq!(negotiated, "ocpp1.6");
+        ws
+    })
+}
+
+/// Keep updating the app until the charge point receives a frame matching `pred`.
+fn pump_until(app: &mut App, rt: &Runtime, ws: &mut ChargePointSocket, pred: impl Fn(&EOcppJFrame) -> bool) -> Option<EOcppJFrame> {
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(3) {
+        app.update();
+        if let Ok(Some(Ok(Message::Text(text)))) = rt.block_on(async { tokio::time::timeout(Duration::from_millis(20), ws.next()).await }) {
+            let frame = EOcppJFrame::parse(text.as_str()).expect("Server sent a malformed frame");
+            if pred(&frame) {
+                return Some(frame);
+            }
+        }
+    }
+    None
+}
+
+#[test]
+fn test_charge_point_boot_over_websocket() {
+    let rt = Runtime::new().unwrap();
+    let (mut app, addr) = start_app_with_server(&rt);
+    let mut ws = connect_charge_point(&rt, addr, "CH001", Some("ocpp1.6")).unwrap();
+
+    let boot = json!([2, "boot-1", "BootNotification", { "chargePointVendor": "Phihong", "chargePointModel": "AC_EU_Dual_V2" }]);
+    rt.block_on(ws.send(Message::Text(boot.to_string().into()))).unwrap();
+
+    let boot_result = pump_until(&mut app, &rt, &mut ws, |f| f.message_id() == "boot-1").expect("No BootNotification reply");
+    let EOcppJFrame::CallResult { payload, .. } = boot_result else {
+        panic!("Expected CALLRESULT, got {:?}", boot_result);
+    };
+    assert_eq!(payload["status"], "Accepted");
+    assert_eq!(payload["interval"], 300);
+
+    // Generic initialization issues central-system CALLs over the same connection.
+    let init_call = pump_until(&mut app, &rt, &mut ws, |f| matches!(f, EOcppJFrame::Call { .. })).expect("No init CALL received");
+    let EOcppJFrame::Call { message_id, action, payload } = init_call else { unreachable!() };
+    assert_eq!(action, "ChangeConfiguration");
+    assert_eq!(payload["key"], "HeartbeatInterval");
+
+    // The charger's CALLRESULT is matched back to the command it answers.
+    let reply = EOcppJFrame::CallResult { message_id, payload: json!({ "status": "RebootRequired" }) };
+    rt.block_on(ws.send(Message::Text(reply.to_text().into()))).unwrap();
+    let entity = app.world().resource::<ExternalIdMap>().0["CH001"];
+    let start = Instant::now();
+    while app.world().get::<OcppCallOutcomes>(entity).unwrap().change_configuration.is_empty() && start.elapsed() < Duration::from_secs(3) {
+        app.update();
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let outcomes = app.world().get::<OcppCallOutcomes>(entity).unwrap();
+    assert_eq!(outcomes.change_configuration.get("HeartbeatInterval"), Some(&EOcppCallOutcome::RebootRequired));
+}
+
+#[test]
+fn test_malformed_frame_gets_call_error() {
+    let rt = Runtime::new().unwrap();
+    let (mut app, addr) = start_app_with_server(&rt);
+    let mut ws = connect_charge_point(&rt, addr, "CH001", Some("ocpp1.6")).unwrap();
+
+    // Malformed replies and unreadable frames must not be answered; only the malformed CALL is.
+    rt.block_on(ws.send(Message::Text(r#"[3, "bad-0"]"#.into()))).unwrap();
+    rt.block_on(ws.send(Message::Text(r#"[4, "bad-0", "NotImplemented"]"#.into()))).unwrap();
+    rt.block_on(ws.send(Message::Text("not json".into()))).unwrap();
+    rt.block_on(ws.send(Message::Text(r#"[2, "bad-1", "Heartbeat"]"#.into()))).unwrap();
+
+    let reply = pump_until(&mut app, &rt, &mut ws, |f| matches!(f, EOcppJFrame::CallError { .. })).expect("No CALLERROR received");
+    assert_eq!(reply.message_id(), "bad-1");
+    let EOcppJFrame::CallError { error_code, .. } = reply else {
+        panic!("Expected CALLERROR, got {:?}", reply);
+    };
+    assert_eq!(error_code, "FormationViolation");
+}
+
+#[test]
+fn test_handshake_requires_configured_subprotocol() {
+    let rt = Runtime::new().unwrap();
+    let (_app, addr) = start_app_with_server(&rt);
+
+    assert!(connect_with_subprotocol(&rt, addr, "CH001", None).is_err());
+    assert!(connect_with_subprotocol(&rt, addr, "CH001", Some("ocpp2.1")).is_err());
+    // Each charger is held to the subprotocol of its configured OCPP version, whatever else it offers.
+    assert!(connect_with_subprotocol(&rt, addr, "CH001", Some("ocpp2.0.1")).is_err());
+    assert!(connect_with_subprotocol(&rt, addr, "CH002", Some("ocpp1.6")).is_err());
+    assert_eq!(connect_with_subprotocol(&rt, addr, "CH001", Some("ocpp2.0.1, ocpp1.6")).unwrap().1, "ocpp1.6");
+    assert_eq!(connect_with_subprotocol(&rt, addr, "CH002", Some("ocpp1.6, ocpp2.0.1")).unwrap().1, "ocpp2.0.1");
+    // Charge points missing from the site config are turned away.
+    assert!(connect_with_subprotocol(&rt, addr, "CH009", Some("ocpp1.6")).is_err());
+}
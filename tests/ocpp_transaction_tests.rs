@@ -0,0 +1,148 @@
+mod common;
+
+use bevy::prelude::*;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::{
+    AuthorizationStatus, ChargingProfilePurposeType, EOutgoingOcppMessage, SetChargingProfileReqPayload,
+};
+use ocpp_bevy_poc::ocpp_protocol_plugin::{Gun, Guns, OcppRequestFromAsset};
+use serde_json::json;
+
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Alfen_AC_EU_Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 1 },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Watts", "profile_phases_in_ocpp_message": 1 },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH002",
+            "template_id": "Alfen_AC_EU_Charger_Template",
+            "instance_components": [
+                { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH002" }
+            ]
+        }
+    ]
+}"#;
+
+/// Send a charger-initiated CALL and return the reply to it.
+fn call(app: &mut App, channels: &AppExternalChannelEnds, message_id: &str, action: &str, payload: serde_json::Value) -> EOutgoingOcppMessage {
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: "CH002".into(),
+        action: action.into(),
+        payload_json: payload.to_string(),
+        ocpp_message_id: message_id.into(),
+    }).unwrap();
+    app.update();
+    app.update();
+    channels.ocpp_to_asset_receiver.try_iter()
+        .find(|cmd| cmd.ocpp_message_id.as_deref() == Some(message_id))
+        .map(|cmd| cmd.message_type)
+        .unwrap_or_else(|| panic!("No reply to {action}"))
+}
+
+fn boot_charger() -> (App, AppExternalChannelEnds) {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    common::boot_charger(&mut app, &channels, "CH002");
+    (app, channels)
+}
+
+fn send_setpoint(app: &mut App, channels: &AppExternalChannelEnds, target_power_kw: f32) -> SetChargingProfileReqPayload {
+    common::charging_profiles(common::send_setpoint(app, channels, "CH002", target_power_kw))
+        .into_iter()
+        .next()
+        .expect("Expected a SetChargingProfileRequest").1
+}
+
+fn gun(app: &App) -> Gun {
+    let entity = app.world().resource::<ExternalIdMap>().0["CH002"];
+    app.world().get::<Guns>(entity).unwrap().0[0].clone()
+}
+
+#[test]
+fn test_transaction_lifecycle_scopes_profiles_to_the_transaction() {
+    let (mut app, channels) = boot_charger();
+
+    let EOutgoingOcppMessage::AuthorizeResponse(conf) = call(&mut app, &channels, "auth-1", "Authorize", json!({ "idTag": "TAG001" })) else {
+        panic!("Expected AuthorizeResponse");
+    };
+    assert_eq!(conf.id_tag_info.status, AuthorizationStatus::Accepted);
+
+    let start = json!({ "connectorId": 1, "idTag": "TAG001", "meterStart": 1200, "timestamp": "2025-01-01T10:00:00Z" });
+    let EOutgoingOcppMessage::StartTransactionResponse(conf) = call(&mut app, &channels, "start-1", "StartTransaction", start) else {
+        panic!("Expected StartTransactionResponse");
+    };
+    assert_eq!(conf.id_tag_info.status, AuthorizationStatus::Accepted);
+    let transaction_id = conf.transaction_id;
+
+    let transaction = gun(&app).transaction.expect("No transaction recorded on the gun");
+    assert_eq!(transaction.transaction_id, transaction_id);
+    assert_eq!(transaction.id_tag, "TAG001");
+    assert_eq!(transaction.meter_start_wh, 1200);
+    assert_eq!(transaction.start_time.to_rfc3339(), "2025-01-01T10:00:00+00:00");
+
+    // The same tag cannot start a second session while the first is open.
+    let EOutgoingOcppMessage::AuthorizeResponse(conf) = call(&mut app, &channels, "auth-2", "Authorize", json!({ "idTag": "TAG001" })) else {
+        panic!("Expected AuthorizeResponse");
+    };
+    assert_eq!(conf.id_tag_info.status, AuthorizationStatus::ConcurrentTx);
+
+    let profile = send_setpoint(&mut app, &channels, 7.0);
+    assert_eq!(profile.connector_id, 1);
+    assert_eq!(profile.cs_charging_profiles.charging_profile_purpose, ChargingProfilePurposeType::TxProfile);
+    assert_eq!(profile.cs_charging_profiles.transaction_id, Some(transaction_id));
+    assert_eq!(profile.cs_charging_profiles.charging_schedule.charging_schedule_period[0].limit, 7000.0);
+
+    let stop = json!({ "transactionId": transaction_id, "idTag": "TAG001", "meterStop": 9200, "timestamp": "2025-01-01T11:00:00Z", "reason": "Local" });
+    let EOutgoingOcppMessage::StopTransactionResponse(conf) = call(&mut app, &channels, "stop-1", "StopTransaction", stop) else {
+        panic!("Expected StopTransactionResponse");
+    };
+    assert_eq!(conf.id_tag_info.map(|info| info.status), Some(AuthorizationStatus::Accepted));
+
+    let gun = gun(&app);
+    assert!(gun.transaction.is_none());
+    let finished = gun.last_transaction.expect("Stopped transaction not kept");
+    assert_eq!(finished.meter_stop_wh, Some(9200));
+    assert!(finished.stop_time.is_some());
+
+    // Without a session the setpoint goes back to the charger-wide default.
+    let profile = send_setpoint(&mut app, &channels, 5.0);
+    assert_eq!(profile.connector_id, 0);
+    assert_eq!(profile.cs_charging_profiles.charging_profile_purpose, ChargingProfilePurposeType::TxDefaultProfile);
+    assert_eq!(profile.cs_charging_profiles.transaction_id, None);
+}
+
+#[test]
+fn test_transactions_on_unknown_connectors_or_ids_still_get_replies() {
+    let (mut app, channels) = boot_charger();
+
+    let start = json!({ "connectorId": 9, "idTag": "TAG001", "meterStart": 0, "timestamp": "2025-01-01T10:00:00Z" });
+    let EOutgoingOcppMessage::StartTransactionResponse(first) = call(&mut app, &channels, "start-1", "StartTransaction", start) else {
+        panic!("Expected StartTransactionResponse");
+    };
+    assert_eq!(first.id_tag_info.status, AuthorizationStatus::Invalid);
+    assert_eq!(first.transaction_id, 0);
+    assert!(gun(&app).transaction.is_none());
+
+    // A rejected start consumes no id; ids start from the clock so a restart does not reuse ones chargers still hold.
+    let start = json!({ "connectorId": 1, "idTag": "TAG002", "meterStart": 0, "timestamp": "2025-01-01T10:00:00Z" });
+    let EOutgoingOcppMessage::StartTransactionResponse(second) = call(&mut app, &channels, "start-2", "StartTransaction", start) else {
+        panic!("Expected StartTransactionResponse");
+    };
+    let seconds_since_2020 = chrono::Utc::now().timestamp() - 1_577_836_800;
+    assert!((seconds_since_2020 - 60..=seconds_since_2020 + 1).contains(&(second.transaction_id as i64)), "{}", second.transaction_id);
+
+    let stop = json!({ "transactionId": 4242, "meterStop": 100, "timestamp": "2025-01-01T11:00:00Z" });
+    let EOutgoingOcppMessage::StopTransactionResponse(conf) = call(&mut app, &channels, "stop-1", "StopTransaction", stop) else {
+        panic!("Expected StopTransactionResponse");
+    };
+    assert!(conf.id_tag_info.is_none());
+    assert_eq!(gun(&app).transaction.map(|tx| tx.transaction_id), Some(second.transaction_id));
+}
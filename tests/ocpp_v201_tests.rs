@@ -0,0 +1,235 @@
+mod common;
+
+use bevy::prelude::*;
+use common::send_setpoint;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::{CurrentMeterReading, LastAppliedSetpointKw};
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::{EOutgoingOcppMessage, RegistrationStatus};
+use ocpp_bevy_poc::ocpp_protocol_plugin::types_v201::{ChargingProfilePurposeEnumType, SetChargingProfileRequest};
+use ocpp_bevy_poc::ocpp_protocol_plugin::{
+    EGunStatusOcpp, EOcppCallResponse, EOutgoingOcpp201Message, Gun, Guns, Ocpp201DeviceModel, OcppCommandToAsset,
+    OcppRequestFromAsset, OcppResponseFromAsset,
+};
+use serde_json::json;
+
+/// The same charger model on both protocol versions, under one site's setpoints.
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "AC_Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Vestel", "model": "EVC04" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Watts", "profile_phases_in_ocpp_message": 3 },
+                { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+            ]
+        }
+    },
+    "assets": [
+        {
+            "external_id": "CH16",
+            "template_id": "AC_Charger_Template",
+            "instance_components": [ { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH16" } ]
+        },
+        {
+            "external_id": "CS201",
+            "template_id": "AC_Charger_Template",
+            "instance_components": [ { "type": "ocpp_config", "version": "V2_0_1", "charge_point_id": "CS201" } ]
+        }
+    ]
+}"#;
+
+fn send(app: &mut App, channels: &AppExternalChannelEnds, charge_point_id: &str, message_id: &str, action: &str, payload: serde_json::Value) {
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: charge_point_id.into(),
+        action: action.into(),
+        payload_json: payload.to_string(),
+        ocpp_message_id: message_id.into(),
+    }).unwrap();
+    app.update();
+    app.update();
+}
+
+/// Send a 2.0.1 station CALL; returns the reply and any other commands sent meanwhile.
+fn call_201(app: &mut App, channels: &AppExternalChannelEnds, message_id: &str, action: &str, payload: serde_json::Value) -> (EOutgoingOcppMessage, Vec<OcppCommandToAsset>) {
+    send(app, channels, "CS201", message_id, action, payload);
+    let (reply, others): (Vec<_>, Vec<_>) = channels.ocpp_to_asset_receiver.try_iter()
+        .partition(|cmd| cmd.ocpp_message_id.as_deref() == Some(message_id));
+    (reply.into_iter().next().unwrap_or_else(|| panic!("No reply to {action}")).message_type, others)
+}
+
+fn boot_site() -> (App, AppExternalChannelEnds, Vec<OcppCommandToAsset>) {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    let mut commands = common::boot_charger(&mut app, &channels, "CH16");
+    send(&mut app, &channels, "CS201", "boot-201", "BootNotification", json!({
+        "chargingStation": { "vendorName": "Vestel", "model": "EVC04", "firmwareVersion": "3.160.0" },
+        "reason": "PowerUp"
+    }));
+    commands.extend(channels.ocpp_to_asset_receiver.try_iter());
+    (app, channels, commands)
+}
+
+fn v201_profiles(commands: Vec<OcppCommandToAsset>) -> Vec<(String, SetChargingProfileRequest)> {
+    commands.into_iter()
+        .filter_map(|cmd| match cmd.message_type {
+            EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::SetChargingProfileRequest(req)) => Some((cmd.ocpp_message_id.unwrap(), req)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn component<T: Component + Clone>(app: &App, external_id: &str) -> T {
+    let entity = app.world().resource::<ExternalIdMap>().0[external_id];
+    app.world().get::<T>(entity).unwrap().clone()
+}
+
+fn gun(app: &App) -> Gun {
+    component::<Guns>(app, "CS201").0[0].clone()
+}
+
+#[test]
+fn test_mixed_site_dispatches_setpoints_by_version() {
+    let (mut app, channels, boot_commands) = boot_site();
+
+    let reply = boot_commands.iter().find(|cmd| cmd.ocpp_message_id.as_deref() == Some("boot-201")).unwrap();
+    let EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::BootNotificationResponse(conf)) = &reply.message_type else {
+        panic!("Expected a 2.0.1 BootNotificationResponse, got {:?}", reply.message_type);
+    };
+    assert_eq!(conf.status, RegistrationStatus::Accepted);
+
+    // Each charger is initialized in its own protocol.
+    let init = |cp_id: &str| boot_commands.iter().filter(|cmd| cmd.charge_point_id == cp_id && cmd.message_type.action_name().is_some())
+        .map(|cmd| cmd.message_type.action_name().unwrap()).collect::<Vec<_>>();
+    assert_eq!(init("CS201"), vec!["GetBaseReport"]);
+    assert!(init("CH16").contains(&"ChangeConfiguration"));
+
+    let commands = send_setpoint(&mut app, &channels, "CH16", 7.0);
+    assert!(commands.iter().any(|cmd| matches!(&cmd.message_type, EOutgoingOcppMessage::SetChargingProfileRequest(req) if req.connector_id == 0)));
+
+    let profiles = v201_profiles(send_setpoint(&mut app, &channels, "CS201", 7.0));
+    assert_eq!(profiles.len(), 1);
+    let (message_id, profile) = &profiles[0];
+    assert_eq!(profile.evse_id, 0, "without a session the limit applies to the whole station");
+    assert_eq!(profile.charging_profile.charging_profile_purpose, ChargingProfilePurposeEnumType::TxDefaultProfile);
+    assert_eq!(profile.charging_profile.charging_schedule[0].charging_schedule_period[0].limit, 7000.0);
+    let payload = EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::SetChargingProfileRequest(profile.clone())).payload().unwrap();
+    assert_eq!(payload["chargingProfile"]["chargingSchedule"][0]["chargingRateUnit"], "W");
+
+    // The station's acceptance makes the setpoint the applied one, as for 1.6.
+    channels.ocpp_response_from_asset_sender.send(OcppResponseFromAsset {
+        charge_point_id: "CS201".into(),
+        ocpp_message_id: message_id.clone(),
+        response: EOcppCallResponse::Result { payload_json: json!({ "status": "Accepted" }).to_string() },
+    }).unwrap();
+    app.update();
+    assert_eq!(component::<LastAppliedSetpointKw>(&app, "CS201").0, 7.0);
+}
+
+#[test]
+fn test_transaction_event_lifecycle() {
+    let (mut app, channels, _) = boot_site();
+    send_setpoint(&mut app, &channels, "CS201", 11.0);
+
+    call_201(&mut app, &channels, "st-1", "StatusNotification", json!({
+        "timestamp": "2025-01-01T09:59:00Z", "connectorStatus": "Occupied", "evseId": 1, "connectorId": 1
+    }));
+    assert_eq!(gun(&app).status, EGunStatusOcpp::Preparing);
+
+    let (reply, commands) = call_201(&mut app, &channels, "tx-1", "TransactionEvent", json!({
+        "eventType": "Started", "timestamp": "2025-01-01T10:00:00Z", "triggerReason": "Authorized", "seqNo": 0,
+        "transactionInfo": { "transactionId": "f4e1c0de-0001", "chargingState": "Charging" },
+        "idToken": { "idToken": "04A2B3C4", "type": "ISO14443" },
+        "evse": { "id": 1, "connectorId": 1 },
+        "meterValue": [{ "timestamp": "2025-01-01T10:00:00Z", "sampledValue": [
+            { "value": 1.5, "context": "Transaction.Begin", "measurand": "Energy.Active.Import.Register", "unitOfMeasure": { "unit": "kWh" } }
+        ] }]
+    }));
+    let EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::TransactionEventResponse(conf)) = reply else {
+        panic!("Expected TransactionEventResponse");
+    };
+    assert!(conf.id_token_info.is_some());
+    let gun_now = gun(&app);
+    assert_eq!(gun_now.status, EGunStatusOcpp::Charging);
+    let transaction = gun_now.transaction.expect("No transaction recorded on the gun");
+    assert_eq!(transaction.station_transaction_id.as_deref(), Some("f4e1c0de-0001"));
+    assert_eq!(transaction.id_tag, "04A2B3C4");
+    assert_eq!(transaction.meter_start_wh, 1500);
+
+    // The new session gets its share as a TxProfile on its EVSE, under the station's transaction id.
+    let profiles = v201_profiles(commands);
+    assert_eq!(profiles.len(), 1);
+    assert_eq!(profiles[0].1.evse_id, 1);
+    assert_eq!(profiles[0].1.charging_profile.charging_profile_purpose, ChargingProfilePurposeEnumType::TxProfile);
+    assert_eq!(profiles[0].1.charging_profile.transaction_id.as_deref(), Some("f4e1c0de-0001"));
+    assert_eq!(profiles[0].1.charging_profile.charging_schedule[0].charging_schedule_period[0].limit, 11000.0);
+
+    // Later events find the transaction by id alone; a multiplier scales the value.
+    call_201(&mut app, &channels, "tx-2", "TransactionEvent", json!({
+        "eventType": "Updated", "timestamp": "2025-01-01T10:15:00Z", "triggerReason": "MeterValuePeriodic", "seqNo": 1,
+        "transactionInfo": { "transactionId": "f4e1c0de-0001", "chargingState": "SuspendedEV" },
+        "meterValue": [{ "timestamp": "2025-01-01T10:15:00Z", "sampledValue": [
+            { "value": 7.2, "measurand": "Power.Active.Import", "unitOfMeasure": { "unit": "W", "multiplier": 3 } }
+        ] }]
+    }));
+    assert_eq!(gun(&app).status, EGunStatusOcpp::SuspendedEV);
+    let reading = component::<CurrentMeterReading>(&app, "CS201");
+    assert!((reading.connectors[0].power_kw - 7.2).abs() < 1e-4);
+
+    call_201(&mut app, &channels, "tx-3", "TransactionEvent", json!({
+        "eventType": "Ended", "timestamp": "2025-01-01T11:00:00Z", "triggerReason": "EVDeparted", "seqNo": 2,
+        "transactionInfo": { "transactionId": "f4e1c0de-0001", "chargingState": "Idle", "stoppedReason": "EVDisconnected" },
+        "meterValue": [{ "timestamp": "2025-01-01T11:00:00Z", "sampledValue": [
+            { "value": 9500, "context": "Transaction.End", "measurand": "Energy.Active.Import.Register" }
+        ] }]
+    }));
+    let gun_now = gun(&app);
+    assert!(gun_now.transaction.is_none());
+    assert_eq!(gun_now.last_transaction.unwrap().meter_stop_wh, Some(9500));
+    assert_eq!(gun_now.status, EGunStatusOcpp::Available);
+}
+
+#[test]
+fn test_notify_report_fills_device_model() {
+    let (mut app, channels, _) = boot_site();
+
+    let report = |seq_no: u32, tbc: bool, data: serde_json::Value| json!({
+        "requestId": 1, "generatedAt": "2025-01-01T10:00:00Z", "seqNo": seq_no, "tbc": tbc, "reportData": data
+    });
+    let (reply, _) = call_201(&mut app, &channels, "nr-1", "NotifyReport", report(0, true, json!([
+        { "component": { "name": "OCPPCommCtrlr" }, "variable": { "name": "HeartbeatInterval" },
+          "variableAttribute": [{ "type": "Actual", "value": "60", "mutability": "ReadWrite" }] }
+    ])));
+    assert!(matches!(reply, EOutgoingOcppMessage::V201(EOutgoingOcpp201Message::NotifyReportResponse(_))));
+    assert!(!component::<Ocpp201DeviceModel>(&app, "CS201").report_complete);
+
+    call_201(&mut app, &channels, "nr-2", "NotifyReport", report(1, false, json!([
+        { "component": { "name": "EVSE", "evse": { "id": 1 } }, "variable": { "name": "Power" },
+          "variableAttribute": [{ "type": "MaxSet", "value": "22000" }, { "value": "11000" }] }
+    ])));
+    let model = component::<Ocpp201DeviceModel>(&app, "CS201");
+    assert!(model.report_complete);
+    assert_eq!(model.variables["OCPPCommCtrlr/HeartbeatInterval"], "60");
+    assert_eq!(model.variables["EVSE[1]/Power"], "11000");
+}
+
+#[test]
+fn test_invalid_calls_get_ocpp201_error_codes() {
+    let (mut app, channels, _) = boot_site();
+
+    let error_code = |reply: EOutgoingOcppMessage| match reply {
+        EOutgoingOcppMessage::CallError(error) => error.error_code,
+        other => panic!("Expected CALLERROR, got {:?}", other),
+    };
+    let (reply, _) = call_201(&mut app, &channels, "bad-1", "TransactionEvent", json!({ "eventType": "Started" }));
+    assert_eq!(error_code(reply), "FormatViolation");
+    let (reply, _) = call_201(&mut app, &channels, "bad-2", "StartTransaction", json!({}));
+    assert_eq!(error_code(reply), "NotImplemented");
+    let (reply, _) = call_201(&mut app, &channels, "bad-3", "SetChargingProfile", json!({}));
+    assert_eq!(error_code(reply), "NotSupported");
+
+    // The 1.6 charger on the same site keeps the 1.6 names.
+    send(&mut app, &channels, "CH16", "bad-4", "StartTransaction", json!({ "connectorId": 1 }));
+    let reply = channels.ocpp_to_asset_receiver.try_iter().find(|cmd| cmd.ocpp_message_id.as_deref() == Some("bad-4")).unwrap();
+    assert_eq!(error_code(reply.message_type), "FormationViolation");
+}
@@ -0,0 +1,298 @@
+use ocpp_bevy_poc::asset_template_plugin::config::{AssetInstance, ComponentConfig};
+use ocpp_bevy_poc::asset_template_plugin::{load_site_config, load_site_config_with_env, save_site_assets, LoadedSiteConfig, SiteConfig};
+use ocpp_bevy_poc::common::error::AppError;
+use std::path::{Path, PathBuf};
+
+const VENDOR_TEMPLATES_TOML: &str = r#"
+[asset_templates.Charger_Template]
+asset_type = "Charger"
+components = [
+    { type = "asset_info", make = "Alfen", model = "Eve Single Pro-Line" },
+    { type = "charger_electrical_config", nominal_voltage_ln = 230.0, active_phase_count = 3 },
+    { type = "ocpp_profile_behavior", rate_unit = "Watts", profile_phases_in_ocpp_message = 3 },
+]
+
+[asset_templates.Battery_Template]
+asset_type = "Battery"
+components = [
+    { type = "asset_info", make = "Generic", model = "ESS-100kWh" },
+    { type = "modbus_control_config", ip = "127.0.0.1", port = 502, unit_id = 1, write_register_map_key = "battery_write_regs" },
+]
+
+[write_register_maps.battery_write_regs]
+setpoint = { address = 200, data_type = "I32", scale = 1000.0, sign_convention = "DischargePositive" }
+"#;
+
+const SITE_YAML: &str = r#"
+include: vendor.toml
+assets:
+  - external_id: CH001
+    template_id: Charger_Template
+    instance_components:
+      - { type: ocpp_config, version: V1_6J, charge_point_id: CH001 }
+  - external_id: BAT001
+    template_id: Battery_Template
+    instance_components: []
+    overrides:
+      modbus_control_config:
+        ip: ${BATTERY_IP}
+        port: ${BATTERY_PORT:-5021}
+asset_groups:
+  chargers: [CH001]
+"#;
+
+/// A fresh directory holding `files`, removed again when dropped.
+struct ConfigDir(PathBuf);
+
+impl ConfigDir {
+    fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("site_config_file_test_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, text) in files {
+            std::fs::write(dir.join(file), text).unwrap();
+        }
+        ConfigDir(dir)
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.0.join(file)
+    }
+}
+
+impl Drop for ConfigDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn test_env(name: &str) -> Option<String> {
+    match name {
+        "BATTERY_IP" => Some("10.0.0.7".to_string()),
+        _ => None,
+    }
+}
+
+fn load(path: &Path) -> Result<LoadedSiteConfig, Vec<AppError>> {
+    load_site_config_with_env(path, &test_env)
+}
+
+fn load_errors(path: &Path) -> Vec<AppError> {
+    match load(path) {
+        Ok(_) => panic!("config should have been rejected"),
+        Err(errors) => errors,
+    }
+}
+
+fn battery_control(config: &SiteConfig) -> (String, u16) {
+    let battery = config.assets.iter().position(|instance| instance.external_id == "BAT001").unwrap();
+    let resolved = config.resolve_asset(battery).expect("battery resolves");
+    let control = resolved.component_configs().find_map(|component| match component {
+        ComponentConfig::ModbusControlConfig { ip, port, .. } => Some((ip.clone(), *port)),
+        _ => None,
+    });
+    control.expect("modbus control config")
+}
+
+#[test]
+fn test_same_config_in_each_format() {
+    let json = r#"{ "asset_templates": { "T": { "asset_type": "Battery", "components": [] } },
+        "assets": [ { "external_id": "BAT001", "template_id": "T", "instance_components": [] } ] }"#;
+    let toml = "[asset_templates.T]\nasset_type = \"Battery\"\ncomponents = []\n\n[[assets]]\nexternal_id = \"BAT001\"\ntemplate_id = \"T\"\ninstance_components = []\n";
+    let yaml = "asset_templates:\n  T: { asset_type: Battery, components: [] }\nassets:\n  - { external_id: BAT001, template_id: T, instance_components: [] }\n";
+    let dir = ConfigDir::new("formats", &[("site.json", json), ("site.toml", toml), ("site.yaml", yaml), ("site.yml", yaml)]);
+
+    let configs: Vec<SiteConfig> = ["site.json", "site.toml", "site.yaml", "site.yml"].iter()
+        .map(|file| SiteConfig::from_json(&load(&dir.path(file)).expect("loads").json).expect("valid site config"))
+        .collect();
+    for config in &configs {
+        assert_eq!(config.assets, configs[0].assets);
+        assert!(config.asset_templates.contains_key("T"));
+    }
+}
+
+#[test]
+fn test_site_file_includes_vendor_library_with_env_placeholders() {
+    let dir = ConfigDir::new("include", &[("vendor.toml", VENDOR_TEMPLATES_TOML), ("site.yaml", SITE_YAML)]);
+
+    let loaded = load(&dir.path("site.yaml")).expect("loads");
+    assert_eq!(loaded.files, vec![dir.path("site.yaml"), dir.path("vendor.toml")]);
+    let config = SiteConfig::from_json(&loaded.json).expect("valid site config");
+    assert_eq!(config.asset_templates.len(), 2);
+    assert_eq!(config.write_register_maps.len(), 1);
+    assert_eq!(config.asset_groups["chargers"], vec!["CH001"]);
+    // BATTERY_IP is set; BATTERY_PORT falls back to its default
+    assert_eq!(battery_control(&config), ("10.0.0.7".to_string(), 5021));
+}
+
+#[test]
+fn test_including_file_wins_and_assets_are_appended() {
+    let shared = r#"{ "asset_templates": { "T": { "asset_type": "Battery", "components": [] },
+                                           "U": { "asset_type": "Battery", "components": [] } },
+        "assets": [ { "external_id": "BAT001", "template_id": "T", "instance_components": [] } ],
+        "balancer_fallback": { "timeout_secs": 30.0, "site_power_kw": 50.0 } }"#;
+    let site = r#"{ "include": ["shared.json"],
+        "asset_templates": { "U": { "asset_type": "GridConnection", "components": [] } },
+        "assets": [ { "external_id": "GRID001", "template_id": "U", "instance_components": [] } ],
+        "balancer_fallback": { "timeout_secs": 10.0, "site_power_kw": 20.0 } }"#;
+    let dir = ConfigDir::new("merge", &[("shared.json", shared), ("site.json", site)]);
+
+    let config = SiteConfig::from_json(&load(&dir.path("site.json")).unwrap().json).expect("valid site config");
+    let ids: Vec<&str> = config.assets.iter().map(|instance| instance.external_id.as_str()).collect();
+    assert_eq!(ids, vec!["BAT001", "GRID001"]);
+    assert_eq!(config.asset_templates.len(), 2);
+    assert_eq!(config.balancer_fallback.unwrap().timeout_secs, 10.0);
+}
+
+#[test]
+fn test_file_errors() {
+    let dir = ConfigDir::new("errors", &[
+        ("site.ini", "assets = []"),
+        ("broken.yaml", "assets: [ unclosed"),
+        ("a.json", r#"{ "include": "b.json" }"#),
+        ("b.json", r#"{ "include": "a.json" }"#),
+        ("missing_include.yaml", "include: nowhere.toml\n"),
+        ("vendor.toml", VENDOR_TEMPLATES_TOML),
+        ("site.yaml", &SITE_YAML.replace("${BATTERY_PORT:-5021}", "${BATTERY_PORT}")),
+    ]);
+
+    assert!(matches!(load_errors(&dir.path("site.ini")).as_slice(), [AppError::UnsupportedConfigFormat { path }] if path.ends_with("site.ini")));
+    assert!(matches!(load_errors(&dir.path("broken.yaml")).as_slice(), [AppError::ConfigFileInvalid { path, .. }] if path.ends_with("broken.yaml")));
+    assert!(matches!(load_errors(&dir.path("missing_include.yaml")).as_slice(),
+        [AppError::ConfigFileUnreadable { path, .. }] if path.ends_with("nowhere.toml")));
+    let errors = load_errors(&dir.path("a.json"));
+    assert!(matches!(errors.as_slice(), [AppError::IncludeCycle { path, cycle }]
+        if path.ends_with("a.json") && cycle.matches("a.json").count() == 2 && cycle.contains("b.json")), "{errors:?}");
+    assert!(matches!(load_errors(&dir.path("site.yaml")).as_slice(),
+        [AppError::UndefinedEnvVar { path, name }] if path.ends_with("site.yaml") && name == "BATTERY_PORT"));
+}
+
+#[test]
+fn test_saved_assets_keep_format_includes_and_placeholders() {
+    // Saving reads the process environment, so every placeholder here has a default
+    let site_yaml = SITE_YAML.replace("${BATTERY_IP}", "${BATTERY_IP:-10.0.0.9}");
+    let dir = ConfigDir::new("save", &[("vendor.toml", VENDOR_TEMPLATES_TOML), ("site.yaml", &site_yaml)]);
+    let site = dir.path("site.yaml");
+
+    let mut assets = SiteConfig::from_json(&load_site_config(&site).unwrap().json).expect("valid site config").assets;
+    assets.push(AssetInstance {
+        external_id: "CH002".into(),
+        template_id: "Charger_Template".into(),
+        instance_components: vec![ComponentConfig::OcppConfig { version: "V1_6J".into(), charge_point_id: "CH002".into() }],
+        overrides: Default::default(),
+    });
+    save_site_assets(&site, &assets).expect("saved");
+
+    let saved = std::fs::read_to_string(&site).unwrap();
+    assert!(saved.contains("include: vendor.toml"), "{saved}");
+    assert!(saved.contains("${BATTERY_PORT:-5021}"), "{saved}");
+    let config = SiteConfig::from_json(&load(&site).unwrap().json).expect("saved config is valid");
+    let ids: Vec<&str> = config.assets.iter().map(|instance| instance.external_id.as_str()).collect();
+    assert_eq!(ids, vec!["CH001", "BAT001", "CH002"]);
+    assert_eq!(config.asset_templates.len(), 2);
+    assert_eq!(battery_control(&config), ("10.0.0.7".to_string(), 5021));
+}
+
+#[test]
+fn test_saved_json_keeps_unquoted_placeholders() {
+    let site_json = r#"{ "include": "vendor.toml", "assets": [
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [],
+          "overrides": { "modbus_control_config": { "ip": "${BATTERY_IP:-10.0.0.9}", "port": ${BATTERY_PORT:-5021} } } }
+    ] }"#;
+    let dir = ConfigDir::new("save_unquoted", &[("vendor.toml", VENDOR_TEMPLATES_TOML), ("site.json", site_json)]);
+    let site = dir.path("site.json");
+
+    // Saving reads the process environment, as loading the assets to save does here
+    let mut assets = SiteConfig::from_json(&load_site_config(&site).unwrap().json).expect("valid site config").assets;
+    assets.push(AssetInstance {
+        external_id: "CH001".into(),
+        template_id: "Charger_Template".into(),
+        instance_components: vec![ComponentConfig::OcppConfig { version: "V1_6J".into(), charge_point_id: "CH001".into() }],
+        overrides: Default::default(),
+    });
+    save_site_assets(&site, &assets).expect("saved");
+
+    let saved = std::fs::read_to_string(&site).unwrap();
+    assert!(saved.contains(r#""port": ${BATTERY_PORT:-5021}"#), "{saved}");
+    assert!(saved.contains(r#""ip": "${BATTERY_IP:-10.0.0.9}""#), "{saved}");
+    let config = SiteConfig::from_json(&load(&site).unwrap().json).expect("saved config is valid");
+    assert_eq!(config.assets.len(), 2);
+    assert_eq!(battery_control(&config), ("10.0.0.7".to_string(), 5021));
+}
+
+#[test]
+fn test_saving_rewrites_only_the_assets_section() {
+    let site_toml = r#"# Site file kept by hand
+include = "vendor.toml"
+
+# Batteries first
+[[assets]]
+external_id = "BAT001"
+template_id = "Battery_Template"
+instance_components = []
+
+[assets.overrides.modbus_control_config]
+ip = "${BATTERY_IP:-10.0.0.9}" # simulator
+
+[[assets]]
+external_id = "CH001"
+template_id = "Charger_Template"
+instance_components = [{ type = "ocpp_config", version = "V1_6J", charge_point_id = "CH001" }]
+
+[asset_groups]
+chargers = ["CH001"] # grouped for the balancer
+"#;
+    let site_yaml = SITE_YAML.replace("${BATTERY_IP}", "${BATTERY_IP:-10.0.0.9}").replace("assets:\n", "# Site assets\nassets:\n")
+        .replace("asset_groups:", "# Groups the balancer can target\nasset_groups:");
+    let site_json = r#"{
+    "include": "vendor.toml",
+    "asset_groups": { "chargers": ["CH001"] },
+    "assets": [
+        { "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [
+            { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" }
+        ] },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [],
+          "overrides": { "modbus_control_config": { "ip": "${BATTERY_IP:-10.0.0.9}" } } }
+    ],
+    "balancer_fallback": { "timeout_secs": 30.0, "site_power_kw": 50.0 }
+}
+"#;
+    let dir = ConfigDir::new("save_section", &[("vendor.toml", VENDOR_TEMPLATES_TOML), ("site.toml", site_toml), ("site.yaml", &site_yaml), ("site.json", site_json)]);
+
+    for (file, kept) in [
+        ("site.toml", vec!["# Site file kept by hand\ninclude = \"vendor.toml\"\n", "# simulator", "chargers = [\"CH001\"] # grouped for the balancer"]),
+        ("site.yaml", vec!["include: vendor.toml\n", "# Site assets\nassets:\n", "# Groups the balancer can target\nasset_groups:\n  chargers: [CH001]\n"]),
+        ("site.json", vec!["{\n    \"include\": \"vendor.toml\",\n    \"asset_groups\": { \"chargers\": [\"CH001\"] },\n    \"assets\": [", "\"balancer_fallback\": { \"timeout_secs\": 30.0, \"site_power_kw\": 50.0 }\n}\n"]),
+    ] {
+        let site = dir.path(file);
+        let mut assets = SiteConfig::from_json(&load_site_config(&site).unwrap().json).expect("valid site config").assets;
+        let ch001 = assets.iter_mut().find(|asset| asset.external_id == "CH001").unwrap();
+        ch001.instance_components = vec![ComponentConfig::OcppConfig { version: "V1_6J".into(), charge_point_id: "CH001-A".into() }];
+        assets.push(AssetInstance {
+            external_id: "CH002".into(),
+            template_id: "Charger_Template".into(),
+            instance_components: vec![ComponentConfig::OcppConfig { version: "V1_6J".into(), charge_point_id: "CH002".into() }],
+            overrides: Default::default(),
+        });
+        save_site_assets(&site, &assets).expect("saved");
+
+        let saved = std::fs::read_to_string(&site).unwrap();
+        for text in kept {
+            assert!(saved.contains(text), "{file} lost {text:?}:\n{saved}");
+        }
+        assert!(saved.contains("${BATTERY_IP:-10.0.0.9}"), "{saved}");
+        let config = SiteConfig::from_json(&load(&site).unwrap().json).expect("saved config is valid");
+        let mut ids: Vec<&str> = config.assets.iter().map(|instance| instance.external_id.as_str()).collect();
+        ids.sort();
+        assert_eq!(ids, vec!["BAT001", "CH001", "CH002"], "{saved}");
+        assert!(saved.contains("CH001-A"), "{saved}");
+    }
+}
+
+#[test]
+fn test_shipped_example_site_loads() {
+    let loaded = load_site_config(Path::new("assets/example_site.yaml")).expect("example site loads");
+    assert_eq!(loaded.files.len(), 2);
+    let config = SiteConfig::from_json(&loaded.json).expect("valid site config");
+    assert_eq!(config.assets.len(), 3);
+}
@@ -0,0 +1,262 @@
+mod common;
+
+use bevy::prelude::*;
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppExternalChannelEnds, AppMode};
+use ocpp_bevy_poc::asset_template_plugin::{watch_site_config_file, SiteConfig};
+use ocpp_bevy_poc::balancer_comms_plugin::{BalancerFallbackConfig, FallbackWeight, SafeSetpointKw};
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::ExternalId;
+use ocpp_bevy_poc::modbus_protocol_plugin::{ModbusPollTimer, ModbusRegisterMaps, ModbusRequestTracker};
+use ocpp_bevy_poc::ocpp_protocol_plugin::{Guns, OcppConfig, OcppConnectionState, OcppRequestFromAsset};
+use std::time::Duration;
+
+const TEMPLATES_JSON: &str = r#"{
+    "Charger_Template": {
+        "asset_type": "Charger",
+        "components": [
+            { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" },
+            { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+            { "type": "ocpp_profile_behavior", "rate_unit": "Watts", "profile_phases_in_ocpp_message": 3 },
+            { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+        ]
+    },
+    "Battery_Template": {
+        "asset_type": "Battery",
+        "components": [
+            { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" }
+        ]
+    }
+}"#;
+
+fn site_config(assets: &str, extra_sections: &str) -> String {
+    format!(r#"{{ "asset_templates": {TEMPLATES_JSON}, "assets": {assets}{extra_sections} }}"#)
+}
+
+fn charger(id: &str) -> String {
+    format!(r#"{{ "external_id": "{id}", "template_id": "Charger_Template", "instance_components": [
+        {{ "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "{id}" }}
+    ] }}"#)
+}
+
+fn battery(id: &str, components: &str) -> String {
+    format!(r#"{{ "external_id": "{id}", "template_id": "Battery_Template", "instance_components": [{components}] }}"#)
+}
+
+/// CH001 and CH002 booted, plus a battery with a fallback weight and a safe setpoint.
+fn started_site() -> (App, AppExternalChannelEnds) {
+    let assets = format!("[{}, {}, {}]", charger("CH001"), charger("CH002"), battery("BAT001", r#"
+        { "type": "fallback_weight", "weight": 1.0 },
+        { "type": "safe_setpoint", "power_kw": 5.0 }"#));
+    let config = site_config(&assets, r#", "balancer_fallback": { "timeout_secs": 30.0, "site_power_kw": 50.0 }"#);
+    let (mut app, channels) = setup_bevy_app(config, AppMode::Headless, None).expect("valid site config");
+    app.update();
+    for cp_id in ["CH001", "CH002"] {
+        common::boot_charger(&mut app, &channels, cp_id);
+    }
+    (app, channels)
+}
+
+fn reload(app: &mut App, channels: &AppExternalChannelEnds, config_json: String) {
+    channels.site_config_reload_sender.send(config_json).unwrap();
+    app.update();
+    app.update();
+}
+
+fn entity(app: &App, external_id: &str) -> Option<Entity> {
+    app.world().resource::<ExternalIdMap>().0.get(external_id).copied()
+}
+
+fn external_ids(app: &mut App) -> Vec<String> {
+    let mut ids: Vec<String> = app.world_mut().query::<&ExternalId>().iter(app.world()).map(|id| id.0.clone()).collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_reload_adds_removes_and_updates_assets() {
+    let (mut app, channels) = started_site();
+    let ch001 = entity(&app, "CH001").unwrap();
+    let bat001 = entity(&app, "BAT001").unwrap();
+
+    let assets = format!("[{}, {}, {}]", charger("CH001"), battery("BAT001", r#"{ "type": "fallback_weight", "weight": 3.0 }"#), charger("CH003"));
+    reload(&mut app, &channels, site_config(&assets, ""));
+
+    assert_eq!(external_ids(&mut app), vec!["BAT001", "CH001", "CH003"]);
+    assert!(entity(&app, "CH002").is_none());
+    assert!(entity(&app, "CH003").is_some());
+    assert_eq!(app.world().resource::<SiteConfig>().assets.len(), 3);
+
+    // Unchanged charger keeps its entity and its session
+    assert_eq!(entity(&app, "CH001"), Some(ch001));
+    assert!(app.world().get::<OcppConnectionState>(ch001).unwrap().is_connected);
+
+    // Changed battery is updated in place: new weight, safe setpoint gone
+    assert_eq!(entity(&app, "BAT001"), Some(bat001));
+    assert_eq!(app.world().get::<FallbackWeight>(bat001).unwrap().0, 3.0);
+    assert!(app.world().get::<SafeSetpointKw>(bat001).is_none());
+
+    // Site-wide sections follow the new config
+    assert!(app.world().get_resource::<BalancerFallbackConfig>().is_none());
+}
+
+#[test]
+fn test_changed_charger_keeps_its_entity() {
+    let (mut app, channels) = started_site();
+    let ch002 = entity(&app, "CH002").unwrap();
+
+    let renamed = r#"{ "external_id": "CH002", "template_id": "Charger_Template", "instance_components": [
+        { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH002-NEW" }
+    ] }"#;
+    let assets = format!("[{}, {}, {}]", charger("CH001"), renamed, battery("BAT001", ""));
+    reload(&mut app, &channels, site_config(&assets, ""));
+
+    assert_eq!(entity(&app, "CH002"), Some(ch002));
+    assert_eq!(app.world().get::<OcppConfig>(ch002).unwrap().charge_point_id, "CH002-NEW");
+    assert!(app.world().get::<OcppConnectionState>(ch002).unwrap().is_connected);
+}
+
+#[test]
+fn test_invalid_reload_keeps_current_config() {
+    let (mut app, channels) = started_site();
+
+    let assets = format!("[{}, {}]", charger("CH001"), charger("CH001"));
+    reload(&mut app, &channels, site_config(&assets, ""));
+    reload(&mut app, &channels, "{ not json".to_string());
+
+    assert_eq!(external_ids(&mut app), vec!["BAT001", "CH001", "CH002"]);
+    assert_eq!(app.world().resource::<SiteConfig>().assets.len(), 3);
+    assert!(app.world().get_resource::<BalancerFallbackConfig>().is_some());
+}
+
+#[test]
+fn test_rejected_asset_change_keeps_its_current_entry() {
+    let (mut app, channels) = started_site();
+    let bat001 = entity(&app, "BAT001").unwrap();
+    let current = app.world().resource::<SiteConfig>().assets[2].clone();
+
+    // The unknown write register map is only caught when the battery is spawned, so the rest of the reload applies
+    let rejected = battery("BAT001", r#"
+        { "type": "fallback_weight", "weight": 2.0 },
+        { "type": "modbus_control_config", "ip": "127.0.0.1", "port": 502, "unit_id": 1, "write_register_map_key": "missing" }"#);
+    reload(&mut app, &channels, site_config(&format!("[{}, {}, {}]", charger("CH001"), charger("CH002"), rejected), ""));
+    assert_eq!(app.world().resource::<SiteConfig>().assets[2], current);
+    assert_eq!(app.world().get::<FallbackWeight>(bat001).unwrap().0, 1.0);
+
+    // A follow-up edit is applied against what the battery actually has
+    let fixed = battery("BAT001", r#"{ "type": "fallback_weight", "weight": 2.0 }"#);
+    reload(&mut app, &channels, site_config(&format!("[{}, {}, {}]", charger("CH001"), charger("CH002"), fixed), ""));
+    assert_eq!(app.world().get::<FallbackWeight>(bat001).unwrap().0, 2.0);
+    assert!(app.world().get::<SafeSetpointKw>(bat001).is_none());
+}
+
+#[test]
+fn test_changed_connectors_keep_sessions_in_progress() {
+    let (mut app, channels) = started_site();
+    let ch001 = entity(&app, "CH001").unwrap();
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: "CH001".into(),
+        action: "StartTransaction".into(),
+        payload_json: r#"{"connectorId":1,"idTag":"TAG1","meterStart":0,"timestamp":"2025-01-01T10:00:00Z"}"#.into(),
+        ocpp_message_id: "start-1".into(),
+    }).unwrap();
+    app.update();
+    app.update();
+    let transaction_id = app.world().get::<Guns>(ch001).unwrap().0[0].transaction.as_ref().expect("session started").transaction_id;
+
+    let with_connectors = |count: u32, max_current_a: f32| {
+        let ch001 = format!(r#"{{ "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [
+            {{ "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" }},
+            {{ "type": "connectors", "count": {count}, "max_current_a": {max_current_a}, "phases": 3, "connector_type": "Type2" }}
+        ] }}"#);
+        site_config(&format!("[{}, {}, {}]", ch001, charger("CH002"), battery("BAT001", "")), "")
+    };
+    let connectors = |app: &App| -> Vec<(u32, Option<f32>, Option<i32>)> {
+        app.world().get::<Guns>(ch001).unwrap().0.iter()
+            .map(|gun| (gun.connector_id, gun.max_current_a, gun.transaction.as_ref().map(|tx| tx.transaction_id)))
+            .collect()
+    };
+
+    reload(&mut app, &channels, with_connectors(2, 16.0));
+    assert_eq!(connectors(&app), vec![(1, Some(16.0), Some(transaction_id)), (2, Some(16.0), None)]);
+    reload(&mut app, &channels, with_connectors(1, 32.0));
+    assert_eq!(connectors(&app), vec![(1, Some(32.0), Some(transaction_id))]);
+}
+
+#[test]
+fn test_poll_timer_follows_metering_source_changes() {
+    let (mut app, channels) = started_site();
+    let bat001 = entity(&app, "BAT001").unwrap();
+    let register_maps = r#", "register_maps": { "regs": { "registers": [ { "field": "power_kw", "address": 100, "kind": "Holding", "data_type": "F32" } ] } }"#;
+    let with_source = |metering_source: &str| {
+        let assets = format!("[{}, {}, {}]", charger("CH001"), charger("CH002"), battery("BAT001", metering_source));
+        site_config(&assets, register_maps)
+    };
+    let modbus_source = |poll_interval_ms: u32| format!(r#"{{ "type": "metering_source", "source_type": "Modbus", "details": {{ "modbus": {{
+        "ip": "127.0.0.1", "port": 502, "unit_id": 1, "poll_interval_ms": {poll_interval_ms}, "register_map_key": "regs" }} }} }}"#);
+    let poll_interval = |app: &App| app.world().get::<ModbusPollTimer>(bat001).map(|timer| timer.0.duration());
+
+    reload(&mut app, &channels, with_source(&modbus_source(1000)));
+    assert_eq!(poll_interval(&app), Some(Duration::from_secs(1)));
+    reload(&mut app, &channels, with_source(&modbus_source(3000)));
+    assert_eq!(poll_interval(&app), Some(Duration::from_secs(3)));
+
+    // Metered some other way, or not at all, the battery is no longer polled
+    reload(&mut app, &channels, with_source(r#"{ "type": "metering_source", "source_type": "InternalCalculation", "details": { "internal_calculation": {} } }"#));
+    assert_eq!(poll_interval(&app), None);
+    assert!(app.world().get::<ModbusRequestTracker>(bat001).is_none());
+    reload(&mut app, &channels, with_source(&modbus_source(1000)));
+    assert_eq!(poll_interval(&app), Some(Duration::from_secs(1)));
+    reload(&mut app, &channels, with_source(""));
+    assert_eq!(poll_interval(&app), None);
+    assert!(app.world().get::<ModbusRequestTracker>(bat001).is_none());
+}
+
+#[test]
+fn test_register_maps_shared_with_bridge_follow_reload() {
+    let (mut app, channels) = started_site();
+    // The bridge holds a clone of the resource, as main.rs gives it one
+    let bridge_maps = app.world().resource::<ModbusRegisterMaps>().clone();
+    assert!(bridge_maps.read_map("regs").is_none());
+
+    let assets = format!("[{}, {}, {}]", charger("CH001"), charger("CH002"), battery("BAT001", ""));
+    reload(&mut app, &channels, site_config(&assets, r#",
+        "register_maps": { "regs": { "registers": [ { "field": "power_kw", "address": 100, "kind": "Holding", "data_type": "F32" } ] } },
+        "write_register_maps": { "write_regs": { "setpoint": { "address": 200, "data_type": "I32", "scale": 1000.0 } } }"#));
+    assert_eq!(bridge_maps.read_map("regs").unwrap().registers[0].address, 100);
+    assert_eq!(bridge_maps.write_map("write_regs").unwrap().setpoint.address, 200);
+
+    reload(&mut app, &channels, site_config(&assets, ""));
+    assert!(bridge_maps.read_map("regs").is_none());
+    assert!(bridge_maps.write_map("write_regs").is_none());
+}
+
+#[test]
+fn test_watcher_sends_changed_file_and_includes() {
+    let dir = std::env::temp_dir().join(format!("site_config_reload_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (site, templates) = (dir.join("site.json"), dir.join("templates.json"));
+    std::fs::write(&templates, format!(r#"{{ "asset_templates": {TEMPLATES_JSON} }}"#)).unwrap();
+    let site_file = |assets: &str| format!(r#"{{ "include": "templates.json", "assets": {assets} }}"#);
+    std::fs::write(&site, site_file(&format!("[{}]", charger("CH001")))).unwrap();
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    let watched = site.clone();
+    std::thread::spawn(move || watch_site_config_file(watched, sender, Duration::from_millis(20)));
+
+    // Unchanged files are not sent; filesystem timestamps can be coarse, so wait before rewriting
+    std::thread::sleep(Duration::from_millis(1100));
+    assert!(receiver.try_recv().is_err());
+    std::fs::write(&site, site_file(&format!("[{}, {}]", charger("CH001"), charger("CH002")))).unwrap();
+    let config = SiteConfig::from_json(&receiver.recv_timeout(Duration::from_secs(5)).unwrap()).expect("valid site config");
+    assert_eq!(config.assets.len(), 2);
+    assert_eq!(config.asset_templates.len(), 2);
+
+    // Editing the included file reloads too; a file that fails to load is not sent
+    std::thread::sleep(Duration::from_millis(1100));
+    std::fs::write(&templates, "{ not json").unwrap();
+    assert!(receiver.recv_timeout(Duration::from_millis(500)).is_err());
+    std::thread::sleep(Duration::from_millis(1100));
+    std::fs::write(&templates, r#"{ "asset_templates": { "Charger_Template": { "asset_type": "Charger", "components": [] } } }"#).unwrap();
+    let config = SiteConfig::from_json(&receiver.recv_timeout(Duration::from_secs(5)).unwrap()).expect("valid site config");
+    assert_eq!(config.asset_templates.len(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
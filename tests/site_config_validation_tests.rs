@@ -0,0 +1,159 @@
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppMode};
+use ocpp_bevy_poc::asset_template_plugin::SiteConfig;
+use ocpp_bevy_poc::common::error::AppError;
+use ocpp_bevy_poc::common::types::EAssetType;
+
+const TEMPLATES_JSON: &str = r#"{
+    "Charger_Template": {
+        "asset_type": "Charger",
+        "components": [
+            { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" },
+            { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+            { "type": "ocpp_profile_behavior", "rate_unit": "Watts", "profile_phases_in_ocpp_message": 3 }
+        ]
+    },
+    "Battery_Template": {
+        "asset_type": "Battery",
+        "components": [
+            { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" }
+        ]
+    }
+}"#;
+
+fn site_config(assets: &str) -> String {
+    format!(r#"{{ "asset_templates": {TEMPLATES_JSON}, "assets": {assets} }}"#)
+}
+
+fn validation_errors(assets: &str) -> Vec<AppError> {
+    match setup_bevy_app(site_config(assets), AppMode::Headless, None) {
+        Ok(_) => panic!("config should have been rejected"),
+        Err(errors) => errors,
+    }
+}
+
+#[test]
+fn test_valid_config_builds_app() {
+    let assets = r#"[
+        { "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [
+            { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "CH001" }
+        ] },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] }
+    ]"#;
+    assert!(setup_bevy_app(site_config(assets), AppMode::Headless, None).is_ok());
+
+    let shipped = std::fs::read_to_string("assets/site_config.json").expect("Failed to read site_config.json");
+    if let Err(errors) = SiteConfig::from_json(&shipped) {
+        panic!("assets/site_config.json is invalid: {:?}", errors);
+    }
+}
+
+#[test]
+fn test_unparseable_json_is_a_serialization_error() {
+    let errors = setup_bevy_app("{ not json".to_string(), AppMode::Headless, None).err().unwrap();
+    assert!(matches!(errors.as_slice(), [AppError::SerializationError(_)]));
+}
+
+#[test]
+fn test_unknown_template_and_duplicate_external_id() {
+    let errors = validation_errors(r#"[
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] },
+        { "external_id": "PV001", "template_id": "Solar_Template", "instance_components": [] },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] }
+    ]"#);
+
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(&errors[0], AppError::UnknownTemplate { asset_id, template_id, path }
+        if asset_id == "PV001" && template_id == "Solar_Template" && path == "assets[1].template_id"));
+    assert!(matches!(&errors[1], AppError::DuplicateExternalId { asset_id, path, .. }
+        if asset_id == "BAT001" && path == "assets[2].external_id"));
+}
+
+#[test]
+fn test_bad_enum_strings_are_reported_with_their_path() {
+    let errors = validation_errors(r#"[
+        { "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [
+            { "type": "ocpp_config", "version": "V1_5", "charge_point_id": "CH001" },
+            { "type": "ocpp_profile_behavior", "rate_unit": "Kilowatts", "profile_phases_in_ocpp_message": 3 },
+            { "type": "connectors", "count": 2, "max_current_a": 32.0, "phases": 3, "connector_type": "Schuko" },
+            { "type": "metering_source", "source_type": "Mqtt", "details": { "ocpp": {} } }
+        ] }
+    ]"#);
+
+    // Reported in the order of the asset's resolved components; the instance's profile behavior replaces the template's
+    let invalid: Vec<(&str, &str)> = errors
+        .iter()
+        .map(|e| match e {
+            AppError::InvalidEnumValue { asset_id, template_id, path, value, .. } => {
+                assert_eq!(asset_id, "CH001");
+                assert_eq!(template_id, "Charger_Template");
+                (path.as_str(), value.as_str())
+            }
+            other => panic!("unexpected error {other:?}"),
+        })
+        .collect();
+    assert_eq!(invalid, vec![
+        ("assets[0].instance_components[1].rate_unit", "Kilowatts"),
+        ("assets[0].instance_components[0].version", "V1_5"),
+        ("assets[0].instance_components[2].connector_type", "Schuko"),
+        ("assets[0].instance_components[3].source_type", "Mqtt"),
+    ]);
+}
+
+#[test]
+fn test_invalid_metering_details_are_reported() {
+    let errors = validation_errors(r#"[
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [
+            { "type": "metering_source", "source_type": "Modbus", "details": { "modbus": { "ip": "127.0.0.1" } } }
+        ] }
+    ]"#);
+
+    assert!(matches!(errors.as_slice(), [AppError::InvalidComponent { path, component: "metering_source", .. }]
+        if path == "assets[0].instance_components[0].details"));
+}
+
+#[test]
+fn test_component_not_allowed_for_asset_type() {
+    let errors = validation_errors(r#"[
+        { "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [
+            { "type": "modbus_control_config", "ip": "127.0.0.1", "port": 502, "unit_id": 1, "write_register_map_key": "regs" }
+        ] },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [
+            { "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "BAT001" }
+        ] }
+    ]"#);
+
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(&errors[0], AppError::ComponentNotAllowed { asset_id, component: "modbus_control_config", asset_type: EAssetType::Charger, .. }
+        if asset_id == "CH001"));
+    assert!(matches!(&errors[1], AppError::ComponentNotAllowed { asset_id, path, component: "ocpp_config", asset_type: EAssetType::Battery, .. }
+        if asset_id == "BAT001" && path == "assets[1].instance_components[0]"));
+}
+
+#[test]
+fn test_missing_required_component() {
+    let errors = validation_errors(r#"[
+        { "external_id": "CH002", "template_id": "Charger_Template", "instance_components": [
+            { "type": "alfen_specific_config", "default_tx_profile_power_watts": 11000.0 }
+        ] }
+    ]"#);
+
+    assert!(matches!(errors.as_slice(), [AppError::MissingComponent { asset_id, path, component: "ocpp_config", asset_type: EAssetType::Charger, .. }]
+        if asset_id == "CH002" && path == "assets[0]"));
+}
+
+#[test]
+fn test_unknown_asset_group_members() {
+    let config = format!(r#"{{ "asset_templates": {TEMPLATES_JSON},
+        "assets": [ {{ "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] }} ],
+        "asset_groups": {{ "storage": ["BAT001", "BAT01"], "chargers": ["CH001"] }} }}"#);
+    let errors = match SiteConfig::from_json(&config) {
+        Ok(_) => panic!("config should have been rejected"),
+        Err(errors) => errors,
+    };
+
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    assert!(matches!(&errors[0], AppError::UnknownGroupMember { group_id, path, asset_id }
+        if group_id == "chargers" && path == "asset_groups.chargers[0]" && asset_id == "CH001"));
+    assert!(matches!(&errors[1], AppError::UnknownGroupMember { group_id, path, asset_id }
+        if group_id == "storage" && path == "asset_groups.storage[1]" && asset_id == "BAT01"));
+}
@@ -0,0 +1,109 @@
+mod common;
+
+use bevy::prelude::*;
+use ocpp_bevy_poc::app_setup::AppExternalChannelEnds;
+use ocpp_bevy_poc::balancer_comms_plugin::balancer_messages::BalancerSetpointMessage;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::core_asset_plugin::{CurrentMeterReading, TargetPowerSetpointKw};
+
+/// Two chargers and a battery behind a metered grid connection allowing 20 kW import and 10 kW export.
+const SITE_CONFIG_JSON: &str = r#"{
+    "asset_templates": {
+        "Charger_Template": {
+            "asset_type": "Charger",
+            "components": [
+                { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" },
+                { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+                { "type": "ocpp_profile_behavior", "rate_unit": "Watts", "profile_phases_in_ocpp_message": 3 }
+            ]
+        },
+        "Battery_Template": {
+            "asset_type": "Battery",
+            "components": [
+                { "type": "asset_info", "make": "Generic", "model": "ESS-100kWh" }
+            ]
+        },
+        "Grid_Template": {
+            "asset_type": "GridConnection",
+            "components": [
+                { "type": "asset_info", "make": "Generic", "model": "Grid Meter" },
+                { "type": "metering_source", "source_type": "InternalCalculation", "details": { "internal_calculation": {} } },
+                { "type": "grid_connection_limits", "import_capacity_kw": 20.0, "export_capacity_kw": 10.0 }
+            ]
+        }
+    },
+    "assets": [
+        { "external_id": "CH001", "template_id": "Charger_Template", "instance_components": [] },
+        { "external_id": "CH002", "template_id": "Charger_Template", "instance_components": [] },
+        { "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [] },
+        { "external_id": "GRID", "template_id": "Grid_Template", "instance_components": [] }
+    ]
+}"#;
+
+fn request(app: &mut App, channels: &AppExternalChannelEnds, setpoints: &[(&str, f32)]) {
+    for &(external_id, target_power_kw) in setpoints {
+        channels.balancer_setpoint_sender.send(BalancerSetpointMessage { external_id: external_id.into(), target_power_kw, ..Default::default() }).unwrap();
+    }
+    app.update();
+}
+
+fn set_measured_power(app: &mut App, external_id: &str, power_kw: f32) {
+    let entity = app.world().resource::<ExternalIdMap>().0[external_id];
+    app.world_mut().get_mut::<CurrentMeterReading>(entity).unwrap().power_kw = power_kw;
+    app.update();
+}
+
+fn target(app: &App, external_id: &str) -> f32 {
+    let entity = app.world().resource::<ExternalIdMap>().0[external_id];
+    app.world().get::<TargetPowerSetpointKw>(entity).unwrap().0
+}
+
+fn assert_targets(app: &App, expected: &[(&str, f32)]) {
+    for &(external_id, kw) in expected {
+        assert!((target(app, external_id) - kw).abs() < 1e-4, "'{external_id}' target {} kW, expected {kw} kW", target(app, external_id));
+    }
+}
+
+#[test]
+fn test_requests_within_capacity_pass_through() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    request(&mut app, &channels, &[("CH001", 8.0), ("CH002", 7.0), ("BAT001", -5.0)]);
+    assert_targets(&app, &[("CH001", 8.0), ("CH002", 7.0), ("BAT001", -5.0)]);
+}
+
+#[test]
+fn test_import_scaled_proportionally_to_capacity() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    request(&mut app, &channels, &[("CH001", 20.0), ("CH002", 10.0)]);
+    assert_targets(&app, &[("CH001", 40.0 / 3.0), ("CH002", 20.0 / 3.0)]);
+
+    // A discharging battery makes room for the chargers.
+    request(&mut app, &channels, &[("BAT001", -4.0)]);
+    assert_targets(&app, &[("CH001", 16.0), ("CH002", 8.0), ("BAT001", -4.0)]);
+
+    // Dropping back under the limit restores the full requests.
+    request(&mut app, &channels, &[("CH001", 10.0)]);
+    assert_targets(&app, &[("CH001", 10.0), ("CH002", 10.0), ("BAT001", -4.0)]);
+}
+
+#[test]
+fn test_measured_base_load_reduces_headroom() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    request(&mut app, &channels, &[("CH001", 5.0), ("CH002", 5.0)]);
+
+    // The grid sees 17 kW while the chargers draw 5 kW: 12 kW of base load leaves 8 kW.
+    set_measured_power(&mut app, "CH001", 5.0);
+    set_measured_power(&mut app, "GRID", 17.0);
+    assert_targets(&app, &[("CH001", 4.0), ("CH002", 4.0)]);
+
+    // Base load beyond the import capacity clamps consumption to zero.
+    set_measured_power(&mut app, "GRID", 30.0);
+    assert_targets(&app, &[("CH001", 0.0), ("CH002", 0.0)]);
+}
+
+#[test]
+fn test_export_scaled_to_capacity() {
+    let (mut app, channels) = common::start_app(SITE_CONFIG_JSON.to_string());
+    request(&mut app, &channels, &[("BAT001", -25.0), ("CH001", 5.0)]);
+    assert_targets(&app, &[("BAT001", -15.0), ("CH001", 5.0)]);
+}
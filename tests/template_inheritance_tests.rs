@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+use ocpp_bevy_poc::app_setup::{setup_bevy_app, AppExternalChannelEnds, AppMode};
+use ocpp_bevy_poc::asset_template_plugin::SiteConfig;
+use ocpp_bevy_poc::common::error::AppError;
+use ocpp_bevy_poc::common::external_id_map::ExternalIdMap;
+use ocpp_bevy_poc::common::types::EAssetType;
+use ocpp_bevy_poc::core_asset_plugin::{AssetInfo, MeteringSource, MeteringSourceDetails};
+use ocpp_bevy_poc::ocpp_protocol_plugin::{ChargerElectricalConfig, Guns, OcppConnectionState, OcppProfileBehavior, OcppRequestFromAsset};
+use ocpp_bevy_poc::ocpp_protocol_plugin::types::EChargingRateUnit;
+
+const TEMPLATES_JSON: &str = r#"{
+    "Charger_Base": {
+        "asset_type": "Charger",
+        "components": [
+            { "type": "asset_info", "make": "Generic", "model": "AC" },
+            { "type": "charger_electrical_config", "nominal_voltage_ln": 230.0, "active_phase_count": 3 },
+            { "type": "ocpp_profile_behavior", "rate_unit": "Amps", "profile_phases_in_ocpp_message": 3 },
+            { "type": "metering_source", "source_type": "Ocpp", "details": { "ocpp": {} } }
+        ]
+    },
+    "Alfen_Template": {
+        "extends": "Charger_Base",
+        "components": [
+            { "type": "asset_info", "make": "Alfen", "model": "Eve Single Pro-Line" },
+            { "type": "ocpp_profile_behavior", "rate_unit": "Watts", "profile_phases_in_ocpp_message": 1 }
+        ]
+    },
+    "Alfen_Dual_Template": {
+        "extends": "Alfen_Template",
+        "components": [
+            { "type": "connectors", "count": 2, "max_current_a": 16.0, "phases": 3, "connector_type": "Type2" }
+        ]
+    },
+    "Battery_Template": {
+        "asset_type": "Battery",
+        "components": [
+            { "type": "metering_source", "source_type": "Modbus", "details": { "modbus": {
+                "ip": "10.0.0.1", "port": 502, "unit_id": 1, "poll_interval_ms": 1000, "register_map_key": "regs" } } }
+        ]
+    }
+}"#;
+
+const REGISTER_MAPS_JSON: &str = r#"{
+    "regs": { "registers": [ { "field": "power_kw", "address": 0, "kind": "Holding", "data_type": "I16" } ] }
+}"#;
+
+fn site_config(templates: &str, assets: &str) -> String {
+    format!(r#"{{ "asset_templates": {templates}, "assets": {assets}, "register_maps": {REGISTER_MAPS_JSON} }}"#)
+}
+
+fn charger(id: &str, template_id: &str, overrides: &str) -> String {
+    format!(r#"{{ "external_id": "{id}", "template_id": "{template_id}", "instance_components": [
+        {{ "type": "ocpp_config", "version": "V1_6J", "charge_point_id": "{id}" }}
+    ], "overrides": {{ {overrides} }} }}"#)
+}
+
+fn started_site(assets: &str) -> (App, AppExternalChannelEnds) {
+    let (mut app, channels) = setup_bevy_app(site_config(TEMPLATES_JSON, assets), AppMode::Headless, None).expect("valid site config");
+    app.update();
+    (app, channels)
+}
+
+fn component<T: Component + Clone>(app: &App, external_id: &str) -> T {
+    let entity = app.world().resource::<ExternalIdMap>().0[external_id];
+    app.world().get::<T>(entity).expect("component present").clone()
+}
+
+fn validation_errors(templates: &str, assets: &str) -> Vec<AppError> {
+    match SiteConfig::from_json(&site_config(templates, assets)) {
+        Ok(_) => panic!("config should have been rejected"),
+        Err(errors) => errors,
+    }
+}
+
+#[test]
+fn test_child_templates_inherit_with_child_wins_per_type() {
+    let assets = format!("[{}, {}]", charger("CH001", "Alfen_Template", ""), charger("CH002", "Alfen_Dual_Template", ""));
+    let (app, _channels) = started_site(&assets);
+
+    for id in ["CH001", "CH002"] {
+        assert_eq!(component::<EAssetType>(&app, id), EAssetType::Charger);
+        assert_eq!(component::<AssetInfo>(&app, id).make, "Alfen");
+        assert_eq!(component::<OcppProfileBehavior>(&app, id).rate_unit, EChargingRateUnit::Watts);
+        assert_eq!(component::<ChargerElectricalConfig>(&app, id).active_phase_count, 3);
+        assert!(matches!(component::<MeteringSource>(&app, id).details, Some(MeteringSourceDetails::Ocpp {})));
+    }
+    assert_eq!(component::<Guns>(&app, "CH001").0.len(), 1);
+    assert_eq!(component::<Guns>(&app, "CH002").0.len(), 2);
+}
+
+#[test]
+fn test_instance_overrides_individual_fields() {
+    let assets = format!("[{}, {}]",
+        charger("CH001", "Alfen_Template", r#""charger_electrical_config": { "active_phase_count": 1 }"#),
+        r#"{ "external_id": "BAT001", "template_id": "Battery_Template", "instance_components": [],
+            "overrides": { "metering_source": { "details": { "modbus": { "ip": "10.0.0.7" } } } } }"#);
+    let (app, _channels) = started_site(&assets);
+
+    let electrical = component::<ChargerElectricalConfig>(&app, "CH001");
+    assert_eq!(electrical.active_phase_count, 1);
+    assert_eq!(electrical.nominal_voltage_ln, 230.0);
+
+    let Some(MeteringSourceDetails::Modbus { ip, port, register_map_key, .. }) = component::<MeteringSource>(&app, "BAT001").details else {
+        panic!("Modbus metering expected");
+    };
+    assert_eq!((ip.as_str(), port, register_map_key.as_str()), ("10.0.0.7", 502, "regs"));
+}
+
+#[test]
+fn test_inheritance_errors() {
+    let templates = r#"{
+        "A": { "extends": "B", "components": [] },
+        "B": { "extends": "A", "components": [] },
+        "Orphan": { "extends": "Missing", "components": [] },
+        "Untyped": { "components": [] }
+    }"#;
+    let assets = r#"[ { "external_id": "X001", "template_id": "A", "instance_components": [] } ]"#;
+    let errors = validation_errors(templates, assets);
+
+    assert_eq!(errors.len(), 4, "{errors:?}");
+    assert!(matches!(&errors[0], AppError::TemplateCycle { template_id, path, cycle }
+        if template_id == "A" && path == "asset_templates.B.extends" && cycle == "A -> B -> A"));
+    assert!(matches!(&errors[1], AppError::TemplateCycle { template_id, cycle, .. } if template_id == "B" && cycle == "B -> A -> B"));
+    assert!(matches!(&errors[2], AppError::UnknownParentTemplate { template_id, path, parent_id }
+        if template_id == "Orphan" && path == "asset_templates.Orphan.extends" && parent_id == "Missing"));
+    assert!(matches!(&errors[3], AppError::MissingAssetType { template_id, .. } if template_id == "Untyped"));
+}
+
+#[test]
+fn test_override_errors() {
+    let assets = format!("[{}, {}, {}]",
+        charger("CH001", "Alfen_Template", r#""alfen_specific_config": { "default_tx_profile_power_watts": 1500.0 }"#),
+        charger("CH002", "Alfen_Template", r#""charger_electrical_config": { "phase_count": 1 }"#),
+        charger("CH003", "Alfen_Template", r#""ocpp_profile_behavior": { "rate_unit": "Kilowatts" }"#));
+    let errors = validation_errors(TEMPLATES_JSON, &assets);
+
+    assert_eq!(errors.len(), 3, "{errors:?}");
+    assert!(matches!(&errors[0], AppError::UnknownOverride { asset_id, path, component, .. }
+        if asset_id == "CH001" && path == "assets[0].overrides.alfen_specific_config" && component == "alfen_specific_config"));
+    assert!(matches!(&errors[1], AppError::InvalidComponent { asset_id, reason, component: "charger_electrical_config", .. }
+        if asset_id == "CH002" && reason.contains("phase_count")));
+    assert!(matches!(&errors[2], AppError::InvalidEnumValue { asset_id, path, value, .. }
+        if asset_id == "CH003" && path == "assets[2].overrides.ocpp_profile_behavior.rate_unit" && value == "Kilowatts"));
+}
+
+#[test]
+fn test_reloaded_override_updates_in_place() {
+    let (mut app, channels) = started_site(&format!("[{}]", charger("CH001", "Alfen_Template", "")));
+    channels.ocpp_from_asset_sender.send(OcppRequestFromAsset {
+        charge_point_id: "CH001".into(),
+        action: "BootNotification".into(),
+        payload_json: r#"{"chargePointVendor":"Alfen","chargePointModel":"Eve"}"#.into(),
+        ocpp_message_id: "boot-1".into(),
+    }).unwrap();
+    app.update();
+
+    let assets = format!("[{}]", charger("CH001", "Alfen_Template", r#""charger_electrical_config": { "active_phase_count": 1 }"#));
+    channels.site_config_reload_sender.send(site_config(TEMPLATES_JSON, &assets)).unwrap();
+    app.update();
+    app.update();
+
+    assert_eq!(component::<ChargerElectricalConfig>(&app, "CH001").active_phase_count, 1);
+    assert!(component::<OcppConnectionState>(&app, "CH001").is_connected);
+}